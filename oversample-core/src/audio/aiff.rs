@@ -0,0 +1,257 @@
+//! AIFF and AIFF-C headers.
+//!
+//! AIFF is IFF with big-endian sizes: a `FORM` container holding a `COMM`
+//! chunk (channels, frame count, bit depth and an 80-bit extended sample
+//! rate) and an `SSND` chunk whose body starts with an offset/block-size
+//! pair. AIFF-C appends a compression type to `COMM`; only the
+//! uncompressed ones (`NONE`/`twos`, little-endian `sowt`, `in24`/`in32`
+//! and `fl32`/`fl64`) are read. The result is a [`WavHeader`] so AIFF
+//! streams through the same code as WAV.
+
+use crate::audio::bwf;
+use crate::audio::loader::{is_supported_pcm, WavHeader};
+
+/// `FORM` container of type `AIFF` or `AIFC`.
+pub fn is_aiff(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[0..4] == b"FORM" && matches!(&bytes[8..12], b"AIFF" | b"AIFC")
+}
+
+/// 80-bit IEEE 754 extended float (the `COMM` sample rate).
+pub fn extended_to_f64(b: &[u8; 10]) -> f64 {
+    let sign = if b[0] & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exponent = (u16::from_be_bytes([b[0], b[1]]) & 0x7FFF) as i32;
+    let mantissa = u64::from_be_bytes(b[2..10].try_into().unwrap());
+    if exponent == 0 && mantissa == 0 {
+        return 0.0;
+    }
+    sign * mantissa as f64 * 2f64.powi(exponent - 16383 - 63)
+}
+
+/// Parse an AIFF/AIFF-C header from the first bytes of a file. `SSND` may
+/// extend past `header_bytes`; its size comes from the chunk header.
+pub fn parse_aiff_header(header_bytes: &[u8]) -> Result<WavHeader, String> {
+    if !is_aiff(header_bytes) {
+        return Err("Not an AIFF/AIFF-C file".into());
+    }
+    let aifc = &header_bytes[8..12] == b"AIFC";
+
+    // (channels, frames, bits, sample_rate, compression)
+    let mut comm: Option<(u16, u32, u16, f64, [u8; 4])> = None;
+    let mut ssnd: Option<(u64, u64)> = None;
+    let mut text_fields: Vec<(String, String)> = Vec::new();
+
+    let mut pos = 12usize;
+    while pos + 8 <= header_bytes.len() {
+        let id: [u8; 4] = header_bytes[pos..pos + 4].try_into().unwrap();
+        let size = u32::from_be_bytes(header_bytes[pos + 4..pos + 8].try_into().unwrap()) as u64;
+        let body_start = pos + 8;
+        let body = header_bytes.get(body_start..(body_start as u64 + size).min(header_bytes.len() as u64) as usize)
+            .unwrap_or(&[]);
+        let fits = body.len() as u64 == size;
+
+        match &id {
+            b"COMM" => {
+                if body.len() < 18 {
+                    return Err("COMM chunk too small or truncated".into());
+                }
+                let channels = u16::from_be_bytes([body[0], body[1]]);
+                let frames = u32::from_be_bytes(body[2..6].try_into().unwrap());
+                let bits = u16::from_be_bytes([body[6], body[7]]);
+                let rate = extended_to_f64(body[8..18].try_into().unwrap());
+                let compression = if aifc && body.len() >= 22 {
+                    body[18..22].try_into().unwrap()
+                } else {
+                    *b"NONE"
+                };
+                comm = Some((channels, frames, bits, rate, compression));
+            }
+            b"SSND" => {
+                if body.len() < 8 {
+                    return Err("SSND chunk truncated".into());
+                }
+                let offset = u32::from_be_bytes(body[0..4].try_into().unwrap()) as u64;
+                let data_offset = body_start as u64 + 8 + offset;
+                ssnd = Some((data_offset, size.saturating_sub(8 + offset)));
+                // Sound data runs past the scanned bytes; COMM is normally first
+                if !fits && comm.is_some() {
+                    break;
+                }
+            }
+            b"NAME" | b"AUTH" | b"(c) " | b"ANNO" if fits => {
+                let key = match &id {
+                    b"NAME" => "AIFF|Name",
+                    b"AUTH" => "AIFF|Author",
+                    b"(c) " => "AIFF|Copyright",
+                    _ => "AIFF|Annotation",
+                };
+                let text = String::from_utf8_lossy(body).trim_end_matches('\0').trim().to_string();
+                if !text.is_empty() {
+                    text_fields.push((key.to_string(), text));
+                }
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        pos = match usize::try_from(body_start as u64 + size + (size & 1)) {
+            Ok(next) if next > pos => next,
+            _ => break,
+        };
+    }
+
+    let (channels, frames, bits, rate, compression) = comm.ok_or("No COMM chunk found in AIFF header")?;
+    let (data_offset, ssnd_size) = ssnd.ok_or("No SSND chunk found in AIFF header")?;
+
+    let (big_endian, is_float, bits_per_sample) = match &compression {
+        b"NONE" | b"twos" => (true, false, bits.div_ceil(8) * 8),
+        b"sowt" => (false, false, bits.div_ceil(8) * 8),
+        b"in24" => (true, false, 24),
+        b"in32" => (true, false, 32),
+        b"fl32" | b"FL32" => (true, true, 32),
+        b"fl64" | b"FL64" => (true, true, 64),
+        other => {
+            return Err(format!("Unsupported AIFF-C compression: {}", String::from_utf8_lossy(other)));
+        }
+    };
+    if !is_supported_pcm(bits_per_sample, is_float) {
+        return Err(format!("Unsupported AIFF sample format: {bits}-bit"));
+    }
+    let sample_rate = rate.round() as u32;
+    let bytes_per_frame = channels as u64 * (bits_per_sample as u64 / 8);
+    if bytes_per_frame == 0 || sample_rate == 0 {
+        return Err("Invalid AIFF: zero channels, bit depth or sample rate".into());
+    }
+
+    // Trust COMM's frame count, but never read past the SSND chunk
+    let total_frames = (frames as u64).min(ssnd_size / bytes_per_frame);
+
+    Ok(WavHeader {
+        sample_rate,
+        channels,
+        bits_per_sample,
+        is_float,
+        big_endian,
+        unsigned_8bit: false,
+        data_offset,
+        data_size: total_frames * bytes_per_frame,
+        total_frames,
+        guano: bwf::merge_into_guano(None, text_fields),
+        wav_markers: Vec::new(),
+        bwf: Vec::new(),
+        audiomoth: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::loader::load_audio;
+
+    /// Encode `value` as an 80-bit extended float.
+    fn f64_to_extended(value: f64) -> [u8; 10] {
+        let mut out = [0u8; 10];
+        if value == 0.0 || !value.is_finite() {
+            return out;
+        }
+        let sign = if value < 0.0 { 0x8000u16 } else { 0 };
+        let v = value.abs();
+        let exp = v.log2().floor() as i32;
+        let mantissa = (v / 2f64.powi(exp) * 2f64.powi(63)) as u64;
+        out[0..2].copy_from_slice(&(sign | (exp + 16383) as u16).to_be_bytes());
+        out[2..10].copy_from_slice(&mantissa.to_be_bytes());
+        out
+    }
+
+    fn chunk(out: &mut Vec<u8>, id: &[u8; 4], body: &[u8]) {
+        out.extend_from_slice(id);
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(body);
+        if !body.len().is_multiple_of(2) {
+            out.push(0);
+        }
+    }
+
+    fn build_aiff(form_type: &[u8; 4], compression: &[u8; 4], data: &[u8], channels: u16, bits: u16, rate: f64) -> Vec<u8> {
+        let frames = data.len() as u32 / (channels as u32 * bits as u32 / 8);
+        let mut comm = Vec::new();
+        comm.extend_from_slice(&channels.to_be_bytes());
+        comm.extend_from_slice(&frames.to_be_bytes());
+        comm.extend_from_slice(&bits.to_be_bytes());
+        comm.extend_from_slice(&f64_to_extended(rate));
+        if form_type == b"AIFC" {
+            comm.extend_from_slice(compression);
+            comm.extend_from_slice(b"\x00\x00");
+        }
+        let mut ssnd = vec![0u8; 8];
+        ssnd.extend_from_slice(data);
+
+        let mut out = Vec::new();
+        out.extend_from_slice(b"FORM\0\0\0\0");
+        out.extend_from_slice(form_type);
+        chunk(&mut out, b"COMM", &comm);
+        chunk(&mut out, b"NAME", b"Pipistrelle pass");
+        chunk(&mut out, b"SSND", &ssnd);
+        let len = out.len() as u32 - 8;
+        out[4..8].copy_from_slice(&len.to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn test_extended_round_trip() {
+        for rate in [8000.0, 44100.0, 250_000.0, 384_000.0, 0.5] {
+            assert_eq!(extended_to_f64(&f64_to_extended(rate)), rate);
+        }
+        // 44100 Hz as written by common encoders
+        let bytes = [0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0];
+        assert_eq!(extended_to_f64(&bytes), 44100.0);
+    }
+
+    #[test]
+    fn test_aiff_big_endian_pcm() {
+        let data: Vec<u8> = [0i16, 16384, -16384, 32767].iter().flat_map(|s| s.to_be_bytes()).collect();
+        let bytes = build_aiff(b"AIFF", b"NONE", &data, 1, 16, 384_000.0);
+        let h = parse_aiff_header(&bytes).unwrap();
+        assert_eq!((h.sample_rate, h.channels, h.bits_per_sample), (384_000, 1, 16));
+        assert!(h.big_endian && !h.is_float);
+        assert_eq!(h.total_frames, 4);
+        assert_eq!(&bytes[h.data_offset as usize..][..8], &data[..]);
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.metadata.format, "AIFF");
+        assert_eq!(audio.samples.len(), 4);
+        assert!((audio.samples[1] - 0.5).abs() < 1e-6);
+        assert!((audio.samples[2] + 0.5).abs() < 1e-6);
+        let g = audio.metadata.guano.unwrap();
+        assert!(g.fields.iter().any(|(k, v)| k == "AIFF|Name" && v == "Pipistrelle pass"));
+    }
+
+    #[test]
+    fn test_aifc_float_and_sowt() {
+        let data: Vec<u8> = [0.25f32, -0.75, 0.5, 1.0].iter().flat_map(|s| s.to_be_bytes()).collect();
+        let bytes = build_aiff(b"AIFC", b"fl32", &data, 2, 32, 48_000.0);
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.channels, 2);
+        assert!(audio.metadata.is_float);
+        assert_eq!(audio.samples.len(), 2);
+        assert!((audio.samples[0] + 0.25).abs() < 1e-6);
+
+        let data: Vec<u8> = [1000i16, -1000].iter().flat_map(|s| s.to_le_bytes()).collect();
+        let bytes = build_aiff(b"AIFC", b"sowt", &data, 1, 16, 48_000.0);
+        let h = parse_aiff_header(&bytes).unwrap();
+        assert!(!h.big_endian);
+        let audio = load_audio(&bytes).unwrap();
+        assert!((audio.samples[0] - 1000.0 / 32768.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rejects_unsupported_widths() {
+        // 83 bits would be an 11-byte sample; 40 bits a 5-byte one
+        for bits in [83, 40, 0] {
+            let mut bytes = build_aiff(b"AIFF", b"NONE", &[0; 66], 1, bits.max(8), 48_000.0);
+            // Patch the COMM bit depth after building (build_aiff sizes frames from it)
+            let comm = bytes.windows(4).position(|w| w == b"COMM").unwrap() + 8;
+            bytes[comm + 6..comm + 8].copy_from_slice(&bits.to_be_bytes());
+            assert!(parse_aiff_header(&bytes).is_err(), "{bits}-bit");
+            assert!(load_audio(&bytes).is_err(), "{bits}-bit");
+        }
+    }
+}
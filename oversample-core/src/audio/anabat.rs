@@ -0,0 +1,521 @@
+//! Reader and writer for Titley Anabat zero-crossing files (`.zc`, `.##`).
+//!
+//! An Anabat file holds no waveform: a fixed-layout text header (tape, date,
+//! location, species, notes; file type 132 adds a timestamp and GPS string)
+//! followed by a compressed list of intervals between divided zero crossings.
+//! Each interval becomes one dot at `divratio / interval` Hz.
+
+use crate::audio::guano::GuanoMetadata;
+
+/// Offset of the data information table in file types 129–132.
+const DATA_INFO_OFFSET: usize = 0x11A;
+/// Offset of the type-132 date/time block.
+const DATETIME_OFFSET: usize = 0x120;
+/// Data start in files we write (end of the type-132 GPS field).
+const DATA_OFFSET_132: usize = 0x150;
+/// Interval clock in Hz when `res1` is the standard 25000.
+const CLOCK_HZ: f64 = 1_000_000.0;
+const STANDARD_RES1: u16 = 25_000;
+
+/// One zero-crossing dot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZcDot {
+    /// Seconds from file start.
+    pub time_secs: f64,
+    pub freq_hz: f64,
+}
+
+/// Recording start from a type-132 header (local time as recorded).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnabatTimestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub hundredths: u8,
+}
+
+impl AnabatTimestamp {
+    /// ISO 8601 without a UTC offset, as GUANO allows for local time.
+    pub fn to_iso8601(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.hundredths,
+        )
+    }
+
+    /// Date/time part of an ISO 8601 string such as a GUANO `Timestamp`
+    /// (`YYYY-MM-DDTHH:MM:SS[.fff][offset]`, offset ignored), moved forward
+    /// by `offset_secs`.
+    pub fn from_iso8601(s: &str, offset_secs: f64) -> Option<Self> {
+        let (date, time) = s.trim().split_once(['T', ' '])?;
+        let mut d = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+        let (year, month, day) = (d.next()??, d.next()??, d.next()??);
+        let time: String = time.chars().take_while(|c| c.is_ascii_digit() || *c == ':' || *c == '.').collect();
+        let mut t = time.splitn(3, ':');
+        let hour = t.next()?.parse::<i64>().ok()?;
+        let minute = t.next()?.parse::<i64>().ok()?;
+        let second = t.next().map(|v| v.parse::<f64>().ok()).unwrap_or(Some(0.0))?;
+
+        let total = days_from_civil(year, month, day) as f64 * 86_400.0
+            + (hour * 3600 + minute * 60) as f64
+            + second
+            + offset_secs;
+        let centis = (total * 100.0).round() as i64;
+        let (days, rem) = (centis.div_euclid(8_640_000), centis.rem_euclid(8_640_000));
+        let (year, month, day) = civil_from_days(days);
+        Some(Self {
+            year: u16::try_from(year).ok()?,
+            month: month as u8,
+            day: day as u8,
+            hour: (rem / 360_000) as u8,
+            minute: (rem / 6_000 % 60) as u8,
+            second: (rem / 100 % 60) as u8,
+            hundredths: (rem % 100) as u8,
+        })
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + if m <= 2 { 1 } else { 0 }, m, d)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnabatHeader {
+    /// 129–132.
+    pub file_type: u8,
+    pub tape: String,
+    pub date: String,
+    pub location: String,
+    pub species: String,
+    pub spec: String,
+    pub notes: String,
+    pub notes1: String,
+    pub div_ratio: u8,
+    pub res1: u16,
+    pub timestamp: Option<AnabatTimestamp>,
+    pub id_code: String,
+    pub gps: String,
+}
+
+impl AnabatHeader {
+    /// Latitude / longitude from the GPS string, e.g. `"WGS84 -33.86 151.21 ..."`.
+    pub fn lat_lon(&self) -> Option<(f64, f64)> {
+        let mut nums = self.gps.split_whitespace().filter_map(|t| t.parse::<f64>().ok());
+        let lat = nums.next()?;
+        let lon = nums.next()?;
+        ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+    }
+
+    /// Type-132 header for an export, filled from GUANO fields. `offset_secs`
+    /// is where the exported span starts within the source recording.
+    pub fn from_guano(guano: Option<&GuanoMetadata>, offset_secs: f64, div_ratio: u8) -> Self {
+        let get = |key: &str| {
+            guano
+                .and_then(|g| g.fields.iter().find(|(k, _)| k == key))
+                .map(|(_, v)| v.trim().to_string())
+                .unwrap_or_default()
+        };
+        let timestamp = AnabatTimestamp::from_iso8601(&get("Timestamp"), offset_secs);
+        let loc_position = get("Loc Position");
+        let gps = match get("Anabat|GPS") {
+            g if g.is_empty() && !loc_position.is_empty() => format!("WGS84 {}", loc_position),
+            g => g,
+        };
+        let location = match get("Anabat|Location") {
+            l if l.is_empty() => loc_position,
+            l => l,
+        };
+        let or = |a: String, b: &str| if a.is_empty() { get(b) } else { a };
+        Self {
+            file_type: 132,
+            tape: or(get("Anabat|Tape"), "Serial"),
+            date: timestamp
+                .map(|t| format!("{:04}{:02}{:02}", t.year, t.month, t.day))
+                .unwrap_or_default(),
+            location,
+            species: get("Species Manual ID"),
+            spec: or(get("Anabat|Spec"), "Model"),
+            notes: get("Note"),
+            notes1: get("Original Filename"),
+            div_ratio,
+            res1: STANDARD_RES1,
+            timestamp,
+            id_code: get("Anabat|ID Code"),
+            gps,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnabatFile {
+    pub header: AnabatHeader,
+    pub dots: Vec<ZcDot>,
+    /// Time of the last dot (the file has no other notion of length).
+    pub duration_secs: f64,
+}
+
+impl AnabatFile {
+    /// Highest dot frequency (Hz), 0 when empty.
+    pub fn max_freq_hz(&self) -> f64 {
+        self.dots.iter().map(|d| d.freq_hz).fold(0.0, f64::max)
+    }
+
+    /// Header fields as GUANO so the metadata panel and timeline can use them.
+    pub fn guano(&self) -> GuanoMetadata {
+        let h = &self.header;
+        let mut g = GuanoMetadata::new();
+        g.add("GUANO|Version", "1.0");
+        g.add("Make", "Titley Scientific");
+        if let Some(ts) = h.timestamp {
+            g.add("Timestamp", &ts.to_iso8601());
+        }
+        if let Some((lat, lon)) = h.lat_lon() {
+            g.add("Loc Position", &format!("{} {}", lat, lon));
+        }
+        if !h.species.is_empty() {
+            g.add("Species Manual ID", &h.species);
+        }
+        let note = [h.notes.as_str(), h.notes1.as_str()]
+            .iter()
+            .filter(|s| !s.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !note.is_empty() {
+            g.add("Note", &note);
+        }
+        g.add("Length", &format!("{:.3}", self.duration_secs));
+        g.add("Anabat|File Type", &h.file_type.to_string());
+        g.add("Anabat|Division Ratio", &h.div_ratio.to_string());
+        for (key, value) in [
+            ("Anabat|Tape", &h.tape),
+            ("Anabat|Date", &h.date),
+            ("Anabat|Location", &h.location),
+            ("Anabat|Spec", &h.spec),
+            ("Anabat|ID Code", &h.id_code),
+            ("Anabat|GPS", &h.gps),
+        ] {
+            if !value.is_empty() {
+                g.add(key, value);
+            }
+        }
+        g
+    }
+}
+
+/// Check the fixed header layout: file type byte and data-info pointer.
+pub fn is_anabat(bytes: &[u8]) -> bool {
+    if bytes.len() < DATA_INFO_OFFSET + 6 {
+        return false;
+    }
+    let info_ptr = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+    (129..=132).contains(&bytes[3]) && info_ptr == DATA_INFO_OFFSET
+}
+
+/// Fixed-width text field: trailing spaces/NULs trimmed, non-ASCII dropped.
+fn text_field(bytes: &[u8], offset: usize, len: usize) -> String {
+    bytes
+        .get(offset..offset + len)
+        .map(|b| {
+            b.iter()
+                .take_while(|&&c| c != 0)
+                .filter(|c| c.is_ascii() && !c.is_ascii_control())
+                .map(|&c| c as char)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_header(bytes: &[u8]) -> Result<(AnabatHeader, usize), String> {
+    if !is_anabat(bytes) {
+        return Err("Not an Anabat zero-crossing file".into());
+    }
+    let data_ptr = u16::from_le_bytes([bytes[DATA_INFO_OFFSET], bytes[DATA_INFO_OFFSET + 1]]) as usize;
+    let res1 = u16::from_le_bytes([bytes[DATA_INFO_OFFSET + 2], bytes[DATA_INFO_OFFSET + 3]]);
+    let div_ratio = bytes[DATA_INFO_OFFSET + 4];
+    if data_ptr > bytes.len() || data_ptr < DATA_INFO_OFFSET + 6 {
+        return Err(format!("Anabat: bad data pointer 0x{:04X}", data_ptr));
+    }
+    if div_ratio == 0 {
+        return Err("Anabat: division ratio is zero".into());
+    }
+    let file_type = bytes[3];
+
+    let (timestamp, id_code, gps) = if file_type >= 132 && bytes.len() >= DATETIME_OFFSET + 48 {
+        let b = &bytes[DATETIME_OFFSET..];
+        let ts = AnabatTimestamp {
+            year: u16::from_le_bytes([b[0], b[1]]),
+            month: b[2],
+            day: b[3],
+            hour: b[4],
+            minute: b[5],
+            second: b[6],
+            hundredths: b[7],
+        };
+        let valid = ts.year >= 1990 && (1..=12).contains(&ts.month) && (1..=31).contains(&ts.day)
+            && ts.hour < 24 && ts.minute < 60 && ts.second < 60;
+        (
+            valid.then_some(ts),
+            text_field(bytes, DATETIME_OFFSET + 10, 6),
+            text_field(bytes, DATETIME_OFFSET + 16, 32),
+        )
+    } else {
+        (None, String::new(), String::new())
+    };
+
+    let header = AnabatHeader {
+        file_type,
+        tape: text_field(bytes, 0x06, 8),
+        date: text_field(bytes, 0x0E, 8),
+        location: text_field(bytes, 0x16, 40),
+        species: text_field(bytes, 0x3E, 50),
+        spec: text_field(bytes, 0x70, 16),
+        notes: text_field(bytes, 0x80, 73),
+        notes1: text_field(bytes, 0xC9, 80),
+        div_ratio,
+        res1,
+        timestamp,
+        id_code,
+        gps,
+    };
+    Ok((header, data_ptr))
+}
+
+/// Decode the interval stream into dots.
+///
+/// Byte codes: `0xxxxxxx` a signed 7-bit change to the previous interval;
+/// `100xxxxx`, `101xxxxx`, `110xxxxx` a new interval of 13, 21 or 29 bits
+/// spread over 2, 3 or 4 bytes; `111xxxxx` a status change whose second byte
+/// is a dot count (skipped — status only affects display style).
+fn decode_intervals(data: &[u8], div_ratio: u8, res1: u16) -> Vec<ZcDot> {
+    let tick_secs = if res1 > 0 {
+        STANDARD_RES1 as f64 / res1 as f64 / CLOCK_HZ
+    } else {
+        1.0 / CLOCK_HZ
+    };
+    let mut dots = Vec::new();
+    let mut interval: i64 = 0;
+    let mut ticks: i64 = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let b = data[i];
+        let (width, value) = match b >> 5 {
+            0..=3 => {
+                let delta = if b & 0x40 != 0 { b as i64 - 0x80 } else { b as i64 };
+                (1, interval + delta)
+            }
+            4..=6 => {
+                let width = (b >> 5) as usize - 2;
+                let Some(rest) = data.get(i + 1..i + width) else { break };
+                let value = rest.iter().fold((b & 0x1F) as i64, |acc, &x| (acc << 8) | x as i64);
+                (width, value)
+            }
+            _ => {
+                i += 2;
+                continue;
+            }
+        };
+        i += width;
+        interval = value;
+        if interval <= 0 {
+            continue;
+        }
+        ticks += interval;
+        let interval_secs = interval as f64 * tick_secs;
+        dots.push(ZcDot {
+            time_secs: ticks as f64 * tick_secs,
+            freq_hz: div_ratio as f64 / interval_secs,
+        });
+    }
+    dots
+}
+
+fn put_text(bytes: &mut [u8], offset: usize, len: usize, text: &str) {
+    let field = &mut bytes[offset..offset + len];
+    field.fill(b' ');
+    for (dst, c) in field.iter_mut().zip(text.bytes().filter(|c| c.is_ascii() && !c.is_ascii_control())) {
+        *dst = c;
+    }
+}
+
+/// Encode divided zero-crossing event times (seconds from file start, as
+/// from `zc_divide::zc_event_times`) as a type-132 Anabat file.
+///
+/// Intervals are whole microseconds; events closer than 1 µs to the
+/// previous one are dropped, as are events after a gap too long for the
+/// 29-bit interval field, which restart the count from themselves.
+pub fn write_anabat(header: &AnabatHeader, event_times: &[f64]) -> Vec<u8> {
+    let mut bytes = vec![0u8; DATA_OFFSET_132];
+    bytes[0..2].copy_from_slice(&(DATA_INFO_OFFSET as u16).to_le_bytes());
+    bytes[3] = 132;
+    put_text(&mut bytes, 0x06, 8, &header.tape);
+    put_text(&mut bytes, 0x0E, 8, &header.date);
+    put_text(&mut bytes, 0x16, 40, &header.location);
+    put_text(&mut bytes, 0x3E, 50, &header.species);
+    put_text(&mut bytes, 0x70, 16, &header.spec);
+    put_text(&mut bytes, 0x80, 73, &header.notes);
+    put_text(&mut bytes, 0xC9, 80, &header.notes1);
+    bytes[DATA_INFO_OFFSET..DATA_INFO_OFFSET + 2].copy_from_slice(&(DATA_OFFSET_132 as u16).to_le_bytes());
+    bytes[DATA_INFO_OFFSET + 2..DATA_INFO_OFFSET + 4].copy_from_slice(&STANDARD_RES1.to_le_bytes());
+    bytes[DATA_INFO_OFFSET + 4] = header.div_ratio.max(1);
+    if let Some(ts) = header.timestamp {
+        let b = &mut bytes[DATETIME_OFFSET..DATETIME_OFFSET + 10];
+        b[0..2].copy_from_slice(&ts.year.to_le_bytes());
+        b[2..8].copy_from_slice(&[ts.month, ts.day, ts.hour, ts.minute, ts.second, ts.hundredths]);
+    }
+    put_text(&mut bytes, DATETIME_OFFSET + 10, 6, &header.id_code);
+    put_text(&mut bytes, DATETIME_OFFSET + 16, 32, &header.gps);
+
+    let mut last_tick: i64 = 0;
+    let mut prev_interval: Option<i64> = None;
+    for &t in event_times {
+        let tick = (t * CLOCK_HZ).round() as i64;
+        let interval = tick - last_tick;
+        if interval < 1 {
+            continue;
+        }
+        match prev_interval.map(|p| interval - p) {
+            Some(delta) if (-64..=63).contains(&delta) => bytes.push((delta & 0x7F) as u8),
+            _ if interval < 1 << 13 => bytes.extend_from_slice(&[0x80 | (interval >> 8) as u8, interval as u8]),
+            _ if interval < 1 << 21 => {
+                bytes.extend_from_slice(&[0xA0 | (interval >> 16) as u8, (interval >> 8) as u8, interval as u8])
+            }
+            _ if interval < 1 << 29 => bytes.extend_from_slice(&[
+                0xC0 | (interval >> 24) as u8,
+                (interval >> 16) as u8,
+                (interval >> 8) as u8,
+                interval as u8,
+            ]),
+            _ => {
+                last_tick = tick;
+                prev_interval = None;
+                continue;
+            }
+        }
+        last_tick = tick;
+        prev_interval = Some(interval);
+    }
+    bytes
+}
+
+/// Parse an Anabat file. Dots outside 1–500 kHz (gap intervals, glitches)
+/// are dropped.
+pub fn parse_anabat(bytes: &[u8]) -> Result<AnabatFile, String> {
+    let (header, data_ptr) = parse_header(bytes)?;
+    let all = decode_intervals(&bytes[data_ptr..], header.div_ratio, header.res1);
+    let duration_secs = all.last().map(|d| d.time_secs).unwrap_or(0.0);
+    let dots = all.into_iter().filter(|d| (1_000.0..=500_000.0).contains(&d.freq_hz)).collect();
+    Ok(AnabatFile { header, dots, duration_secs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(div_ratio: u8, data: &[u8]) -> Vec<u8> {
+        let data_ptr = 0x150usize;
+        let mut bytes = vec![0x20u8; data_ptr];
+        bytes[0..2].copy_from_slice(&(DATA_INFO_OFFSET as u16).to_le_bytes());
+        bytes[2] = 0;
+        bytes[3] = 132;
+        bytes[0x3E..0x3E + 6].copy_from_slice(b"Myoluc");
+        bytes[DATA_INFO_OFFSET..DATA_INFO_OFFSET + 2].copy_from_slice(&(data_ptr as u16).to_le_bytes());
+        bytes[DATA_INFO_OFFSET + 2..DATA_INFO_OFFSET + 4].copy_from_slice(&STANDARD_RES1.to_le_bytes());
+        bytes[DATA_INFO_OFFSET + 4] = div_ratio;
+        bytes[DATA_INFO_OFFSET + 5] = 0;
+        bytes[DATETIME_OFFSET..DATETIME_OFFSET + 2].copy_from_slice(&2024u16.to_le_bytes());
+        bytes[DATETIME_OFFSET + 2..DATETIME_OFFSET + 8].copy_from_slice(&[7, 14, 22, 5, 9, 50]);
+        bytes[DATETIME_OFFSET + 16..DATETIME_OFFSET + 33].copy_from_slice(b"WGS84 51.50 -0.12");
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_header_fields() {
+        let f = parse_anabat(&build(8, &[])).unwrap();
+        assert_eq!(f.header.file_type, 132);
+        assert_eq!(f.header.species, "Myoluc");
+        assert_eq!(f.header.timestamp.unwrap().to_iso8601(), "2024-07-14T22:05:09.50");
+        assert_eq!(f.header.lat_lon(), Some((51.5, -0.12)));
+        let g = f.guano();
+        assert!(g.fields.iter().any(|(k, v)| k == "Species Manual ID" && v == "Myoluc"));
+        assert!(!is_anabat(b"RIFF\0\0\0\0WAVE"));
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let mut guano = GuanoMetadata::new();
+        guano.add("Timestamp", "2024-07-14T23:59:59.50+01:00");
+        guano.add("Species Manual ID", "Pippip");
+        guano.add("Loc Position", "51.5 -0.12");
+        let header = AnabatHeader::from_guano(Some(&guano), 1.0, 8);
+        assert_eq!(header.timestamp.unwrap().to_iso8601(), "2024-07-15T00:00:00.50");
+        assert_eq!(header.date, "20240715");
+
+        // 40 kHz run, a 0.5 s gap, then a sweep down and a 21-bit interval
+        let mut times: Vec<f64> = (1..=20).map(|i| i as f64 * 0.0002).collect();
+        times.extend((0..20).map(|i| 0.504 + i as f64 * 0.00025 + (i * i) as f64 * 0.000002));
+        let bytes = write_anabat(&header, &times);
+        let f = parse_anabat(&bytes).unwrap();
+        assert_eq!(f.header.species, "Pippip");
+        assert_eq!(f.header.lat_lon(), Some((51.5, -0.12)));
+        assert_eq!(f.header.div_ratio, 8);
+        // The gap interval decodes below 1 kHz and is dropped
+        assert_eq!(f.dots.len(), times.len() - 1);
+        assert!((f.dots[5].freq_hz - 40_000.0).abs() < 1e-6);
+        assert!((f.duration_secs - times.last().unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_write_skips_overlong_gap() {
+        let header = AnabatHeader::from_guano(None, 1.0, 8);
+        // A 10-minute gap overflows the 29-bit interval; the events after it
+        // still encode from the first one that follows the gap
+        let mut times: Vec<f64> = (1..=10).map(|i| i as f64 * 0.0002).collect();
+        times.extend((0..10).map(|i| 600.0 + i as f64 * 0.0002));
+        let f = parse_anabat(&write_anabat(&header, &times)).unwrap();
+        assert_eq!(f.dots.len(), times.len() - 1);
+        assert!((f.dots.last().unwrap().freq_hz - 40_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_interval_decoding() {
+        // 200 µs (13-bit) → 40 kHz at div 8; −10 µs (7-bit) → 190 µs; a status
+        // pair; a 65536 µs gap (21-bit) and +5 µs on it, both dropped as < 1 kHz;
+        // then 100 µs (13-bit) → 80 kHz.
+        let data = [0x80, 200, 0x76, 0xE1, 0x05, 0xA1, 0x00, 0x00, 0x05, 0x80, 100];
+        let f = parse_anabat(&build(8, &data)).unwrap();
+        assert_eq!(f.dots.len(), 3);
+        assert!((f.dots[0].freq_hz - 40_000.0).abs() < 1e-6);
+        assert!((f.dots[0].time_secs - 0.000200).abs() < 1e-12);
+        assert!((f.dots[1].freq_hz - 8.0 / 0.000190).abs() < 1e-6);
+        assert!((f.dots[1].time_secs - 0.000390).abs() < 1e-12);
+        assert!((f.dots[2].freq_hz - 80_000.0).abs() < 1e-6);
+        let end = (200 + 190 + 65_536 + 65_541 + 100) as f64 * 1e-6;
+        assert!((f.dots[2].time_secs - end).abs() < 1e-9);
+        assert!((f.duration_secs - end).abs() < 1e-9);
+    }
+}
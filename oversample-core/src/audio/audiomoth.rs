@@ -0,0 +1,321 @@
+//! AudioMoth metadata: the `LIST`/`INFO` `ICMT` comment and filename timestamps.
+//!
+//! AudioMoth firmware writes no GUANO. Its configuration is a sentence in
+//! the WAV comment, e.g.
+//! `Recorded at 00:00:00 01/01/2021 (UTC+1) by AudioMoth 249BC30461CBB1E6 at
+//! medium gain while battery was 4.2V and temperature was 17.8C.`
+//! followed by optional trigger, filter and stop-reason sentences. Files are
+//! named by start time: `YYYYMMDD_HHMMSS.WAV` (`T` suffix for triggered
+//! recordings), or an 8-digit hex Unix time on early firmware.
+
+use crate::audio::guano::GuanoMetadata;
+
+pub const MAKE: &str = "Open Acoustic Devices";
+pub const MODEL: &str = "AudioMoth";
+
+/// Fields parsed from an AudioMoth comment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioMothComment {
+    /// Start time as ISO 8601 with the UTC offset from the comment.
+    pub timestamp: Option<String>,
+    /// 64-bit device ID in hex.
+    pub device_id: Option<String>,
+    /// Gain setting, e.g. "medium".
+    pub gain: Option<String>,
+    /// Battery voltage as written, e.g. "4.2V" or "less than 3.6V".
+    pub battery: Option<String>,
+    pub temperature_c: Option<f64>,
+    /// Amplitude or frequency trigger sentence.
+    pub trigger: Option<String>,
+    /// Band-pass, low-pass or high-pass filter sentence.
+    pub filter: Option<String>,
+    /// Why the recording ended early, if it did.
+    pub stopped: Option<String>,
+    /// The comment as written.
+    pub text: String,
+}
+
+impl AudioMothComment {
+    /// GUANO fields: standard keys first, then `AudioMoth|` settings.
+    pub fn guano_fields(&self, sample_rate: Option<u32>) -> Vec<(String, String)> {
+        let mut fields: Vec<(String, String)> = Vec::new();
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(v) = value.filter(|v| !v.is_empty()) {
+                fields.push((key.to_string(), v));
+            }
+        };
+        push("Timestamp", self.timestamp.clone());
+        push("Make", Some(MAKE.into()));
+        push("Model", Some(MODEL.into()));
+        push("Serial", self.device_id.clone());
+        push("Samplerate", sample_rate.map(|sr| sr.to_string()));
+        push("Temperature Int", self.temperature_c.map(|t| t.to_string()));
+        push("AudioMoth|Gain", self.gain.clone());
+        push("AudioMoth|Battery", self.battery.clone());
+        push("AudioMoth|Trigger", self.trigger.clone());
+        push("AudioMoth|Filter", self.filter.clone());
+        push("AudioMoth|Stopped", self.stopped.clone());
+        push("AudioMoth|Comment", Some(self.text.clone()));
+        fields
+    }
+}
+
+/// Parse an AudioMoth comment. `None` unless it names an AudioMoth.
+pub fn parse_comment(text: &str) -> Option<AudioMothComment> {
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    if !text.starts_with("Recorded at ") || !text.contains("AudioMoth") {
+        return None;
+    }
+    let mut c = AudioMothComment { text: text.to_string(), ..Default::default() };
+
+    // Sentences end in ". "; decimals ("4.2V") have no space after the point
+    let mut sentences = text.split(". ").map(|s| s.trim_end_matches('.'));
+    let first = sentences.next()?;
+
+    let rest = first.strip_prefix("Recorded at ")?;
+    let mut words = rest.splitn(3, ' ');
+    let (time, date) = (words.next()?, words.next()?);
+    let rest = words.next().unwrap_or("");
+    let (zone, rest) = match rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+        Some((zone, rest)) => (Some(zone), rest),
+        None => (None, rest),
+    };
+    c.timestamp = comment_timestamp(time, date, zone);
+
+    if let Some(after) = rest.split_once("by AudioMoth ").map(|(_, a)| a) {
+        c.device_id = after
+            .split_whitespace()
+            .next()
+            .filter(|id| id.chars().all(|ch| ch.is_ascii_hexdigit()))
+            .map(str::to_string);
+    }
+    if let Some((before, _)) = rest.split_once(" gain") {
+        c.gain = before.rsplit(' ').next().filter(|g| !g.is_empty()).map(str::to_string);
+    }
+    let battery = rest.split_once("battery state was ").or_else(|| rest.split_once("battery was "));
+    if let Some((_, after)) = battery {
+        c.battery = Some(after.split(" and ").next().unwrap_or(after).trim().to_string());
+    }
+    if let Some((_, after)) = rest.split_once("temperature was ") {
+        c.temperature_c = after.trim().trim_end_matches('C').parse().ok();
+    }
+
+    for sentence in sentences {
+        let lower = sentence.to_ascii_lowercase();
+        let slot = if lower.contains("threshold") {
+            &mut c.trigger
+        } else if lower.contains("filter") {
+            &mut c.filter
+        } else if lower.starts_with("recording stopped") || lower.starts_with("recording cancelled") {
+            &mut c.stopped
+        } else {
+            continue;
+        };
+        *slot = Some(sentence.trim().to_string());
+    }
+    Some(c)
+}
+
+/// `HH:MM:SS[.fff]`, `DD/MM/YYYY` and `UTC[±H[:MM]]` to ISO 8601.
+fn comment_timestamp(time: &str, date: &str, zone: Option<&str>) -> Option<String> {
+    let mut d = date.split('/');
+    let (day, month, year): (u32, u32, u32) = (d.next()?.parse().ok()?, d.next()?.parse().ok()?, d.next()?.parse().ok()?);
+    let (hms, frac) = time.split_once('.').unwrap_or((time, ""));
+    let mut t = hms.split(':');
+    let (h, m, s): (u32, u32, u32) = (t.next()?.parse().ok()?, t.next()?.parse().ok()?, t.next()?.parse().ok()?);
+    if !valid_date_time(year, month, day, h, m, s) || !frac.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let offset = match zone.map(str::trim) {
+        None | Some("UTC") => "+00:00".to_string(),
+        Some(z) => {
+            let off = z.strip_prefix("UTC")?;
+            let (sign, off) = match (off.strip_prefix('+'), off.strip_prefix('-')) {
+                (Some(off), _) => ('+', off),
+                (_, Some(off)) => ('-', off),
+                _ => return None,
+            };
+            let (oh, om) = off.split_once(':').unwrap_or((off, "0"));
+            format!("{sign}{:02}:{:02}", oh.parse::<u32>().ok()?, om.parse::<u32>().ok()?)
+        }
+    };
+    let frac = if frac.is_empty() { String::new() } else { format!(".{frac}") };
+    Some(format!("{year:04}-{month:02}-{day:02}T{h:02}:{m:02}:{s:02}{frac}{offset}"))
+}
+
+fn valid_date_time(year: u32, month: u32, day: u32, h: u32, m: u32, s: u32) -> bool {
+    (2000..2100).contains(&year) && (1..=12).contains(&month) && (1..=31).contains(&day) && h < 24 && m < 60 && s < 60
+}
+
+/// Start time encoded in an AudioMoth filename, as ISO 8601 with no UTC
+/// offset.
+///
+/// Recognises `YYYYMMDD_HHMMSS[_mmm][T].ext` and early firmware's
+/// `XXXXXXXX.ext` upper-case hex Unix time. Devices can be set to UTC or to
+/// local time, so the offset is unknown; only the comment carries it.
+pub fn filename_timestamp(filename: &str) -> Option<String> {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let stem = name.rsplit_once('.').map(|(s, _)| s).unwrap_or(name);
+    let stem = stem.strip_suffix('T').unwrap_or(stem);
+    let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    let hex = |c: char| c.is_ascii_digit() || matches!(c, 'A'..='F');
+    if stem.len() == 8 && stem.chars().all(hex) && !digits(stem) {
+        let secs = i64::from_str_radix(stem, 16).ok()?;
+        let ts = crate::audio::anabat::AnabatTimestamp::from_iso8601("1970-01-01T00:00:00", secs as f64)?;
+        // Only early firmware used hex names
+        if !(2017..2021).contains(&ts.year) {
+            return None;
+        }
+        return Some(ts.to_iso8601()[..19].to_string());
+    }
+
+    let mut parts = stem.split('_');
+    let (date, time) = (parts.next()?, parts.next()?);
+    let millis = parts.next();
+    if parts.next().is_some() || date.len() != 8 || time.len() != 6 || !digits(date) || !digits(time) {
+        return None;
+    }
+    if millis.is_some_and(|ms| ms.len() != 3 || !digits(ms)) {
+        return None;
+    }
+    let num = |s: &str| s.parse::<u32>().unwrap_or(0);
+    let (year, month, day) = (num(&date[0..4]), num(&date[4..6]), num(&date[6..8]));
+    let (h, m, s) = (num(&time[0..2]), num(&time[2..4]), num(&time[4..6]));
+    if !valid_date_time(year, month, day, h, m, s) {
+        return None;
+    }
+    let frac = millis.map(|ms| format!(".{ms}")).unwrap_or_default();
+    Some(format!("{year:04}-{month:02}-{day:02}T{h:02}:{m:02}:{s:02}{frac}"))
+}
+
+/// `ICMT` text from a `LIST` chunk body of type `INFO`.
+pub fn info_comment(list_body: &[u8]) -> Option<String> {
+    let mut data = list_body.strip_prefix(b"INFO")?;
+    while data.len() >= 8 {
+        let size = u32::from_le_bytes(data[4..8].try_into().ok()?) as usize;
+        let body = data.get(8..8 + size).unwrap_or(&data[8..]);
+        if &data[0..4] == b"ICMT" {
+            let end = body.iter().position(|&b| b == 0).unwrap_or(body.len());
+            return Some(String::from_utf8_lossy(&body[..end]).into_owned());
+        }
+        data = data.get(8 + ((size + 1) & !1)..)?;
+    }
+    None
+}
+
+/// Add AudioMoth fields to GUANO metadata. Keys the file already has
+/// (for instance a GUANO `Timestamp`) are kept.
+pub fn merge_into_guano(
+    guano: Option<GuanoMetadata>,
+    comment: Option<&AudioMothComment>,
+    sample_rate: Option<u32>,
+) -> Option<GuanoMetadata> {
+    let Some(comment) = comment else { return guano };
+    let mut guano = guano.unwrap_or_default();
+    for (key, value) in comment.guano_fields(sample_rate) {
+        if !guano.fields.iter().any(|(k, _)| *k == key) {
+            guano.fields.push((key, value));
+        }
+    }
+    Some(guano)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::loader::load_audio;
+
+    const COMMENT: &str = "Recorded at 19:30:00 10/07/2021 (UTC+1) by AudioMoth 243B1F075E8D0E4F at medium gain while \
+        battery was 4.2V and temperature was 21.3C. Amplitude threshold was 0.1% with 5s minimum trigger duration. \
+        Band-pass filter with frequencies of 20.0kHz and 100.0kHz applied.";
+
+    #[test]
+    fn test_parse_comment() {
+        let c = parse_comment(COMMENT).unwrap();
+        assert_eq!(c.timestamp.as_deref(), Some("2021-07-10T19:30:00+01:00"));
+        assert_eq!(c.device_id.as_deref(), Some("243B1F075E8D0E4F"));
+        assert_eq!(c.gain.as_deref(), Some("medium"));
+        assert_eq!(c.battery.as_deref(), Some("4.2V"));
+        assert_eq!(c.temperature_c, Some(21.3));
+        assert_eq!(c.trigger.as_deref(), Some("Amplitude threshold was 0.1% with 5s minimum trigger duration"));
+        assert!(c.filter.as_deref().unwrap().starts_with("Band-pass filter"));
+        assert_eq!(c.stopped, None);
+
+        let old = parse_comment(
+            "Recorded at 10:00:00 21/04/2019 (UTC) by AudioMoth 24A04F085C9F5E5F at medium gain setting \
+             while battery state was less than 3.6V. Recording stopped due to low voltage.",
+        )
+        .unwrap();
+        assert_eq!(old.timestamp.as_deref(), Some("2019-04-21T10:00:00+00:00"));
+        assert_eq!(old.battery.as_deref(), Some("less than 3.6V"));
+        assert_eq!(old.temperature_c, None);
+        assert_eq!(old.stopped.as_deref(), Some("Recording stopped due to low voltage"));
+
+        assert!(parse_comment("Recorded at dusk by hand").is_none());
+
+        // Non-ASCII after "UTC" is an unknown zone, not a panic
+        for zone in ["(UTC\u{a9})", "(UTC\u{2212}5)", "(UTC+\u{e9})"] {
+            let c = parse_comment(&COMMENT.replace("(UTC+1)", zone)).unwrap();
+            assert_eq!(c.timestamp, None, "{zone}");
+        }
+    }
+
+    #[test]
+    fn test_filename_timestamp() {
+        assert_eq!(filename_timestamp("20190421_100000.WAV").as_deref(), Some("2019-04-21T10:00:00"));
+        assert_eq!(filename_timestamp("dir/20190421_235959T.wav").as_deref(), Some("2019-04-21T23:59:59"));
+        assert_eq!(filename_timestamp("20220101_120000_250.WAV").as_deref(), Some("2022-01-01T12:00:00.250"));
+        assert_eq!(filename_timestamp("5CBC3F20.WAV").as_deref(), Some("2019-04-21T10:00:00"));
+        assert_eq!(filename_timestamp("deadbeef.wav"), None);
+        assert_eq!(filename_timestamp("DEADBEEF.WAV"), None);
+        assert_eq!(filename_timestamp("260212_0041.wav"), None);
+        assert_eq!(filename_timestamp("20190421_100000_1-2.wav"), None);
+        assert_eq!(filename_timestamp("12345678.wav"), None);
+    }
+
+    #[test]
+    fn test_wav_info_comment_to_guano() {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for i in 0..100 {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        let mut bytes = cursor.into_inner();
+
+        // AudioMoth puts LIST/INFO before the data chunk
+        let mut icmt = COMMENT.as_bytes().to_vec();
+        icmt.push(0);
+        if icmt.len() % 2 == 1 {
+            icmt.push(0);
+        }
+        let mut list = b"INFO".to_vec();
+        list.extend_from_slice(b"ICMT");
+        list.extend_from_slice(&(icmt.len() as u32).to_le_bytes());
+        list.extend_from_slice(&icmt);
+        let mut chunk = b"LIST".to_vec();
+        chunk.extend_from_slice(&(list.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(&list);
+        bytes.splice(12..12, chunk);
+        let riff_size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.samples.len(), 100);
+        let g = audio.metadata.guano.unwrap();
+        let get = |key: &str| g.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        assert_eq!(get("Timestamp"), Some("2021-07-10T19:30:00+01:00"));
+        assert_eq!(get("Model"), Some("AudioMoth"));
+        assert_eq!(get("Serial"), Some("243B1F075E8D0E4F"));
+        assert_eq!(get("Temperature Int"), Some("21.3"));
+        assert_eq!(get("Samplerate"), Some("48000"));
+        assert_eq!(get("AudioMoth|Gain"), Some("medium"));
+    }
+}
@@ -0,0 +1,396 @@
+//! Broadcast WAV (`bext`, EBU Tech 3285) and iXML chunks.
+//!
+//! Field recorders and several bat detectors describe a recording with these
+//! instead of (or as well as) GUANO. Both are surfaced as pipe-prefixed
+//! metadata fields (`BWF|Originator`, `iXML|SPEED/TIMECODE_RATE`) merged
+//! after the GUANO fields, so the metadata panel groups them by section.
+
+use crate::audio::anabat::AnabatTimestamp;
+use crate::audio::guano::GuanoMetadata;
+
+const DESCRIPTION_LEN: usize = 256;
+const ORIGINATOR_LEN: usize = 32;
+const ORIGINATOR_REFERENCE_LEN: usize = 32;
+const DATE_LEN: usize = 10;
+const TIME_LEN: usize = 8;
+const UMID_LEN: usize = 64;
+/// Fixed part of a version 1/2 `bext` body; coding history follows.
+pub const BEXT_FIXED_LEN: usize = 602;
+const TIME_REFERENCE_OFFSET: usize = DESCRIPTION_LEN + ORIGINATOR_LEN + ORIGINATOR_REFERENCE_LEN + DATE_LEN + TIME_LEN;
+const VERSION_OFFSET: usize = TIME_REFERENCE_OFFSET + 8;
+const UMID_OFFSET: usize = VERSION_OFFSET + 2;
+
+/// Broadcast audio extension chunk.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bext {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    /// `YYYY-MM-DD` as written (separators normalised to `-`).
+    pub origination_date: String,
+    /// `HH:MM:SS` as written (separators normalised to `:`).
+    pub origination_time: String,
+    /// First sample's position in samples since midnight.
+    pub time_reference: u64,
+    pub version: u16,
+    /// SMPTE UMID, empty when the field is all zeros.
+    pub umid: Vec<u8>,
+    pub coding_history: String,
+}
+
+impl Bext {
+    /// Origination date and time as ISO 8601 without a UTC offset.
+    pub fn origination(&self) -> Option<String> {
+        AnabatTimestamp::from_iso8601(&format!("{}T{}", self.origination_date, self.origination_time), 0.0)
+            .map(|ts| ts.to_iso8601()[..19].to_string())
+    }
+
+    /// Start of the first sample: origination date plus the time reference.
+    pub fn start(&self, sample_rate: u32) -> Option<String> {
+        if self.time_reference == 0 || sample_rate == 0 {
+            return None;
+        }
+        let secs = self.time_reference as f64 / sample_rate as f64;
+        AnabatTimestamp::from_iso8601(&format!("{}T00:00:00", self.origination_date), secs).map(|ts| ts.to_iso8601())
+    }
+
+    /// Fields for the metadata panel.
+    pub fn fields(&self, sample_rate: Option<u32>) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        let mut push = |key: &str, value: String| {
+            if !value.is_empty() {
+                fields.push((format!("BWF|{key}"), value));
+            }
+        };
+        push("Description", self.description.clone());
+        push("Originator", self.originator.clone());
+        push("Originator Reference", self.originator_reference.clone());
+        push("Origination", self.origination().unwrap_or_default());
+        if self.time_reference > 0 {
+            let mut value = self.time_reference.to_string();
+            if let Some(sr) = sample_rate.filter(|&sr| sr > 0) {
+                let secs = self.time_reference as f64 / sr as f64;
+                let s = secs.rem_euclid(86_400.0);
+                value.push_str(&format!(
+                    " ({:02}:{:02}:{:06.3})",
+                    (s / 3600.0) as u32,
+                    (s / 60.0 % 60.0) as u32,
+                    s % 60.0,
+                ));
+            }
+            push("Time Reference", value);
+        }
+        push("Start", sample_rate.and_then(|sr| self.start(sr)).unwrap_or_default());
+        push("UMID", self.umid.iter().map(|b| format!("{b:02X}")).collect());
+        push("Version", self.version.to_string());
+        push("Coding History", self.coding_history.clone());
+        fields
+    }
+
+    /// A bext describing audio that starts `offset_secs` after the GUANO
+    /// `Timestamp` (or a `BWF|Start` read from the source). `None` when
+    /// there is no parseable timestamp.
+    pub fn from_guano(guano: &GuanoMetadata, offset_secs: f64, sample_rate: u32) -> Option<Self> {
+        let get = |key: &str| guano.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.trim());
+        let start = get("Timestamp").or_else(|| get("BWF|Start"))?;
+        let ts = AnabatTimestamp::from_iso8601(start, offset_secs)?;
+        let iso = ts.to_iso8601();
+        let since_midnight = (ts.hour as u64 * 3600 + ts.minute as u64 * 60 + ts.second as u64) as f64
+            + ts.hundredths as f64 / 100.0;
+        let originator = [get("Make"), get("Model")]
+            .into_iter()
+            .flatten()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(Self {
+            description: get("Note").unwrap_or_default().to_string(),
+            originator: if originator.is_empty() { "Oversample".into() } else { originator },
+            originator_reference: get("Serial").unwrap_or_default().to_string(),
+            origination_date: iso[..10].to_string(),
+            origination_time: iso[11..19].to_string(),
+            time_reference: (since_midnight * sample_rate as f64).round() as u64,
+            version: 1,
+            umid: Vec::new(),
+            coding_history: String::new(),
+        })
+    }
+
+    /// Serialise as a `bext` chunk body. Text is truncated to the field widths.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = vec![0u8; BEXT_FIXED_LEN];
+        let mut pos = 0;
+        for (text, len) in [
+            (&self.description, DESCRIPTION_LEN),
+            (&self.originator, ORIGINATOR_LEN),
+            (&self.originator_reference, ORIGINATOR_REFERENCE_LEN),
+            (&self.origination_date, DATE_LEN),
+            (&self.origination_time, TIME_LEN),
+        ] {
+            let bytes = ascii_bytes(text);
+            let n = bytes.len().min(len);
+            body[pos..pos + n].copy_from_slice(&bytes[..n]);
+            pos += len;
+        }
+        body[TIME_REFERENCE_OFFSET..VERSION_OFFSET].copy_from_slice(&self.time_reference.to_le_bytes());
+        body[VERSION_OFFSET..UMID_OFFSET].copy_from_slice(&self.version.to_le_bytes());
+        let n = self.umid.len().min(UMID_LEN);
+        body[UMID_OFFSET..UMID_OFFSET + n].copy_from_slice(&self.umid[..n]);
+        body.extend_from_slice(&ascii_bytes(&self.coding_history));
+        body
+    }
+}
+
+/// Non-ASCII characters become `?`; bext text fields are ASCII.
+fn ascii_bytes(s: &str) -> Vec<u8> {
+    s.chars().map(|c| if c.is_ascii() { c as u8 } else { b'?' }).collect()
+}
+
+fn fixed_text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+/// Parse a `bext` chunk body.
+pub fn parse_bext(body: &[u8]) -> Option<Bext> {
+    if body.len() < UMID_OFFSET {
+        return None;
+    }
+    let mut pos = 0;
+    let mut take = |len: usize| {
+        let text = fixed_text(&body[pos..pos + len]);
+        pos += len;
+        text
+    };
+    let description = take(DESCRIPTION_LEN);
+    let originator = take(ORIGINATOR_LEN);
+    let originator_reference = take(ORIGINATOR_REFERENCE_LEN);
+    // The spec allows any of `-_:. ` as separators
+    let origination_date = take(DATE_LEN).replace(['_', ':', '.', ' '], "-");
+    let origination_time = take(TIME_LEN).replace(['-', '_', '.', ' '], ":");
+    let time_reference = u64::from_le_bytes(body[TIME_REFERENCE_OFFSET..VERSION_OFFSET].try_into().ok()?);
+    let version = u16::from_le_bytes(body[VERSION_OFFSET..UMID_OFFSET].try_into().ok()?);
+    let umid = body
+        .get(UMID_OFFSET..UMID_OFFSET + UMID_LEN)
+        .filter(|u| u.iter().any(|&b| b != 0))
+        .map(|u| u.to_vec())
+        .unwrap_or_default();
+    let coding_history = body.get(BEXT_FIXED_LEN..).map(fixed_text).unwrap_or_default();
+    Some(Bext {
+        description,
+        originator,
+        originator_reference,
+        origination_date,
+        origination_time,
+        time_reference,
+        version,
+        umid,
+        coding_history,
+    })
+}
+
+/// Flatten an iXML document into `iXML|PATH/TO/LEAF` fields (the `BWFXML`
+/// root is dropped). Elements with children and empty leaves are skipped.
+pub fn parse_ixml(text: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut path: Vec<&str> = Vec::new();
+    // Byte offset just after the most recent open tag, while it has no children
+    let mut leaf_start: Option<usize> = None;
+    let mut pos = 0;
+    while let Some(rel) = text[pos..].find('<') {
+        let tag_start = pos + rel;
+        let rest = &text[tag_start..];
+        let skip_to = |end: &str| rest.find(end).map(|i| tag_start + i + end.len());
+        let next = if rest.starts_with("<!--") {
+            skip_to("-->")
+        } else if rest.starts_with("<![CDATA[") {
+            skip_to("]]>")
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            leaf_start = None;
+            skip_to(">")
+        } else {
+            let Some(tag_end) = skip_to(">") else { break };
+            let inner = &text[tag_start + 1..tag_end - 1];
+            if let Some(name) = inner.strip_prefix('/') {
+                let name = name.trim();
+                if let Some(start) = leaf_start.take() {
+                    let value = leaf_text(&text[start..tag_start]);
+                    if path.len() > 1 && !value.is_empty() {
+                        fields.push((format!("iXML|{}", path[1..].join("/")), value));
+                    }
+                }
+                if let Some(i) = path.iter().rposition(|p| *p == name) {
+                    path.truncate(i);
+                }
+            } else if !inner.ends_with('/') {
+                let name = inner.split(|c: char| c.is_whitespace()).next().unwrap_or("");
+                path.push(name);
+                leaf_start = Some(tag_end);
+            }
+            Some(tag_end)
+        };
+        let Some(next) = next else { break };
+        pos = next;
+    }
+    fields
+}
+
+fn leaf_text(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(cdata) = raw.strip_prefix("<![CDATA[").and_then(|s| s.strip_suffix("]]>")) {
+        return cdata.trim().to_string();
+    }
+    raw.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Metadata fields from the `bext` and `iXML` chunks among `chunks`
+/// (chunk id and body pairs, e.g. from [`crate::audio::riff::Chunks::with_bodies`]).
+pub fn bwf_fields<'a>(
+    chunks: impl IntoIterator<Item = ([u8; 4], &'a [u8])>,
+    sample_rate: Option<u32>,
+) -> Vec<(String, String)> {
+    let mut bext = Vec::new();
+    let mut ixml = Vec::new();
+    for (id, body) in chunks {
+        match &id {
+            b"bext" if bext.is_empty() => {
+                if let Some(b) = parse_bext(body) {
+                    bext = b.fields(sample_rate);
+                }
+            }
+            b"iXML" if ixml.is_empty() => {
+                let text = String::from_utf8_lossy(body);
+                ixml = parse_ixml(text.trim_end_matches('\0'));
+            }
+            _ => {}
+        }
+    }
+    bext.extend(ixml);
+    bext
+}
+
+/// Append BWF/iXML fields after any GUANO fields.
+pub fn merge_into_guano(guano: Option<GuanoMetadata>, fields: Vec<(String, String)>) -> Option<GuanoMetadata> {
+    if fields.is_empty() {
+        return guano;
+    }
+    let mut guano = guano.unwrap_or_default();
+    guano.fields.extend(fields);
+    Some(guano)
+}
+
+/// Insert a `bext` chunk right after the RIFF/WAVE header, where readers
+/// expect it, and update the RIFF size.
+pub fn insert_bext_chunk(wav_bytes: &mut Vec<u8>, bext: &Bext) {
+    let body = bext.to_bytes();
+    let mut chunk = Vec::with_capacity(body.len() + 9);
+    chunk.extend_from_slice(b"bext");
+    chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(&body);
+    if !body.len().is_multiple_of(2) {
+        chunk.push(0);
+    }
+    wav_bytes.splice(12..12, chunk);
+    let riff_size = (wav_bytes.len() - 8) as u32;
+    wav_bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::loader::{load_audio, parse_wav_header};
+
+    fn wav(rate: u32, frames: usize) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for i in 0..frames {
+            writer.write_sample((i % 100) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn test_bext_round_trip_from_guano() {
+        let mut g = GuanoMetadata::new();
+        g.add("Timestamp", "2024-06-01T23:59:58.5+02:00");
+        g.add("Make", "Wildlife Acoustics");
+        g.add("Model", "SM4BAT");
+        let bext = Bext::from_guano(&g, 2.0, 256_000).unwrap();
+        assert_eq!(bext.origination_date, "2024-06-02");
+        assert_eq!(bext.origination_time, "00:00:00");
+        assert_eq!(bext.time_reference, 128_000);
+
+        let parsed = parse_bext(&bext.to_bytes()).unwrap();
+        assert_eq!(parsed, bext);
+        assert_eq!(parsed.start(256_000).as_deref(), Some("2024-06-02T00:00:00.50"));
+        let fields = parsed.fields(Some(256_000));
+        assert!(fields.contains(&("BWF|Originator".into(), "Wildlife Acoustics SM4BAT".into())));
+        assert!(fields.contains(&("BWF|Origination".into(), "2024-06-02T00:00:00".into())));
+        assert!(fields.contains(&("BWF|Time Reference".into(), "128000 (00:00:00.500)".into())));
+    }
+
+    #[test]
+    fn test_parse_ixml_leaves() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<BWFXML>
+  <IXML_VERSION>1.61</IXML_VERSION>
+  <PROJECT>Bats &amp; Moths</PROJECT>
+  <!-- comment -->
+  <SPEED>
+    <NOTE/>
+    <TIMECODE_RATE>25/1</TIMECODE_RATE>
+  </SPEED>
+  <TAPE></TAPE>
+</BWFXML>"#;
+        assert_eq!(
+            parse_ixml(xml),
+            vec![
+                ("iXML|IXML_VERSION".to_string(), "1.61".to_string()),
+                ("iXML|PROJECT".to_string(), "Bats & Moths".to_string()),
+                ("iXML|SPEED/TIMECODE_RATE".to_string(), "25/1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wav_with_bext_and_ixml() {
+        let mut bytes = wav(48_000, 480);
+        let bext = Bext {
+            originator: "Recorder".into(),
+            origination_date: "2023-05-04".into(),
+            origination_time: "21:30:00".into(),
+            time_reference: 21 * 3600 * 48_000 + 30 * 60 * 48_000,
+            version: 1,
+            ..Default::default()
+        };
+        insert_bext_chunk(&mut bytes, &bext);
+        let ixml = "<BWFXML><SCENE>12</SCENE></BWFXML>\0";
+        bytes.extend_from_slice(b"iXML");
+        bytes.extend_from_slice(&(ixml.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(ixml.as_bytes());
+        bytes.push(0);
+        let riff_size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let h = parse_wav_header(&bytes).unwrap();
+        assert_eq!(h.total_frames, 480);
+        assert!(h.bwf.contains(&("BWF|Start".into(), "2023-05-04T21:30:00.00".into())));
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.samples.len(), 480);
+        let g = audio.metadata.guano.unwrap();
+        assert!(g.fields.contains(&("BWF|Originator".into(), "Recorder".into())));
+        assert!(g.fields.contains(&("iXML|SCENE".into(), "12".into())));
+    }
+}
@@ -0,0 +1,213 @@
+//! Apple Core Audio Format (CAF) headers.
+//!
+//! A CAF file is `caff` + version + flags, then chunks with a FourCC and a
+//! big-endian i64 size. `desc` describes the stream and `data` starts with
+//! a 4-byte edit count; a `data` size of -1 means "to end of file", which
+//! recorders use while still writing. Only linear PCM (`lpcm`, integer or
+//! float, either byte order) is read; the result is a [`WavHeader`] so CAF
+//! streams through the same code as WAV.
+
+use crate::audio::bwf;
+use crate::audio::loader::{is_supported_pcm, WavHeader};
+
+const FLAG_IS_FLOAT: u32 = 1;
+const FLAG_IS_LITTLE_ENDIAN: u32 = 2;
+
+/// `desc` chunk: the stream description.
+struct Desc {
+    sample_rate: f64,
+    format_id: [u8; 4],
+    flags: u32,
+    bytes_per_packet: u32,
+    frames_per_packet: u32,
+    channels: u32,
+    bits: u32,
+}
+
+pub fn is_caf(bytes: &[u8]) -> bool {
+    bytes.len() >= 8 && &bytes[0..4] == b"caff"
+}
+
+/// Parse a CAF header from the first bytes of a file. `file_size` resolves
+/// a `data` chunk of unknown length; without it the scanned bytes are used.
+pub fn parse_caf_header(header_bytes: &[u8], file_size: Option<u64>) -> Result<WavHeader, String> {
+    if !is_caf(header_bytes) {
+        return Err("Not a CAF file".into());
+    }
+    let file_size = file_size.unwrap_or(header_bytes.len() as u64);
+
+    let mut desc: Option<Desc> = None;
+    let mut data: Option<(u64, u64)> = None;
+    let mut info_fields: Vec<(String, String)> = Vec::new();
+
+    let mut pos = 8usize;
+    while pos + 12 <= header_bytes.len() {
+        let id: [u8; 4] = header_bytes[pos..pos + 4].try_into().unwrap();
+        let raw_size = i64::from_be_bytes(header_bytes[pos + 4..pos + 12].try_into().unwrap());
+        let body_start = pos + 12;
+        let size = if raw_size < 0 {
+            file_size.saturating_sub(body_start as u64)
+        } else {
+            raw_size as u64
+        };
+        let body_end = (body_start as u64).saturating_add(size).min(header_bytes.len() as u64) as usize;
+        let body = header_bytes.get(body_start..body_end).unwrap_or(&[]);
+        let fits = body.len() as u64 == size;
+
+        match &id {
+            b"desc" => {
+                if body.len() < 32 {
+                    return Err("desc chunk too small or truncated".into());
+                }
+                let u = |i: usize| u32::from_be_bytes(body[i..i + 4].try_into().unwrap());
+                desc = Some(Desc {
+                    sample_rate: f64::from_be_bytes(body[0..8].try_into().unwrap()),
+                    format_id: body[8..12].try_into().unwrap(),
+                    flags: u(12),
+                    bytes_per_packet: u(16),
+                    frames_per_packet: u(20),
+                    channels: u(24),
+                    bits: u(28),
+                });
+            }
+            b"data" => {
+                // Skip the edit count
+                data = Some((body_start as u64 + 4, size.saturating_sub(4)));
+                if !fits && desc.is_some() {
+                    break;
+                }
+            }
+            b"info" if fits && body.len() >= 4 => {
+                let strings: Vec<String> = body[4..]
+                    .split(|&b| b == 0)
+                    .map(|s| String::from_utf8_lossy(s).trim().to_string())
+                    .collect();
+                for pair in strings.chunks_exact(2) {
+                    if !pair[0].is_empty() && !pair[1].is_empty() {
+                        info_fields.push((format!("CAF|{}", pair[0]), pair[1].clone()));
+                    }
+                }
+            }
+            _ => {}
+        }
+        pos = match usize::try_from(body_start as u64 + size) {
+            Ok(next) if next > pos => next,
+            _ => break,
+        };
+    }
+
+    let Desc { sample_rate: rate, format_id, flags, bytes_per_packet, frames_per_packet, channels, bits } =
+        desc.ok_or("No desc chunk found in CAF header")?;
+    let (data_offset, data_size) = data.ok_or("No data chunk found in CAF header")?;
+
+    if &format_id != b"lpcm" {
+        return Err(format!("Unsupported CAF codec: {}", String::from_utf8_lossy(&format_id)));
+    }
+    if channels == 0 || channels > u16::MAX as u32 || frames_per_packet != 1 || !bytes_per_packet.is_multiple_of(channels) {
+        return Err("Invalid CAF: unsupported packet layout".into());
+    }
+    let is_float = flags & FLAG_IS_FLOAT != 0;
+    // Container width; samples narrower than their container are rare in CAF
+    let bits_per_sample = u16::try_from(bytes_per_packet / channels).ok().and_then(|b| b.checked_mul(8)).unwrap_or(0);
+    if bits_per_sample == 0 || bits > bits_per_sample as u32 {
+        return Err("Invalid CAF: bad bit depth".into());
+    }
+    if !is_supported_pcm(bits_per_sample, is_float) {
+        return Err(format!("Unsupported CAF sample format: {bits_per_sample}-bit"));
+    }
+    let sample_rate = rate.round() as u32;
+    if sample_rate == 0 {
+        return Err("Invalid CAF: zero sample rate".into());
+    }
+
+    let bytes_per_frame = bytes_per_packet as u64;
+    let total_frames = data_size / bytes_per_frame;
+
+    Ok(WavHeader {
+        sample_rate,
+        channels: channels as u16,
+        bits_per_sample,
+        is_float,
+        big_endian: flags & FLAG_IS_LITTLE_ENDIAN == 0,
+        unsigned_8bit: false,
+        data_offset,
+        data_size: total_frames * bytes_per_frame,
+        total_frames,
+        guano: bwf::merge_into_guano(None, info_fields),
+        wav_markers: Vec::new(),
+        bwf: Vec::new(),
+        audiomoth: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::loader::load_audio;
+
+    fn build_caf(flags: u32, channels: u32, bits: u32, data: &[u8], unknown_size: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"caff\x00\x01\x00\x00");
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&256_000f64.to_be_bytes());
+        desc.extend_from_slice(b"lpcm");
+        for v in [flags, channels * bits / 8, 1, channels, bits] {
+            desc.extend_from_slice(&v.to_be_bytes());
+        }
+        out.extend_from_slice(b"desc");
+        out.extend_from_slice(&(desc.len() as i64).to_be_bytes());
+        out.extend_from_slice(&desc);
+
+        let info = b"\x00\x00\x00\x01title\x00Harbour wall\x00";
+        out.extend_from_slice(b"info");
+        out.extend_from_slice(&(info.len() as i64).to_be_bytes());
+        out.extend_from_slice(info);
+
+        out.extend_from_slice(b"data");
+        let size = if unknown_size { -1 } else { data.len() as i64 + 4 };
+        out.extend_from_slice(&size.to_be_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(data);
+        out
+    }
+
+    #[test]
+    fn test_caf_big_endian_int() {
+        let data: Vec<u8> = [8192i16, -8192, 0].iter().flat_map(|s| s.to_be_bytes()).collect();
+        let bytes = build_caf(0, 1, 16, &data, false);
+        let h = parse_caf_header(&bytes, None).unwrap();
+        assert_eq!((h.sample_rate, h.channels, h.bits_per_sample), (256_000, 1, 16));
+        assert!(h.big_endian && !h.is_float);
+        assert_eq!(h.total_frames, 3);
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.metadata.format, "CAF");
+        assert!((audio.samples[0] - 0.25).abs() < 1e-6);
+        assert!((audio.samples[1] + 0.25).abs() < 1e-6);
+        let g = audio.metadata.guano.unwrap();
+        assert!(g.fields.iter().any(|(k, v)| k == "CAF|title" && v == "Harbour wall"));
+    }
+
+    #[test]
+    fn test_caf_little_endian_float_unknown_size() {
+        let data: Vec<u8> = [0.5f32, 0.25, -0.5, -0.25].iter().flat_map(|s| s.to_le_bytes()).collect();
+        let bytes = build_caf(FLAG_IS_FLOAT | FLAG_IS_LITTLE_ENDIAN, 2, 32, &data, true);
+        let h = parse_caf_header(&bytes[..100], Some(bytes.len() as u64)).unwrap();
+        assert!(!h.big_endian && h.is_float);
+        assert_eq!(h.total_frames, 2);
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.channels, 2);
+        assert!((audio.samples[0] - 0.375).abs() < 1e-6);
+        assert!((audio.samples[1] + 0.375).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rejects_unsupported_widths() {
+        for (flags, bits) in [(0, 88), (0, 40), (0, 64), (FLAG_IS_FLOAT, 16), (FLAG_IS_FLOAT, 24)] {
+            let bytes = build_caf(flags, 1, bits, &[0; 44], false);
+            assert!(parse_caf_header(&bytes, None).is_err(), "{bits}-bit, flags {flags}");
+            assert!(load_audio(&bytes).is_err(), "{bits}-bit, flags {flags}");
+        }
+    }
+}
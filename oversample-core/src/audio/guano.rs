@@ -57,40 +57,11 @@ pub fn append_guano_chunk(wav_bytes: &mut Vec<u8>, guano_text: &str) {
     wav_bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
 }
 
-/// Search raw WAV bytes for a "guan" RIFF subchunk and parse GUANO metadata.
+/// Search raw WAV bytes (RIFF, RF64 or Wave64) for a "guan" subchunk and
+/// parse GUANO metadata.
 pub fn parse_guano(bytes: &[u8]) -> Option<GuanoMetadata> {
-    // Must be RIFF/WAVE or RF64/WAVE
-    if bytes.len() < 12 || &bytes[8..12] != b"WAVE" {
-        return None;
-    }
-    let magic = &bytes[0..4];
-    if magic != b"RIFF" && magic != b"RF64" {
-        return None;
-    }
-
-    let mut pos = 12;
-    while pos + 8 <= bytes.len() {
-        let chunk_id = &bytes[pos..pos + 4];
-        let chunk_size = u32::from_le_bytes([
-            bytes[pos + 4],
-            bytes[pos + 5],
-            bytes[pos + 6],
-            bytes[pos + 7],
-        ]) as usize;
-        let data_start = pos + 8;
-        let data_end = data_start + chunk_size;
-
-        if chunk_id == b"guan" && data_end <= bytes.len() {
-            let text = std::str::from_utf8(&bytes[data_start..data_end])
-                .ok()?;
-            return Some(parse_guano_text(text));
-        }
-
-        // Chunks are word-aligned (padded to even size)
-        pos = data_start + ((chunk_size + 1) & !1);
-    }
-
-    None
+    use crate::audio::riff;
+    riff::find_chunk(riff::chunks(bytes)?, b"guan").and_then(parse_guano_chunk)
 }
 
 /// Parse GUANO metadata from raw chunk body bytes (without the "guan" chunk header).
@@ -1,5 +1,13 @@
+use crate::audio::aiff;
+use crate::audio::anabat;
+use crate::audio::audiomoth::{self, AudioMothComment};
+use crate::audio::bwf;
+use crate::audio::caf;
+use crate::audio::flac;
 use crate::audio::guano::{self, parse_guano, GuanoMetadata};
-use crate::audio::source::InMemorySource;
+use crate::audio::riff;
+use crate::audio::source::{InMemorySource, SilentSource};
+use crate::audio::wavpack;
 use crate::types::{AudioData, FileMetadata, WavMarker};
 use std::io::Cursor;
 use std::sync::Arc;
@@ -11,18 +19,30 @@ pub struct WavHeader {
     pub channels: u16,
     pub bits_per_sample: u16,
     pub is_float: bool,
+    /// Samples are big-endian (AIFF, most CAF); WAV is always little-endian.
+    pub big_endian: bool,
+    /// 8-bit samples are unsigned with a 128 offset (WAV); AIFF and CAF
+    /// store them signed like every other width.
+    pub unsigned_8bit: bool,
     pub data_offset: u64,       // byte offset of PCM "data" chunk body within file
     pub data_size: u64,         // byte length of PCM data
     pub total_frames: u64,      // data_size / (channels * bytes_per_sample / 8)
     pub guano: Option<GuanoMetadata>,
     /// Cue-point markers from `cue ` + `LIST`/`adtl` chunks, if present.
     pub wav_markers: Vec<WavMarker>,
+    /// BWF `bext` and iXML fields (see [`bwf::bwf_fields`]) from the
+    /// scanned bytes; merge into `guano` with [`bwf::merge_into_guano`].
+    pub bwf: Vec<(String, String)>,
+    /// AudioMoth settings from the `LIST`/`INFO` comment; merge into `guano`
+    /// with [`audiomoth::merge_into_guano`].
+    pub audiomoth: Option<AudioMothComment>,
 }
 
 /// Parse only the WAV header from the given bytes (typically first 8-64KB of file).
 /// Returns enough metadata to open the file for streaming without decoding all samples.
 ///
-/// Supports both standard RIFF/WAVE and RF64/WAVE (used by recorders for files >4 GB).
+/// Supports standard RIFF/WAVE, RF64/WAVE and Sony Wave64 (both used by
+/// recorders for files >4 GB).
 ///
 /// If the GUANO chunk is before the data chunk, it will be included. If GUANO is after
 /// the data chunk (common), the caller must provide tail bytes separately via
@@ -38,14 +58,8 @@ pub fn parse_wav_header_with_file_size(header_bytes: &[u8], file_size: Option<u6
         return Err("File too small for WAV header".into());
     }
 
-    let is_rf64 = &header_bytes[0..4] == b"RF64" && &header_bytes[8..12] == b"WAVE";
-    let is_riff = &header_bytes[0..4] == b"RIFF" && &header_bytes[8..12] == b"WAVE";
+    let chunks = riff::chunks(header_bytes).ok_or("Not a RIFF/WAVE, RF64/WAVE or Wave64 file")?;
 
-    if !is_riff && !is_rf64 {
-        return Err("Not a RIFF/WAVE or RF64/WAVE file".into());
-    }
-
-    let mut pos = 12usize;
     let mut fmt_chunk: Option<(u16, u32, u16, u16)> = None; // (format_tag, sample_rate, channels, bits)
     let mut data_offset: Option<u64> = None;
     let mut data_size: Option<u64> = None;
@@ -53,61 +67,42 @@ pub fn parse_wav_header_with_file_size(header_bytes: &[u8], file_size: Option<u6
     let mut cue_points: Vec<(u32, u64)> = Vec::new(); // (id, sample_position)
     let mut labels: Vec<(u32, String)> = Vec::new();   // (cue_id, text)
     let mut notes: Vec<(u32, String)> = Vec::new();    // (cue_id, text)
+    let mut bwf_chunks: Vec<([u8; 4], &[u8])> = Vec::new();
+    let mut audiomoth: Option<AudioMothComment> = None;
 
-    // RF64: 64-bit sizes from the ds64 chunk (must appear before fmt/data)
-    let mut ds64_data_size: Option<u64> = None;
-
-    while pos + 8 <= header_bytes.len() {
-        let chunk_id = &header_bytes[pos..pos + 4];
-        let chunk_size = u32::from_le_bytes(
-            header_bytes[pos + 4..pos + 8].try_into().map_err(|_| "Invalid chunk size")?,
-        ) as u64;
-        let body_start = pos + 8;
+    // RF64 `data` sizes come from the ds64 chunk (resolved by the iterator)
+    for chunk in chunks {
+        let chunk_size = chunk.size;
+        let body_start = chunk.body_start;
         // Use u64 to avoid usize overflow on 32-bit WASM for large chunks
-        let body_end_u64 = body_start as u64 + chunk_size;
+        let body_end_u64 = chunk.body_end();
         let chunk_fits = body_end_u64 <= header_bytes.len() as u64;
 
-        match chunk_id {
-            b"ds64" => {
-                // RF64 Data Size 64 chunk: provides 64-bit sizes
-                // Layout: riffSize(8) + dataSize(8) + sampleCount(8) + ...
-                if body_start + 24 <= header_bytes.len() {
-                    let ds = &header_bytes[body_start..];
-                    // bytes 0..8: RIFF size (not needed)
-                    // bytes 8..16: data chunk size (64-bit)
-                    ds64_data_size = Some(u64::from_le_bytes([
-                        ds[8], ds[9], ds[10], ds[11], ds[12], ds[13], ds[14], ds[15],
-                    ]));
-                }
-            }
+        match &chunk.id {
             b"fmt " => {
                 if chunk_size < 16 || !chunk_fits {
                     return Err("fmt chunk too small or truncated".into());
                 }
                 let body_end = body_end_u64 as usize;
                 let fmt = &header_bytes[body_start..body_end];
-                let format_tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                let mut format_tag = u16::from_le_bytes([fmt[0], fmt[1]]);
                 let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
                 let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
                 let bits_per_sample = u16::from_le_bytes([fmt[14], fmt[15]]);
+                // WAVE_FORMAT_EXTENSIBLE: the real tag leads the SubFormat GUID
+                if format_tag == 0xFFFE && fmt.len() >= 26 {
+                    format_tag = u16::from_le_bytes([fmt[24], fmt[25]]);
+                }
                 fmt_chunk = Some((format_tag, sample_rate, channels, bits_per_sample));
             }
             b"data" => {
                 data_offset = Some(body_start as u64);
-                // For RF64, the data chunk size field is 0xFFFFFFFF; use ds64 value
-                if is_rf64 && chunk_size == 0xFFFFFFFF {
-                    data_size = ds64_data_size;
-                } else {
-                    data_size = Some(chunk_size);
-                }
+                data_size = Some(chunk_size);
                 // Data chunk extends past our header bytes — stop scanning
                 if guano.is_some() || !chunk_fits {
                     break;
                 }
-                // Skip past the data chunk to look for GUANO after it
-                let aligned = ((chunk_size + 1) & !1) as usize;
-                pos = body_start + aligned;
-                continue;
+                // Otherwise keep going to look for GUANO after it
             }
             b"guan" => {
                 if chunk_fits {
@@ -132,6 +127,9 @@ pub fn parse_wav_header_with_file_size(header_bytes: &[u8], file_size: Option<u6
                     }
                 }
             }
+            b"bext" | b"iXML" if chunk_fits => {
+                bwf_chunks.push((chunk.id, &header_bytes[body_start..body_end_u64 as usize]));
+            }
             b"LIST" => {
                 if chunk_fits && chunk_size >= 4 {
                     let body_end = body_end_u64 as usize;
@@ -139,18 +137,13 @@ pub fn parse_wav_header_with_file_size(header_bytes: &[u8], file_size: Option<u6
                     let list_type = &list_data[0..4];
                     if list_type == b"adtl" {
                         parse_adtl_subchunks(&list_data[4..], &mut labels, &mut notes);
+                    } else if list_type == b"INFO" {
+                        audiomoth = audiomoth::info_comment(list_data).as_deref().and_then(audiomoth::parse_comment);
                     }
                 }
             }
             _ => {}
         }
-
-        // Advance to next chunk (word-aligned)
-        let aligned = ((chunk_size + 1) & !1) as usize;
-        match body_start.checked_add(aligned) {
-            Some(next) if next > pos => pos = next,
-            _ => break, // overflow or no progress — stop
-        }
     }
 
     let (format_tag, sample_rate, channels, bits_per_sample) =
@@ -203,16 +196,20 @@ pub fn parse_wav_header_with_file_size(header_bytes: &[u8], file_size: Option<u6
         channels,
         bits_per_sample,
         is_float,
+        big_endian: false,
+        unsigned_8bit: true,
         data_offset,
         data_size,
         total_frames,
         guano,
         wav_markers,
+        bwf: bwf::bwf_fields(bwf_chunks, Some(sample_rate)),
+        audiomoth,
     })
 }
 
 /// Parse `labl` and `note` sub-chunks from a LIST/adtl body.
-fn parse_adtl_subchunks(data: &[u8], labels: &mut Vec<(u32, String)>, notes: &mut Vec<(u32, String)>) {
+pub(crate) fn parse_adtl_subchunks(data: &[u8], labels: &mut Vec<(u32, String)>, notes: &mut Vec<(u32, String)>) {
     let mut pos = 0;
     while pos + 8 <= data.len() {
         let sub_id = &data[pos..pos + 4];
@@ -398,13 +395,15 @@ pub fn parse_mp3_header(header_bytes: &[u8], file_size: u64) -> Result<Mp3Header
     })
 }
 
-/// Load audio from raw file bytes. Detects WAV, W4V, FLAC, OGG, or MP3 by header magic bytes.
+/// Load audio from raw file bytes. Detects WAV, W4V, FLAC, OGG, MP3, or Anabat ZC by header magic bytes.
 /// Extract WAV markers from raw file bytes (for non-streaming loads).
-/// Returns an empty Vec for non-WAV files or files without cue markers.
+/// FLAC files carry them in APPLICATION `riff` blocks.
+/// Returns an empty Vec for other formats or files without cue markers.
 pub fn parse_wav_markers(bytes: &[u8]) -> Vec<WavMarker> {
     if bytes.len() < 12 { return Vec::new(); }
     match &bytes[0..4] {
-        b"RIFF" | b"RF64" => {}
+        _ if riff::is_wave(bytes) => {}
+        b"fLaC" => return flac::parse_flac_metadata(bytes).1,
         _ => return Vec::new(),
     }
     parse_wav_header_with_file_size(bytes, None)
@@ -412,6 +411,79 @@ pub fn parse_wav_markers(bytes: &[u8]) -> Vec<WavMarker> {
         .unwrap_or_default()
 }
 
+/// Build WAV `cue ` and `LIST`/`adtl` chunks for the given markers.
+/// Returns the raw bytes to insert into the RIFF stream (before the GUANO chunk).
+pub fn encode_wav_cue_chunks(markers: &[WavMarker]) -> Vec<u8> {
+    if markers.is_empty() {
+        return Vec::new();
+    }
+
+    let mut buf = Vec::new();
+
+    // ── cue chunk ──
+    // cue chunk body: u32 num_cue_points, then per point:
+    //   u32 id, u32 position, 4-byte data_id ("data"), u32 chunk_start, u32 block_start, u32 sample_offset
+    let num_points = markers.len() as u32;
+    let cue_body_size = 4 + num_points * 24;
+    buf.extend_from_slice(b"cue ");
+    buf.extend_from_slice(&cue_body_size.to_le_bytes());
+    buf.extend_from_slice(&num_points.to_le_bytes());
+    for m in markers {
+        buf.extend_from_slice(&m.id.to_le_bytes());          // id
+        buf.extend_from_slice(&(m.position as u32).to_le_bytes()); // position
+        buf.extend_from_slice(b"data");                       // fcc_chunk
+        buf.extend_from_slice(&0u32.to_le_bytes());           // chunk_start
+        buf.extend_from_slice(&0u32.to_le_bytes());           // block_start
+        buf.extend_from_slice(&(m.position as u32).to_le_bytes()); // sample_offset
+    }
+
+    // ── LIST/adtl chunk with labl and note sub-chunks ──
+    let mut adtl_body = Vec::new();
+    adtl_body.extend_from_slice(b"adtl");
+    for m in markers {
+        if let Some(ref label) = m.label {
+            let text_bytes = label.as_bytes();
+            // labl sub-chunk: u32 cue_id + null-terminated string
+            let sub_size = 4 + text_bytes.len() as u32 + 1; // +1 for null terminator
+            adtl_body.extend_from_slice(b"labl");
+            adtl_body.extend_from_slice(&sub_size.to_le_bytes());
+            adtl_body.extend_from_slice(&m.id.to_le_bytes());
+            adtl_body.extend_from_slice(text_bytes);
+            adtl_body.push(0); // null terminator
+            // RIFF word-alignment padding
+            if !sub_size.is_multiple_of(2) {
+                adtl_body.push(0);
+            }
+        }
+        if let Some(ref note) = m.note {
+            let text_bytes = note.as_bytes();
+            let sub_size = 4 + text_bytes.len() as u32 + 1;
+            adtl_body.extend_from_slice(b"note");
+            adtl_body.extend_from_slice(&sub_size.to_le_bytes());
+            adtl_body.extend_from_slice(&m.id.to_le_bytes());
+            adtl_body.extend_from_slice(text_bytes);
+            adtl_body.push(0);
+            if !sub_size.is_multiple_of(2) {
+                adtl_body.push(0);
+            }
+        }
+    }
+
+    // Only write the LIST chunk if there are labl/note sub-chunks
+    if adtl_body.len() > 4 {
+        let list_size = adtl_body.len() as u32;
+        buf.extend_from_slice(b"LIST");
+        buf.extend_from_slice(&list_size.to_le_bytes());
+        buf.extend_from_slice(&adtl_body);
+        // Word-align the LIST chunk
+        if !list_size.is_multiple_of(2) {
+            buf.push(0);
+        }
+    }
+
+    buf
+}
+
 pub fn load_audio(bytes: &[u8]) -> Result<AudioData, String> {
     if bytes.len() < 4 {
         return Err("File too small".into());
@@ -420,11 +492,16 @@ pub fn load_audio(bytes: &[u8]) -> Result<AudioData, String> {
     match &bytes[0..4] {
         b"RIFF" | b"RF64" if is_w4v(bytes) => load_w4v(bytes),
         b"RIFF" | b"RF64" => load_wav(bytes),
+        _ if riff::is_wave64(bytes) => load_wav(bytes),
+        b"FORM" if aiff::is_aiff(bytes) => load_pcm(bytes, aiff::parse_aiff_header(bytes)?, "AIFF"),
+        b"caff" => load_pcm(bytes, caf::parse_caf_header(bytes, Some(bytes.len() as u64))?, "CAF"),
+        b"wvpk" => load_wavpack(bytes),
         b"fLaC" => load_flac(bytes),
         b"OggS" => load_ogg(bytes),
         _ if is_m4a(bytes) => load_m4a(bytes),
         _ if is_mp3(bytes) => load_mp3(bytes),
-        _ => Err("Unknown file format (expected WAV, W4V, FLAC, OGG, MP3, or M4A)".into()),
+        _ if anabat::is_anabat(bytes) => load_anabat(bytes),
+        _ => Err("Unknown file format (expected WAV, RF64, W64, W4V, WavPack, AIFF, CAF, FLAC, OGG, MP3, M4A, or Anabat ZC)".into()),
     }
 }
 
@@ -738,47 +815,19 @@ pub fn parse_ogg_header(header_bytes: &[u8], file_size: u64) -> Result<OggHeader
 
 /// Rebuild a minimal RIFF/WAVE with only the `fmt` and `data` chunks.
 /// Hound 3.5 doesn't handle RIFF word-alignment padding on odd-length chunks
-/// (e.g. a 651-byte `bext` chunk), so we strip extraneous chunks and produce
-/// a clean WAV that hound can always parse.
-fn normalize_riff(bytes: &[u8]) -> Option<Vec<u8>> {
-    if bytes.len() < 12 || &bytes[8..12] != b"WAVE" {
-        return None;
-    }
-    let magic = &bytes[0..4];
-    if magic != b"RIFF" && magic != b"RF64" {
-        return None;
-    }
-
-    let mut pos = 12usize;
-    let mut fmt_data: Option<&[u8]> = None;
-    let mut audio_data: Option<&[u8]> = None;
-
-    while pos + 8 <= bytes.len() {
-        let chunk_id = &bytes[pos..pos + 4];
-        let chunk_size = u32::from_le_bytes(
-            bytes[pos + 4..pos + 8].try_into().ok()?,
-        ) as usize;
-        let data_start = pos + 8;
-        let data_end = data_start + chunk_size;
-        if data_end > bytes.len() {
-            break;
-        }
-
-        match chunk_id {
-            b"fmt " => fmt_data = Some(&bytes[data_start..data_end]),
-            b"data" => {
-                audio_data = Some(&bytes[data_start..data_end]);
-                break; // data is always last useful chunk
-            }
-            _ => {}
-        }
-
-        // Advance with RIFF word-alignment (same as guano.rs)
-        pos = data_start + ((chunk_size + 1) & !1);
-    }
-
-    let fmt = fmt_data?;
-    let data = audio_data?;
+/// (e.g. a 651-byte `bext` chunk), nor RF64 or Wave64 containers, so we strip
+/// extraneous chunks and produce a clean WAV that hound can always parse.
+///
+/// Truncated data is cut to whole frames. The 32-bit RIFF sizes cap the
+/// output at 4 GB of PCM; larger files go through the streaming path.
+pub fn normalize_riff(bytes: &[u8]) -> Option<Vec<u8>> {
+    let fmt = riff::find_chunk(riff::chunks(bytes)?, b"fmt ")?;
+    let header = parse_wav_header(bytes).ok()?;
+    let bytes_per_frame = header.channels as u64 * (header.bits_per_sample as u64 / 8);
+    let available = (bytes.len() as u64).saturating_sub(header.data_offset).min(header.data_size);
+    let data_len = (available / bytes_per_frame * bytes_per_frame).min(u32::MAX as u64 - 64);
+    let data_start = header.data_offset as usize;
+    let data = &bytes[data_start..data_start + data_len as usize];
 
     // WAVE + fmt chunk header + fmt body + data chunk header + data body
     let riff_body_len = 4 + 8 + fmt.len() + 8 + data.len();
@@ -818,15 +867,51 @@ fn load_w4v(bytes: &[u8]) -> Result<AudioData, String> {
             guano: header.guano,
             data_offset: Some(header.data_offset),
             data_size: Some(header.data_size),
+            time_expansion: 1.0,
+        },
+    })
+}
+
+/// Anabat zero-crossing files have no waveform. They load as silence at a
+/// virtual sample rate high enough for their dots, so the spectrogram and
+/// ZC chart share a timebase; the dots themselves come from
+/// `anabat::parse_anabat`. The silence is a `SilentSource`, so `samples`
+/// stays empty however long the file.
+fn load_anabat(bytes: &[u8]) -> Result<AudioData, String> {
+    let zc = anabat::parse_anabat(bytes)?;
+    let sample_rate = anabat_sample_rate(&zc);
+    let len = ((zc.duration_secs * sample_rate as f64).ceil() as u64).max(1);
+
+    Ok(AudioData {
+        samples: Arc::new(Vec::new()),
+        source: Arc::new(SilentSource { total_samples: len, sample_rate }),
+        sample_rate,
+        channels: 1,
+        duration_secs: len as f64 / sample_rate as f64,
+        metadata: FileMetadata {
+            file_size: bytes.len(),
+            format: "Anabat ZC",
+            bits_per_sample: 16,
+            is_float: false,
+            guano: Some(zc.guano()),
+            data_offset: None,
+            data_size: None,
+            time_expansion: 1.0,
         },
     })
 }
 
+/// Virtual sample rate for a ZC file: 256 kHz unless dots reach past 125 kHz.
+pub fn anabat_sample_rate(zc: &anabat::AnabatFile) -> u32 {
+    if zc.max_freq_hz() > 125_000.0 { 512_000 } else { 256_000 }
+}
+
 fn load_wav(bytes: &[u8]) -> Result<AudioData, String> {
     // Parse original header for data_offset/data_size before normalization
-    let (orig_data_offset, orig_data_size) = parse_wav_header_with_file_size(bytes, Some(bytes.len() as u64))
-        .map(|h| (Some(h.data_offset), Some(h.data_size)))
-        .unwrap_or((None, None));
+    let (orig_data_offset, orig_data_size, bwf_fields, audiomoth_comment) =
+        parse_wav_header_with_file_size(bytes, Some(bytes.len() as u64))
+            .map(|h| (Some(h.data_offset), Some(h.data_size), h.bwf, h.audiomoth))
+            .unwrap_or_default();
 
     let normalized;
     let wav_bytes = match normalize_riff(bytes) {
@@ -858,7 +943,8 @@ fn load_wav(bytes: &[u8]) -> Result<AudioData, String> {
         }
     };
 
-    let guano = parse_guano(bytes);
+    let guano = bwf::merge_into_guano(parse_guano(bytes), bwf_fields);
+    let guano = audiomoth::merge_into_guano(guano, audiomoth_comment.as_ref(), Some(sample_rate));
 
     let (samples, source) = build_source(all_samples, channels, sample_rate);
     let duration_secs = samples.len() as f64 / sample_rate as f64;
@@ -877,6 +963,103 @@ fn load_wav(bytes: &[u8]) -> Result<AudioData, String> {
             guano,
             data_offset: orig_data_offset,
             data_size: orig_data_size,
+            time_expansion: 1.0,
+        },
+    })
+}
+
+/// Whether `decode_pcm` handles a sample format: 8/16/24/32-bit integer or
+/// 32/64-bit float. Header parsers reject anything else.
+pub(crate) fn is_supported_pcm(bits_per_sample: u16, is_float: bool) -> bool {
+    if is_float {
+        matches!(bits_per_sample, 32 | 64)
+    } else {
+        matches!(bits_per_sample, 8 | 16 | 24 | 32)
+    }
+}
+
+/// Decode raw PCM samples to f32. 8-bit samples are signed (AIFF/CAF);
+/// 64-bit samples must be float.
+pub fn decode_pcm(bytes: &[u8], bits_per_sample: u16, is_float: bool, big_endian: bool) -> Vec<f32> {
+    let width = (bits_per_sample as usize).div_ceil(8);
+    if width == 0 || width > 8 {
+        return Vec::new();
+    }
+    bytes
+        .chunks_exact(width)
+        .map(|b| {
+            let mut le = [0u8; 8];
+            le[..width].copy_from_slice(b);
+            if big_endian {
+                le[..width].reverse();
+            }
+            match (is_float, width) {
+                (true, 4) => f32::from_le_bytes(le[..4].try_into().unwrap()),
+                (true, 8) => f64::from_le_bytes(le) as f32,
+                (false, 1..=4) => {
+                    // Sign-extend from the top byte of the sample
+                    let v = i32::from_le_bytes(le[..4].try_into().unwrap()) << (32 - 8 * width);
+                    v as f32 / 2_147_483_648.0
+                }
+                _ => 0.0,
+            }
+        })
+        .collect()
+}
+
+/// AIFF and CAF: uncompressed PCM described by a [`WavHeader`].
+fn load_pcm(bytes: &[u8], header: WavHeader, format: &'static str) -> Result<AudioData, String> {
+    let start = header.data_offset.min(bytes.len() as u64) as usize;
+    let end = (header.data_offset + header.data_size).min(bytes.len() as u64) as usize;
+    let all_samples = decode_pcm(&bytes[start..end], header.bits_per_sample, header.is_float, header.big_endian);
+    let channels = header.channels as u32;
+    let sample_rate = header.sample_rate;
+
+    let (samples, source) = build_source(all_samples, channels, sample_rate);
+    let duration_secs = samples.len() as f64 / sample_rate as f64;
+
+    Ok(AudioData {
+        samples,
+        source,
+        sample_rate,
+        channels,
+        duration_secs,
+        metadata: FileMetadata {
+            file_size: bytes.len(),
+            format,
+            bits_per_sample: header.bits_per_sample,
+            is_float: header.is_float,
+            guano: header.guano,
+            data_offset: Some(header.data_offset),
+            data_size: Some(header.data_size),
+            time_expansion: 1.0,
+        },
+    })
+}
+
+fn load_wavpack(bytes: &[u8]) -> Result<AudioData, String> {
+    let (header, all_samples) = wavpack::decode_wavpack(bytes)?;
+    let channels = header.channels as u32;
+    let sample_rate = header.sample_rate;
+
+    let (samples, source) = build_source(all_samples, channels, sample_rate);
+    let duration_secs = samples.len() as f64 / sample_rate as f64;
+
+    Ok(AudioData {
+        samples,
+        source,
+        sample_rate,
+        channels,
+        duration_secs,
+        metadata: FileMetadata {
+            file_size: bytes.len(),
+            format: "WavPack",
+            bits_per_sample: header.bits_per_sample,
+            is_float: header.is_float,
+            guano: header.guano,
+            data_offset: None,
+            data_size: None,
+            time_expansion: 1.0,
         },
     })
 }
@@ -915,9 +1098,10 @@ fn load_flac(bytes: &[u8]) -> Result<AudioData, String> {
             format: "FLAC",
             bits_per_sample: bits as u16,
             is_float: false,
-            guano: None,
+            guano: flac::parse_flac_metadata(bytes).0,
             data_offset: flac_data_offset,
             data_size: flac_data_size,
+            time_expansion: 1.0,
         },
     })
 }
@@ -1016,6 +1200,7 @@ fn load_ogg(bytes: &[u8]) -> Result<AudioData, String> {
             guano: None,
             data_offset: ogg_page_region(bytes).0,
             data_size: ogg_page_region(bytes).1,
+            time_expansion: 1.0,
         },
     })
 }
@@ -1124,6 +1309,7 @@ fn load_mp3(bytes: &[u8]) -> Result<AudioData, String> {
                     .saturating_sub(mp3_data_offset)
                     .saturating_sub(mp3_trailer_size(bytes)),
             ),
+            time_expansion: 1.0,
         },
     })
 }
@@ -1701,6 +1887,7 @@ fn load_m4a(bytes: &[u8]) -> Result<AudioData, String> {
             guano: if tags.fields.is_empty() { None } else { Some(tags) },
             data_offset: None,
             data_size: None,
+            time_expansion: 1.0,
         },
     })
 }
@@ -1,3 +1,12 @@
 pub mod source;
 pub mod guano;
+pub mod anabat;
+pub mod flac;
+pub mod riff;
+pub mod bwf;
+pub mod audiomoth;
+pub mod aiff;
+pub mod caf;
+pub mod wavpack;
+pub mod time_expansion;
 pub mod loader;
@@ -0,0 +1,274 @@
+//! Chunk walking for RIFF/WAVE, RF64/WAVE (EBU Tech 3306) and Sony Wave64.
+//!
+//! RF64 keeps the RIFF layout but stores 0xFFFFFFFF in the 32-bit size
+//! fields and the real 64-bit sizes in a `ds64` chunk ahead of `fmt `.
+//! Wave64 replaces FourCCs with 16-byte GUIDs and sizes with u64 values
+//! that include the 24-byte chunk header; chunks are 8-byte aligned.
+
+/// Wave64 `riff` container GUID.
+const W64_RIFF: [u8; 16] = [
+    b'r', b'i', b'f', b'f', 0x2E, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00, 0x00,
+];
+/// Wave64 `list` chunk GUID.
+const W64_LIST: [u8; 16] = [
+    b'l', b'i', b's', b't', 0x2F, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00, 0x00,
+];
+/// Suffix shared by `wave`, `fmt `, `data` and every other Wave64 GUID
+/// that mirrors a RIFF FourCC (the FourCC is the first four bytes).
+const W64_SUFFIX: [u8; 12] = [0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A];
+
+/// Size of a `ds64` body without a chunk-size table. Writers reserve this
+/// much as a `JUNK` chunk so the header can be turned into RF64 in place.
+pub const DS64_BODY_LEN: usize = 28;
+
+/// Sony Wave64: `riff` GUID, u64 size, `wave` GUID.
+pub fn is_wave64(bytes: &[u8]) -> bool {
+    bytes.len() >= 40
+        && bytes[0..16] == W64_RIFF
+        && &bytes[24..28] == b"wave"
+        && bytes[28..40] == W64_SUFFIX
+}
+
+/// RIFF/WAVE, RF64/WAVE or Wave64.
+pub fn is_wave(bytes: &[u8]) -> bool {
+    is_wave64(bytes) || (bytes.len() >= 12 && &bytes[8..12] == b"WAVE" && matches!(&bytes[0..4], b"RIFF" | b"RF64"))
+}
+
+/// One chunk header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Chunk {
+    /// FourCC; Wave64 GUIDs are mapped back to the FourCC they mirror.
+    pub id: [u8; 4],
+    /// Offset of the chunk body.
+    pub body_start: usize,
+    /// Body length. For RF64 `data` this is the `ds64` value; the body
+    /// may extend past the bytes that were scanned.
+    pub size: u64,
+}
+
+impl Chunk {
+    pub fn body_end(&self) -> u64 {
+        self.body_start as u64 + self.size
+    }
+}
+
+/// Iterator over the chunks of a WAVE stream (or of a slice that starts on
+/// a chunk boundary, such as the bytes after a `data` chunk).
+pub struct Chunks<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    wave64: bool,
+    ds64_data_size: Option<u64>,
+}
+
+/// Chunks of a RIFF, RF64 or Wave64 file, or `None` for anything else.
+pub fn chunks(bytes: &[u8]) -> Option<Chunks<'_>> {
+    if !is_wave(bytes) {
+        return None;
+    }
+    let wave64 = is_wave64(bytes);
+    Some(chunks_from(bytes, if wave64 { 40 } else { 12 }, wave64))
+}
+
+/// Chunks starting at `pos`. For Wave64, `pos` must be 8-byte aligned
+/// relative to the start of the file.
+pub fn chunks_from(bytes: &[u8], pos: usize, wave64: bool) -> Chunks<'_> {
+    Chunks { bytes, pos, wave64, ds64_data_size: None }
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        let b = self.bytes;
+        let pos = self.pos;
+        let (id, body_start, mut size) = if self.wave64 {
+            let header = b.get(pos..pos + 24)?;
+            let id = if header[0..16] == W64_LIST {
+                *b"LIST"
+            } else if header[4..16] == W64_SUFFIX {
+                header[0..4].try_into().ok()?
+            } else {
+                *b"    "
+            };
+            let total = u64::from_le_bytes(header[16..24].try_into().ok()?);
+            (id, pos + 24, total.checked_sub(24)?)
+        } else {
+            let header = b.get(pos..pos + 8)?;
+            let id: [u8; 4] = header[0..4].try_into().ok()?;
+            (id, pos + 8, u32::from_le_bytes(header[4..8].try_into().ok()?) as u64)
+        };
+
+        if &id == b"ds64" && size >= 16 {
+            self.ds64_data_size = b
+                .get(body_start + 8..body_start + 16)
+                .map(|d| u64::from_le_bytes(d.try_into().unwrap()));
+        }
+        if &id == b"data" && size == 0xFFFF_FFFF {
+            size = self.ds64_data_size.unwrap_or(size);
+        }
+
+        let align = if self.wave64 { 7 } else { 1 };
+        let next = (body_start as u64)
+            .checked_add(size)
+            .and_then(|end| end.checked_add(align))
+            .map(|end| end & !align)
+            .and_then(|end| usize::try_from(end).ok());
+        // Stop after this chunk on overflow or no progress
+        self.pos = match next {
+            Some(n) if n > pos => n,
+            _ => usize::MAX,
+        };
+        Some(Chunk { id, body_start, size })
+    }
+}
+
+impl<'a> Chunks<'a> {
+    /// Chunk ids paired with their bodies, skipping chunks that run past
+    /// the scanned bytes.
+    pub fn with_bodies(self) -> impl Iterator<Item = ([u8; 4], &'a [u8])> {
+        let bytes = self.bytes;
+        self.filter(move |c| c.body_end() <= bytes.len() as u64)
+            .map(move |c| (c.id, &bytes[c.body_start..c.body_end() as usize]))
+    }
+}
+
+/// Body of the first chunk with `id`, if it lies entirely within `bytes`.
+pub fn find_chunk<'a>(chunks: Chunks<'a>, id: &[u8; 4]) -> Option<&'a [u8]> {
+    chunks.with_bodies().find(|(cid, _)| cid == id).map(|(_, body)| body)
+}
+
+/// `ds64` body: 64-bit RIFF size, data size and sample (frame) count, with
+/// an empty chunk-size table.
+pub fn ds64_body(riff_size: u64, data_size: u64, sample_count: u64) -> [u8; DS64_BODY_LEN] {
+    let mut body = [0u8; DS64_BODY_LEN];
+    body[0..8].copy_from_slice(&riff_size.to_le_bytes());
+    body[8..16].copy_from_slice(&data_size.to_le_bytes());
+    body[16..24].copy_from_slice(&sample_count.to_le_bytes());
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::loader::{load_audio, parse_wav_header, parse_wav_header_with_file_size};
+
+    fn fmt_body(channels: u16, rate: u32, bits: u16) -> Vec<u8> {
+        let align = channels * bits / 8;
+        let mut f = Vec::new();
+        f.extend_from_slice(&1u16.to_le_bytes());
+        f.extend_from_slice(&channels.to_le_bytes());
+        f.extend_from_slice(&rate.to_le_bytes());
+        f.extend_from_slice(&(rate * align as u32).to_le_bytes());
+        f.extend_from_slice(&align.to_le_bytes());
+        f.extend_from_slice(&bits.to_le_bytes());
+        f
+    }
+
+    fn pcm(n: usize) -> Vec<u8> {
+        (0..n).flat_map(|i| ((i % 300) as i16 * 100).to_le_bytes()).collect()
+    }
+
+    fn w64_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], body: &[u8]) {
+        out.extend_from_slice(fourcc);
+        out.extend_from_slice(&W64_SUFFIX);
+        out.extend_from_slice(&(body.len() as u64 + 24).to_le_bytes());
+        out.extend_from_slice(body);
+        while !out.len().is_multiple_of(8) {
+            out.push(0);
+        }
+    }
+
+    fn build_w64(data: &[u8], guano: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&W64_RIFF);
+        out.extend_from_slice(&0u64.to_le_bytes());
+        out.extend_from_slice(b"wave");
+        out.extend_from_slice(&W64_SUFFIX);
+        w64_chunk(&mut out, b"fmt ", &fmt_body(1, 384_000, 16));
+        w64_chunk(&mut out, b"data", data);
+        w64_chunk(&mut out, b"guan", guano.as_bytes());
+        let len = out.len() as u64;
+        out[16..24].copy_from_slice(&len.to_le_bytes());
+        out
+    }
+
+    fn build_rf64(data: &[u8], guano: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RF64");
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+        out.extend_from_slice(b"ds64");
+        out.extend_from_slice(&(DS64_BODY_LEN as u32).to_le_bytes());
+        let ds64_pos = out.len();
+        out.extend_from_slice(&[0; DS64_BODY_LEN]);
+        let fmt = fmt_body(1, 500_000, 16);
+        out.extend_from_slice(b"fmt ");
+        out.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+        out.extend_from_slice(&fmt);
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(data);
+        out.extend_from_slice(b"guan");
+        out.extend_from_slice(&(guano.len() as u32).to_le_bytes());
+        out.extend_from_slice(guano.as_bytes());
+        let body = ds64_body(out.len() as u64 - 8, data.len() as u64, data.len() as u64 / 2);
+        out[ds64_pos..ds64_pos + DS64_BODY_LEN].copy_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn test_wave64_header_and_load() {
+        let bytes = build_w64(&pcm(1001), "GUANO|Version: 1.0\nNote: w64\n");
+        assert!(is_wave64(&bytes) && is_wave(&bytes));
+        let ids: Vec<[u8; 4]> = chunks(&bytes).unwrap().map(|c| c.id).collect();
+        assert_eq!(ids, [*b"fmt ", *b"data", *b"guan"]);
+
+        let h = parse_wav_header(&bytes).unwrap();
+        assert_eq!(h.sample_rate, 384_000);
+        assert_eq!(h.data_offset, 40 + 24 + 16 + 24);
+        assert_eq!(h.total_frames, 1001);
+        assert!(h.guano.is_some());
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.samples.len(), 1001);
+        assert!((audio.samples[3] - 300.0 / 32768.0).abs() < 1e-6);
+        let g = audio.metadata.guano.unwrap();
+        assert!(g.fields.iter().any(|(k, v)| k == "Note" && v == "w64"));
+    }
+
+    #[test]
+    fn test_rf64_ds64_sizes() {
+        let bytes = build_rf64(&pcm(777), "GUANO|Version: 1.0\n");
+        let h = parse_wav_header_with_file_size(&bytes, Some(bytes.len() as u64)).unwrap();
+        assert_eq!(h.data_size, 1554);
+        assert_eq!(h.total_frames, 777);
+        assert!(h.guano.is_some());
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.samples.len(), 777);
+        assert!(audio.metadata.guano.is_some());
+    }
+
+    #[test]
+    fn test_junk_reserved_header() {
+        // RIFF with a JUNK placeholder where a ds64 would go
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF\0\0\0\0WAVEJUNK");
+        bytes.extend_from_slice(&(DS64_BODY_LEN as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; DS64_BODY_LEN]);
+        let fmt = fmt_body(2, 48_000, 16);
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&fmt);
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&400u32.to_le_bytes());
+        bytes.extend_from_slice(&pcm(200));
+        let len = bytes.len() as u32 - 8;
+        bytes[4..8].copy_from_slice(&len.to_le_bytes());
+        let h = parse_wav_header(&bytes).unwrap();
+        assert_eq!(h.data_offset, 80);
+        assert_eq!(h.total_frames, 100);
+        assert_eq!(load_audio(&bytes).unwrap().channels, 2);
+    }
+}
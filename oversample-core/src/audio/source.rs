@@ -200,6 +200,47 @@ pub trait AudioSource: Send + Sync {
     fn as_any(&self) -> &dyn std::any::Any;
 }
 
+/// Silence of a fixed length, generated on read. Used for formats with no
+/// waveform (Anabat zero-crossing files) so long recordings don't allocate
+/// a buffer of zeros.
+#[derive(Debug)]
+pub struct SilentSource {
+    pub total_samples: u64,
+    pub sample_rate: u32,
+}
+
+impl AudioSource for SilentSource {
+    fn total_samples(&self) -> u64 {
+        self.total_samples
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channel_count(&self) -> u32 {
+        1
+    }
+
+    fn read_samples(&self, _channel: ChannelView, start: u64, buf: &mut [f32]) -> usize {
+        let n = buf.len().min(self.total_samples.saturating_sub(start) as usize);
+        buf[..n].fill(0.0);
+        n
+    }
+
+    fn is_fully_loaded(&self) -> bool {
+        true
+    }
+
+    fn as_contiguous(&self) -> Option<&[f32]> {
+        None
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
 /// In-memory audio source wrapping `Arc<Vec<f32>>`.
 ///
 /// This is the zero-cost migration path: the existing mono-mixed sample
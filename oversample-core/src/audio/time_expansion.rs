@@ -0,0 +1,271 @@
+//! Time-expanded recordings.
+//!
+//! Older detectors (Pettersson D240x, Batbox Griffin, ...) capture a short
+//! ultrasonic buffer and replay it 10× slower into a 44.1 kHz recorder, so a
+//! 40 kHz call is stored at 4 kHz and a 1 s pass lasts 10 s. Files are read
+//! as-is and then wrapped in a [`TimeExpandedSource`] that reports the
+//! real-world sample rate (file rate × factor); every time and frequency
+//! derived from `sample_rate` is then in real units without further changes.
+//!
+//! The factor comes from the GUANO `TE` field when present, otherwise from
+//! a `TE10`/`10xTE`-style filename token or a known time-expansion device in
+//! `Make`/`Model`.
+
+use std::sync::Arc;
+
+use crate::audio::guano::GuanoMetadata;
+use crate::audio::source::{AudioSource, ChannelView};
+use crate::types::AudioData;
+
+/// Filename and device hints are only trusted for audio-band sample rates;
+/// a 384 kHz file named `te10` is not time-expanded.
+const MAX_EXPANDED_FILE_RATE: u32 = 96_000;
+
+/// Range of plausible factors. Detectors use 10, 20 or 32.
+const MIN_FACTOR: f64 = 2.0;
+const MAX_FACTOR: f64 = 100.0;
+
+/// Devices that only record time-expanded audio, matched case-insensitively
+/// against GUANO `Make Model` and the filename, and their default factor.
+const TE_DEVICES: &[(&str, f64)] = &[
+    ("d240", 10.0),
+    ("d230", 10.0),
+    ("batbox griffin", 10.0),
+];
+
+/// Wraps a source recorded at `inner.sample_rate()` and reports the real-world
+/// rate. Samples are passed through unchanged.
+pub struct TimeExpandedSource {
+    inner: Arc<dyn AudioSource>,
+    factor: f64,
+    sample_rate: u32,
+}
+
+impl TimeExpandedSource {
+    pub fn new(inner: Arc<dyn AudioSource>, factor: f64) -> Self {
+        let sample_rate = (inner.sample_rate() as f64 * factor).round() as u32;
+        Self { inner, factor, sample_rate }
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    /// The source as stored in the file.
+    pub fn inner(&self) -> &Arc<dyn AudioSource> {
+        &self.inner
+    }
+}
+
+impl AudioSource for TimeExpandedSource {
+    fn total_samples(&self) -> u64 {
+        self.inner.total_samples()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channel_count(&self) -> u32 {
+        self.inner.channel_count()
+    }
+
+    fn read_samples(&self, channel: ChannelView, start: u64, buf: &mut [f32]) -> usize {
+        self.inner.read_samples(channel, start, buf)
+    }
+
+    fn is_fully_loaded(&self) -> bool {
+        self.inner.is_fully_loaded()
+    }
+
+    fn as_contiguous(&self) -> Option<&[f32]> {
+        self.inner.as_contiguous()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The source underneath any time-expansion wrapper, for downcasting to a
+/// concrete (e.g. streaming) source type.
+pub fn base_source(source: &dyn AudioSource) -> &dyn AudioSource {
+    match source.as_any().downcast_ref::<TimeExpandedSource>() {
+        Some(te) => te.inner.as_ref(),
+        None => source,
+    }
+}
+
+/// Parse a factor such as `10`, `x10`, `10x` or `20.0`.
+fn parse_factor(s: &str) -> Option<f64> {
+    let s = s.trim().trim_start_matches(['x', 'X']).trim_end_matches(['x', 'X']);
+    let f: f64 = s.parse().ok()?;
+    (MIN_FACTOR..=MAX_FACTOR).contains(&f).then_some(f)
+}
+
+/// Look for a `TE10`, `TE_10`, `TEx10`, `10xTE` or `x10-TE` token in a filename.
+fn factor_from_name(name: &str) -> Option<f64> {
+    let stem = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let stem = stem.rsplit_once('.').map_or(stem, |(s, _)| s).to_ascii_lowercase();
+    let tokens: Vec<&str> = stem
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .collect();
+    for (i, tok) in tokens.iter().enumerate() {
+        if let Some(rest) = tok.strip_prefix("te") {
+            if let Some(f) = parse_factor(rest) {
+                return Some(f);
+            }
+            if rest.is_empty() {
+                // "te_10" / "x10_te"
+                let next = tokens.get(i + 1).and_then(|t| parse_factor(t));
+                let prev = i.checked_sub(1).and_then(|p| parse_factor(tokens[p]));
+                if let Some(f) = next.or(prev) {
+                    return Some(f);
+                }
+            }
+        }
+        if let Some(f) = tok.strip_suffix("te").and_then(parse_factor) {
+            return Some(f);
+        }
+    }
+    None
+}
+
+fn device_factor(text: &str) -> Option<f64> {
+    let text = text.to_ascii_lowercase();
+    TE_DEVICES.iter().find(|(pat, _)| text.contains(pat)).map(|&(_, f)| f)
+}
+
+/// Detect the time-expansion factor of a file. Returns `None` for real-time
+/// recordings.
+pub fn detect_time_expansion(name: &str, file_sample_rate: u32, guano: Option<&GuanoMetadata>) -> Option<f64> {
+    let field = |key: &str| {
+        guano.and_then(|g| g.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str()))
+    };
+    if let Some(te) = field("TE") {
+        // An explicit TE of 1 means real time; don't second-guess it
+        return te.trim().parse::<f64>().ok().filter(|&f| f > 1.0 && f <= MAX_FACTOR);
+    }
+    if file_sample_rate > MAX_EXPANDED_FILE_RATE {
+        return None;
+    }
+    let device = format!("{} {}", field("Make").unwrap_or(""), field("Model").unwrap_or(""));
+    factor_from_name(name)
+        .or_else(|| device_factor(&device))
+        .or_else(|| device_factor(name))
+}
+
+/// Set the time-expansion factor of loaded audio, rescaling its sample rate
+/// and duration to real-world units. A factor of 1.0 undoes the correction.
+pub fn apply_time_expansion(audio: &mut AudioData, factor: f64) {
+    let factor = if factor.is_finite() && factor >= 1.0 { factor } else { 1.0 };
+    let old = audio.metadata.time_expansion;
+    let base = audio
+        .source
+        .as_any()
+        .downcast_ref::<TimeExpandedSource>()
+        .map(|te| te.inner.clone())
+        .unwrap_or_else(|| audio.source.clone());
+    audio.source = if factor == 1.0 {
+        base
+    } else {
+        Arc::new(TimeExpandedSource::new(base, factor))
+    };
+    audio.sample_rate = audio.source.sample_rate();
+    // Scale rather than recompute: MP3/OGG durations are estimates
+    audio.duration_secs = audio.duration_secs * old / factor;
+    audio.metadata.time_expansion = factor;
+}
+
+/// Detect and apply the time-expansion factor of freshly loaded audio.
+/// Returns the factor applied, if any.
+pub fn detect_and_apply(audio: &mut AudioData, name: &str) -> Option<f64> {
+    let file_rate = base_source(audio.source.as_ref()).sample_rate();
+    let factor = detect_time_expansion(name, file_rate, audio.metadata.guano.as_ref())?;
+    apply_time_expansion(audio, factor);
+    Some(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::source::InMemorySource;
+    use crate::types::FileMetadata;
+
+    fn guano(fields: &[(&str, &str)]) -> GuanoMetadata {
+        let mut g = GuanoMetadata::new();
+        for (k, v) in fields {
+            g.add(k, v);
+        }
+        g
+    }
+
+    #[test]
+    fn test_detect_sources() {
+        // GUANO TE wins and is trusted at any rate
+        let g = guano(&[("TE", "20"), ("Model", "D240x")]);
+        assert_eq!(detect_time_expansion("a.wav", 44_100, Some(&g)), Some(20.0));
+        assert_eq!(detect_time_expansion("a.wav", 44_100, Some(&guano(&[("TE", "1")]))), None);
+
+        for name in ["pip_TE10.wav", "2019-06-01 te_10.wav", "x10-TE run.wav", "pass_10xTE.wav", "TEx20.WAV"] {
+            assert!(detect_time_expansion(name, 44_100, None).is_some(), "{name}");
+        }
+        assert_eq!(detect_time_expansion("TEx20.WAV", 44_100, None), Some(20.0));
+        assert_eq!(detect_time_expansion("D240X_0012.wav", 44_100, None), Some(10.0));
+        let g = guano(&[("Make", "Pettersson"), ("Model", "D240x")]);
+        assert_eq!(detect_time_expansion("0012.wav", 48_000, Some(&g)), Some(10.0));
+        let g = guano(&[("Make", "Batbox"), ("Model", "Griffin")]);
+        assert_eq!(detect_time_expansion("0012.wav", 44_100, Some(&g)), Some(10.0));
+
+        // No false positives on ordinary names or full-band recordings
+        for name in ["street_test.wav", "20240601_221500.WAV", "site3.wav", "note10.wav"] {
+            assert_eq!(detect_time_expansion(name, 44_100, None), None, "{name}");
+        }
+        assert_eq!(detect_time_expansion("pip_TE10.wav", 384_000, None), None);
+    }
+
+    #[test]
+    fn test_apply_and_undo() {
+        let samples = Arc::new(vec![0.0f32; 44_100]);
+        let source = Arc::new(InMemorySource {
+            samples: samples.clone(),
+            raw_samples: None,
+            sample_rate: 44_100,
+            channels: 1,
+        });
+        let mut audio = AudioData {
+            samples,
+            source,
+            sample_rate: 44_100,
+            channels: 1,
+            duration_secs: 1.0,
+            metadata: FileMetadata {
+                file_size: 0,
+                format: "WAV",
+                bits_per_sample: 16,
+                is_float: false,
+                guano: None,
+                data_offset: None,
+                data_size: None,
+                time_expansion: 1.0,
+            },
+        };
+
+        assert_eq!(detect_and_apply(&mut audio, "batbox_TE10.wav"), Some(10.0));
+        assert_eq!(audio.sample_rate, 441_000);
+        assert_eq!(audio.source.sample_rate(), 441_000);
+        assert!((audio.duration_secs - 0.1).abs() < 1e-9);
+        assert!((audio.source.duration_secs() - 0.1).abs() < 1e-9);
+        assert!(base_source(audio.source.as_ref()).as_any().downcast_ref::<InMemorySource>().is_some());
+
+        // Changing the factor rewraps the original source rather than nesting
+        apply_time_expansion(&mut audio, 20.0);
+        assert_eq!(audio.sample_rate, 882_000);
+        assert!((audio.duration_secs - 0.05).abs() < 1e-9);
+        apply_time_expansion(&mut audio, 1.0);
+        assert_eq!(audio.sample_rate, 44_100);
+        assert!((audio.duration_secs - 1.0).abs() < 1e-9);
+        assert!(audio.source.as_any().downcast_ref::<InMemorySource>().is_some());
+    }
+}
@@ -0,0 +1,1423 @@
+//! WavPack (`.wv`) decoder.
+//!
+//! A WavPack stream is a run of `wvpk` blocks, each carrying one or two
+//! channels of a frame; multichannel frames run from a block flagged
+//! `INITIAL` to one flagged `FINAL`. A block holds metadata sub-blocks
+//! (decorrelation terms, weights and history, entropy medians, hybrid and
+//! float parameters, the source RIFF header) followed by the bitstream of
+//! adaptive Golomb-style residuals. Lossless, hybrid (lossy; the `.wvc`
+//! correction file is not read), 32-bit integer and float streams decode
+//! the way libwavpack does; DSD does not.
+
+use crate::audio::audiomoth;
+use crate::audio::bwf;
+use crate::audio::guano::{self, GuanoMetadata};
+use crate::audio::riff;
+
+const BLOCK_HEADER_LEN: usize = 32;
+/// Largest block libwavpack writes. Bigger claims are corrupt, and decoding
+/// would size buffers from them before reading a single sample.
+const MAX_BLOCK_SAMPLES: u32 = 131_072;
+
+// Block header flags
+const BYTES_STORED: u32 = 0x3;
+const MONO_FLAG: u32 = 0x4;
+const HYBRID_FLAG: u32 = 0x8;
+const JOINT_STEREO: u32 = 0x10;
+const FLOAT_DATA: u32 = 0x80;
+const HYBRID_BITRATE: u32 = 0x200;
+const HYBRID_BALANCE: u32 = 0x400;
+const INITIAL_BLOCK: u32 = 0x800;
+const FINAL_BLOCK: u32 = 0x1000;
+const SHIFT_LSB: u32 = 13;
+const SRATE_LSB: u32 = 23;
+const FALSE_STEREO: u32 = 0x4000_0000;
+const DSD_FLAG: u32 = 0x8000_0000;
+/// One channel is coded, whether output is mono or false stereo.
+const MONO_DATA: u32 = MONO_FLAG | FALSE_STEREO;
+
+/// Sample rates indexed by the header's 4-bit rate field; 15 means the
+/// rate is in an `ID_SAMPLE_RATE` sub-block.
+const SAMPLE_RATES: [u32; 15] = [
+    6000, 8000, 9600, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 64000, 88200, 96000, 192000,
+];
+
+// Sub-block ids
+const ID_FUNCTION: u8 = 0x3f;
+const ID_ODD_SIZE: u8 = 0x40;
+const ID_LARGE: u8 = 0x80;
+const ID_DECORR_TERMS: u8 = 0x02;
+const ID_DECORR_WEIGHTS: u8 = 0x03;
+const ID_DECORR_SAMPLES: u8 = 0x04;
+const ID_ENTROPY_VARS: u8 = 0x05;
+const ID_HYBRID_PROFILE: u8 = 0x06;
+const ID_FLOAT_INFO: u8 = 0x08;
+const ID_INT32_INFO: u8 = 0x09;
+const ID_WV_BITSTREAM: u8 = 0x0a;
+const ID_WVX_BITSTREAM: u8 = 0x0c;
+const ID_CHANNEL_INFO: u8 = 0x0d;
+const ID_RIFF_HEADER: u8 = 0x21;
+const ID_RIFF_TRAILER: u8 = 0x22;
+const ID_SAMPLE_RATE: u8 = 0x27;
+
+// Float info flags
+const FLOAT_SHIFT_ONES: u8 = 0x1;
+const FLOAT_SHIFT_SAME: u8 = 0x2;
+const FLOAT_SHIFT_SENT: u8 = 0x4;
+const FLOAT_ZEROS_SENT: u8 = 0x8;
+const FLOAT_NEG_ZEROS: u8 = 0x10;
+
+/// Unary counts at or above this are escaped with an Elias-style length.
+const LIMIT_ONES: u32 = 16;
+/// Hybrid bitrate mode: `slow_level` is a running log2 with this shift.
+const SLS: u32 = 8;
+const SLO: u32 = 1 << (SLS - 1);
+
+/// 256 * (2^(i/256) - 1): fractional part of the log-domain values.
+const EXP2_TABLE: [u8; 256] = [
+    0x00, 0x01, 0x01, 0x02, 0x03, 0x03, 0x04, 0x05, 0x06, 0x06, 0x07, 0x08, 0x08, 0x09, 0x0a, 0x0b,
+    0x0b, 0x0c, 0x0d, 0x0e, 0x0e, 0x0f, 0x10, 0x10, 0x11, 0x12, 0x13, 0x13, 0x14, 0x15, 0x16, 0x16,
+    0x17, 0x18, 0x19, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1d, 0x1e, 0x1f, 0x20, 0x20, 0x21, 0x22, 0x23,
+    0x24, 0x24, 0x25, 0x26, 0x27, 0x28, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2c, 0x2d, 0x2e, 0x2f, 0x30,
+    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x3a, 0x3b, 0x3c, 0x3d,
+    0x3e, 0x3f, 0x40, 0x41, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x48, 0x49, 0x4a, 0x4b,
+    0x4c, 0x4d, 0x4e, 0x4f, 0x50, 0x51, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a,
+    0x5b, 0x5c, 0x5d, 0x5e, 0x5e, 0x5f, 0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79,
+    0x7a, 0x7b, 0x7c, 0x7d, 0x7e, 0x7f, 0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x87, 0x88, 0x89, 0x8a,
+    0x8b, 0x8c, 0x8d, 0x8e, 0x8f, 0x90, 0x91, 0x92, 0x93, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0x9b,
+    0x9c, 0x9d, 0x9f, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa8, 0xa9, 0xaa, 0xab, 0xac, 0xad,
+    0xaf, 0xb0, 0xb1, 0xb2, 0xb3, 0xb4, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xbc, 0xbd, 0xbe, 0xbf, 0xc0,
+    0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc8, 0xc9, 0xca, 0xcb, 0xcd, 0xce, 0xcf, 0xd0, 0xd2, 0xd3, 0xd4,
+    0xd6, 0xd7, 0xd8, 0xd9, 0xdb, 0xdc, 0xdd, 0xde, 0xe0, 0xe1, 0xe2, 0xe4, 0xe5, 0xe6, 0xe8, 0xe9,
+    0xea, 0xec, 0xed, 0xee, 0xf0, 0xf1, 0xf2, 0xf4, 0xf5, 0xf6, 0xf8, 0xf9, 0xfa, 0xfc, 0xfd, 0xff,
+];
+/// 256 * log2(1 + i/256).
+const LOG2_TABLE: [u8; 256] = [
+    0x00, 0x01, 0x03, 0x04, 0x06, 0x07, 0x09, 0x0a, 0x0b, 0x0d, 0x0e, 0x10, 0x11, 0x12, 0x14, 0x15,
+    0x16, 0x18, 0x19, 0x1a, 0x1c, 0x1d, 0x1e, 0x20, 0x21, 0x22, 0x24, 0x25, 0x26, 0x28, 0x29, 0x2a,
+    0x2c, 0x2d, 0x2e, 0x2f, 0x31, 0x32, 0x33, 0x34, 0x36, 0x37, 0x38, 0x39, 0x3b, 0x3c, 0x3d, 0x3e,
+    0x3f, 0x41, 0x42, 0x43, 0x44, 0x45, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4d, 0x4e, 0x4f, 0x50, 0x51,
+    0x52, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x5c, 0x5d, 0x5e, 0x5f, 0x60, 0x61, 0x62, 0x63,
+    0x64, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f, 0x70, 0x71, 0x72, 0x74, 0x75,
+    0x76, 0x77, 0x78, 0x79, 0x7a, 0x7b, 0x7c, 0x7d, 0x7e, 0x7f, 0x80, 0x81, 0x82, 0x83, 0x84, 0x85,
+    0x86, 0x87, 0x88, 0x89, 0x8a, 0x8b, 0x8c, 0x8d, 0x8e, 0x8f, 0x90, 0x91, 0x92, 0x93, 0x94, 0x95,
+    0x96, 0x97, 0x98, 0x99, 0x9a, 0x9b, 0x9b, 0x9c, 0x9d, 0x9e, 0x9f, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4,
+    0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xa9, 0xaa, 0xab, 0xac, 0xad, 0xae, 0xaf, 0xb0, 0xb1, 0xb2, 0xb2,
+    0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xb9, 0xba, 0xbb, 0xbc, 0xbd, 0xbe, 0xbf, 0xc0, 0xc0,
+    0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcb, 0xcc, 0xcd, 0xce,
+    0xcf, 0xd0, 0xd0, 0xd1, 0xd2, 0xd3, 0xd4, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd8, 0xd9, 0xda, 0xdb,
+    0xdc, 0xdc, 0xdd, 0xde, 0xdf, 0xe0, 0xe0, 0xe1, 0xe2, 0xe3, 0xe4, 0xe4, 0xe5, 0xe6, 0xe7, 0xe7,
+    0xe8, 0xe9, 0xea, 0xea, 0xeb, 0xec, 0xed, 0xee, 0xee, 0xef, 0xf0, 0xf1, 0xf1, 0xf2, 0xf3, 0xf4,
+    0xf4, 0xf5, 0xf6, 0xf7, 0xf7, 0xf8, 0xf9, 0xf9, 0xfa, 0xfb, 0xfc, 0xfc, 0xfd, 0xfe, 0xff, 0xff,
+];
+
+pub fn is_wavpack(bytes: &[u8]) -> bool {
+    bytes.len() >= BLOCK_HEADER_LEN && &bytes[0..4] == b"wvpk"
+}
+
+/// Stream parameters from the first frame, plus metadata from the stored
+/// RIFF header and trailer.
+#[derive(Clone, Debug)]
+pub struct WavPackHeader {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Bit depth of the source file (from its RIFF `fmt ` when stored).
+    pub bits_per_sample: u16,
+    pub is_float: bool,
+    /// Hybrid (lossy) stream; decoded without its `.wvc` correction file.
+    pub hybrid: bool,
+    /// Frames per channel, if the encoder knew it.
+    pub total_frames: Option<u64>,
+    pub guano: Option<GuanoMetadata>,
+}
+
+/// Parse the stream parameters from the start of a WavPack file. With the
+/// whole file, metadata after the audio (the RIFF trailer) is read too.
+pub fn parse_wavpack_header(header_bytes: &[u8]) -> Result<WavPackHeader, String> {
+    if !is_wavpack(header_bytes) {
+        return Err("Not a WavPack file".into());
+    }
+
+    let mut first: Option<BlockHeader> = None;
+    let mut frame_channels = 0u16;
+    let mut frame_done = false;
+    let mut channel_info: Option<u16> = None;
+    let mut custom_rate: Option<u32> = None;
+    let mut riff_header: Option<&[u8]> = None;
+    let mut riff_trailer: Option<&[u8]> = None;
+
+    for (h, body) in blocks(header_bytes) {
+        for (id, data) in sub_blocks(body) {
+            match id {
+                ID_RIFF_HEADER if riff_header.is_none() => riff_header = Some(data),
+                ID_RIFF_TRAILER => riff_trailer = Some(data),
+                ID_CHANNEL_INFO if !data.is_empty() && channel_info.is_none() => {
+                    channel_info = Some(data[0] as u16);
+                }
+                ID_SAMPLE_RATE if data.len() >= 3 && custom_rate.is_none() => {
+                    let high = data.get(3).copied().unwrap_or(0);
+                    custom_rate = Some(u32::from_le_bytes([data[0], data[1], data[2], high]));
+                }
+                _ => {}
+            }
+        }
+        // Metadata-only blocks carry no samples
+        if h.block_samples == 0 || frame_done {
+            continue;
+        }
+        frame_channels += if h.flags & MONO_FLAG != 0 { 1 } else { 2 };
+        frame_done = h.flags & FINAL_BLOCK != 0;
+        first.get_or_insert(h);
+    }
+
+    let h = first.ok_or("No audio blocks found in WavPack file")?;
+    if h.flags & DSD_FLAG != 0 {
+        return Err("DSD WavPack files are not supported".into());
+    }
+    let rate_index = ((h.flags >> SRATE_LSB) & 0xf) as usize;
+    let sample_rate = SAMPLE_RATES
+        .get(rate_index)
+        .copied()
+        .or(custom_rate)
+        .filter(|&r| r > 0)
+        .ok_or("WavPack sample rate missing")?;
+    // Later blocks of a multichannel frame may lie past the scanned bytes
+    let channels = channel_info.filter(|&c| c > 0).unwrap_or(frame_channels);
+
+    let (riff_bits, guano) = riff_metadata(riff_header, riff_trailer, sample_rate);
+    let is_float = h.flags & FLOAT_DATA != 0;
+    let bits_per_sample = riff_bits.unwrap_or(if is_float { 32 } else { ((h.flags & BYTES_STORED) as u16 + 1) * 8 });
+
+    Ok(WavPackHeader {
+        sample_rate,
+        channels,
+        bits_per_sample,
+        is_float,
+        hybrid: h.flags & HYBRID_FLAG != 0,
+        total_frames: h.total_samples,
+        guano,
+    })
+}
+
+/// Decode a whole WavPack file to interleaved f32 samples.
+pub fn decode_wavpack(bytes: &[u8]) -> Result<(WavPackHeader, Vec<f32>), String> {
+    let mut header = parse_wavpack_header(bytes)?;
+    let channels = header.channels as usize;
+    if channels == 0 {
+        return Err("WavPack file has no channels".into());
+    }
+
+    // The header's total is a hint; don't reserve more than the file could hold
+    let capacity = header.total_frames.unwrap_or(0).saturating_mul(channels as u64).min(bytes.len() as u64 * 4) as usize;
+    let mut out: Vec<f32> = Vec::with_capacity(capacity);
+    // Frame being assembled from its per-channel blocks; it joins `out` once
+    // the next frame's first block has decoded
+    let mut frame: Vec<f32> = Vec::new();
+    let mut frame_len = 0usize;
+    let mut next_channel = 0usize;
+    let mut crc_errors = 0usize;
+
+    for (h, body) in blocks(bytes) {
+        if h.block_samples == 0 {
+            continue;
+        }
+        let initial = h.flags & INITIAL_BLOCK != 0;
+        let block = match decode_block(&h, body) {
+            Ok(block) => block,
+            Err(e) if !out.is_empty() || (initial && !frame.is_empty()) => {
+                // Truncated tail: keep the complete frames
+                log::warn!("WavPack decode stopped early: {e}");
+                if initial {
+                    out.append(&mut frame);
+                }
+                frame.clear();
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        if initial {
+            out.append(&mut frame);
+            frame_len = h.block_samples as usize;
+            next_channel = 0;
+        }
+        if !block.crc_ok {
+            crc_errors += 1;
+        }
+        if block.samples.len() != frame_len * block.channels {
+            return Err("WavPack block length does not match its frame".into());
+        }
+        if initial {
+            frame.resize(frame_len * channels, 0.0);
+        }
+        for (i, samples) in block.samples.chunks_exact(block.channels).enumerate() {
+            for (c, &v) in samples.iter().enumerate() {
+                if next_channel + c < channels {
+                    frame[i * channels + next_channel + c] = v;
+                }
+            }
+        }
+        next_channel += block.channels;
+    }
+    out.append(&mut frame);
+
+    if crc_errors > 0 {
+        log::warn!("WavPack: {crc_errors} block(s) failed their CRC check");
+    }
+    header.total_frames = Some((out.len() / channels) as u64);
+    Ok((header, out))
+}
+
+/// Bit depth from the stored `fmt ` chunk, and GUANO/BWF/AudioMoth fields
+/// from the RIFF header and trailer (chunks before and after `data`).
+fn riff_metadata(header: Option<&[u8]>, trailer: Option<&[u8]>, sample_rate: u32) -> (Option<u16>, Option<GuanoMetadata>) {
+    let wave64 = header.is_some_and(riff::is_wave64);
+    let mut chunks: Vec<([u8; 4], &[u8])> = Vec::new();
+    if let Some(c) = header.and_then(riff::chunks) {
+        chunks.extend(c.with_bodies());
+    }
+    if let Some(t) = trailer {
+        chunks.extend(riff::chunks_from(t, 0, wave64).with_bodies());
+    }
+
+    let bits = chunks
+        .iter()
+        .find(|(id, body)| id == b"fmt " && body.len() >= 16)
+        .map(|(_, body)| u16::from_le_bytes([body[14], body[15]]));
+    let guano = chunks
+        .iter()
+        .find(|(id, _)| id == b"guan")
+        .and_then(|(_, body)| guano::parse_guano_chunk(body));
+    let comment = chunks
+        .iter()
+        .filter(|(id, _)| id == b"LIST")
+        .find_map(|(_, body)| audiomoth::info_comment(body))
+        .as_deref()
+        .and_then(audiomoth::parse_comment);
+
+    let guano = bwf::merge_into_guano(guano, bwf::bwf_fields(chunks, Some(sample_rate)));
+    (bits, audiomoth::merge_into_guano(guano, comment.as_ref(), Some(sample_rate)))
+}
+
+struct BlockHeader {
+    /// Whole block length, including the `wvpk` + size preamble.
+    len: usize,
+    version: u16,
+    total_samples: Option<u64>,
+    block_samples: u32,
+    flags: u32,
+    crc: u32,
+}
+
+fn block_header(bytes: &[u8]) -> Option<BlockHeader> {
+    let h = bytes.get(..BLOCK_HEADER_LEN)?;
+    if &h[0..4] != b"wvpk" {
+        return None;
+    }
+    let u32_at = |i: usize| u32::from_le_bytes(h[i..i + 4].try_into().unwrap());
+    let len = (u32_at(4) as usize).checked_add(8)?;
+    if len < BLOCK_HEADER_LEN {
+        return None;
+    }
+    // Bits 32+ of the total live in a spare byte; all-ones means unknown.
+    // Each 2^32 span skips the reserved value, hence the subtraction.
+    let total = u32_at(12);
+    let total_u8 = h[11] as u64;
+    let total_samples = (total != u32::MAX).then(|| (total_u8 << 32) + total as u64 - total_u8);
+    let block_samples = u32_at(20);
+    if block_samples > MAX_BLOCK_SAMPLES {
+        return None;
+    }
+    Some(BlockHeader {
+        len,
+        version: u16::from_le_bytes([h[8], h[9]]),
+        total_samples,
+        block_samples,
+        flags: u32_at(24),
+        crc: u32_at(28),
+    })
+}
+
+/// Blocks as (header, sub-block bytes), resyncing on the next `wvpk` after
+/// junk such as an APEv2 tag. The last block may be cut short.
+fn blocks(bytes: &[u8]) -> impl Iterator<Item = (BlockHeader, &[u8])> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || loop {
+        let rest = bytes.get(pos..)?;
+        if let Some(h) = block_header(rest) {
+            let body = &rest[BLOCK_HEADER_LEN..h.len.min(rest.len())];
+            // A length that overflows can only be corrupt; saturating ends the scan
+            pos = pos.saturating_add(h.len);
+            return Some((h, body));
+        }
+        let skip = rest.get(1..)?.windows(4).position(|w| w == b"wvpk")?;
+        pos += skip + 1;
+    })
+}
+
+/// Metadata sub-blocks as (function id, data). Sizes are in 16-bit words;
+/// odd-sized data has one byte of padding.
+fn sub_blocks(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || {
+        let id = *data.get(pos)?;
+        let (words, header_len) = if id & ID_LARGE != 0 {
+            let b = data.get(pos + 1..pos + 4)?;
+            (u32::from_le_bytes([b[0], b[1], b[2], 0]) as usize, 4)
+        } else {
+            (*data.get(pos + 1)? as usize, 2)
+        };
+        let start = pos + header_len;
+        pos = start + words * 2;
+        let len = if id & ID_ODD_SIZE != 0 { (words * 2).checked_sub(1)? } else { words * 2 };
+        let body = data.get(start..(start + len).min(data.len()))?;
+        Some((id & ID_FUNCTION, body))
+    })
+}
+
+/// LSB-first bit reader. Reads past the end return zeros and are reported
+/// by [`BitReader::overrun`].
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    acc: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, acc: 0, count: 0 }
+    }
+
+    fn refill(&mut self) {
+        while self.count <= 56 {
+            let byte = self.data.get(self.pos).copied().unwrap_or(0);
+            self.pos += 1;
+            self.acc |= (byte as u64) << self.count;
+            self.count += 8;
+        }
+    }
+
+    fn bit(&mut self) -> u32 {
+        if self.count == 0 {
+            self.refill();
+        }
+        let bit = (self.acc & 1) as u32;
+        self.acc >>= 1;
+        self.count -= 1;
+        bit
+    }
+
+    fn bits(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        if self.count < n {
+            self.refill();
+        }
+        let value = (self.acc & ((1u64 << n) - 1)) as u32;
+        self.acc >>= n;
+        self.count -= n;
+        value
+    }
+
+    fn overrun(&self) -> bool {
+        self.pos * 8 - self.count as usize > self.data.len() * 8
+    }
+}
+
+/// Log-domain value (8.8 fixed point) back to linear.
+fn exp2s(log: i32) -> i32 {
+    if log < 0 {
+        return exp2s(-log).wrapping_neg();
+    }
+    let value = EXP2_TABLE[(log & 0xff) as usize] as u32 | 0x100;
+    let shift = log >> 8;
+    if shift <= 9 {
+        (value >> (9 - shift)) as i32
+    } else {
+        value.checked_shl((shift - 9) as u32).unwrap_or(0) as i32
+    }
+}
+
+/// Linear value to the 8.8 fixed-point log used by hybrid bitrate control.
+fn wp_log2(value: u32) -> i32 {
+    let value = value.wrapping_add(value >> 9);
+    let dbits = 32 - value.leading_zeros();
+    let frac = if dbits <= 9 { value << (9 - dbits) } else { value >> (dbits - 9) };
+    ((dbits << 8) + LOG2_TABLE[(frac & 0xff) as usize] as u32) as i32
+}
+
+fn restore_weight(weight: i8) -> i32 {
+    let result = (weight as i32) << 3;
+    if result > 0 { result + ((result + 64) >> 7) } else { result }
+}
+
+#[derive(Clone, Copy, Default)]
+struct DecorrPass {
+    term: i32,
+    delta: i32,
+    weight_a: i32,
+    weight_b: i32,
+    samples_a: [i32; 8],
+    samples_b: [i32; 8],
+}
+
+fn apply_weight(weight: i32, sample: i32) -> i32 {
+    ((weight as i64 * sample as i64 + 512) >> 10) as i32
+}
+
+/// Nudge `weight` toward agreeing with the sign of `input * sample`.
+fn update_weight(weight: &mut i32, delta: i32, sample: i32, input: i32) {
+    if sample != 0 && input != 0 {
+        if (sample ^ input) < 0 {
+            *weight -= delta;
+        } else {
+            *weight += delta;
+        }
+    }
+}
+
+fn update_weight_clip(weight: &mut i32, delta: i32, sample: i32, input: i32) {
+    update_weight(weight, delta, sample, input);
+    *weight = (*weight).clamp(-1024, 1024);
+}
+
+impl DecorrPass {
+    /// Prediction source for positive terms, and where the output goes.
+    fn predict(samples: &mut [i32; 8], term: i32, pos: usize) -> (i32, usize) {
+        if term > 8 {
+            let a = if term & 1 != 0 {
+                samples[0].wrapping_mul(2).wrapping_sub(samples[1])
+            } else {
+                samples[0].wrapping_mul(3).wrapping_sub(samples[1]) >> 1
+            };
+            samples[1] = samples[0];
+            (a, 0)
+        } else {
+            (samples[pos], (pos + term as usize) & 7)
+        }
+    }
+
+    fn mono(&mut self, t: i32, pos: usize) -> i32 {
+        let (a, j) = Self::predict(&mut self.samples_a, self.term, pos);
+        let s = t.wrapping_add(apply_weight(self.weight_a, a));
+        update_weight(&mut self.weight_a, self.delta, a, t);
+        self.samples_a[j] = s;
+        s
+    }
+
+    fn stereo(&mut self, l: &mut i32, r: &mut i32, pos: usize) {
+        match self.term {
+            t if t > 0 => {
+                let (a, j) = Self::predict(&mut self.samples_a, t, pos);
+                let (b, _) = Self::predict(&mut self.samples_b, t, pos);
+                let l2 = l.wrapping_add(apply_weight(self.weight_a, a));
+                let r2 = r.wrapping_add(apply_weight(self.weight_b, b));
+                update_weight(&mut self.weight_a, self.delta, a, *l);
+                update_weight(&mut self.weight_b, self.delta, b, *r);
+                self.samples_a[j] = l2;
+                self.samples_b[j] = r2;
+                (*l, *r) = (l2, r2);
+            }
+            -1 => {
+                let l2 = l.wrapping_add(apply_weight(self.weight_a, self.samples_a[0]));
+                update_weight_clip(&mut self.weight_a, self.delta, self.samples_a[0], *l);
+                *l = l2;
+                let r2 = r.wrapping_add(apply_weight(self.weight_b, l2));
+                update_weight_clip(&mut self.weight_b, self.delta, l2, *r);
+                *r = r2;
+                self.samples_a[0] = r2;
+            }
+            t => {
+                let r2 = r.wrapping_add(apply_weight(self.weight_b, self.samples_b[0]));
+                update_weight_clip(&mut self.weight_b, self.delta, self.samples_b[0], *r);
+                *r = r2;
+                // -3 cross-feeds the previous right sample instead
+                let source = if t == -3 {
+                    std::mem::replace(&mut self.samples_a[0], r2)
+                } else {
+                    r2
+                };
+                let l2 = l.wrapping_add(apply_weight(self.weight_a, source));
+                update_weight_clip(&mut self.weight_a, self.delta, source, *l);
+                *l = l2;
+                self.samples_b[0] = l2;
+            }
+        }
+    }
+}
+
+/// Per-channel entropy coder state.
+#[derive(Clone, Copy, Default)]
+struct Entropy {
+    median: [u32; 3],
+    slow_level: u32,
+    error_limit: u32,
+    bitrate_acc: u32,
+    bitrate_delta: u32,
+}
+
+impl Entropy {
+    fn get_med(&self, n: usize) -> u32 {
+        (self.median[n] >> 4) + 1
+    }
+
+    fn dec_med(&mut self, n: usize) {
+        let div = 128 >> n;
+        self.median[n] = self.median[n].wrapping_sub(((self.median[n].wrapping_add(div - 2)) / div) * 2);
+    }
+
+    fn inc_med(&mut self, n: usize) {
+        let div = 128 >> n;
+        self.median[n] = self.median[n].wrapping_add(((self.median[n].wrapping_add(div)) / div) * 5);
+    }
+
+    fn decay_slow_level(&mut self) {
+        self.slow_level = self.slow_level.wrapping_sub(self.slow_level.wrapping_add(SLO) >> SLS);
+    }
+
+    fn slow_log(&self) -> i32 {
+        (self.slow_level.wrapping_add(SLO) >> SLS) as i32
+    }
+
+    fn next_bitrate(&mut self) -> i32 {
+        self.bitrate_acc = self.bitrate_acc.wrapping_add(self.bitrate_delta);
+        (self.bitrate_acc >> 16) as i32
+    }
+}
+
+/// Error limit in bitrate mode: how far the level sits above the target.
+fn hybrid_limit(slow_log: i32, bitrate: i32) -> u32 {
+    if slow_log - bitrate > -0x100 { exp2s(slow_log - bitrate + 0x100) as u32 } else { 0 }
+}
+
+struct Words {
+    flags: u32,
+    c: [Entropy; 2],
+    holding_one: bool,
+    holding_zero: bool,
+    zeros_acc: u32,
+}
+
+/// Elias-gamma-like count: unary bit length, then the bits below the top one.
+fn read_count(br: &mut BitReader) -> Result<u32, String> {
+    let mut cbits = 0;
+    while cbits < 33 && br.bit() == 1 {
+        cbits += 1;
+    }
+    match cbits {
+        33 => Err("WavPack bitstream: bad run length".into()),
+        0 | 1 => Ok(cbits),
+        _ => Ok(br.bits(cbits - 1) | (1 << (cbits - 1))),
+    }
+}
+
+/// Truncated binary code for a value in `0..=maxcode`.
+fn read_code(br: &mut BitReader, maxcode: u32) -> u32 {
+    if maxcode < 2 {
+        return if maxcode != 0 { br.bit() } else { 0 };
+    }
+    let bitcount = 32 - maxcode.leading_zeros();
+    let extras = (1u64 << bitcount) - maxcode as u64 - 1;
+    let code = br.bits(bitcount - 1) as u64;
+    if code >= extras {
+        ((code << 1) - extras + br.bit() as u64) as u32
+    } else {
+        code as u32
+    }
+}
+
+impl Words {
+    fn update_error_limit(&mut self) {
+        let mut bitrate_0 = self.c[0].next_bitrate();
+        if self.flags & MONO_DATA != 0 {
+            self.c[0].error_limit = if self.flags & HYBRID_BITRATE != 0 {
+                hybrid_limit(self.c[0].slow_log(), bitrate_0)
+            } else {
+                exp2s(bitrate_0) as u32
+            };
+            return;
+        }
+        let mut bitrate_1 = self.c[1].next_bitrate();
+        if self.flags & HYBRID_BITRATE != 0 {
+            let (slow_log_0, slow_log_1) = (self.c[0].slow_log(), self.c[1].slow_log());
+            if self.flags & HYBRID_BALANCE != 0 {
+                let balance = (slow_log_1 - slow_log_0 + bitrate_1 + 1) >> 1;
+                if balance > bitrate_0 {
+                    bitrate_1 = bitrate_0 * 2;
+                    bitrate_0 = 0;
+                } else if -balance > bitrate_0 {
+                    bitrate_0 *= 2;
+                    bitrate_1 = 0;
+                } else {
+                    bitrate_1 = bitrate_0 + balance;
+                    bitrate_0 -= balance;
+                }
+            }
+            self.c[0].error_limit = hybrid_limit(slow_log_0, bitrate_0);
+            self.c[1].error_limit = hybrid_limit(slow_log_1, bitrate_1);
+        } else {
+            self.c[0].error_limit = exp2s(bitrate_0) as u32;
+            self.c[1].error_limit = exp2s(bitrate_1) as u32;
+        }
+    }
+
+    fn get_word(&mut self, br: &mut BitReader, chan: usize) -> Result<i32, String> {
+        // Runs of zeros are coded as a count once both medians collapse
+        if self.c[0].median[0] < 2 && self.c[1].median[0] < 2 && !self.holding_zero && !self.holding_one {
+            if self.zeros_acc > 0 {
+                self.zeros_acc -= 1;
+                if self.zeros_acc > 0 {
+                    self.c[chan].decay_slow_level();
+                    return Ok(0);
+                }
+            } else {
+                self.zeros_acc = read_count(br)?;
+                if self.zeros_acc > 0 {
+                    self.c[chan].decay_slow_level();
+                    self.c[0].median = [0; 3];
+                    self.c[1].median = [0; 3];
+                    return Ok(0);
+                }
+            }
+        }
+
+        let ones_count = if self.holding_zero {
+            self.holding_zero = false;
+            0
+        } else {
+            let mut n = 0;
+            while n <= LIMIT_ONES && br.bit() == 1 {
+                n += 1;
+            }
+            if n > LIMIT_ONES {
+                return Err("WavPack bitstream: bad ones count".into());
+            }
+            if n == LIMIT_ONES {
+                n += read_count(br)?;
+            }
+            // The low bit says whether the next word's count is non-zero
+            let held = self.holding_one;
+            self.holding_one = n & 1 != 0;
+            self.holding_zero = !self.holding_one;
+            if held { (n >> 1) + 1 } else { n >> 1 }
+        };
+
+        if self.flags & HYBRID_FLAG != 0 && chan == 0 {
+            self.update_error_limit();
+        }
+
+        let c = &mut self.c[chan];
+        let (mut low, mut high);
+        if ones_count == 0 {
+            low = 0;
+            high = c.get_med(0) - 1;
+            c.dec_med(0);
+        } else {
+            low = c.get_med(0);
+            c.inc_med(0);
+            if ones_count == 1 {
+                high = low + c.get_med(1) - 1;
+                c.dec_med(1);
+            } else {
+                low = low.wrapping_add(c.get_med(1));
+                c.inc_med(1);
+                if ones_count == 2 {
+                    high = low.wrapping_add(c.get_med(2) - 1);
+                    c.dec_med(2);
+                } else {
+                    low = low.wrapping_add((ones_count - 2).wrapping_mul(c.get_med(2)));
+                    high = low.wrapping_add(c.get_med(2) - 1);
+                    c.inc_med(2);
+                }
+            }
+        }
+
+        low &= 0x7fff_ffff;
+        high = (high & 0x7fff_ffff).max(low);
+        let mut mid = (high + low + 1) >> 1;
+        if c.error_limit == 0 {
+            mid = read_code(br, high - low) + low;
+        } else {
+            // Hybrid: narrow the interval until it is within the error limit
+            while high - low > c.error_limit {
+                if br.bit() == 1 {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+                mid = (high + low + 1) >> 1;
+            }
+        }
+
+        let negative = br.bit() == 1;
+        if self.flags & HYBRID_BITRATE != 0 {
+            c.decay_slow_level();
+            c.slow_level = c.slow_level.wrapping_add(wp_log2(mid) as u32);
+        }
+        Ok(if negative { !(mid as i32) } else { mid as i32 })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct FloatInfo {
+    flags: u8,
+    shift: u8,
+    max_exp: u8,
+}
+
+#[derive(Clone, Copy)]
+struct Int32Info {
+    sent_bits: u8,
+    zeros: u8,
+    ones: u8,
+    dups: u8,
+}
+
+struct DecodedBlock {
+    channels: usize,
+    /// Interleaved, normalised to ±1.0.
+    samples: Vec<f32>,
+    crc_ok: bool,
+}
+
+fn decode_block(h: &BlockHeader, body: &[u8]) -> Result<DecodedBlock, String> {
+    let flags = h.flags;
+    if flags & DSD_FLAG != 0 {
+        return Err("DSD WavPack files are not supported".into());
+    }
+    let stereo = flags & MONO_DATA == 0;
+    let per_channel = if stereo { 2 } else { 1 };
+
+    let mut passes: Vec<DecorrPass> = Vec::new();
+    let mut words = Words { flags, c: [Entropy::default(); 2], holding_one: false, holding_zero: false, zeros_acc: 0 };
+    let mut float_info: Option<FloatInfo> = None;
+    let mut int32_info: Option<Int32Info> = None;
+    let mut bitstream: Option<&[u8]> = None;
+    let mut extra_bits: Option<&[u8]> = None;
+
+    let subs: Vec<(u8, &[u8])> = sub_blocks(body).collect();
+    // Terms first: weights and history are stored per pass
+    for &(id, data) in &subs {
+        if id == ID_DECORR_TERMS {
+            passes = data
+                .iter()
+                .rev()
+                .map(|&b| DecorrPass { term: (b & 0x1f) as i32 - 5, delta: (b >> 5) as i32, ..Default::default() })
+                .collect();
+            let valid = |t: i32| matches!(t, 1..=8 | 17 | 18) || (stereo && matches!(t, -3..=-1));
+            if passes.iter().any(|p| !valid(p.term)) {
+                return Err("WavPack: invalid decorrelation term".into());
+            }
+        }
+    }
+    for &(id, data) in &subs {
+        match id {
+            ID_DECORR_WEIGHTS => {
+                let count = data.len() / per_channel;
+                for (k, pass) in passes.iter_mut().rev().take(count).enumerate() {
+                    pass.weight_a = restore_weight(data[k * per_channel] as i8);
+                    if stereo {
+                        pass.weight_b = restore_weight(data[k * per_channel + 1] as i8);
+                    }
+                }
+            }
+            ID_DECORR_SAMPLES => {
+                let mut data = data;
+                // 4.02 hybrid streams lead with a value that is not history
+                if h.version == 0x402 && flags & HYBRID_FLAG != 0 {
+                    data = data.get(2 * per_channel..).unwrap_or(&[]);
+                }
+                let mut next = data
+                    .chunks_exact(2)
+                    .map(|b| exp2s(i16::from_le_bytes([b[0], b[1]]) as i32))
+                    .peekable();
+                for pass in passes.iter_mut().rev() {
+                    if next.peek().is_none() {
+                        break;
+                    }
+                    let mut take = || next.next().unwrap_or(0);
+                    if pass.term > 8 {
+                        pass.samples_a[0] = take();
+                        pass.samples_a[1] = take();
+                        if stereo {
+                            pass.samples_b[0] = take();
+                            pass.samples_b[1] = take();
+                        }
+                    } else if pass.term < 0 {
+                        pass.samples_a[0] = take();
+                        pass.samples_b[0] = take();
+                    } else {
+                        for m in 0..pass.term as usize {
+                            pass.samples_a[m] = take();
+                            if stereo {
+                                pass.samples_b[m] = take();
+                            }
+                        }
+                    }
+                }
+            }
+            ID_ENTROPY_VARS => {
+                for (i, b) in data.chunks_exact(2).take(3 * per_channel).enumerate() {
+                    words.c[i / 3].median[i % 3] = exp2s(u16::from_le_bytes([b[0], b[1]]) as i32) as u32;
+                }
+            }
+            ID_HYBRID_PROFILE => {
+                let mut v = data.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+                if flags & HYBRID_BITRATE != 0 {
+                    for c in &mut words.c[..per_channel] {
+                        c.slow_level = exp2s(v.next().unwrap_or(0) as i32) as u32;
+                    }
+                }
+                for c in &mut words.c[..per_channel] {
+                    c.bitrate_acc = (v.next().unwrap_or(0) as u32) << 16;
+                }
+                for c in &mut words.c[..per_channel] {
+                    if let Some(d) = v.next() {
+                        c.bitrate_delta = exp2s(d as i16 as i32) as u32;
+                    }
+                }
+            }
+            ID_FLOAT_INFO if data.len() >= 4 => {
+                float_info = Some(FloatInfo { flags: data[0], shift: data[1], max_exp: data[2] });
+            }
+            ID_INT32_INFO if data.len() >= 4 => {
+                int32_info = Some(Int32Info { sent_bits: data[0], zeros: data[1], ones: data[2], dups: data[3] });
+            }
+            ID_WV_BITSTREAM => bitstream = Some(data),
+            ID_WVX_BITSTREAM if data.len() > 4 => extra_bits = Some(&data[4..]),
+            _ => {}
+        }
+    }
+
+    let mut br = BitReader::new(bitstream.ok_or("WavPack block has no bitstream")?);
+    let n = h.block_samples as usize;
+    let mut values: Vec<i32> = Vec::with_capacity(n * per_channel);
+    let mut crc: u32 = 0xffff_ffff;
+    let mut pos = 0usize;
+
+    for _ in 0..n {
+        if stereo {
+            let mut l = words.get_word(&mut br, 0)?;
+            let mut r = words.get_word(&mut br, 1)?;
+            for pass in &mut passes {
+                pass.stereo(&mut l, &mut r, pos);
+            }
+            if flags & JOINT_STEREO != 0 {
+                r = r.wrapping_sub(l >> 1);
+                l = l.wrapping_add(r);
+            }
+            crc = crc.wrapping_mul(3).wrapping_add(l as u32).wrapping_mul(3).wrapping_add(r as u32);
+            values.push(l);
+            values.push(r);
+        } else {
+            let mut s = words.get_word(&mut br, 0)?;
+            for pass in &mut passes {
+                s = pass.mono(s, pos);
+            }
+            crc = crc.wrapping_mul(3).wrapping_add(s as u32);
+            values.push(s);
+        }
+        pos = (pos + 1) & 7;
+    }
+    if br.overrun() {
+        return Err("WavPack block is truncated".into());
+    }
+
+    let mut extra = extra_bits.map(BitReader::new);
+    let samples = if flags & FLOAT_DATA != 0 {
+        let info = float_info.ok_or("WavPack float block has no float info")?;
+        float_values(&values, info, extra.as_mut())
+    } else {
+        int_values(values, flags, int32_info, extra.as_mut())
+    };
+
+    // False stereo codes one channel for two identical outputs
+    let (channels, samples) = if flags & FALSE_STEREO != 0 && flags & MONO_FLAG == 0 {
+        (2, samples.iter().flat_map(|&s| [s, s]).collect())
+    } else {
+        (per_channel, samples)
+    };
+    Ok(DecodedBlock { channels, samples, crc_ok: crc == h.crc })
+}
+
+/// Restore low bits dropped by the encoder (32-bit streams), apply the
+/// header shift and normalise to ±1.0.
+fn int_values(mut values: Vec<i32>, flags: u32, int32: Option<Int32Info>, mut extra: Option<&mut BitReader>) -> Vec<f32> {
+    let mut shift = (flags >> SHIFT_LSB) & 0x1f;
+    if let Some(i) = int32 {
+        let (sent, zeros, ones, dups) = (i.sent_bits as u32, i.zeros as u32, i.ones as u32, i.dups as u32);
+        if extra.is_some() || (sent == 0 && zeros + ones + dups > 0) {
+            for v in values.iter_mut() {
+                if let Some(x) = extra.as_deref_mut() {
+                    if sent > 0 {
+                        *v = v.wrapping_shl(sent) | x.bits(sent) as i32;
+                    }
+                }
+                if zeros > 0 {
+                    *v = v.wrapping_shl(zeros);
+                } else if ones > 0 {
+                    *v = v.wrapping_add(1).wrapping_shl(ones).wrapping_sub(1);
+                } else if dups > 0 {
+                    let odd = *v & 1;
+                    *v = v.wrapping_add(odd).wrapping_shl(dups).wrapping_sub(odd);
+                }
+            }
+        } else {
+            shift += zeros + sent + ones + dups;
+        }
+    }
+    shift &= 0x1f;
+
+    let bytes = (flags & BYTES_STORED) + 1;
+    if flags & HYBRID_FLAG != 0 {
+        // Lossy values can overshoot; clip to the stored width
+        let max = (i32::MAX as u32 >> (32 - 8 * bytes)) as i32;
+        let (min_value, max_value) = ((-max - 1) >> shift, max >> shift);
+        for v in values.iter_mut() {
+            *v = (*v).clamp(min_value, max_value) << shift;
+        }
+    } else if shift > 0 {
+        for v in values.iter_mut() {
+            *v = v.wrapping_shl(shift);
+        }
+    }
+
+    let scale = 1.0 / (1u64 << (8 * bytes - 1)) as f32;
+    values.into_iter().map(|v| v as f32 * scale).collect()
+}
+
+/// Rebuild IEEE floats from the integer stream: values are mantissas
+/// relative to `max_exp`, with exceptions, zeros and dropped low bits
+/// recovered from the extra-bits stream when present.
+fn float_values(values: &[i32], info: FloatInfo, mut extra: Option<&mut BitReader>) -> Vec<f32> {
+    values
+        .iter()
+        .map(|&v| {
+            let mut value = v;
+            let mut exp = info.max_exp as i32;
+            let (mut mantissa, mut exponent, mut sign) = (0u32, 0u32, 0u32);
+
+            if value == 0 {
+                if let Some(x) = extra.as_deref_mut() {
+                    if info.flags & FLOAT_ZEROS_SENT != 0 {
+                        if x.bit() == 1 {
+                            mantissa = x.bits(23);
+                            if exp >= 25 {
+                                exponent = x.bits(8);
+                            }
+                            sign = x.bit();
+                        } else if info.flags & FLOAT_NEG_ZEROS != 0 {
+                            sign = x.bit();
+                        }
+                    }
+                }
+            } else {
+                value = value.wrapping_shl(info.shift as u32);
+                if value < 0 {
+                    value = value.wrapping_neg();
+                    sign = 1;
+                }
+                if value >= 0x100_0000 {
+                    if let Some(x) = extra.as_deref_mut() {
+                        // Inf/NaN
+                        if x.bit() == 1 {
+                            mantissa = x.bits(23);
+                        }
+                        exponent = 255;
+                    } else {
+                        while value & 0xf00_0000 != 0 {
+                            value >>= 1;
+                            exp += 1;
+                        }
+                        mantissa = value as u32;
+                        exponent = exp as u32;
+                    }
+                } else {
+                    let mut shift_count = 0u32;
+                    if exp != 0 {
+                        while value & 0x80_0000 == 0 {
+                            exp -= 1;
+                            if exp == 0 {
+                                break;
+                            }
+                            shift_count += 1;
+                            value <<= 1;
+                        }
+                    }
+                    if shift_count > 0 {
+                        let mask = (1i32 << shift_count) - 1;
+                        match extra.as_deref_mut() {
+                            Some(x) => {
+                                if info.flags & FLOAT_SHIFT_ONES != 0
+                                    || (info.flags & FLOAT_SHIFT_SAME != 0 && x.bit() == 1)
+                                {
+                                    value |= mask;
+                                } else if info.flags & FLOAT_SHIFT_SENT != 0 {
+                                    value |= x.bits(shift_count) as i32 & mask;
+                                }
+                            }
+                            None if info.flags & FLOAT_SHIFT_ONES != 0 => value |= mask,
+                            None => {}
+                        }
+                    }
+                    mantissa = value as u32;
+                    exponent = exp as u32;
+                }
+            }
+            f32::from_bits((sign << 31) | ((exponent & 0xff) << 23) | (mantissa & 0x7f_ffff))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::loader::load_audio;
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        len: usize,
+    }
+
+    impl BitWriter {
+        fn bit(&mut self, b: u32) {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if b != 0 {
+                *self.bytes.last_mut().unwrap() |= 1 << (self.len % 8);
+            }
+            self.len += 1;
+        }
+
+        fn bits(&mut self, v: u32, n: u32) {
+            for i in 0..n {
+                self.bit((v >> i) & 1);
+            }
+        }
+
+        /// Inverse of `read_count`.
+        fn count(&mut self, v: u32) {
+            let cbits = 32 - v.leading_zeros();
+            for _ in 0..cbits {
+                self.bit(1);
+            }
+            self.bit(0);
+            if cbits >= 2 {
+                self.bits(v, cbits - 1);
+            }
+        }
+
+        /// Inverse of `read_code`.
+        fn code(&mut self, value: u32, maxcode: u32) {
+            if maxcode < 2 {
+                if maxcode != 0 {
+                    self.bit(value);
+                }
+                return;
+            }
+            let bitcount = 32 - maxcode.leading_zeros();
+            let extras = (1u32 << bitcount) - maxcode - 1;
+            if value < extras {
+                self.bits(value, bitcount - 1);
+            } else {
+                self.bits((value + extras) >> 1, bitcount - 1);
+                self.bit((value + extras) & 1);
+            }
+        }
+    }
+
+    /// Word coder for (channel, value) pairs, mirroring `get_word`. The
+    /// medians must never drop into zero-run mode. With a non-zero
+    /// `error_limit` it codes hybrid (lossy) words and also returns the
+    /// values a decoder will see.
+    fn encode_words(words: &[(usize, i32)], c: [Entropy; 2]) -> Vec<u8> {
+        encode_words_hybrid(words, c, 0).0
+    }
+
+    fn encode_words_hybrid(words: &[(usize, i32)], mut c: [Entropy; 2], error_limit: u32) -> (Vec<u8>, Vec<i32>) {
+        // (ones_count, low, high, magnitude, negative)
+        let coded: Vec<(u32, u32, u32, u32, bool)> = words
+            .iter()
+            .map(|&(chan, v)| {
+                assert!(c[0].median[0] >= 2 || c[1].median[0] >= 2);
+                let e = &mut c[chan];
+                let m = if v < 0 { !v as u32 } else { v as u32 };
+                if m < e.get_med(0) {
+                    let high = e.get_med(0) - 1;
+                    e.dec_med(0);
+                    return (0, 0, high, m, v < 0);
+                }
+                let low = e.get_med(0);
+                e.inc_med(0);
+                if m - low < e.get_med(1) {
+                    let high = low + e.get_med(1) - 1;
+                    e.dec_med(1);
+                    return (1, low, high, m, v < 0);
+                }
+                let low = low + e.get_med(1);
+                e.inc_med(1);
+                let step = e.get_med(2);
+                let k = (m - low) / step;
+                if k == 0 { e.dec_med(2) } else { e.inc_med(2) }
+                (2 + k, low + k * step, low + k * step + step - 1, m, v < 0)
+            })
+            .collect();
+
+        let mut w = BitWriter::default();
+        let mut decoded = Vec::with_capacity(coded.len());
+        let (mut holding_one, mut holding_zero) = (false, false);
+        for (i, &(ones, low, high, m, negative)) in coded.iter().enumerate() {
+            if holding_zero {
+                assert_eq!(ones, 0);
+                holding_zero = false;
+            } else {
+                let next = coded.get(i + 1).is_some_and(|n| n.0 > 0);
+                let t = 2 * (ones - holding_one as u32) + next as u32;
+                for _ in 0..t.min(LIMIT_ONES) {
+                    w.bit(1);
+                }
+                w.bit(0);
+                if t >= LIMIT_ONES {
+                    w.count(t - LIMIT_ONES);
+                }
+                holding_one = next;
+                holding_zero = !next;
+            }
+            let mid = if error_limit == 0 {
+                w.code(m - low, high - low);
+                m
+            } else {
+                let (mut low, mut high) = (low, high);
+                let mut mid = (high + low + 1) >> 1;
+                while high - low > error_limit {
+                    if m >= mid {
+                        w.bit(1);
+                        low = mid;
+                    } else {
+                        w.bit(0);
+                        high = mid - 1;
+                    }
+                    mid = (high + low + 1) >> 1;
+                }
+                mid
+            };
+            w.bit(negative as u32);
+            decoded.push(if negative { !(mid as i32) } else { mid as i32 });
+        }
+        (w.bytes, decoded)
+    }
+
+    fn sub_block(out: &mut Vec<u8>, id: u8, data: &[u8]) {
+        let words = data.len().div_ceil(2);
+        let odd = if data.len().is_multiple_of(2) { 0 } else { ID_ODD_SIZE };
+        if words > 255 {
+            out.push(id | odd | ID_LARGE);
+            out.extend_from_slice(&(words as u32).to_le_bytes()[..3]);
+        } else {
+            out.push(id | odd);
+            out.push(words as u8);
+        }
+        out.extend_from_slice(data);
+        if odd != 0 {
+            out.push(0);
+        }
+    }
+
+    fn block(flags: u32, samples: u32, crc: u32, subs: &[u8]) -> Vec<u8> {
+        let mut out = b"wvpk".to_vec();
+        out.extend_from_slice(&((BLOCK_HEADER_LEN - 8 + subs.len()) as u32).to_le_bytes());
+        out.extend_from_slice(&0x410u16.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        for v in [samples, 0, samples, flags, crc] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(subs);
+        out
+    }
+
+    /// Entropy medians from the log values stored in `ID_ENTROPY_VARS`.
+    fn medians(logs: &[u16]) -> ([Entropy; 2], Vec<u8>) {
+        let mut c = [Entropy::default(); 2];
+        for (i, &l) in logs.iter().enumerate() {
+            c[i / 3].median[i % 3] = exp2s(l as i32) as u32;
+        }
+        (c, logs.iter().flat_map(|l| l.to_le_bytes()).collect())
+    }
+
+    #[test]
+    fn test_mono_decorrelation_custom_rate() {
+        let signal: Vec<i32> = (0..3000).map(|i| ((i as f64 * 0.17).sin() * 3000.0) as i32).collect();
+        // Terms as stored: 17 (delta 2) then 2 (delta 3); decoding runs them in reverse
+        let term_bytes = [(2 << 5) | (17 + 5), (3 << 5) | (2 + 5)];
+        let mut passes: Vec<DecorrPass> = term_bytes
+            .iter()
+            .rev()
+            .map(|&b| DecorrPass { term: (b & 0x1f) as i32 - 5, delta: (b >> 5) as i32, ..Default::default() })
+            .collect();
+
+        let mut crc = 0xffff_ffffu32;
+        let residuals: Vec<(usize, i32)> = signal
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| {
+                crc = crc.wrapping_mul(3).wrapping_add(s as u32);
+                let mut x = s;
+                for pass in passes.iter_mut().rev() {
+                    let (a, j) = DecorrPass::predict(&mut pass.samples_a, pass.term, i & 7);
+                    let t = x - apply_weight(pass.weight_a, a);
+                    update_weight(&mut pass.weight_a, pass.delta, a, t);
+                    pass.samples_a[j] = x;
+                    x = t;
+                }
+                (0, x)
+            })
+            .collect();
+
+        let (c, vars) = medians(&[0x0e00, 0x0e00, 0x0e00]);
+        let mut subs = Vec::new();
+        sub_block(&mut subs, ID_DECORR_TERMS, &term_bytes);
+        sub_block(&mut subs, ID_ENTROPY_VARS, &vars);
+        sub_block(&mut subs, ID_SAMPLE_RATE, &384_000u32.to_le_bytes()[..3]);
+        sub_block(&mut subs, ID_WV_BITSTREAM, &encode_words(&residuals, c));
+        let flags = 1 | MONO_FLAG | INITIAL_BLOCK | FINAL_BLOCK | (15 << SRATE_LSB);
+        let bytes = block(flags, signal.len() as u32, crc, &subs);
+
+        let h = parse_wavpack_header(&bytes).unwrap();
+        assert_eq!((h.sample_rate, h.channels, h.bits_per_sample), (384_000, 1, 16));
+        assert_eq!(h.total_frames, Some(3000));
+
+        let (_, samples) = decode_wavpack(&bytes).unwrap();
+        let expected: Vec<f32> = signal.iter().map(|&s| s as f32 / 32768.0).collect();
+        assert_eq!(samples, expected);
+        assert!(decode_block(&block_header(&bytes).unwrap(), &bytes[BLOCK_HEADER_LEN..]).unwrap().crc_ok);
+    }
+
+    #[test]
+    fn test_joint_stereo_24bit_with_riff_metadata() {
+        let frames: Vec<(i32, i32)> = (0..500)
+            .map(|i| {
+                let l = ((i as f64 * 0.05).sin() * 4_000_000.0) as i32;
+                (l, l / 2 + i * 7)
+            })
+            .collect();
+        let mut crc = 0xffff_ffffu32;
+        let mut words = Vec::new();
+        for &(l_out, r_out) in &frames {
+            crc = crc.wrapping_mul(3).wrapping_add(l_out as u32).wrapping_mul(3).wrapping_add(r_out as u32);
+            let l = l_out - r_out;
+            words.push((0, l));
+            words.push((1, r_out + (l >> 1)));
+        }
+
+        let mut riff = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+        for v in [1u16, 2] {
+            riff.extend_from_slice(&v.to_le_bytes());
+        }
+        riff.extend_from_slice(&48_000u32.to_le_bytes());
+        riff.extend_from_slice(&(48_000u32 * 6).to_le_bytes());
+        for v in [6u16, 24] {
+            riff.extend_from_slice(&v.to_le_bytes());
+        }
+        riff.extend_from_slice(b"data");
+        riff.extend_from_slice(&3000u32.to_le_bytes());
+        let guano = b"GUANO|Version: 1.0\nNote: packed\n";
+        let mut trailer = b"guan".to_vec();
+        trailer.extend_from_slice(&(guano.len() as u32).to_le_bytes());
+        trailer.extend_from_slice(guano);
+
+        let (c, vars) = medians(&[0x1400; 6]);
+        let mut subs = Vec::new();
+        sub_block(&mut subs, ID_RIFF_HEADER, &riff);
+        sub_block(&mut subs, ID_ENTROPY_VARS, &vars);
+        sub_block(&mut subs, ID_WV_BITSTREAM, &encode_words(&words, c));
+        let flags = 2 | JOINT_STEREO | INITIAL_BLOCK | FINAL_BLOCK | (10 << SRATE_LSB);
+        let mut bytes = block(flags, frames.len() as u32, crc, &subs);
+        let mut subs = Vec::new();
+        sub_block(&mut subs, ID_RIFF_TRAILER, &trailer);
+        bytes.extend(block(0, 0, 0, &subs));
+
+        let audio = load_audio(&bytes).unwrap();
+        assert_eq!(audio.metadata.format, "WavPack");
+        assert_eq!((audio.sample_rate, audio.channels), (48_000, 2));
+        assert_eq!(audio.metadata.bits_per_sample, 24);
+        let g = audio.metadata.guano.unwrap();
+        assert!(g.fields.iter().any(|(k, v)| k == "Note" && v == "packed"));
+
+        let (_, samples) = decode_wavpack(&bytes).unwrap();
+        for (i, &(l, r)) in frames.iter().enumerate() {
+            assert_eq!(samples[2 * i], l as f32 / 8_388_608.0);
+            assert_eq!(samples[2 * i + 1], r as f32 / 8_388_608.0);
+        }
+    }
+
+    #[test]
+    fn test_hybrid_lossy_mono() {
+        let signal: Vec<i32> = (0..2000).map(|i| ((i as f64 * 0.11).sin() * 6000.0) as i32).collect();
+        let words: Vec<(usize, i32)> = signal.iter().map(|&v| (0, v)).collect();
+        // Constant bitrate (no delta): error limit exp2s(0x600) = 64
+        let bitrate = 0x600u16;
+        let limit = exp2s(bitrate as i32) as u32;
+        let (c, vars) = medians(&[0x1400; 3]);
+        let (bitstream, decoded) = encode_words_hybrid(&words, c, limit);
+        let crc = decoded.iter().fold(0xffff_ffffu32, |crc, &s| crc.wrapping_mul(3).wrapping_add(s as u32));
+
+        let mut subs = Vec::new();
+        sub_block(&mut subs, ID_ENTROPY_VARS, &vars);
+        sub_block(&mut subs, ID_HYBRID_PROFILE, &[bitrate.to_le_bytes(), 0u16.to_le_bytes()].concat());
+        sub_block(&mut subs, ID_WV_BITSTREAM, &bitstream);
+        let flags = 1 | MONO_FLAG | HYBRID_FLAG | INITIAL_BLOCK | FINAL_BLOCK | (9 << SRATE_LSB);
+        let bytes = block(flags, signal.len() as u32, crc, &subs);
+
+        let block = decode_block(&block_header(&bytes).unwrap(), &bytes[BLOCK_HEADER_LEN..]).unwrap();
+        assert!(block.crc_ok);
+        let (_, samples) = decode_wavpack(&bytes).unwrap();
+        for ((&s, &d), &orig) in samples.iter().zip(&decoded).zip(&signal) {
+            assert_eq!(s, d as f32 / 32768.0);
+            // Lossy, but never further off than the error limit
+            assert!((d - orig).unsigned_abs() <= limit, "{d} vs {orig}");
+        }
+    }
+
+    #[test]
+    fn test_float_values() {
+        // Mantissas relative to max_exp 127: 0x800000 is 1.0
+        let values = [0x40_0000, -0x20_0000, 0, 0x60_0000, 1];
+        let info = FloatInfo { flags: 0, shift: 0, max_exp: 127 };
+        let floats = float_values(&values, info, None);
+        assert_eq!(&floats[..4], &[0.5, -0.25, 0.0, 0.75]);
+        assert_eq!(floats[4], 2f32.powi(-23));
+
+        let words: Vec<(usize, i32)> = values.iter().map(|&v| (0, v)).collect();
+        let (c, vars) = medians(&[0x1400; 3]);
+        let mut subs = Vec::new();
+        sub_block(&mut subs, ID_FLOAT_INFO, &[0, 0, 127, 127]);
+        sub_block(&mut subs, ID_ENTROPY_VARS, &vars);
+        sub_block(&mut subs, ID_WV_BITSTREAM, &encode_words(&words, c));
+        let flags = 3 | MONO_FLAG | FLOAT_DATA | INITIAL_BLOCK | FINAL_BLOCK | (9 << SRATE_LSB);
+        let bytes = block(flags, values.len() as u32, 0, &subs);
+        let (h, samples) = decode_wavpack(&bytes).unwrap();
+        assert!(h.is_float);
+        assert_eq!((h.sample_rate, h.bits_per_sample), (44_100, 32));
+        assert_eq!(samples, floats);
+    }
+
+    #[test]
+    fn test_malformed_block_sizes() {
+        // 64 bytes claiming ~2^31 samples per block: rejected, not allocated
+        let flags = 1 | MONO_FLAG | INITIAL_BLOCK | FINAL_BLOCK | (9 << SRATE_LSB);
+        let mut subs = Vec::new();
+        sub_block(&mut subs, ID_WV_BITSTREAM, &[0xff; 30]);
+        let bytes = block(flags, 0x7fff_ffff, 0, &subs);
+        assert_eq!(bytes.len(), 64);
+        assert!(decode_wavpack(&bytes).is_err());
+
+        // A block length near usize::MAX ends the block scan
+        let mut bytes = block(flags, 16, 0, &subs);
+        bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(blocks(&bytes).count(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+//! Soundscape acoustic indices for triaging long recordings.
+//!
+//! All indices are computed from a non-overlapping Hann STFT of each time
+//! window (default one minute):
+//!
+//! - **ACI** (Acoustic Complexity Index, Pieretti et al. 2011): per frequency
+//!   bin, the summed absolute frame-to-frame intensity change divided by the
+//!   summed intensity; summed over bins.
+//! - **NDSI** (Normalized Difference Soundscape Index, Kasten et al. 2012):
+//!   `(B − A) / (B + A)` of the power in the biophony (B) and anthrophony (A)
+//!   bands, in −1..1.
+//! - **Hf / Ht / H** (Sueur et al. 2008): Shannon entropy of the mean spectrum
+//!   and of the frame-RMS envelope, each normalised to 0..1; `H = Hf · Ht`.
+//! - **BI** (Bioacoustic Index, Boelman et al. 2007): area of the mean dB
+//!   spectrum above its minimum within the BI band (dB·kHz).
+//!
+//! Bands are configurable; `IndexBands::ultrasonic()` moves biophony and BI
+//! into the bat range for full-spectrum detector recordings.
+
+use crate::dsp::fft::compute_stft_columns;
+
+/// Frequency bands (Hz) used by NDSI and BI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexBands {
+    pub anthrophony_hz: (f64, f64),
+    pub biophony_hz: (f64, f64),
+    pub bi_hz: (f64, f64),
+}
+
+impl IndexBands {
+    /// The conventional audible-range bands.
+    pub fn audible() -> Self {
+        Self {
+            anthrophony_hz: (1_000.0, 2_000.0),
+            biophony_hz: (2_000.0, 11_000.0),
+            bi_hz: (2_000.0, 8_000.0),
+        }
+    }
+
+    /// Bands for ultrasonic recordings: audible noise vs. the bat range.
+    pub fn ultrasonic() -> Self {
+        Self {
+            anthrophony_hz: (1_000.0, 15_000.0),
+            biophony_hz: (15_000.0, 120_000.0),
+            bi_hz: (15_000.0, 120_000.0),
+        }
+    }
+
+    /// Ultrasonic bands for sample rates above 96 kHz, audible otherwise.
+    pub fn for_sample_rate(sample_rate: u32) -> Self {
+        if sample_rate > 96_000 { Self::ultrasonic() } else { Self::audible() }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexParams {
+    pub fft_size: usize,
+    /// Length of each analysis window (seconds).
+    pub window_secs: f64,
+    /// Bins below this are ignored by ACI and the entropies (Hz).
+    pub min_freq_hz: f64,
+    pub bands: IndexBands,
+}
+
+impl IndexParams {
+    pub fn for_sample_rate(sample_rate: u32) -> Self {
+        Self {
+            fft_size: 512,
+            window_secs: 60.0,
+            min_freq_hz: 0.0,
+            bands: IndexBands::for_sample_rate(sample_rate),
+        }
+    }
+}
+
+/// Indices for one time window (or a whole file, when aggregated).
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexWindow {
+    /// Seconds from file start.
+    pub start_time: f64,
+    pub end_time: f64,
+    pub aci: f64,
+    pub ndsi: f64,
+    pub spectral_entropy: f64,
+    pub temporal_entropy: f64,
+    pub bi: f64,
+}
+
+impl IndexWindow {
+    /// Total entropy `H = Hf · Ht`.
+    pub fn entropy(&self) -> f64 {
+        self.spectral_entropy * self.temporal_entropy
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AcousticIndices {
+    pub windows: Vec<IndexWindow>,
+    /// Duration-weighted mean over the windows.
+    pub overall: IndexWindow,
+}
+
+/// Normalised Shannon entropy (0..1) of non-negative weights.
+fn entropy(values: &[f64]) -> f64 {
+    let total: f64 = values.iter().sum();
+    if values.len() < 2 || total <= 0.0 {
+        return 0.0;
+    }
+    let h: f64 = values
+        .iter()
+        .filter(|&&v| v > 0.0)
+        .map(|&v| {
+            let p = v / total;
+            -p * p.ln()
+        })
+        .sum();
+    h / (values.len() as f64).ln()
+}
+
+/// Indices for one window of samples (`samples[0]` at `start_time`).
+pub fn compute_window(samples: &[f32], sample_rate: u32, start_time: f64, params: &IndexParams) -> IndexWindow {
+    let fft = params.fft_size;
+    let end_time = start_time + samples.len() as f64 / sample_rate.max(1) as f64;
+    let empty = IndexWindow {
+        start_time,
+        end_time,
+        aci: 0.0,
+        ndsi: 0.0,
+        spectral_entropy: 0.0,
+        temporal_entropy: 0.0,
+        bi: 0.0,
+    };
+    if sample_rate == 0 || fft < 4 || samples.len() < fft * 2 {
+        return empty;
+    }
+    let n_frames = samples.len() / fft;
+    let columns = compute_stft_columns(samples, sample_rate, fft, fft, 0, n_frames);
+    let n_bins = fft / 2 + 1;
+    let bin_hz = sample_rate as f64 / fft as f64;
+    let first_bin = ((params.min_freq_hz / bin_hz).ceil() as usize).max(1).min(n_bins - 1);
+    let band_bins = |(lo, hi): (f64, f64)| {
+        let a = ((lo / bin_hz).ceil() as usize).max(1);
+        let b = ((hi / bin_hz).floor() as usize).min(n_bins - 1);
+        a..=b
+    };
+
+    // ACI over bins, and the mean power spectrum
+    let mut aci = 0.0;
+    let mut mean_power = vec![0.0f64; n_bins];
+    for (k, power) in mean_power.iter_mut().enumerate().skip(first_bin) {
+        let (mut diff, mut sum) = (0.0f64, 0.0f64);
+        for (t, col) in columns.iter().enumerate() {
+            let m = col.magnitudes[k] as f64;
+            sum += m;
+            *power += m * m;
+            if t > 0 {
+                diff += (m - columns[t - 1].magnitudes[k] as f64).abs();
+            }
+        }
+        if sum > 0.0 {
+            aci += diff / sum;
+        }
+    }
+    for p in mean_power.iter_mut() {
+        *p /= columns.len() as f64;
+    }
+
+    let band_power = |band| band_bins(band).map(|k| mean_power[k]).sum::<f64>();
+    let (a, b) = (band_power(params.bands.anthrophony_hz), band_power(params.bands.biophony_hz));
+    let ndsi = if a + b > 0.0 { (b - a) / (b + a) } else { 0.0 };
+
+    let spectral_entropy = entropy(&mean_power[first_bin..]);
+    let envelope: Vec<f64> = samples
+        .chunks_exact(fft)
+        .map(|c| (c.iter().map(|&s| (s as f64) * (s as f64)).sum::<f64>() / fft as f64).sqrt())
+        .collect();
+    let temporal_entropy = entropy(&envelope);
+
+    let bi_db: Vec<f64> = band_bins(params.bands.bi_hz)
+        .map(|k| 10.0 * mean_power[k].max(1e-20).log10())
+        .collect();
+    let bi = match bi_db.iter().cloned().reduce(f64::min) {
+        Some(min) => bi_db.iter().map(|v| v - min).sum::<f64>() * bin_hz / 1000.0,
+        None => 0.0,
+    };
+
+    IndexWindow { aci, ndsi, spectral_entropy, temporal_entropy, bi, ..empty }
+}
+
+/// Duration-weighted mean of per-window indices.
+pub fn aggregate(windows: &[IndexWindow]) -> IndexWindow {
+    let total: f64 = windows.iter().map(|w| w.end_time - w.start_time).sum();
+    let mean = |f: fn(&IndexWindow) -> f64| {
+        if total > 0.0 {
+            windows.iter().map(|w| f(w) * (w.end_time - w.start_time)).sum::<f64>() / total
+        } else {
+            0.0
+        }
+    };
+    IndexWindow {
+        start_time: windows.first().map(|w| w.start_time).unwrap_or(0.0),
+        end_time: windows.last().map(|w| w.end_time).unwrap_or(0.0),
+        aci: mean(|w| w.aci),
+        ndsi: mean(|w| w.ndsi),
+        spectral_entropy: mean(|w| w.spectral_entropy),
+        temporal_entropy: mean(|w| w.temporal_entropy),
+        bi: mean(|w| w.bi),
+    }
+}
+
+/// Sample ranges of the analysis windows over `total` samples. A trailing
+/// window shorter than half the window length is merged into the previous
+/// one, so a short tail adds no noisy window of its own.
+pub fn window_bounds(total: u64, sample_rate: u32, params: &IndexParams) -> Vec<(u64, u64)> {
+    let win = ((params.window_secs * sample_rate as f64) as u64).max(params.fft_size as u64 * 2);
+    let mut bounds: Vec<(u64, u64)> = (0..total).step_by(win as usize).map(|s| (s, (s + win).min(total))).collect();
+    if bounds.len() > 1 && bounds[bounds.len() - 1].1 - bounds[bounds.len() - 1].0 < win / 2 {
+        let last = bounds.pop().unwrap_or_default();
+        if let Some(prev) = bounds.last_mut() {
+            prev.1 = last.1;
+        }
+    }
+    bounds
+}
+
+/// Split `samples` (`samples[0]` at `start_time`) into windows (see
+/// [`window_bounds`]) and compute the indices for each.
+pub fn analyze_indices(samples: &[f32], sample_rate: u32, start_time: f64, params: &IndexParams) -> AcousticIndices {
+    let windows: Vec<IndexWindow> = window_bounds(samples.len() as u64, sample_rate, params)
+        .into_iter()
+        .map(|(s, e)| {
+            let (s, e) = (s as usize, e as usize);
+            compute_window(&samples[s..e], sample_rate, start_time + s as f64 / sample_rate as f64, params)
+        })
+        .collect();
+    let overall = aggregate(&windows);
+    AcousticIndices { windows, overall }
+}
+
+/// Tab-separated per-window table for spreadsheets.
+pub fn indices_tsv(windows: &[IndexWindow]) -> String {
+    let mut out = String::from("start_s\tend_s\taci\tndsi\th_spectral\th_temporal\th_total\tbi\n");
+    for w in windows {
+        out.push_str(&format!(
+            "{:.1}\t{:.1}\t{:.2}\t{:.3}\t{:.3}\t{:.3}\t{:.3}\t{:.2}\n",
+            w.start_time, w.end_time, w.aci, w.ndsi, w.spectral_entropy, w.temporal_entropy, w.entropy(), w.bi,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: u32 = 256_000;
+
+    fn noise(len: usize, amp: f32) -> Vec<f32> {
+        let mut x = 987_654_321u32;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                (x as f32 / u32::MAX as f32 - 0.5) * 2.0 * amp
+            })
+            .collect()
+    }
+
+    fn tone(len: usize, freq: f64, amp: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| amp * (2.0 * std::f64::consts::PI * freq * i as f64 / SR as f64).sin() as f32)
+            .collect()
+    }
+
+    fn params() -> IndexParams {
+        IndexParams { window_secs: 1.0, ..IndexParams::for_sample_rate(SR) }
+    }
+
+    #[test]
+    fn test_entropy_noise_vs_tone() {
+        let n = SR as usize;
+        let w_noise = compute_window(&noise(n, 0.3), SR, 0.0, &params());
+        let w_tone = compute_window(&tone(n, 40_000.0, 0.3), SR, 0.0, &params());
+        assert!(w_noise.spectral_entropy > 0.9, "{w_noise:?}");
+        assert!(w_tone.spectral_entropy < 0.3, "{w_tone:?}");
+        // Both are stationary: flat envelope
+        assert!(w_noise.temporal_entropy > 0.99 && w_tone.temporal_entropy > 0.99);
+    }
+
+    #[test]
+    fn test_ndsi_sign_follows_band_energy() {
+        let n = SR as usize;
+        let floor = noise(n, 0.001);
+        let with_floor = |a: Vec<f32>| a.iter().zip(floor.iter()).map(|(x, y)| x + y).collect::<Vec<f32>>();
+        let bat = compute_window(&with_floor(tone(n, 45_000.0, 0.3)), SR, 0.0, &params());
+        let hum = compute_window(&with_floor(tone(n, 5_000.0, 0.3)), SR, 0.0, &params());
+        assert!(bat.ndsi > 0.9, "{bat:?}");
+        assert!(hum.ndsi < -0.9, "{hum:?}");
+        assert!(bat.bi > hum.bi);
+    }
+
+    #[test]
+    fn test_aci_higher_for_intermittent_calls() {
+        let n = SR as usize;
+        let steady = tone(n, 45_000.0, 0.3);
+        // Same tone gated on/off every 50 ms
+        let gated: Vec<f32> = steady
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| if (i / (SR as usize / 20)).is_multiple_of(2) { s } else { 0.0 })
+            .collect();
+        let base = noise(n, 0.001);
+        let mix = |a: &[f32]| a.iter().zip(base.iter()).map(|(x, y)| x + y).collect::<Vec<f32>>();
+        let w_steady = compute_window(&mix(&steady), SR, 0.0, &params());
+        let w_gated = compute_window(&mix(&gated), SR, 0.0, &params());
+        assert!(w_gated.aci > w_steady.aci, "{} vs {}", w_gated.aci, w_steady.aci);
+        assert!(w_gated.temporal_entropy < w_steady.temporal_entropy);
+    }
+
+    #[test]
+    fn test_windows_and_aggregate() {
+        let samples = noise((2.3 * SR as f64) as usize, 0.1);
+        let result = analyze_indices(&samples, SR, 10.0, &params());
+        // 2.3 s → windows of 1 s and 1.3 s (short tail merged)
+        assert_eq!(result.windows.len(), 2);
+        assert_eq!(result.windows[0].start_time, 10.0);
+        assert!((result.windows[1].end_time - 12.3).abs() < 1e-6);
+        assert!((result.overall.end_time - 12.3).abs() < 1e-6);
+        let lo = result.windows.iter().map(|w| w.spectral_entropy).fold(f64::INFINITY, f64::min);
+        let hi = result.windows.iter().map(|w| w.spectral_entropy).fold(0.0, f64::max);
+        assert!(result.overall.spectral_entropy >= lo && result.overall.spectral_entropy <= hi);
+    }
+}
@@ -0,0 +1,300 @@
+//! Bat pass segmentation and activity indices.
+//!
+//! A *bat pass* is a train of pulses where no gap between consecutive pulses
+//! exceeds `PassParams::max_gap_secs`. Pulses are placed on an absolute
+//! (epoch) time axis so passes can span file boundaries within a sequence.
+//!
+//! The activity summary reports, per local clock hour and per night:
+//! pass count, pulse count and minutes-with-activity (the number of distinct
+//! clock minutes containing at least one pulse, as used by the Miller
+//! activity index).
+
+const MS_PER_MINUTE: f64 = 60_000.0;
+const MS_PER_HOUR: f64 = 3_600_000.0;
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+/// A pulse placed on the absolute time axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimedPulse {
+    /// Which recording the pulse came from (caller-defined, e.g. a file index).
+    pub source: usize,
+    pub start_epoch_ms: f64,
+    pub end_epoch_ms: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct PassParams {
+    /// Maximum silence between pulses of the same pass (seconds).
+    pub max_gap_secs: f64,
+    /// Minimum pulses for a train to count as a pass.
+    pub min_pulses: usize,
+}
+
+impl Default for PassParams {
+    fn default() -> Self {
+        Self { max_gap_secs: 1.0, min_pulses: 2 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatPass {
+    pub start_epoch_ms: f64,
+    pub end_epoch_ms: f64,
+    pub pulse_count: usize,
+    /// Sources the pass spans (usually one; two when it crosses a file boundary).
+    pub sources: Vec<usize>,
+}
+
+impl BatPass {
+    pub fn duration_secs(&self) -> f64 {
+        (self.end_epoch_ms - self.start_epoch_ms) / 1000.0
+    }
+}
+
+/// Group pulses into passes. Pulses need not be sorted.
+pub fn segment_passes(pulses: &[TimedPulse], params: &PassParams) -> Vec<BatPass> {
+    let mut sorted: Vec<&TimedPulse> = pulses.iter().collect();
+    sorted.sort_by(|a, b| a.start_epoch_ms.partial_cmp(&b.start_epoch_ms).unwrap_or(std::cmp::Ordering::Equal));
+
+    let max_gap_ms = params.max_gap_secs * 1000.0;
+    let mut passes = Vec::new();
+    let mut current: Option<BatPass> = None;
+
+    for p in sorted {
+        if let Some(ref mut pass) = current {
+            if p.start_epoch_ms - pass.end_epoch_ms <= max_gap_ms {
+                pass.end_epoch_ms = pass.end_epoch_ms.max(p.end_epoch_ms);
+                pass.pulse_count += 1;
+                if !pass.sources.contains(&p.source) {
+                    pass.sources.push(p.source);
+                }
+                continue;
+            }
+        }
+        if let Some(pass) = current.take() {
+            if pass.pulse_count >= params.min_pulses {
+                passes.push(pass);
+            }
+        }
+        current = Some(BatPass {
+            start_epoch_ms: p.start_epoch_ms,
+            end_epoch_ms: p.end_epoch_ms,
+            pulse_count: 1,
+            sources: vec![p.source],
+        });
+    }
+    if let Some(pass) = current {
+        if pass.pulse_count >= params.min_pulses {
+            passes.push(pass);
+        }
+    }
+    passes
+}
+
+/// Activity within one local clock hour.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HourActivity {
+    /// Start of the hour (epoch ms, UTC).
+    pub hour_start_epoch_ms: f64,
+    /// Passes starting in this hour.
+    pub passes: usize,
+    pub pulses: usize,
+    /// Distinct minutes in this hour containing at least one pulse.
+    pub active_minutes: usize,
+    /// Minutes of this hour covered by recordings.
+    pub recorded_minutes: f64,
+}
+
+/// Activity for one night (local noon to noon).
+#[derive(Clone, Debug, PartialEq)]
+pub struct NightActivity {
+    /// Local noon that starts the night (epoch ms, UTC).
+    pub night_start_epoch_ms: f64,
+    pub passes: usize,
+    pub pulses: usize,
+    pub active_minutes: usize,
+    pub recorded_minutes: f64,
+    /// Passes per recorded hour.
+    pub passes_per_hour: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActivitySummary {
+    pub hours: Vec<HourActivity>,
+    pub nights: Vec<NightActivity>,
+    pub total_passes: usize,
+    pub total_pulses: usize,
+    pub total_active_minutes: usize,
+}
+
+/// Bucket index of `epoch_ms` in local time, for buckets of `size_ms`
+/// starting `phase_ms` after local midnight.
+fn local_bucket(epoch_ms: f64, utc_offset_minutes: i32, size_ms: f64, phase_ms: f64) -> i64 {
+    let local = epoch_ms + utc_offset_minutes as f64 * MS_PER_MINUTE - phase_ms;
+    (local / size_ms).floor() as i64
+}
+
+fn bucket_start(bucket: i64, utc_offset_minutes: i32, size_ms: f64, phase_ms: f64) -> f64 {
+    bucket as f64 * size_ms + phase_ms - utc_offset_minutes as f64 * MS_PER_MINUTE
+}
+
+/// Summarise activity per local hour and per night.
+///
+/// `recorded_spans` are the `(start, end)` epoch-ms spans of the recordings;
+/// hours covered by a recording are reported even when silent, so zero
+/// activity is distinguishable from no recording. `utc_offset_minutes` is the
+/// local time offset (e.g. +600 for AEST).
+pub fn activity_summary(
+    pulses: &[TimedPulse],
+    passes: &[BatPass],
+    recorded_spans: &[(f64, f64)],
+    utc_offset_minutes: i32,
+) -> ActivitySummary {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    let hour_of = |t: f64| local_bucket(t, utc_offset_minutes, MS_PER_HOUR, 0.0);
+    let minute_of = |t: f64| local_bucket(t, utc_offset_minutes, MS_PER_MINUTE, 0.0);
+
+    #[derive(Default)]
+    struct Acc {
+        passes: usize,
+        pulses: usize,
+        minutes: BTreeSet<i64>,
+        recorded_ms: f64,
+    }
+    let mut hours: BTreeMap<i64, Acc> = BTreeMap::new();
+
+    // Recording coverage, split at hour boundaries
+    for &(start, end) in recorded_spans {
+        let mut t = start;
+        while t < end {
+            let h = hour_of(t);
+            let h_end = bucket_start(h + 1, utc_offset_minutes, MS_PER_HOUR, 0.0);
+            let seg_end = end.min(h_end);
+            hours.entry(h).or_default().recorded_ms += seg_end - t;
+            t = seg_end;
+        }
+    }
+    for p in pulses {
+        let acc = hours.entry(hour_of(p.start_epoch_ms)).or_default();
+        acc.pulses += 1;
+        acc.minutes.insert(minute_of(p.start_epoch_ms));
+    }
+    for pass in passes {
+        hours.entry(hour_of(pass.start_epoch_ms)).or_default().passes += 1;
+    }
+
+    let mut summary = ActivitySummary::default();
+    let mut nights: BTreeMap<i64, NightActivity> = BTreeMap::new();
+    let noon = MS_PER_HOUR * 12.0;
+
+    for (h, acc) in hours {
+        let hour_start = bucket_start(h, utc_offset_minutes, MS_PER_HOUR, 0.0);
+        let hour = HourActivity {
+            hour_start_epoch_ms: hour_start,
+            passes: acc.passes,
+            pulses: acc.pulses,
+            active_minutes: acc.minutes.len(),
+            recorded_minutes: acc.recorded_ms / MS_PER_MINUTE,
+        };
+        let n = local_bucket(hour_start, utc_offset_minutes, MS_PER_DAY, noon);
+        let night = nights.entry(n).or_insert_with(|| NightActivity {
+            night_start_epoch_ms: bucket_start(n, utc_offset_minutes, MS_PER_DAY, noon),
+            passes: 0,
+            pulses: 0,
+            active_minutes: 0,
+            recorded_minutes: 0.0,
+            passes_per_hour: 0.0,
+        });
+        night.passes += hour.passes;
+        night.pulses += hour.pulses;
+        night.active_minutes += hour.active_minutes;
+        night.recorded_minutes += hour.recorded_minutes;
+
+        summary.total_passes += hour.passes;
+        summary.total_pulses += hour.pulses;
+        summary.total_active_minutes += hour.active_minutes;
+        summary.hours.push(hour);
+    }
+
+    summary.nights = nights
+        .into_values()
+        .map(|mut n| {
+            if n.recorded_minutes > 0.0 {
+                n.passes_per_hour = n.passes as f64 / (n.recorded_minutes / 60.0);
+            }
+            n
+        })
+        .collect();
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-01T00:00:00Z
+    const T0: f64 = 1_704_067_200_000.0;
+
+    fn p(source: usize, start_ms: f64) -> TimedPulse {
+        TimedPulse { source, start_epoch_ms: T0 + start_ms, end_epoch_ms: T0 + start_ms + 5.0 }
+    }
+
+    #[test]
+    fn test_segment_passes() {
+        let pulses = vec![
+            p(0, 0.0), p(0, 100.0), p(0, 200.0),
+            // 3 s gap → new pass, crossing into the next file
+            p(0, 3200.0), p(1, 3300.0),
+            // isolated single pulse → dropped
+            p(1, 10_000.0),
+        ];
+        let passes = segment_passes(&pulses, &PassParams::default());
+        assert_eq!(passes.len(), 2);
+        assert_eq!(passes[0].pulse_count, 3);
+        assert_eq!(passes[1].sources, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_unsorted_input() {
+        let pulses = vec![p(0, 200.0), p(0, 0.0), p(0, 100.0)];
+        let passes = segment_passes(&pulses, &PassParams::default());
+        assert_eq!(passes.len(), 1);
+        assert_eq!(passes[0].start_epoch_ms, T0);
+    }
+
+    #[test]
+    fn test_activity_summary() {
+        // Pulses at 20:00:10, 20:00:20 (same minute), 20:05:00, and 21:30:00 local (UTC+10)
+        let local = |h: f64, m: f64, s: f64| (h - 10.0) * MS_PER_HOUR + m * MS_PER_MINUTE + s * 1000.0;
+        let pulses = vec![
+            p(0, local(20.0, 0.0, 10.0)),
+            p(0, local(20.0, 0.0, 20.0)),
+            p(0, local(20.0, 5.0, 0.0)),
+            p(0, local(21.0, 30.0, 0.0)),
+        ];
+        let passes = segment_passes(&pulses, &PassParams { max_gap_secs: 15.0, min_pulses: 1 });
+        // Recorded 20:00 – 23:00 local
+        let spans = [(T0 + local(20.0, 0.0, 0.0), T0 + local(23.0, 0.0, 0.0))];
+        let s = activity_summary(&pulses, &passes, &spans, 600);
+
+        assert_eq!(s.hours.len(), 3);
+        assert_eq!(s.hours[0].pulses, 3);
+        assert_eq!(s.hours[0].active_minutes, 2);
+        assert_eq!(s.hours[0].passes, 2);
+        assert!((s.hours[0].recorded_minutes - 60.0).abs() < 1e-9);
+        assert_eq!(s.hours[2].pulses, 0);
+        assert_eq!(s.nights.len(), 1);
+        assert_eq!(s.nights[0].passes, 3);
+        assert!((s.nights[0].passes_per_hour - 1.0).abs() < 1e-9);
+        assert_eq!(s.total_active_minutes, 3);
+    }
+
+    #[test]
+    fn test_night_boundary_is_local_noon() {
+        // 11:00 and 13:00 local on the same date belong to different nights
+        let pulses = vec![p(0, (11.0 - 10.0) * MS_PER_HOUR), p(0, (13.0 - 10.0) * MS_PER_HOUR)];
+        let s = activity_summary(&pulses, &[], &[], 600);
+        assert_eq!(s.nights.len(), 2);
+    }
+}
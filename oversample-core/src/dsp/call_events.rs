@@ -0,0 +1,282 @@
+//! Sequence-level call events: feeding buzzes and social calls.
+//!
+//! Works on the pulse list from `detect_pulses` plus the per-call
+//! measurements from `measure_calls`:
+//!
+//! - **Feeding buzz**: a run of pulses whose inter-pulse interval collapses to
+//!   a few milliseconds (the terminal phase of a prey capture attempt).
+//! - **Social call**: pulses that are clearly lower in frequency and longer
+//!   than the echolocation calls in the same recording. Adjacent social
+//!   pulses are merged into one interval.
+
+use crate::dsp::call_params::CallParameters;
+use crate::dsp::pulse_detect::DetectedPulse;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallEventKind {
+    FeedingBuzz,
+    SocialCall,
+}
+
+impl CallEventKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::FeedingBuzz => "Feeding buzz",
+            Self::SocialCall => "Social call",
+        }
+    }
+
+    /// Tag applied to annotations created from this event kind.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::FeedingBuzz => "feeding-buzz",
+            Self::SocialCall => "social-call",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallEvent {
+    pub kind: CallEventKind,
+    pub start_time: f64,
+    pub end_time: f64,
+    /// Pulse indices (1-based, `DetectedPulse::index`) that make up the event.
+    pub pulse_indices: Vec<usize>,
+    /// Shortest inter-pulse interval inside the event (ms), if it has 2+ pulses.
+    pub min_ipi_ms: Option<f64>,
+    /// Lowest and highest peak frequency over the event's pulses (Hz).
+    pub freq_low_hz: f64,
+    pub freq_high_hz: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct CallEventParams {
+    /// Inter-pulse interval (ms) at or below which pulses count as buzz pulses.
+    pub buzz_max_ipi_ms: f64,
+    /// Minimum number of pulses in a buzz.
+    pub buzz_min_pulses: usize,
+    /// The buzz IPI must be at most this fraction of the median IPI of the
+    /// sequence — a "sharp drop" rather than a consistently fast pulse rate.
+    pub buzz_ipi_ratio: f64,
+    /// Social calls have Fchar below this fraction of the median echolocation Fchar.
+    pub social_freq_ratio: f64,
+    /// Social calls last at least this multiple of the median call duration.
+    pub social_duration_ratio: f64,
+    /// Social pulses closer than this (ms) are merged into one event.
+    pub social_merge_gap_ms: f64,
+}
+
+impl Default for CallEventParams {
+    fn default() -> Self {
+        Self {
+            buzz_max_ipi_ms: 12.0,
+            buzz_min_pulses: 5,
+            buzz_ipi_ratio: 0.5,
+            social_freq_ratio: 0.7,
+            social_duration_ratio: 1.5,
+            social_merge_gap_ms: 50.0,
+        }
+    }
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Some(values[values.len() / 2])
+}
+
+/// Detect feeding buzzes and social calls. `calls` must be the output of
+/// `measure_calls` for the same `pulses`. Events are returned sorted by start time.
+pub fn detect_call_events(
+    pulses: &[DetectedPulse],
+    calls: &[CallParameters],
+    params: &CallEventParams,
+) -> Vec<CallEvent> {
+    if pulses.is_empty() {
+        return Vec::new();
+    }
+
+    let mut events = detect_buzzes(pulses, params);
+    let in_buzz = |index: usize| {
+        events
+            .iter()
+            .any(|e| e.kind == CallEventKind::FeedingBuzz && e.pulse_indices.contains(&index))
+    };
+    let social = detect_social(pulses, calls, params, &in_buzz);
+    events.extend(social);
+    events.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap_or(std::cmp::Ordering::Equal));
+    events
+}
+
+fn detect_buzzes(pulses: &[DetectedPulse], params: &CallEventParams) -> Vec<CallEvent> {
+    // ipis[i] = interval from pulse i to pulse i+1 (start to start)
+    let ipis: Vec<f64> = pulses
+        .windows(2)
+        .map(|w| (w[1].start_time - w[0].start_time) * 1000.0)
+        .collect();
+    let Some(median_ipi) = median(ipis.clone()) else {
+        return Vec::new();
+    };
+    let max_ipi = params.buzz_max_ipi_ms.min(median_ipi * params.buzz_ipi_ratio);
+    // A file of nothing but buzz pulses has no slower reference rate.
+    let max_ipi = if median_ipi <= params.buzz_max_ipi_ms { params.buzz_max_ipi_ms } else { max_ipi };
+
+    let mut events = Vec::new();
+    let mut i = 0;
+    while i < ipis.len() {
+        if ipis[i] > max_ipi {
+            i += 1;
+            continue;
+        }
+        let run_start = i;
+        while i < ipis.len() && ipis[i] <= max_ipi {
+            i += 1;
+        }
+        // Intervals run_start..i connect pulses run_start..=i
+        let members = &pulses[run_start..=i];
+        if members.len() >= params.buzz_min_pulses {
+            events.push(make_event(
+                CallEventKind::FeedingBuzz,
+                members.iter().collect(),
+                ipis[run_start..i].iter().copied().reduce(f64::min),
+            ));
+        }
+    }
+    events
+}
+
+fn detect_social(
+    pulses: &[DetectedPulse],
+    calls: &[CallParameters],
+    params: &CallEventParams,
+    in_buzz: &dyn Fn(usize) -> bool,
+) -> Vec<CallEvent> {
+    let freq_of = |p: &DetectedPulse| {
+        calls
+            .iter()
+            .find(|c| c.index == p.index)
+            .map(|c| c.f_char_hz)
+            .unwrap_or(p.peak_freq)
+    };
+    // Reference echolocation values from the non-buzz pulses.
+    let reference: Vec<&DetectedPulse> = pulses.iter().filter(|p| !in_buzz(p.index)).collect();
+    let (Some(median_freq), Some(median_dur)) = (
+        median(reference.iter().map(|p| freq_of(p)).collect()),
+        median(reference.iter().map(|p| p.duration_ms()).collect()),
+    ) else {
+        return Vec::new();
+    };
+
+    let is_social = |p: &DetectedPulse| {
+        freq_of(p) < median_freq * params.social_freq_ratio
+            && p.duration_ms() >= median_dur * params.social_duration_ratio
+    };
+
+    let mut events = Vec::new();
+    let mut group: Vec<&DetectedPulse> = Vec::new();
+    for p in reference.iter().copied().filter(|p| is_social(p)) {
+        if let Some(last) = group.last() {
+            if (p.start_time - last.end_time) * 1000.0 > params.social_merge_gap_ms {
+                events.push(social_event(std::mem::take(&mut group)));
+            }
+        }
+        group.push(p);
+    }
+    if !group.is_empty() {
+        events.push(social_event(group));
+    }
+    events
+}
+
+fn social_event(members: Vec<&DetectedPulse>) -> CallEvent {
+    let min_ipi = members
+        .windows(2)
+        .map(|w| (w[1].start_time - w[0].start_time) * 1000.0)
+        .reduce(f64::min);
+    make_event(CallEventKind::SocialCall, members, min_ipi)
+}
+
+fn make_event(kind: CallEventKind, members: Vec<&DetectedPulse>, min_ipi_ms: Option<f64>) -> CallEvent {
+    CallEvent {
+        kind,
+        start_time: members.first().map(|p| p.start_time).unwrap_or(0.0),
+        end_time: members.iter().map(|p| p.end_time).fold(0.0, f64::max),
+        pulse_indices: members.iter().map(|p| p.index).collect(),
+        min_ipi_ms,
+        freq_low_hz: members.iter().map(|p| p.peak_freq).fold(f64::INFINITY, f64::min),
+        freq_high_hz: members.iter().map(|p| p.peak_freq).fold(0.0, f64::max),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pulse(index: usize, start: f64, dur_ms: f64, freq: f64) -> DetectedPulse {
+        DetectedPulse {
+            index,
+            start_time: start,
+            end_time: start + dur_ms / 1000.0,
+            peak_time: start + dur_ms / 2000.0,
+            peak_freq: freq,
+            snr_db: 20.0,
+            peak_amplitude: 0.5,
+        }
+    }
+
+    /// Search-phase pulses every 100 ms, then a buzz at 5 ms, then search again.
+    fn foraging_sequence() -> Vec<DetectedPulse> {
+        let mut times = Vec::new();
+        let mut t = 0.0;
+        for _ in 0..6 {
+            times.push(t);
+            t += 0.1;
+        }
+        for _ in 0..10 {
+            times.push(t);
+            t += 0.005;
+        }
+        t += 0.2;
+        for _ in 0..6 {
+            times.push(t);
+            t += 0.1;
+        }
+        times.iter().enumerate().map(|(i, &t)| pulse(i + 1, t, 1.5, 45000.0)).collect()
+    }
+
+    #[test]
+    fn test_detects_buzz() {
+        let pulses = foraging_sequence();
+        let events = detect_call_events(&pulses, &[], &CallEventParams::default());
+        assert_eq!(events.len(), 1, "events: {events:?}");
+        let buzz = &events[0];
+        assert_eq!(buzz.kind, CallEventKind::FeedingBuzz);
+        // The last search pulse leads into the buzz (100 ms gap) so is not part of it
+        assert_eq!(buzz.pulse_indices.first(), Some(&7));
+        assert_eq!(buzz.pulse_indices.len(), 10);
+        assert!((buzz.min_ipi_ms.unwrap() - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_regular_sequence_has_no_events() {
+        let pulses: Vec<_> = (0..20).map(|i| pulse(i + 1, i as f64 * 0.08, 4.0, 45000.0)).collect();
+        assert!(detect_call_events(&pulses, &[], &CallEventParams::default()).is_empty());
+    }
+
+    #[test]
+    fn test_detects_social_calls() {
+        let mut pulses: Vec<_> = (0..10).map(|i| pulse(i + 1, i as f64 * 0.1, 4.0, 45000.0)).collect();
+        // Two long, low calls close together, then one far away
+        pulses.push(pulse(11, 1.02, 15.0, 20000.0));
+        pulses.push(pulse(12, 1.06, 15.0, 21000.0));
+        pulses.push(pulse(13, 1.60, 12.0, 19000.0));
+        let events = detect_call_events(&pulses, &[], &CallEventParams::default());
+        let social: Vec<_> = events.iter().filter(|e| e.kind == CallEventKind::SocialCall).collect();
+        assert_eq!(social.len(), 2, "events: {events:?}");
+        assert_eq!(social[0].pulse_indices, vec![11, 12]);
+        assert_eq!(social[1].pulse_indices, vec![13]);
+        assert_eq!(social[0].freq_low_hz, 20000.0);
+    }
+}
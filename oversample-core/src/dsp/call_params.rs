@@ -0,0 +1,463 @@
+//! Per-call parameter extraction for detected pulses.
+//!
+//! Follows the spectrogram ridge (peak bin per STFT column) through each
+//! `DetectedPulse` and derives the standard bat-call measurements reported by
+//! most analysis packages: start/end frequency, Fmax/Fmin, characteristic
+//! frequency, knee frequency, frequency of maximum energy, bandwidth, mean
+//! slope and inter-pulse interval.
+
+use crate::dsp::pulse_detect::DetectedPulse;
+use crate::types::SpectrogramData;
+
+/// Measurements for a single call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallParameters {
+    /// 1-based pulse number (same as `DetectedPulse::index`).
+    pub index: usize,
+    /// Call start time (seconds).
+    pub start_time: f64,
+    /// Call duration (ms).
+    pub duration_ms: f64,
+    /// Frequency at the start of the call (Hz).
+    pub f_start_hz: f64,
+    /// Frequency at the end of the call (Hz).
+    pub f_end_hz: f64,
+    /// Highest frequency reached by the ridge (Hz).
+    pub f_max_hz: f64,
+    /// Lowest frequency reached by the ridge (Hz).
+    pub f_min_hz: f64,
+    /// Characteristic frequency: the flattest part of the final 40% of the call (Hz).
+    pub f_char_hz: f64,
+    /// Knee frequency: where the call turns from steep FM into its flatter tail (Hz).
+    pub f_knee_hz: f64,
+    /// Frequency of maximum energy (Hz).
+    pub f_max_energy_hz: f64,
+    /// `f_max_hz - f_min_hz` (Hz).
+    pub bandwidth_hz: f64,
+    /// Mean slope from start to end in kHz/ms. Negative for downward sweeps.
+    pub mean_slope_khz_per_ms: f64,
+    /// Time from the start of the previous call to the start of this one (ms).
+    /// None for the first call.
+    pub ipi_ms: Option<f64>,
+    /// Number of spectrogram columns that contributed to the ridge.
+    /// Zero means no columns were available and frequencies fall back to the
+    /// pulse's peak frequency.
+    pub ridge_points: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct CallMeasurementParams {
+    /// Ridge points more than this many dB below the call's loudest point are
+    /// ignored (trims the faint start/end of the call and noise between harmonics).
+    pub threshold_db: f64,
+    /// Lower bound of the peak search (Hz). 0 = no limit.
+    pub freq_low_hz: f64,
+    /// Upper bound of the peak search (Hz). 0 = Nyquist.
+    pub freq_high_hz: f64,
+}
+
+impl Default for CallMeasurementParams {
+    fn default() -> Self {
+        Self {
+            threshold_db: 20.0,
+            freq_low_hz: 0.0,
+            freq_high_hz: 0.0,
+        }
+    }
+}
+
+/// One point on the spectrogram ridge of a call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RidgePoint {
+    pub time: f64,
+    pub freq: f64,
+    pub magnitude: f32,
+}
+
+/// Measure every pulse in `pulses` against `spectrogram`.
+///
+/// Returns one row per pulse, in the same order. Pulses must be sorted by
+/// start time (as returned by `detect_pulses`) for the inter-pulse interval
+/// to be meaningful.
+pub fn measure_calls(
+    pulses: &[DetectedPulse],
+    spectrogram: &SpectrogramData,
+    params: &CallMeasurementParams,
+) -> Vec<CallParameters> {
+    measure_calls_with_ridges(pulses, spectrogram, params).0
+}
+
+/// `measure_calls`, also returning the ridge each row was measured from so
+/// later stages (shape classification) don't have to extract it again.
+pub fn measure_calls_with_ridges(
+    pulses: &[DetectedPulse],
+    spectrogram: &SpectrogramData,
+    params: &CallMeasurementParams,
+) -> (Vec<CallParameters>, Vec<Vec<RidgePoint>>) {
+    let mut prev_start: Option<f64> = None;
+    pulses
+        .iter()
+        .map(|pulse| {
+            let ridge = extract_ridge(spectrogram, pulse.start_time, pulse.end_time, params);
+            let mut row = parameters_from_ridge(pulse, &ridge);
+            row.ipi_ms = prev_start.map(|t| (pulse.start_time - t) * 1000.0);
+            prev_start = Some(pulse.start_time);
+            (row, ridge)
+        })
+        .unzip()
+}
+
+/// Follow the peak bin of each spectrogram column between `start_time` and
+/// `end_time`, with parabolic interpolation for sub-bin frequency accuracy.
+pub(crate) fn extract_ridge(
+    spectrogram: &SpectrogramData,
+    start_time: f64,
+    end_time: f64,
+    params: &CallMeasurementParams,
+) -> Vec<RidgePoint> {
+    let columns = &spectrogram.columns;
+    if columns.is_empty() || spectrogram.freq_resolution <= 0.0 {
+        return Vec::new();
+    }
+
+    // Column time_offset is the frame start; the frame centre is half a window later.
+    let half_window = 0.5 / spectrogram.freq_resolution;
+    let n_bins = columns[0].magnitudes.len();
+    let lo_bin = if params.freq_low_hz > 0.0 {
+        ((params.freq_low_hz / spectrogram.freq_resolution).floor() as usize).min(n_bins.saturating_sub(1))
+    } else {
+        1 // skip DC
+    };
+    let hi_bin = if params.freq_high_hz > 0.0 {
+        ((params.freq_high_hz / spectrogram.freq_resolution).ceil() as usize).min(n_bins.saturating_sub(1))
+    } else {
+        n_bins.saturating_sub(1)
+    };
+    if hi_bin <= lo_bin {
+        return Vec::new();
+    }
+
+    let mut ridge: Vec<RidgePoint> = columns
+        .iter()
+        .filter(|c| {
+            let t = c.time_offset + half_window;
+            t >= start_time && t <= end_time
+        })
+        .filter_map(|c| ridge_point(&c.magnitudes, lo_bin, hi_bin, c.time_offset + half_window, spectrogram.freq_resolution))
+        .collect();
+
+    // Calls shorter than one hop: use the column nearest the call centre.
+    if ridge.is_empty() {
+        let centre = (start_time + end_time) / 2.0;
+        if let Some(col) = columns.iter().min_by(|a, b| {
+            let da = (a.time_offset + half_window - centre).abs();
+            let db = (b.time_offset + half_window - centre).abs();
+            da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+        }) {
+            ridge.extend(ridge_point(&col.magnitudes, lo_bin, hi_bin, centre, spectrogram.freq_resolution));
+        }
+    }
+
+    let peak = ridge.iter().map(|p| p.magnitude).fold(0.0f32, f32::max);
+    if peak <= 0.0 {
+        return Vec::new();
+    }
+    let floor = peak * 10f32.powf(-(params.threshold_db as f32) / 20.0);
+    ridge.retain(|p| p.magnitude >= floor);
+    ridge
+}
+
+fn ridge_point(
+    magnitudes: &[f32],
+    lo_bin: usize,
+    hi_bin: usize,
+    time: f64,
+    freq_resolution: f64,
+) -> Option<RidgePoint> {
+    let hi_bin = hi_bin.min(magnitudes.len().checked_sub(1)?);
+    if hi_bin <= lo_bin {
+        return None;
+    }
+    let (bin, &mag) = magnitudes[lo_bin..=hi_bin]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(i, m)| (i + lo_bin, m))?;
+    if mag <= 0.0 {
+        return None;
+    }
+    Some(RidgePoint {
+        time,
+        freq: (bin as f64 + parabolic_offset(magnitudes, bin)) * freq_resolution,
+        magnitude: mag,
+    })
+}
+
+/// Sub-bin peak offset (-0.5..0.5) from a parabola through the peak bin and
+/// its neighbours, fitted on log magnitudes.
+pub(crate) fn parabolic_offset(magnitudes: &[f32], bin: usize) -> f64 {
+    if bin == 0 || bin + 1 >= magnitudes.len() {
+        return 0.0;
+    }
+    let a = (magnitudes[bin - 1].max(1e-12) as f64).ln();
+    let b = (magnitudes[bin].max(1e-12) as f64).ln();
+    let c = (magnitudes[bin + 1].max(1e-12) as f64).ln();
+    let denom = a - 2.0 * b + c;
+    if denom.abs() < 1e-12 {
+        return 0.0;
+    }
+    (0.5 * (a - c) / denom).clamp(-0.5, 0.5)
+}
+
+fn parameters_from_ridge(pulse: &DetectedPulse, ridge: &[RidgePoint]) -> CallParameters {
+    let duration_ms = pulse.duration_ms();
+
+    let (Some(first), Some(last)) = (ridge.first(), ridge.last()) else {
+        let f = pulse.peak_freq;
+        return CallParameters {
+            index: pulse.index,
+            start_time: pulse.start_time,
+            duration_ms,
+            f_start_hz: f,
+            f_end_hz: f,
+            f_max_hz: f,
+            f_min_hz: f,
+            f_char_hz: f,
+            f_knee_hz: f,
+            f_max_energy_hz: f,
+            bandwidth_hz: 0.0,
+            mean_slope_khz_per_ms: 0.0,
+            ipi_ms: None,
+            ridge_points: 0,
+        };
+    };
+
+    let f_max_hz = ridge.iter().map(|p| p.freq).fold(f64::MIN, f64::max);
+    let f_min_hz = ridge.iter().map(|p| p.freq).fold(f64::MAX, f64::min);
+    let f_max_energy_hz = ridge
+        .iter()
+        .max_by(|a, b| a.magnitude.partial_cmp(&b.magnitude).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|p| p.freq)
+        .unwrap_or(pulse.peak_freq);
+
+    let mean_slope_khz_per_ms = if duration_ms > 0.0 {
+        (last.freq - first.freq) / 1000.0 / duration_ms
+    } else {
+        0.0
+    };
+
+    CallParameters {
+        index: pulse.index,
+        start_time: pulse.start_time,
+        duration_ms,
+        f_start_hz: first.freq,
+        f_end_hz: last.freq,
+        f_max_hz,
+        f_min_hz,
+        f_char_hz: characteristic_freq(ridge),
+        f_knee_hz: knee_freq(ridge),
+        f_max_energy_hz,
+        bandwidth_hz: f_max_hz - f_min_hz,
+        mean_slope_khz_per_ms,
+        ipi_ms: None,
+        ridge_points: ridge.len(),
+    }
+}
+
+/// Characteristic frequency: the end of the flattest stretch in the final 40%
+/// of the call (the AnalookW definition). Falls back to the end frequency
+/// when there are too few points to measure a slope.
+fn characteristic_freq(ridge: &[RidgePoint]) -> f64 {
+    let n = ridge.len();
+    let Some(last) = ridge.last() else { return 0.0 };
+    if n < 3 {
+        return last.freq;
+    }
+    let t0 = ridge[0].time;
+    let t_split = t0 + (last.time - t0) * 0.6;
+    let tail_start = ridge.iter().position(|p| p.time >= t_split).unwrap_or(n - 1).max(1);
+
+    let mut best_slope = f64::MAX;
+    let mut best_freq = last.freq;
+    for i in tail_start..n {
+        let dt = ridge[i].time - ridge[i - 1].time;
+        if dt <= 0.0 {
+            continue;
+        }
+        let slope = ((ridge[i].freq - ridge[i - 1].freq) / dt).abs();
+        // `<=` so ties move to the later point, i.e. the end of the flat stretch.
+        if slope <= best_slope {
+            best_slope = slope;
+            best_freq = ridge[i].freq;
+        }
+    }
+    best_freq
+}
+
+/// Knee frequency: the ridge point furthest from the straight line joining
+/// the first and last points (maximum-curvature "kneedle" criterion).
+fn knee_freq(ridge: &[RidgePoint]) -> f64 {
+    let (Some(first), Some(last)) = (ridge.first(), ridge.last()) else { return 0.0 };
+    if ridge.len() < 3 {
+        return last.freq;
+    }
+    // Normalise both axes to 0..1 so the distance isn't dominated by Hz.
+    let t_span = (last.time - first.time).max(1e-12);
+    let f_hi = ridge.iter().map(|p| p.freq).fold(f64::MIN, f64::max);
+    let f_lo = ridge.iter().map(|p| p.freq).fold(f64::MAX, f64::min);
+    let f_span = (f_hi - f_lo).max(1e-12);
+    let norm = |p: &RidgePoint| ((p.time - first.time) / t_span, (p.freq - f_lo) / f_span);
+
+    let (x0, y0) = norm(first);
+    let (x1, y1) = norm(last);
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let len = (dx * dx + dy * dy).sqrt().max(1e-12);
+
+    ridge
+        .iter()
+        .map(|p| {
+            let (x, y) = norm(p);
+            ((dy * (x - x0) - dx * (y - y0)).abs() / len, p.freq)
+        })
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(_, f)| f)
+        .unwrap_or(last.freq)
+}
+
+/// Render measurements as a tab-separated table with a header row
+/// (frequencies in kHz, times in ms) for clipboard and file export.
+pub fn call_parameters_tsv(rows: &[CallParameters]) -> String {
+    let mut out = String::from(
+        "Pulse\tStart (s)\tDuration (ms)\tFstart (kHz)\tFend (kHz)\tFmax (kHz)\tFmin (kHz)\tFchar (kHz)\tFknee (kHz)\tFmaxE (kHz)\tBandwidth (kHz)\tSlope (kHz/ms)\tIPI (ms)\n",
+    );
+    for r in rows {
+        let ipi = r.ipi_ms.map(|v| format!("{:.2}", v)).unwrap_or_default();
+        out.push_str(&format!(
+            "{}\t{:.4}\t{:.2}\t{:.1}\t{:.1}\t{:.1}\t{:.1}\t{:.1}\t{:.1}\t{:.1}\t{:.1}\t{:.2}\t{}\n",
+            r.index,
+            r.start_time,
+            r.duration_ms,
+            r.f_start_hz / 1000.0,
+            r.f_end_hz / 1000.0,
+            r.f_max_hz / 1000.0,
+            r.f_min_hz / 1000.0,
+            r.f_char_hz / 1000.0,
+            r.f_knee_hz / 1000.0,
+            r.f_max_energy_hz / 1000.0,
+            r.bandwidth_hz / 1000.0,
+            r.mean_slope_khz_per_ms,
+            ipi,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::fft::compute_stft_columns;
+    use std::sync::Arc;
+
+    const SR: u32 = 384_000;
+
+    /// Linear FM sweep from `f0` to `f1` Hz starting at `t0`, inside silence.
+    fn sweep_audio(total_secs: f64, t0: f64, dur: f64, f0: f64, f1: f64) -> Vec<f32> {
+        let n = (SR as f64 * total_secs) as usize;
+        let mut out = vec![0.0f32; n];
+        let start = (t0 * SR as f64) as usize;
+        let len = (dur * SR as f64) as usize;
+        let k = (f1 - f0) / dur;
+        for i in 0..len {
+            let t = i as f64 / SR as f64;
+            let phase = 2.0 * std::f64::consts::PI * (f0 * t + 0.5 * k * t * t);
+            out[start + i] = phase.sin() as f32 * 0.5;
+        }
+        out
+    }
+
+    fn spectrogram_of(samples: &[f32], fft: usize, hop: usize) -> SpectrogramData {
+        let columns = compute_stft_columns(samples, SR, fft, hop, 0, usize::MAX);
+        SpectrogramData {
+            total_columns: columns.len(),
+            columns: Arc::new(columns),
+            freq_resolution: SR as f64 / fft as f64,
+            time_resolution: hop as f64 / SR as f64,
+            max_freq: SR as f64 / 2.0,
+            sample_rate: SR,
+        }
+    }
+
+    fn pulse(index: usize, start: f64, end: f64) -> DetectedPulse {
+        DetectedPulse {
+            index,
+            start_time: start,
+            end_time: end,
+            peak_time: (start + end) / 2.0,
+            peak_freq: 0.0,
+            snr_db: 30.0,
+            peak_amplitude: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_downward_sweep_parameters() {
+        let samples = sweep_audio(0.05, 0.01, 0.005, 80_000.0, 40_000.0);
+        let spec = spectrogram_of(&samples, 256, 64);
+        let rows = measure_calls(&[pulse(1, 0.01, 0.015)], &spec, &CallMeasurementParams::default());
+        assert_eq!(rows.len(), 1);
+        let r = &rows[0];
+        assert!(r.ridge_points > 5, "ridge_points={}", r.ridge_points);
+        assert!(r.f_start_hz > r.f_end_hz, "start {} end {}", r.f_start_hz, r.f_end_hz);
+        assert!((r.f_max_hz - 80_000.0).abs() < 8_000.0, "Fmax={}", r.f_max_hz);
+        assert!((r.f_min_hz - 40_000.0).abs() < 8_000.0, "Fmin={}", r.f_min_hz);
+        assert!(r.mean_slope_khz_per_ms < -4.0 && r.mean_slope_khz_per_ms > -10.0,
+            "slope={}", r.mean_slope_khz_per_ms);
+        // Linear sweep: every tail segment is equally steep, so Fchar lands at the end.
+        assert!(r.f_char_hz < 50_000.0, "Fchar={}", r.f_char_hz);
+    }
+
+    #[test]
+    fn test_inter_pulse_interval() {
+        let mut samples = sweep_audio(0.1, 0.01, 0.004, 60_000.0, 40_000.0);
+        let second = sweep_audio(0.1, 0.06, 0.004, 60_000.0, 40_000.0);
+        for (a, b) in samples.iter_mut().zip(second.iter()) {
+            *a += *b;
+        }
+        let spec = spectrogram_of(&samples, 256, 64);
+        let rows = measure_calls(
+            &[pulse(1, 0.01, 0.014), pulse(2, 0.06, 0.064)],
+            &spec,
+            &CallMeasurementParams::default(),
+        );
+        assert_eq!(rows[0].ipi_ms, None);
+        let ipi = rows[1].ipi_ms.unwrap();
+        assert!((ipi - 50.0).abs() < 1e-6, "ipi={ipi}");
+    }
+
+    #[test]
+    fn test_empty_spectrogram_falls_back_to_peak_freq() {
+        let spec = SpectrogramData {
+            columns: Arc::new(Vec::new()),
+            total_columns: 0,
+            freq_resolution: 1500.0,
+            time_resolution: 0.001,
+            max_freq: 192_000.0,
+            sample_rate: SR,
+        };
+        let mut p = pulse(1, 0.0, 0.005);
+        p.peak_freq = 45_000.0;
+        let rows = measure_calls(&[p], &spec, &CallMeasurementParams::default());
+        assert_eq!(rows[0].ridge_points, 0);
+        assert_eq!(rows[0].f_char_hz, 45_000.0);
+        assert_eq!(rows[0].bandwidth_hz, 0.0);
+    }
+
+    #[test]
+    fn test_tsv_has_header_and_rows() {
+        let spec = spectrogram_of(&sweep_audio(0.03, 0.01, 0.005, 70_000.0, 35_000.0), 256, 64);
+        let rows = measure_calls(&[pulse(1, 0.01, 0.015)], &spec, &CallMeasurementParams::default());
+        let tsv = call_parameters_tsv(&rows);
+        assert_eq!(tsv.lines().count(), 2);
+        assert!(tsv.starts_with("Pulse\t"));
+    }
+}
@@ -0,0 +1,348 @@
+//! Call-shape classification (CF / FM / QCF / FM-QCF / CF-FM / clicks).
+//!
+//! Looks at the frequency contour of each detected pulse — bandwidth,
+//! duration and how the slope is distributed along the call — and scores it
+//! against each shape class using soft thresholds. The best class wins and
+//! its share of the total score is reported as the confidence.
+//!
+//! Labels use the same vocabulary as the bat book's `call_type` field so
+//! recorded calls can be matched against book entries.
+
+use crate::dsp::call_params::{CallParameters, RidgePoint};
+
+/// Slope (kHz/ms) below which a ridge segment counts as constant frequency.
+const CF_SLOPE_KHZ_PER_MS: f64 = 0.15;
+/// Slope (kHz/ms) below which a ridge segment counts as quasi-constant.
+const FLAT_SLOPE_KHZ_PER_MS: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallShape {
+    /// Constant frequency (long, very narrowband).
+    Cf,
+    /// Frequency modulated (steep, broadband sweep).
+    Fm,
+    /// Quasi-constant frequency (shallow, narrowband sweep).
+    Qcf,
+    /// Steep FM start ending in a QCF tail (e.g. pipistrelles).
+    FmQcf,
+    /// Long CF component with FM sweeps (rhinolophids, hipposiderids).
+    CfFm,
+    /// Very short broadband transients (tongue clicks).
+    Clicks,
+}
+
+impl CallShape {
+    pub const ALL: &'static [CallShape] = &[
+        Self::Cf,
+        Self::Fm,
+        Self::Qcf,
+        Self::FmQcf,
+        Self::CfFm,
+        Self::Clicks,
+    ];
+
+    /// Label matching the bat book `call_type` vocabulary.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Cf => "CF",
+            Self::Fm => "FM",
+            Self::Qcf => "QCF",
+            Self::FmQcf => "FM-QCF",
+            Self::CfFm => "CF-FM",
+            Self::Clicks => "clicks",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|s| s.label().eq_ignore_ascii_case(label.trim()))
+    }
+
+    /// How well this shape fits a bat book `call_type` string.
+    ///
+    /// 1.0 for an exact match, 0.5 for a related shape (e.g. an FM-QCF call
+    /// recorded without its tail looks like FM), 0.0 otherwise.
+    pub fn book_compatibility(self, call_type: &str) -> f64 {
+        let call_type = call_type.trim();
+        if call_type.eq_ignore_ascii_case(self.label()) {
+            return 1.0;
+        }
+        // Compound book types ("FM clicks", "FM-QCF", "CF-FM") partially match their parts.
+        let parts: Vec<String> = call_type
+            .split([' ', '-'])
+            .filter(|p| !p.is_empty())
+            .map(|p| p.to_ascii_lowercase())
+            .collect();
+        let own_parts: Vec<String> = self
+            .label()
+            .split('-')
+            .map(|p| p.to_ascii_lowercase())
+            .collect();
+        if own_parts.iter().any(|p| parts.contains(p)) {
+            0.5
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Contour features the classifier works from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShapeFeatures {
+    pub duration_ms: f64,
+    pub bandwidth_khz: f64,
+    /// Mean absolute slope over the whole call (kHz/ms).
+    pub mean_abs_slope: f64,
+    /// Mean absolute slope over the first 40% of the call (kHz/ms).
+    pub head_abs_slope: f64,
+    /// Fraction of the call duration that is constant frequency.
+    pub cf_fraction: f64,
+    /// Fraction of the call duration that is quasi-constant.
+    pub flat_fraction: f64,
+    /// Fraction of the final 40% of the call that is quasi-constant.
+    pub tail_flat_fraction: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShapeClassification {
+    /// 1-based pulse number (same as `DetectedPulse::index`).
+    pub index: usize,
+    pub shape: CallShape,
+    /// Share of the winning class in the total score (0..1).
+    pub confidence: f64,
+    pub features: ShapeFeatures,
+}
+
+/// Classify each call. `calls` and `ridges` are the output of
+/// `measure_calls_with_ridges`.
+pub fn classify_calls(calls: &[CallParameters], ridges: &[Vec<RidgePoint>]) -> Vec<ShapeClassification> {
+    calls
+        .iter()
+        .zip(ridges.iter())
+        .map(|(call, ridge)| {
+            let features = shape_features(call, ridge);
+            let (shape, confidence) = classify_features(&features);
+            ShapeClassification { index: call.index, shape, confidence, features }
+        })
+        .collect()
+}
+
+fn shape_features(call: &CallParameters, ridge: &[RidgePoint]) -> ShapeFeatures {
+    let mut features = ShapeFeatures {
+        duration_ms: call.duration_ms,
+        bandwidth_khz: call.bandwidth_hz / 1000.0,
+        mean_abs_slope: 0.0,
+        head_abs_slope: 0.0,
+        cf_fraction: 0.0,
+        flat_fraction: 0.0,
+        tail_flat_fraction: 0.0,
+    };
+    if ridge.len() < 2 {
+        return features;
+    }
+
+    let t0 = ridge[0].time;
+    let span = ridge[ridge.len() - 1].time - t0;
+    if span <= 0.0 {
+        return features;
+    }
+    let head_end = t0 + span * 0.4;
+    let tail_start = t0 + span * 0.6;
+
+    let (mut total, mut head_total, mut tail_total) = (0.0, 0.0, 0.0);
+    let (mut slope_sum, mut head_slope_sum) = (0.0, 0.0);
+    let (mut cf_time, mut flat_time, mut tail_flat_time) = (0.0, 0.0, 0.0);
+
+    for w in ridge.windows(2) {
+        let dt = w[1].time - w[0].time;
+        if dt <= 0.0 {
+            continue;
+        }
+        let slope = ((w[1].freq - w[0].freq) / 1000.0 / (dt * 1000.0)).abs();
+        let mid = (w[0].time + w[1].time) / 2.0;
+        total += dt;
+        slope_sum += slope * dt;
+        if slope < CF_SLOPE_KHZ_PER_MS {
+            cf_time += dt;
+        }
+        if slope < FLAT_SLOPE_KHZ_PER_MS {
+            flat_time += dt;
+        }
+        if mid <= head_end {
+            head_total += dt;
+            head_slope_sum += slope * dt;
+        }
+        if mid >= tail_start {
+            tail_total += dt;
+            if slope < FLAT_SLOPE_KHZ_PER_MS {
+                tail_flat_time += dt;
+            }
+        }
+    }
+
+    if total > 0.0 {
+        features.mean_abs_slope = slope_sum / total;
+        features.cf_fraction = cf_time / total;
+        features.flat_fraction = flat_time / total;
+    }
+    if head_total > 0.0 {
+        features.head_abs_slope = head_slope_sum / head_total;
+    }
+    if tail_total > 0.0 {
+        features.tail_flat_fraction = tail_flat_time / tail_total;
+    }
+    features
+}
+
+/// Linear ramp from 0 at `lo` to 1 at `hi`.
+fn ramp(x: f64, lo: f64, hi: f64) -> f64 {
+    ((x - lo) / (hi - lo)).clamp(0.0, 1.0)
+}
+
+/// Score each class and return the winner with its share of the total score.
+pub fn classify_features(f: &ShapeFeatures) -> (CallShape, f64) {
+    let clicks = 1.0 - ramp(f.duration_ms, 0.5, 1.5);
+    let not_click = 1.0 - clicks;
+    let long = ramp(f.duration_ms, 5.0, 12.0);
+    let broadband = ramp(f.bandwidth_khz, 8.0, 15.0);
+
+    let scores = [
+        (CallShape::Clicks, clicks),
+        (
+            CallShape::Cf,
+            not_click * long * ramp(f.cf_fraction, 0.6, 0.85) * (1.0 - ramp(f.bandwidth_khz, 4.0, 10.0)),
+        ),
+        (
+            CallShape::CfFm,
+            not_click * long * ramp(f.cf_fraction, 0.5, 0.75) * ramp(f.bandwidth_khz, 4.0, 10.0),
+        ),
+        (
+            CallShape::Qcf,
+            not_click
+                * (1.0 - broadband)
+                * ramp(f.flat_fraction, 0.6, 0.9)
+                * (1.0 - ramp(f.cf_fraction, 0.6, 0.85) * long),
+        ),
+        (
+            CallShape::FmQcf,
+            not_click
+                * broadband
+                * ramp(f.tail_flat_fraction, 0.4, 0.8)
+                * ramp(f.head_abs_slope, 2.0, 5.0)
+                * (1.0 - ramp(f.cf_fraction, 0.5, 0.75) * long),
+        ),
+        (
+            CallShape::Fm,
+            not_click
+                * broadband
+                * (1.0 - ramp(f.tail_flat_fraction, 0.4, 0.8))
+                * ramp(f.mean_abs_slope, 1.0, 3.0),
+        ),
+    ];
+
+    let total: f64 = scores.iter().map(|(_, s)| s).sum();
+    let (shape, best) = scores
+        .iter()
+        .copied()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or((CallShape::Fm, 0.0));
+
+    if total <= 1e-9 {
+        // Nothing fits well: a short sweep is most likely a partial FM call.
+        return (CallShape::Fm, 0.0);
+    }
+    (shape, best / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(duration_ms: f64, bandwidth_khz: f64) -> ShapeFeatures {
+        ShapeFeatures {
+            duration_ms,
+            bandwidth_khz,
+            mean_abs_slope: 0.0,
+            head_abs_slope: 0.0,
+            cf_fraction: 0.0,
+            flat_fraction: 0.0,
+            tail_flat_fraction: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_pure_cf() {
+        let f = ShapeFeatures {
+            cf_fraction: 0.95,
+            flat_fraction: 1.0,
+            tail_flat_fraction: 1.0,
+            mean_abs_slope: 0.05,
+            ..features(40.0, 1.0)
+        };
+        let (shape, conf) = classify_features(&f);
+        assert_eq!(shape, CallShape::Cf);
+        assert!(conf > 0.5, "conf={conf}");
+    }
+
+    #[test]
+    fn test_cf_with_terminal_fm() {
+        let f = ShapeFeatures {
+            cf_fraction: 0.85,
+            flat_fraction: 0.9,
+            tail_flat_fraction: 0.7,
+            mean_abs_slope: 0.8,
+            head_abs_slope: 0.1,
+            ..features(45.0, 18.0)
+        };
+        assert_eq!(classify_features(&f).0, CallShape::CfFm);
+    }
+
+    #[test]
+    fn test_steep_fm() {
+        let f = ShapeFeatures {
+            mean_abs_slope: 12.0,
+            head_abs_slope: 15.0,
+            tail_flat_fraction: 0.1,
+            ..features(3.0, 45.0)
+        };
+        assert_eq!(classify_features(&f).0, CallShape::Fm);
+    }
+
+    #[test]
+    fn test_fm_qcf() {
+        let f = ShapeFeatures {
+            mean_abs_slope: 4.0,
+            head_abs_slope: 8.0,
+            flat_fraction: 0.55,
+            tail_flat_fraction: 0.9,
+            ..features(6.0, 25.0)
+        };
+        assert_eq!(classify_features(&f).0, CallShape::FmQcf);
+    }
+
+    #[test]
+    fn test_qcf() {
+        let f = ShapeFeatures {
+            mean_abs_slope: 0.4,
+            head_abs_slope: 0.5,
+            cf_fraction: 0.1,
+            flat_fraction: 1.0,
+            tail_flat_fraction: 1.0,
+            ..features(18.0, 6.0)
+        };
+        assert_eq!(classify_features(&f).0, CallShape::Qcf);
+    }
+
+    #[test]
+    fn test_click() {
+        assert_eq!(classify_features(&features(0.3, 40.0)).0, CallShape::Clicks);
+    }
+
+    #[test]
+    fn test_book_compatibility() {
+        assert_eq!(CallShape::FmQcf.book_compatibility("FM-QCF"), 1.0);
+        assert_eq!(CallShape::Fm.book_compatibility("FM-QCF"), 0.5);
+        assert_eq!(CallShape::Clicks.book_compatibility("FM clicks"), 0.5);
+        assert_eq!(CallShape::Cf.book_compatibility("QCF"), 0.0);
+        assert_eq!(CallShape::from_label("cf-fm"), Some(CallShape::CfFm));
+    }
+}
@@ -0,0 +1,402 @@
+//! Constant-frequency (CF) call analysis for horseshoe and leaf-nosed bats.
+//!
+//! Rhinolophid and hipposiderid calls are a long CF component, usually
+//! flanked by a short initial upward FM (iFM) and a terminal downward FM
+//! (tFM). The bats lower their emitted CF in flight so the Doppler-shifted
+//! echo stays at the ear's acoustic fovea, so within one pass the CF drifts
+//! by a few hundred Hz; the highest CF is the best estimate of the resting
+//! frequency.
+//!
+//! Per call, the CF segment is found on the frequency contour as the longest
+//! run of near-flat slope. Its frequency is then measured on a zero-padded
+//! FFT of just the CF samples with parabolic interpolation, which resolves
+//! a few Hz rather than the STFT bin width.
+
+use crate::dsp::bat_passes::{self, PassParams, TimedPulse};
+use crate::dsp::call_params::parabolic_offset;
+use crate::dsp::contour::ContourPoint;
+use realfft::RealFftPlanner;
+use std::cell::RefCell;
+
+thread_local! {
+    static CF_FFT_PLANNER: RefCell<RealFftPlanner<f32>> = RefCell::new(RealFftPlanner::new());
+}
+
+/// Speed of sound used for the Doppler velocity estimate (m/s).
+const SPEED_OF_SOUND: f64 = 343.0;
+
+#[derive(Clone, Debug)]
+pub struct CfParams {
+    /// Contour points with a local slope below this are part of the CF (kHz/ms).
+    pub max_cf_slope_khz_per_ms: f64,
+    /// Shortest flat run that counts as a CF component (ms).
+    pub min_cf_duration_ms: f64,
+    /// Zero-padding factor for the CF spectrum (FFT length / CF samples).
+    pub zero_pad: usize,
+    /// Calls in the same pass whose CF differs from the pass maximum by more
+    /// than this fraction start a new pass (another bat or species).
+    pub max_doppler_fraction: f64,
+    /// Histogram bin width (Hz).
+    pub histogram_bin_hz: f64,
+}
+
+impl Default for CfParams {
+    fn default() -> Self {
+        Self {
+            max_cf_slope_khz_per_ms: 0.3,
+            min_cf_duration_ms: 2.0,
+            zero_pad: 8,
+            max_doppler_fraction: 0.03,
+            histogram_bin_hz: 100.0,
+        }
+    }
+}
+
+/// CF measurements for one call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfCall {
+    /// 1-based pulse number (same as `DetectedPulse::index`).
+    pub index: usize,
+    /// CF frequency from the interpolated high-resolution spectrum (Hz).
+    pub cf_hz: f64,
+    /// CF component limits (seconds from file start).
+    pub cf_start_time: f64,
+    pub cf_end_time: f64,
+    /// Initial FM: duration (ms) and lowest frequency before the CF (Hz).
+    pub ifm_duration_ms: f64,
+    pub ifm_start_hz: Option<f64>,
+    /// Terminal FM: duration (ms) and lowest frequency after the CF (Hz).
+    pub tfm_duration_ms: f64,
+    pub tfm_end_hz: Option<f64>,
+}
+
+impl CfCall {
+    pub fn cf_duration_ms(&self) -> f64 {
+        (self.cf_end_time - self.cf_start_time) * 1000.0
+    }
+
+    /// Terminal FM bandwidth below the CF (Hz).
+    pub fn tfm_bandwidth_hz(&self) -> f64 {
+        self.tfm_end_hz.map(|f| (self.cf_hz - f).max(0.0)).unwrap_or(0.0)
+    }
+}
+
+/// Doppler compensation within one pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfPass {
+    pub start_time: f64,
+    pub end_time: f64,
+    /// Pulse numbers of the CF calls in the pass.
+    pub indices: Vec<usize>,
+    /// Highest CF in the pass: resting-frequency estimate (Hz).
+    pub resting_hz: f64,
+    pub cf_min_hz: f64,
+    /// `resting_hz − cf_min_hz`.
+    pub doppler_range_hz: f64,
+    /// Approach speed that would account for the full range (m/s).
+    pub doppler_speed_m_s: f64,
+}
+
+/// File-level CF summary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfAnalysis {
+    pub calls: Vec<CfCall>,
+    pub passes: Vec<CfPass>,
+    /// `(bin_start_hz, count)` over the range of call CFs.
+    pub histogram: Vec<(f64, usize)>,
+}
+
+/// Index range `[start, end]` of the CF component on a contour: the longest
+/// run of points whose local slope is below `max_cf_slope_khz_per_ms`.
+pub fn find_cf_segment(points: &[ContourPoint], params: &CfParams) -> Option<(usize, usize)> {
+    if points.len() < 3 {
+        return None;
+    }
+    // Local slope over ±2 points, so sub-bin jitter doesn't break up the CF
+    let flat: Vec<bool> = (0..points.len())
+        .map(|i| {
+            let a = &points[i.saturating_sub(2)];
+            let b = &points[(i + 2).min(points.len() - 1)];
+            let dt_ms = (b.time - a.time) * 1000.0;
+            dt_ms > 0.0 && ((b.freq - a.freq) / 1000.0 / dt_ms).abs() <= params.max_cf_slope_khz_per_ms
+        })
+        .collect();
+
+    let mut best: Option<(usize, usize)> = None;
+    let mut i = 0;
+    while i < flat.len() {
+        if !flat[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + 1 < flat.len() && flat[i + 1] {
+            i += 1;
+        }
+        let longer = best.is_none_or(|(s, e)| points[i].time - points[start].time > points[e].time - points[s].time);
+        if longer {
+            best = Some((start, i));
+        }
+        i += 1;
+    }
+    best.filter(|&(s, e)| (points[e].time - points[s].time) * 1000.0 >= params.min_cf_duration_ms)
+}
+
+/// Peak frequency of `samples` near `approx_hz` from a Hann-windowed,
+/// zero-padded FFT with parabolic interpolation.
+pub fn refine_cf_frequency(samples: &[f32], sample_rate: u32, approx_hz: f64, params: &CfParams) -> Option<f64> {
+    let n = samples.len();
+    if n < 16 || sample_rate == 0 {
+        return None;
+    }
+    let nfft = (n * params.zero_pad.max(1)).next_power_of_two();
+    let fft = CF_FFT_PLANNER.with(|p| p.borrow_mut().plan_fft_forward(nfft));
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+    for (i, (inp, &s)) in input.iter_mut().zip(samples.iter()).enumerate() {
+        let w = 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / (n - 1) as f32).cos());
+        *inp = s * w;
+    }
+    fft.process(&mut input, &mut spectrum).ok()?;
+    let magnitudes: Vec<f32> = spectrum.iter().map(|c| c.norm()).collect();
+
+    let bin_hz = sample_rate as f64 / nfft as f64;
+    // Search within ±2 unpadded bins of the contour estimate
+    let half_width = 2.0 * sample_rate as f64 / n as f64;
+    let lo = (((approx_hz - half_width) / bin_hz).floor().max(1.0)) as usize;
+    let hi = (((approx_hz + half_width) / bin_hz).ceil() as usize).min(magnitudes.len() - 2);
+    if hi <= lo {
+        return None;
+    }
+    let peak = (lo..=hi).max_by(|&a, &b| {
+        magnitudes[a].partial_cmp(&magnitudes[b]).unwrap_or(std::cmp::Ordering::Equal)
+    })?;
+    Some((peak as f64 + parabolic_offset(&magnitudes, peak)) * bin_hz)
+}
+
+/// Measure one call. `samples` must cover the contour's time span, with
+/// `samples[0]` at `samples_start_time` (file seconds).
+pub fn analyse_call(
+    index: usize,
+    contour: &[ContourPoint],
+    samples: &[f32],
+    sample_rate: u32,
+    samples_start_time: f64,
+    params: &CfParams,
+) -> Option<CfCall> {
+    let (s, e) = find_cf_segment(contour, params)?;
+    let cf_points = &contour[s..=e];
+    let approx = cf_points.iter().map(|p| p.freq).sum::<f64>() / cf_points.len() as f64;
+
+    let sr = sample_rate as f64;
+    let first = ((cf_points[0].time - samples_start_time) * sr).round().max(0.0) as usize;
+    let last = (((cf_points[cf_points.len() - 1].time - samples_start_time) * sr).round() as usize).min(samples.len());
+    let cf_hz = samples
+        .get(first..last)
+        .and_then(|seg| refine_cf_frequency(seg, sample_rate, approx, params))
+        .unwrap_or(approx);
+
+    let lowest = |pts: &[ContourPoint]| pts.iter().map(|p| p.freq).reduce(f64::min);
+    let (before, after) = (&contour[..s], &contour[e + 1..]);
+    Some(CfCall {
+        index,
+        cf_hz,
+        cf_start_time: contour[s].time,
+        cf_end_time: contour[e].time,
+        ifm_duration_ms: before.first().map(|p| (contour[s].time - p.time) * 1000.0).unwrap_or(0.0),
+        ifm_start_hz: lowest(before),
+        tfm_duration_ms: after.last().map(|p| (p.time - contour[e].time) * 1000.0).unwrap_or(0.0),
+        tfm_end_hz: lowest(after),
+    })
+}
+
+/// Group CF calls into passes and report the Doppler range in each.
+///
+/// Calls are split by `pass_params.max_gap_secs` first, then a call whose CF
+/// is more than `max_doppler_fraction` away from the running pass maximum
+/// starts a new pass.
+pub fn doppler_passes(calls: &[CfCall], pass_params: &PassParams, params: &CfParams) -> Vec<CfPass> {
+    let timed: Vec<TimedPulse> = calls
+        .iter()
+        .map(|c| TimedPulse { source: 0, start_epoch_ms: c.cf_start_time * 1000.0, end_epoch_ms: c.cf_end_time * 1000.0 })
+        .collect();
+    let trains = bat_passes::segment_passes(&timed, &PassParams { min_pulses: 1, ..pass_params.clone() });
+
+    let mut sorted: Vec<&CfCall> = calls.iter().collect();
+    sorted.sort_by(|a, b| a.cf_start_time.partial_cmp(&b.cf_start_time).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut passes = Vec::new();
+    for train in &trains {
+        let mut group: Vec<&CfCall> = Vec::new();
+        for &c in sorted.iter().filter(|c| {
+            let t = c.cf_start_time * 1000.0;
+            t >= train.start_epoch_ms && t <= train.end_epoch_ms
+        }) {
+            let max = group.iter().map(|g| g.cf_hz).fold(0.0, f64::max);
+            if !group.is_empty() && (c.cf_hz - max).abs() > max * params.max_doppler_fraction {
+                passes.extend(cf_pass(&group, pass_params.min_pulses));
+                group.clear();
+            }
+            group.push(c);
+        }
+        passes.extend(cf_pass(&group, pass_params.min_pulses));
+    }
+    passes
+}
+
+fn cf_pass(group: &[&CfCall], min_calls: usize) -> Option<CfPass> {
+    if group.is_empty() || group.len() < min_calls {
+        return None;
+    }
+    let resting_hz = group.iter().map(|c| c.cf_hz).fold(f64::NEG_INFINITY, f64::max);
+    let cf_min_hz = group.iter().map(|c| c.cf_hz).fold(f64::INFINITY, f64::min);
+    let range = resting_hz - cf_min_hz;
+    Some(CfPass {
+        start_time: group[0].cf_start_time,
+        end_time: group[group.len() - 1].cf_end_time,
+        indices: group.iter().map(|c| c.index).collect(),
+        resting_hz,
+        cf_min_hz,
+        doppler_range_hz: range,
+        // Two-way shift: Δf/f ≈ 2v/c
+        doppler_speed_m_s: if resting_hz > 0.0 { SPEED_OF_SOUND * range / (2.0 * resting_hz) } else { 0.0 },
+    })
+}
+
+/// Histogram of call CFs with `bin_hz` bins aligned to multiples of the width.
+pub fn cf_histogram(calls: &[CfCall], bin_hz: f64) -> Vec<(f64, usize)> {
+    if calls.is_empty() || bin_hz <= 0.0 {
+        return Vec::new();
+    }
+    let bin_of = |f: f64| (f / bin_hz).floor() as i64;
+    let lo = calls.iter().map(|c| bin_of(c.cf_hz)).min().unwrap_or(0);
+    let hi = calls.iter().map(|c| bin_of(c.cf_hz)).max().unwrap_or(0);
+    let mut counts = vec![0usize; (hi - lo + 1) as usize];
+    for c in calls {
+        counts[(bin_of(c.cf_hz) - lo) as usize] += 1;
+    }
+    counts.into_iter().enumerate().map(|(i, n)| ((lo + i as i64) as f64 * bin_hz, n)).collect()
+}
+
+/// Passes and histogram for a file's CF calls.
+pub fn summarize(calls: Vec<CfCall>, pass_params: &PassParams, params: &CfParams) -> CfAnalysis {
+    let passes = doppler_passes(&calls, pass_params, params);
+    let histogram = cf_histogram(&calls, params.histogram_bin_hz);
+    CfAnalysis { calls, passes, histogram }
+}
+
+/// Tab-separated per-call table for spreadsheets.
+pub fn cf_calls_tsv(calls: &[CfCall]) -> String {
+    let mut out = String::from(
+        "pulse\tcf_khz\tcf_start_s\tcf_duration_ms\tifm_duration_ms\tifm_start_khz\ttfm_duration_ms\ttfm_end_khz\ttfm_bandwidth_khz\n",
+    );
+    let khz = |f: Option<f64>| f.map(|v| format!("{:.3}", v / 1000.0)).unwrap_or_default();
+    for c in calls {
+        out.push_str(&format!(
+            "{}\t{:.3}\t{:.4}\t{:.2}\t{:.2}\t{}\t{:.2}\t{}\t{:.3}\n",
+            c.index, c.cf_hz / 1000.0, c.cf_start_time, c.cf_duration_ms(), c.ifm_duration_ms,
+            khz(c.ifm_start_hz), c.tfm_duration_ms, khz(c.tfm_end_hz), c.tfm_bandwidth_hz() / 1000.0,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::contour::{trace_contour, ContourParams};
+    use crate::dsp::fft::compute_stft_columns;
+    use crate::types::SpectrogramData;
+    use std::sync::Arc;
+
+    const SR: u32 = 256_000;
+
+    /// Rhinolophus-like call: 1 ms iFM up to `cf`, 30 ms CF, 2 ms tFM down 12 kHz.
+    fn add_cf_call(buf: &mut [f32], t0: f64, cf: f64) {
+        let segments = [(0.001, cf - 6_000.0, cf), (0.030, cf, cf), (0.002, cf, cf - 12_000.0)];
+        let mut i = (t0 * SR as f64) as usize;
+        let mut phase = 0.0f64;
+        for (dur, f0, f1) in segments {
+            let n = (dur * SR as f64) as usize;
+            for k in 0..n {
+                let f = f0 + (f1 - f0) * k as f64 / n as f64;
+                phase += 2.0 * std::f64::consts::PI * f / SR as f64;
+                if let Some(s) = buf.get_mut(i) {
+                    *s += (0.5 * phase.sin()) as f32;
+                }
+                i += 1;
+            }
+        }
+    }
+
+    fn spectrogram(samples: &[f32], fft: usize, hop: usize) -> SpectrogramData {
+        let cols = (samples.len() - fft) / hop + 1;
+        let columns = compute_stft_columns(samples, SR, fft, hop, 0, cols);
+        SpectrogramData {
+            total_columns: columns.len(),
+            columns: Arc::new(columns),
+            freq_resolution: SR as f64 / fft as f64,
+            time_resolution: hop as f64 / SR as f64,
+            max_freq: SR as f64 / 2.0,
+            sample_rate: SR,
+        }
+    }
+
+    fn analyse(samples: &[f32], spec: &SpectrogramData, index: usize, t0: f64) -> CfCall {
+        let points = trace_contour(spec, t0 - 0.001, t0 + 0.035, &ContourParams::default());
+        analyse_call(index, &points, samples, SR, 0.0, &CfParams::default()).expect("CF call")
+    }
+
+    #[test]
+    fn test_cf_frequency_is_sub_bin_accurate() {
+        let mut samples = vec![0.0f32; (0.05 * SR as f64) as usize];
+        add_cf_call(&mut samples, 0.01, 83_130.0);
+        // 500 Hz STFT bins; the CF lies between bins
+        let spec = spectrogram(&samples, 512, 64);
+        let call = analyse(&samples, &spec, 1, 0.01);
+        assert!((call.cf_hz - 83_130.0).abs() < 30.0, "cf {}", call.cf_hz);
+        assert!(call.cf_duration_ms() > 25.0 && call.cf_duration_ms() < 32.0, "{call:?}");
+        assert!(call.tfm_duration_ms > 0.5, "{call:?}");
+        assert!(call.tfm_bandwidth_hz() > 6_000.0, "{call:?}");
+        assert!(call.ifm_start_hz.is_some_and(|f| f < 83_130.0 - 2_000.0), "{call:?}");
+    }
+
+    #[test]
+    fn test_pure_fm_sweep_has_no_cf() {
+        let n = 200;
+        let points: Vec<ContourPoint> = (0..n)
+            .map(|i| ContourPoint { time: i as f64 * 0.000025, freq: 80_000.0 - i as f64 * 200.0, level_db: 0.0 })
+            .collect();
+        assert_eq!(find_cf_segment(&points, &CfParams::default()), None);
+    }
+
+    #[test]
+    fn test_doppler_range_within_pass() {
+        let mut samples = vec![0.0f32; (0.4 * SR as f64) as usize];
+        let calls_at = [(0.01, 83_000.0), (0.09, 82_700.0), (0.17, 82_400.0)];
+        for (t0, cf) in calls_at {
+            add_cf_call(&mut samples, t0, cf);
+        }
+        // A different species later in the same train
+        add_cf_call(&mut samples, 0.25, 105_000.0);
+        let spec = spectrogram(&samples, 512, 64);
+        let calls: Vec<CfCall> = calls_at
+            .iter()
+            .chain([(0.25, 105_000.0)].iter())
+            .enumerate()
+            .map(|(i, &(t0, _))| analyse(&samples, &spec, i + 1, t0))
+            .collect();
+
+        let analysis = summarize(calls, &PassParams::default(), &CfParams::default());
+        assert_eq!(analysis.passes.len(), 1, "{:?}", analysis.passes);
+        let pass = &analysis.passes[0];
+        assert_eq!(pass.indices, vec![1, 2, 3]);
+        assert!((pass.resting_hz - 83_000.0).abs() < 30.0);
+        assert!((pass.doppler_range_hz - 600.0).abs() < 60.0, "{pass:?}");
+        assert!(pass.doppler_speed_m_s > 1.0 && pass.doppler_speed_m_s < 1.5);
+
+        let total: usize = analysis.histogram.iter().map(|(_, n)| n).sum();
+        assert_eq!(total, 4);
+        assert!(analysis.histogram.iter().all(|(f, _)| f % 100.0 == 0.0));
+    }
+}
@@ -0,0 +1,336 @@
+//! Frequency-contour (ridge) tracking for individual calls.
+//!
+//! Produces a time→frequency polyline with amplitude for each call. Tracking
+//! starts at the strongest time–frequency cell of the call and follows the
+//! local spectral peak forwards and backwards in time, only allowing the
+//! frequency to move by a limited amount per frame, so harmonics and nearby
+//! calls from other bats are not picked up.
+//!
+//! Two sources are supported:
+//! - STFT magnitudes from `SpectrogramData` (always available), and
+//! - a reassigned tile from `fft::compute_reassigned_tile`, which gives much
+//!   sharper frequency traces for steep FM sweeps.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::call_params::parabolic_offset;
+use crate::dsp::fft::compute_reassigned_tile;
+use crate::dsp::pulse_detect::DetectedPulse;
+use crate::types::SpectrogramData;
+
+/// One vertex of a call contour.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContourPoint {
+    /// Seconds from file start.
+    pub time: f64,
+    /// Instantaneous frequency (Hz).
+    pub freq: f64,
+    /// Level relative to the strongest point of the contour (dB, ≤ 0).
+    pub level_db: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallContour {
+    /// 1-based pulse number (same as `DetectedPulse::index`).
+    pub index: usize,
+    pub points: Vec<ContourPoint>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ContourParams {
+    /// Stop tracking when the peak falls this far (dB) below the contour maximum.
+    pub threshold_db: f32,
+    /// Largest frequency change allowed between consecutive frames (kHz/ms).
+    pub max_slope_khz_per_ms: f64,
+    /// Band limits (0.0 = unbounded).
+    pub freq_low_hz: f64,
+    pub freq_high_hz: f64,
+}
+
+impl Default for ContourParams {
+    fn default() -> Self {
+        Self {
+            threshold_db: 24.0,
+            max_slope_khz_per_ms: 20.0,
+            freq_low_hz: 0.0,
+            freq_high_hz: 0.0,
+        }
+    }
+}
+
+/// A frame of dB values, indexed by frequency bin (bin 0 = DC).
+struct Frame {
+    time: f64,
+    db: Vec<f32>,
+}
+
+/// Trace contours for every pulse from the STFT magnitudes.
+pub fn trace_calls(
+    pulses: &[DetectedPulse],
+    spectrogram: &SpectrogramData,
+    params: &ContourParams,
+) -> Vec<CallContour> {
+    pulses
+        .iter()
+        .map(|p| CallContour {
+            index: p.index,
+            points: trace_contour(spectrogram, p.start_time, p.end_time, params),
+        })
+        .collect()
+}
+
+/// Trace a contour between `start_time` and `end_time` from STFT magnitudes.
+pub fn trace_contour(
+    spectrogram: &SpectrogramData,
+    start_time: f64,
+    end_time: f64,
+    params: &ContourParams,
+) -> Vec<ContourPoint> {
+    if spectrogram.freq_resolution <= 0.0 {
+        return Vec::new();
+    }
+    // Column time_offset is the frame start; the frame centre is half a window later.
+    let half_window = 0.5 / spectrogram.freq_resolution;
+    let frames: Vec<Frame> = spectrogram
+        .columns
+        .iter()
+        .filter(|c| {
+            let t = c.time_offset + half_window;
+            t >= start_time && t <= end_time
+        })
+        .map(|c| Frame {
+            time: c.time_offset + half_window,
+            db: c.magnitudes.iter().map(|&m| 20.0 * m.max(1e-12).log10()).collect(),
+        })
+        .collect();
+    track(&frames, spectrogram.freq_resolution, params)
+}
+
+/// Trace a contour from a reassigned spectrogram of `samples`.
+///
+/// `samples_start_time` is the file time of `samples[0]`; the slice should
+/// cover the call `(start_time, end_time)` plus at least `fft_size` samples
+/// of padding.
+pub fn trace_contour_reassigned(
+    samples: &[f32],
+    sample_rate: u32,
+    samples_start_time: f64,
+    fft_size: usize,
+    hop_size: usize,
+    (start_time, end_time): (f64, f64),
+    params: &ContourParams,
+) -> Vec<ContourPoint> {
+    if sample_rate == 0 || hop_size == 0 || samples.len() < fft_size {
+        return Vec::new();
+    }
+    let col_count = (samples.len() - fft_size) / hop_size + 1;
+    let tile = compute_reassigned_tile(samples, col_count, fft_size, hop_size, -120.0);
+    let (width, height) = (tile.width as usize, tile.height as usize);
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+
+    let sr = sample_rate as f64;
+    let bin_hz = sr / fft_size as f64;
+    let half_window = fft_size as f64 / 2.0 / sr;
+    let frames: Vec<Frame> = (0..width)
+        .filter_map(|col| {
+            let time = samples_start_time + (col * hop_size) as f64 / sr + half_window;
+            if time < start_time || time > end_time {
+                return None;
+            }
+            // Tile rows are flipped (row 0 = highest frequency)
+            let db = (0..height)
+                .map(|bin| tile.db_data[(height - 1 - bin) * width + col])
+                .map(|v| if v.is_finite() { v } else { -240.0 })
+                .collect();
+            Some(Frame { time, db })
+        })
+        .collect();
+    track(&frames, bin_hz, params)
+}
+
+fn bin_range(n_bins: usize, bin_hz: f64, params: &ContourParams) -> (usize, usize) {
+    let last = n_bins.saturating_sub(1);
+    let lo = if params.freq_low_hz > 0.0 {
+        ((params.freq_low_hz / bin_hz).floor() as usize).min(last)
+    } else {
+        1 // skip DC
+    };
+    let hi = if params.freq_high_hz > 0.0 {
+        ((params.freq_high_hz / bin_hz).ceil() as usize).min(last)
+    } else {
+        last
+    };
+    (lo, hi)
+}
+
+fn peak_in(db: &[f32], lo: usize, hi: usize) -> Option<(usize, f32)> {
+    db.get(lo..=hi)?
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(i, &v)| (i + lo, v))
+}
+
+/// Sub-bin frequency of the peak at `bin`, interpolating on dB values.
+fn refine(db: &[f32], bin: usize, bin_hz: f64) -> f64 {
+    // parabolic_offset works on linear magnitudes; convert the 3 neighbours back.
+    let lo = bin.saturating_sub(1);
+    let hi = (bin + 1).min(db.len() - 1);
+    let local: Vec<f32> = db[lo..=hi].iter().map(|&v| 10f32.powf(v / 20.0)).collect();
+    (bin as f64 + parabolic_offset(&local, bin - lo)) * bin_hz
+}
+
+fn track(frames: &[Frame], bin_hz: f64, params: &ContourParams) -> Vec<ContourPoint> {
+    let Some(n_bins) = frames.first().map(|f| f.db.len()) else {
+        return Vec::new();
+    };
+    let (lo, hi) = bin_range(n_bins, bin_hz, params);
+    if hi <= lo {
+        return Vec::new();
+    }
+
+    // Anchor: strongest cell over the whole call
+    let Some((anchor_frame, anchor_bin, anchor_db)) = frames
+        .iter()
+        .enumerate()
+        .filter_map(|(i, f)| peak_in(&f.db, lo, hi).map(|(b, v)| (i, b, v)))
+        .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
+    else {
+        return Vec::new();
+    };
+    let floor = anchor_db - params.threshold_db;
+
+    let frame_dt = if frames.len() > 1 { frames[1].time - frames[0].time } else { 0.0 };
+    let max_jump_bins = ((params.max_slope_khz_per_ms * 1e6 * frame_dt) / bin_hz).ceil().max(1.0) as usize;
+
+    let point = |frame: &Frame, bin: usize| ContourPoint {
+        time: frame.time,
+        freq: refine(&frame.db, bin, bin_hz),
+        level_db: frame.db[bin] - anchor_db,
+    };
+
+    // Follow the ridge away from the anchor in one direction until it fades out.
+    let follow = |indices: &mut dyn Iterator<Item = usize>| {
+        let mut out = Vec::new();
+        let mut prev = anchor_bin;
+        for i in indices {
+            let frame = &frames[i];
+            let search_lo = prev.saturating_sub(max_jump_bins).max(lo);
+            let search_hi = (prev + max_jump_bins).min(hi);
+            match peak_in(&frame.db, search_lo, search_hi) {
+                Some((bin, v)) if v >= floor => {
+                    out.push(point(frame, bin));
+                    prev = bin;
+                }
+                _ => break,
+            }
+        }
+        out
+    };
+
+    let mut before = follow(&mut (0..anchor_frame).rev());
+    let after = follow(&mut (anchor_frame + 1..frames.len()));
+    before.reverse();
+    before.push(point(&frames[anchor_frame], anchor_bin));
+    before.extend(after);
+    before
+}
+
+/// Downsample a contour for storage/drawing, keeping at most `max_points`
+/// (always keeps the first and last point).
+pub fn simplify(points: &[ContourPoint], max_points: usize) -> Vec<ContourPoint> {
+    if points.len() <= max_points || max_points < 2 {
+        return points.to_vec();
+    }
+    let step = (points.len() - 1) as f64 / (max_points - 1) as f64;
+    (0..max_points)
+        .map(|i| points[((i as f64 * step).round() as usize).min(points.len() - 1)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::fft::compute_stft_columns;
+    use crate::types::SpectrogramColumn;
+    use std::sync::Arc;
+
+    const SR: u32 = 256_000;
+
+    /// Linear chirp from f0 to f1 over `dur` seconds, starting at `t0` in a
+    /// buffer of `len` seconds, plus a weaker unrelated tone.
+    fn chirp_with_tone(f0: f64, f1: f64, t0: f64, dur: f64, len: f64, tone_hz: f64) -> Vec<f32> {
+        let n = (len * SR as f64) as usize;
+        let mut phase = 0.0f64;
+        (0..n)
+            .map(|i| {
+                let t = i as f64 / SR as f64;
+                let tone = 0.05 * (2.0 * std::f64::consts::PI * tone_hz * t).sin();
+                let call = if t >= t0 && t < t0 + dur {
+                    let f = f0 + (f1 - f0) * (t - t0) / dur;
+                    phase += 2.0 * std::f64::consts::PI * f / SR as f64;
+                    phase.sin()
+                } else {
+                    0.0
+                };
+                (call + tone) as f32
+            })
+            .collect()
+    }
+
+    fn spectrogram(samples: &[f32], fft: usize, hop: usize) -> SpectrogramData {
+        let cols = (samples.len() - fft) / hop + 1;
+        let columns: Vec<SpectrogramColumn> = compute_stft_columns(samples, SR, fft, hop, 0, cols);
+        SpectrogramData {
+            total_columns: columns.len(),
+            columns: Arc::new(columns),
+            freq_resolution: SR as f64 / fft as f64,
+            time_resolution: hop as f64 / SR as f64,
+            max_freq: SR as f64 / 2.0,
+            sample_rate: SR,
+        }
+    }
+
+    #[test]
+    fn test_stft_contour_follows_sweep() {
+        let samples = chirp_with_tone(80_000.0, 40_000.0, 0.01, 0.005, 0.03, 20_000.0);
+        let spec = spectrogram(&samples, 256, 32);
+        let points = trace_contour(&spec, 0.01, 0.015, &ContourParams::default());
+        assert!(points.len() >= 10, "only {} points", points.len());
+        // Monotonic downward sweep, never jumps to the 20 kHz tone
+        assert!(points.windows(2).all(|w| w[1].time > w[0].time));
+        assert!(points.iter().all(|p| p.freq > 35_000.0 && p.freq < 85_000.0));
+        assert!(points.first().unwrap().freq > points.last().unwrap().freq + 20_000.0);
+        assert!(points.iter().all(|p| p.level_db <= 0.0));
+    }
+
+    #[test]
+    fn test_reassigned_contour_is_close_to_true_frequency() {
+        let (t0, dur, f0, f1) = (0.01, 0.005, 80_000.0, 40_000.0);
+        let samples = chirp_with_tone(f0, f1, t0, dur, 0.03, 20_000.0);
+        let points = trace_contour_reassigned(&samples, SR, 0.0, 512, 32, (t0, t0 + dur), &ContourParams::default());
+        assert!(points.len() >= 10, "only {} points", points.len());
+        // Compare against the true instantaneous frequency away from the call edges
+        let errors: Vec<f64> = points
+            .iter()
+            .filter(|p| p.time > t0 + 0.001 && p.time < t0 + dur - 0.001)
+            .map(|p| (p.freq - (f0 + (f1 - f0) * (p.time - t0) / dur)).abs())
+            .collect();
+        assert!(!errors.is_empty());
+        let mean = errors.iter().sum::<f64>() / errors.len() as f64;
+        assert!(mean < 2_000.0, "mean error {mean} Hz");
+    }
+
+    #[test]
+    fn test_simplify_keeps_endpoints() {
+        let points: Vec<ContourPoint> = (0..100)
+            .map(|i| ContourPoint { time: i as f64, freq: 1000.0, level_db: 0.0 })
+            .collect();
+        let s = simplify(&points, 10);
+        assert_eq!(s.len(), 10);
+        assert_eq!(s[0].time, 0.0);
+        assert_eq!(s[9].time, 99.0);
+    }
+}
@@ -158,8 +158,9 @@ pub fn compute_reassigned_tile(
             let xth64 = Complex::<f64>::new(spec_th[k].re as f64, spec_th[k].im as f64);
             let xdh64 = Complex::<f64>::new(spec_dh[k].re as f64, spec_dh[k].im as f64);
 
-            // Corrected time: t_hat = n - Re(X_th / X_h)
-            let t_hat = col_i as f64 - (xth64 / xh64).re;
+            // Corrected time (in columns): Re(X_th / X_h) is the energy's offset
+            // from the frame centre in samples, so scale by the hop size.
+            let t_hat = col_i as f64 + (xth64 / xh64).re / hop_size as f64;
 
             // Corrected frequency bin: f_hat = k + (N / 2π) * Im(X_dh / X_h)
             let f_hat = k as f64 + fft_over_two_pi * (xdh64 / xh64).im;
@@ -461,6 +462,7 @@ mod tests {
                 guano: None,
                 data_offset: None,
                 data_size: None,
+                time_expansion: 1.0,
             },
         }
     }
@@ -500,4 +502,30 @@ mod tests {
             "Peak at {peak_freq} Hz, expected ~{freq} Hz"
         );
     }
+
+    #[test]
+    fn test_reassigned_impulse_lands_in_its_column() {
+        let fft_size = 256;
+        let hop_size = 64;
+        let col_count = 20;
+        // An impulse at the centre of column 10's frame
+        let mut samples = vec![0.0f32; col_count * hop_size + fft_size];
+        samples[10 * hop_size + fft_size / 2] = 1.0;
+
+        let tile = compute_reassigned_tile(&samples, col_count, fft_size, hop_size, -200.0);
+        let mut col_energy = vec![0.0f64; col_count];
+        for (i, &db) in tile.db_data.iter().enumerate() {
+            if db.is_finite() {
+                col_energy[i % col_count] += 10f64.powf(db as f64 / 10.0);
+            }
+        }
+        let total: f64 = col_energy.iter().sum();
+        // Every frame that sees the impulse must reassign it back to column
+        // 10; measuring the offset in samples, or with the sign flipped,
+        // scatters it to the edges of the tile.
+        assert!(
+            col_energy[10] > 0.99 * total,
+            "column energy: {col_energy:?}"
+        );
+    }
 }
@@ -14,4 +14,13 @@ pub mod zero_crossing;
 pub mod chromagram;
 pub mod psd;
 pub mod pulse_detect;
+pub mod call_params;
+pub mod call_shape;
+pub mod call_events;
+pub mod bat_passes;
+pub mod contour;
+pub mod template_match;
+pub mod cf_analysis;
+pub mod tdoa;
+pub mod acoustic_indices;
 pub mod resonators;
@@ -56,13 +56,23 @@ pub fn detect_pulses(
 ) -> Vec<DetectedPulse> {
     let total = audio.source.total_samples() as usize;
     let samples = audio.source.read_region(ChannelView::MonoMix, 0, total);
-    let sr = audio.sample_rate;
+    detect_pulses_in_samples(&samples, audio.sample_rate, Some(spectrogram), params)
+}
+
+/// Detect pulses in mono `samples` starting at time 0, e.g. one chunk of a
+/// long recording. Peak frequencies are 0 without a spectrogram.
+pub fn detect_pulses_in_samples(
+    samples: &[f32],
+    sr: u32,
+    spectrogram: Option<&SpectrogramData>,
+    params: &PulseDetectionParams,
+) -> Vec<DetectedPulse> {
     if samples.len() < 2 {
         return Vec::new();
     }
 
     // Step 1: Bandpass filter to focus frequency range
-    let filtered = bandpass(&samples, sr, params.bandpass_low_hz, params.bandpass_high_hz);
+    let filtered = bandpass(samples, sr, params.bandpass_low_hz, params.bandpass_high_hz);
 
     // Step 2: Compute energy envelope (~0.25ms window for bat calls)
     let env_window = ((sr as f64 * 0.00025) as usize).max(1);
@@ -105,7 +115,7 @@ pub fn detect_pulses(
         let peak_time = peak_sample as f64 / sr as f64;
 
         // Step 6: Find peak frequency from spectrogram
-        let peak_freq = find_peak_frequency(spectrogram, start_time, end_time);
+        let peak_freq = spectrogram.map_or(0.0, |s| find_peak_frequency(s, start_time, end_time));
 
         // Step 7: Compute SNR
         let snr_db = if noise_floor > 0.0 {
@@ -251,3 +261,42 @@ fn find_peak_frequency(
 
     best_bin as f64 * spectrogram.freq_resolution
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_pulses_in_samples() {
+        let sr = 192_000u32;
+        let mut seed = 0x2545_f491_u32;
+        let mut samples: Vec<f32> = (0..sr as usize * 2).map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            (seed as f32 / u32::MAX as f32 - 0.5) * 0.002
+        }).collect();
+        // 5 ms, 40 kHz calls every 300 ms
+        let starts = [0.2, 0.5, 0.8, 1.1, 1.4];
+        for &t in &starts {
+            let s0 = (t * sr as f64) as usize;
+            for i in 0..(0.005 * sr as f64) as usize {
+                let phase = std::f64::consts::TAU * 40_000.0 * i as f64 / sr as f64;
+                samples[s0 + i] += 0.5 * phase.sin() as f32;
+            }
+        }
+
+        let pulses = detect_pulses_in_samples(&samples, sr, None, &PulseDetectionParams::default());
+        assert_eq!(pulses.len(), starts.len());
+        for (i, (p, &t)) in pulses.iter().zip(&starts).enumerate() {
+            assert_eq!(p.index, i + 1);
+            assert!((p.start_time - t).abs() < 0.001, "pulse {i} starts at {}", p.start_time);
+            // The smoothed envelope takes a while to fall back to the noise floor
+            assert!(p.end_time > t + 0.004 && p.end_time < starts.get(i + 1).copied().unwrap_or(2.0));
+            assert_eq!(p.peak_freq, 0.0);
+            assert!(p.snr_db > 20.0);
+        }
+
+        assert!(detect_pulses_in_samples(&[0.0; 1000], sr, None, &PulseDetectionParams::default()).is_empty());
+    }
+}
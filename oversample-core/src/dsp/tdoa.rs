@@ -0,0 +1,451 @@
+//! Time-difference-of-arrival (TDOA) estimation and source localisation for
+//! synchronised microphone arrays.
+//!
+//! Delays between channels come from the generalised cross-correlation with
+//! phase transform (GCC-PHAT): the cross-spectrum is whitened to unit
+//! magnitude so the correlation peak is sharp even for narrowband calls and
+//! in reverberant recordings, then transformed back to the lag domain. The
+//! peak lag is refined with a parabola for sub-sample resolution.
+//!
+//! Given the microphone positions, a source position is found by
+//! least-squares fitting of the range differences `c·τᵢ = |p − mᵢ| − |p − m₀|`
+//! (Levenberg–Marquardt from several starting points).
+
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+
+thread_local! {
+    static TDOA_FFT_PLANNER: RefCell<RealFftPlanner<f32>> = RefCell::new(RealFftPlanner::new());
+}
+
+/// Speed of sound in air at ~20 °C (m/s).
+pub const SPEED_OF_SOUND: f64 = 343.0;
+
+/// Microphone position in metres.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MicPosition {
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub z: f64,
+}
+
+/// Estimated source position (metres, in the microphone coordinate frame).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SourcePosition {
+    pub x: f64,
+    pub y: f64,
+    /// Present for 3D fixes.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub z: Option<f64>,
+    /// RMS range-difference residual of the fit (m).
+    pub residual_m: f64,
+    /// Arrival delay of each channel relative to channel 0 (ms).
+    #[serde(default)]
+    pub delays_ms: Vec<Option<f64>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TdoaParams {
+    /// Largest delay searched in either direction (seconds). Should be at
+    /// least the largest mic spacing divided by the speed of sound.
+    pub max_lag_secs: f64,
+    /// Band limits applied to the cross-spectrum (0.0 = unbounded).
+    pub freq_low_hz: f64,
+    pub freq_high_hz: f64,
+    /// Minimum GCC-PHAT peak height (0–1) for a delay to be trusted.
+    pub min_peak: f64,
+    pub speed_of_sound: f64,
+}
+
+impl Default for TdoaParams {
+    fn default() -> Self {
+        Self {
+            max_lag_secs: 0.003,
+            freq_low_hz: 0.0,
+            freq_high_hz: 0.0,
+            min_peak: 0.1,
+            speed_of_sound: SPEED_OF_SOUND,
+        }
+    }
+}
+
+/// A delay estimate between two channels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Delay {
+    /// How much later the sound reaches the second channel (seconds).
+    pub secs: f64,
+    /// Normalised GCC-PHAT peak height (0–1).
+    pub peak: f64,
+}
+
+/// GCC-PHAT delay of `b` relative to `a` (positive = `b` hears it later).
+pub fn gcc_phat(a: &[f32], b: &[f32], sample_rate: u32, params: &TdoaParams) -> Option<Delay> {
+    if a.is_empty() || b.is_empty() || sample_rate == 0 {
+        return None;
+    }
+    let n = (a.len() + b.len()).next_power_of_two();
+    let (fwd, inv) = TDOA_FFT_PLANNER.with(|p| {
+        let mut p = p.borrow_mut();
+        (p.plan_fft_forward(n), p.plan_fft_inverse(n))
+    });
+    let spectrum = |x: &[f32]| {
+        let mut input = fwd.make_input_vec();
+        input[..x.len()].copy_from_slice(x);
+        let mut out = fwd.make_output_vec();
+        fwd.process(&mut input, &mut out).ok().map(|_| out)
+    };
+    let (sa, sb) = (spectrum(a)?, spectrum(b)?);
+
+    let bin_hz = sample_rate as f64 / n as f64;
+    let lo = if params.freq_low_hz > 0.0 { (params.freq_low_hz / bin_hz).floor() as usize } else { 1 };
+    let hi = if params.freq_high_hz > 0.0 { (params.freq_high_hz / bin_hz).ceil() as usize } else { sa.len() - 1 };
+    // conj(A)·B peaks at the lag by which b trails a; PHAT keeps only the phase
+    let mut cross = inv.make_input_vec();
+    let mut used = 0usize;
+    for (k, c) in cross.iter_mut().enumerate() {
+        if k < lo || k > hi || k == 0 || k == sa.len() - 1 {
+            continue;
+        }
+        let x = sa[k].conj() * sb[k];
+        let mag = x.norm();
+        if mag > 1e-20 {
+            *c = x / mag;
+            used += 1;
+        }
+    }
+    if used == 0 {
+        return None;
+    }
+    let mut corr = inv.make_output_vec();
+    inv.process(&mut cross, &mut corr).ok()?;
+
+    // Circular lags: index k ↔ lag k, index n−k ↔ lag −k
+    let max_lag = ((params.max_lag_secs * sample_rate as f64).ceil() as usize).min(n / 2 - 1);
+    let at = |lag: isize| corr[lag.rem_euclid(n as isize) as usize] as f64;
+    let (best_lag, best) = (-(max_lag as isize)..=max_lag as isize)
+        .map(|lag| (lag, at(lag)))
+        .max_by(|x, y| x.1.partial_cmp(&y.1).unwrap_or(std::cmp::Ordering::Equal))?;
+
+    let (l, r) = (at(best_lag - 1), at(best_lag + 1));
+    let denom = l - 2.0 * best + r;
+    let offset = if denom.abs() > 1e-12 { (0.5 * (l - r) / denom).clamp(-0.5, 0.5) } else { 0.0 };
+    Some(Delay {
+        secs: (best_lag as f64 + offset) / sample_rate as f64,
+        // The real inverse FFT adds each whitened bin and its mirror image, so
+        // a perfect match peaks at 2·used
+        peak: best / (2.0 * used as f64),
+    })
+}
+
+/// Delays of every channel relative to channel 0 (entry 0 is always `Some(0)`).
+/// Delays with a weak correlation peak are `None`.
+pub fn channel_delays(channels: &[Vec<f32>], sample_rate: u32, params: &TdoaParams) -> Vec<Option<Delay>> {
+    let Some(reference) = channels.first() else {
+        return Vec::new();
+    };
+    std::iter::once(Some(Delay { secs: 0.0, peak: 1.0 }))
+        .chain(channels[1..].iter().map(|ch| {
+            gcc_phat(reference, ch, sample_rate, params).filter(|d| d.peak >= params.min_peak)
+        }))
+        .collect()
+}
+
+/// Largest mic-to-mic distance divided by the speed of sound, plus 10%.
+pub fn max_lag_for(mics: &[MicPosition], speed_of_sound: f64) -> f64 {
+    let max_dist = mics
+        .iter()
+        .flat_map(|a| mics.iter().map(move |b| dist(&[a.x, a.y, a.z], &[b.x, b.y, b.z])))
+        .fold(0.0, f64::max);
+    1.1 * max_dist / speed_of_sound
+}
+
+fn dist(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Least-squares source position from delays relative to mic 0.
+///
+/// With `three_d == false` the source is assumed to lie in the plane
+/// `z = mean mic z`. Needs at least as many valid delays as unknowns.
+pub fn localize(
+    mics: &[MicPosition],
+    delays_secs: &[Option<f64>],
+    three_d: bool,
+    speed_of_sound: f64,
+) -> Option<SourcePosition> {
+    let m: Vec<[f64; 3]> = mics.iter().map(|p| [p.x, p.y, p.z]).collect();
+    let obs: Vec<(usize, f64)> = delays_secs
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(i, d)| Some((i, (*d)? * speed_of_sound)))
+        .filter(|(i, _)| *i < m.len())
+        .collect();
+    let dims = if three_d { 3 } else { 2 };
+    if m.is_empty() || obs.len() < dims {
+        return None;
+    }
+
+    let centroid = [0, 1, 2].map(|k| m.iter().map(|p| p[k]).sum::<f64>() / m.len() as f64);
+    let aperture = m.iter().map(|p| dist(p, &centroid)).fold(0.0, f64::max).max(0.1);
+
+    let residuals = |p: &[f64; 3]| -> Vec<f64> {
+        let d0 = dist(p, &m[0]);
+        obs.iter().map(|&(i, rd)| dist(p, &m[i]) - d0 - rd).collect()
+    };
+    let cost = |p: &[f64; 3]| residuals(p).iter().map(|r| r * r).sum::<f64>();
+
+    // Several starts around the array so the fit isn't trapped on the wrong side
+    let mut starts = vec![centroid];
+    for k in 0..dims {
+        for sign in [-1.0, 1.0] {
+            let mut s = centroid;
+            s[k] += sign * 3.0 * aperture;
+            starts.push(s);
+        }
+    }
+
+    let mut best: Option<([f64; 3], f64)> = None;
+    for start in starts {
+        let mut p = start;
+        let mut c = cost(&p);
+        let mut lambda = 1e-3;
+        for _ in 0..100 {
+            let r = residuals(&p);
+            let d0 = dist(&p, &m[0]).max(1e-9);
+            // Jacobian of each residual w.r.t. the free coordinates
+            let jac: Vec<[f64; 3]> = obs
+                .iter()
+                .map(|&(i, _)| {
+                    let di = dist(&p, &m[i]).max(1e-9);
+                    [0, 1, 2].map(|k| if k < dims { (p[k] - m[i][k]) / di - (p[k] - m[0][k]) / d0 } else { 0.0 })
+                })
+                .collect();
+            let mut jtj = [[0.0f64; 3]; 3];
+            let mut jtr = [0.0f64; 3];
+            for (row, ri) in jac.iter().zip(r.iter()) {
+                for a in 0..dims {
+                    jtr[a] += row[a] * ri;
+                    for b in 0..dims {
+                        jtj[a][b] += row[a] * row[b];
+                    }
+                }
+            }
+            let mut improved = false;
+            while lambda < 1e9 {
+                let mut lhs = jtj;
+                for (a, row) in lhs.iter_mut().enumerate().take(dims) {
+                    row[a] += lambda * (1.0 + jtj[a][a]);
+                }
+                let Some(step) = solve(&lhs, &jtr, dims) else { break };
+                let mut candidate = p;
+                for k in 0..dims {
+                    candidate[k] -= step[k];
+                }
+                let cc = cost(&candidate);
+                if cc < c {
+                    p = candidate;
+                    c = cc;
+                    lambda = (lambda * 0.3).max(1e-9);
+                    improved = true;
+                    break;
+                }
+                lambda *= 10.0;
+            }
+            if !improved || c < 1e-14 {
+                break;
+            }
+        }
+        if best.is_none_or(|(_, bc)| c < bc) {
+            best = Some((p, c));
+        }
+    }
+
+    let (p, c) = best?;
+    Some(SourcePosition {
+        x: p[0],
+        y: p[1],
+        z: three_d.then_some(p[2]),
+        residual_m: (c / obs.len() as f64).sqrt(),
+        delays_ms: delays_secs.iter().map(|d| d.map(|s| s * 1000.0)).collect(),
+    })
+}
+
+/// Solve the top-left `n×n` system `a·x = b` by Gaussian elimination.
+fn solve(a: &[[f64; 3]; 3], b: &[f64; 3], n: usize) -> Option<[f64; 3]> {
+    let mut a = *a;
+    let mut b = *b;
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap_or(std::cmp::Ordering::Equal))?;
+        if a[pivot][col].abs() < 1e-15 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col];
+        for row in col + 1..n {
+            let f = a[row][col] / pivot_row[col];
+            for (x, p) in a[row][col..n].iter_mut().zip(&pivot_row[col..n]) {
+                *x -= f * p;
+            }
+            b[row] -= f * b[col];
+        }
+    }
+    let mut x = [0.0f64; 3];
+    for row in (0..n).rev() {
+        let s: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - s) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Parse mic positions, one per line as `x y [z]` (metres, comma or space separated).
+pub fn parse_mic_positions(text: &str) -> Result<Vec<MicPosition>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .enumerate()
+        .map(|(i, line)| {
+            let vals: Result<Vec<f64>, _> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(str::parse::<f64>)
+                .collect();
+            match vals.as_deref() {
+                Ok([x, y]) => Ok(MicPosition { x: *x, y: *y, z: 0.0 }),
+                Ok([x, y, z]) => Ok(MicPosition { x: *x, y: *y, z: *z }),
+                _ => Err(format!("Mic {}: expected \"x y\" or \"x y z\"", i + 1)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: u32 = 384_000;
+
+    /// FM sweep arriving at `delay` seconds into a buffer of `len` samples.
+    fn sweep_at(delay: f64, len: usize) -> Vec<f32> {
+        let dur = 0.003;
+        (0..len)
+            .map(|i| {
+                let t = i as f64 / SR as f64 - delay;
+                if (0.0..dur).contains(&t) {
+                    // 80 → 30 kHz linear sweep; phase is the integral of the frequency
+                    let phase = 2.0 * std::f64::consts::PI * (80_000.0 * t - 50_000.0 / (2.0 * dur) * t * t);
+                    (phase.sin() * (std::f64::consts::PI * t / dur).sin()) as f32
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    fn square_array() -> Vec<MicPosition> {
+        vec![
+            MicPosition { x: 0.0, y: 0.0, z: 0.0 },
+            MicPosition { x: 1.0, y: 0.0, z: 0.0 },
+            MicPosition { x: 1.0, y: 1.0, z: 0.0 },
+            MicPosition { x: 0.0, y: 1.0, z: 0.3 },
+        ]
+    }
+
+    #[test]
+    fn test_gcc_phat_sub_sample_delay() {
+        let len = (0.01 * SR as f64) as usize;
+        let a = sweep_at(0.002, len);
+        let b = sweep_at(0.002 + 0.000_412_3, len);
+        let d = gcc_phat(&a, &b, SR, &TdoaParams::default()).unwrap();
+        assert!((d.secs - 0.000_412_3).abs() < 2e-6, "{d:?}");
+        assert!(d.peak > 0.3, "{d:?}");
+
+        let back = gcc_phat(&b, &a, SR, &TdoaParams::default()).unwrap();
+        assert!((back.secs + 0.000_412_3).abs() < 2e-6, "{back:?}");
+    }
+
+    #[test]
+    fn test_gcc_phat_peak_is_coherence() {
+        // b shares a's spectrum below `split` and is independent noise above,
+        // so the fraction of coherent bins is known
+        let len = 8192;
+        let noise = |seed: u32| {
+            let mut x = seed;
+            (0..len)
+                .map(|_| {
+                    x ^= x << 13;
+                    x ^= x >> 17;
+                    x ^= x << 5;
+                    x as f32 / u32::MAX as f32 - 0.5
+                })
+                .collect::<Vec<f32>>()
+        };
+        let a = noise(12_345);
+        let other = noise(67_890);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let (fwd, inv) = (planner.plan_fft_forward(len), planner.plan_fft_inverse(len));
+        let spectrum = |x: &[f32]| {
+            let mut input = x.to_vec();
+            let mut out = fwd.make_output_vec();
+            fwd.process(&mut input, &mut out).unwrap();
+            out
+        };
+        let (sa, so) = (spectrum(&a), spectrum(&other));
+        let params = TdoaParams { max_lag_secs: 0.0005, ..TdoaParams::default() };
+        for coherence in [1.0, 0.6, 0.27] {
+            let split = (coherence * sa.len() as f64) as usize;
+            let mut sb: Vec<_> = sa[..split].iter().chain(&so[split..]).copied().collect();
+            let mut b = inv.make_output_vec();
+            inv.process(&mut sb, &mut b).unwrap();
+            let d = gcc_phat(&a, &b, SR, &params).unwrap();
+            assert!(d.secs.abs() < 1e-6, "{d:?}");
+            assert!((d.peak - coherence).abs() < 0.05, "{coherence}: {d:?}");
+        }
+    }
+
+    #[test]
+    fn test_localize_recovers_source() {
+        let mics = square_array();
+        let source = [2.5, 3.0, 1.2];
+        let t: Vec<f64> = mics
+            .iter()
+            .map(|m| dist(&[m.x, m.y, m.z], &source) / SPEED_OF_SOUND)
+            .collect();
+        let delays: Vec<Option<f64>> = t.iter().map(|ti| Some(ti - t[0])).collect();
+
+        let p = localize(&mics, &delays, true, SPEED_OF_SOUND).unwrap();
+        assert!((p.x - 2.5).abs() < 0.05 && (p.y - 3.0).abs() < 0.05, "{p:?}");
+        assert!((p.z.unwrap() - 1.2).abs() < 0.1, "{p:?}");
+        assert!(p.residual_m < 1e-3);
+    }
+
+    #[test]
+    fn test_end_to_end_2d() {
+        let mics: Vec<MicPosition> = square_array().into_iter().map(|m| MicPosition { z: 0.0, ..m }).collect();
+        let source = [-1.5, 2.0, 0.0];
+        let len = (0.03 * SR as f64) as usize;
+        let channels: Vec<Vec<f32>> = mics
+            .iter()
+            .map(|m| sweep_at(0.005 + dist(&[m.x, m.y, m.z], &source) / SPEED_OF_SOUND, len))
+            .collect();
+        let params = TdoaParams { max_lag_secs: max_lag_for(&mics, SPEED_OF_SOUND), ..TdoaParams::default() };
+        let delays: Vec<Option<f64>> = channel_delays(&channels, SR, &params).iter().map(|d| d.map(|d| d.secs)).collect();
+        assert!(delays.iter().all(Option::is_some));
+
+        let p = localize(&mics, &delays, false, SPEED_OF_SOUND).unwrap();
+        assert!((p.x + 1.5).abs() < 0.1 && (p.y - 2.0).abs() < 0.1, "{p:?}");
+        assert_eq!(p.z, None);
+        assert_eq!(p.delays_ms.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_mic_positions() {
+        let mics = parse_mic_positions("0 0\n1.5, 0, 0.2\n\n# comment\n0 1").unwrap();
+        assert_eq!(mics.len(), 3);
+        assert_eq!(mics[1], MicPosition { x: 1.5, y: 0.0, z: 0.2 });
+        assert!(parse_mic_positions("1 2 3 4").is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Spectrogram template matching: find calls similar to an exemplar.
+//!
+//! A time–frequency box (usually an annotated `Region`) is cut out of the
+//! spectrogram as a dB patch. The patch is slid across the spectrogram one
+//! column at a time and compared with normalized cross-correlation (Pearson
+//! correlation of the dB values), optionally allowing a small frequency
+//! shift so calls of the same type at a slightly different pitch still match.
+//! Local maxima above a score threshold are returned as hits.
+//!
+//! Targets can be a `SpectrogramData` whose columns are in memory, or raw
+//! samples for which STFT columns are computed on demand (large/streamed
+//! files, or files with a different sample rate than the template).
+
+use crate::dsp::fft::compute_stft_columns;
+use crate::types::{SpectrogramColumn, SpectrogramData};
+
+/// Normalized dB patch cut from a spectrogram.
+#[derive(Clone, Debug)]
+pub struct Template {
+    /// Frequency of the first template row (Hz).
+    pub freq_low_hz: f64,
+    /// Frequency of the last template row (Hz).
+    pub freq_high_hz: f64,
+    /// Bin width of the source spectrogram (Hz).
+    pub freq_resolution: f64,
+    /// Hop of the source spectrogram (seconds).
+    pub time_resolution: f64,
+    /// Length of the selected box (seconds).
+    pub duration: f64,
+    /// Time from the start of the box to the centre of the first template column.
+    pub lead: f64,
+    pub cols: usize,
+    pub bins: usize,
+    /// Zero-mean, unit-norm dB values, column-major (`col * bins + bin`).
+    pub data: Vec<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TemplateMatchParams {
+    /// Minimum correlation (0–1) for a hit.
+    pub min_score: f64,
+    /// Largest frequency shift tried in either direction (Hz).
+    pub max_freq_shift_hz: f64,
+    /// dB values more than this far below the patch maximum are clamped,
+    /// so background noise doesn't dominate the correlation.
+    pub dynamic_range_db: f32,
+    /// Stop after this many hits (strongest kept).
+    pub max_hits: usize,
+}
+
+impl Default for TemplateMatchParams {
+    fn default() -> Self {
+        Self {
+            min_score: 0.6,
+            max_freq_shift_hz: 2000.0,
+            dynamic_range_db: 60.0,
+            max_hits: 500,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateHit {
+    /// Seconds from file start.
+    pub start_time: f64,
+    pub end_time: f64,
+    /// Template band moved by the best-matching frequency shift (Hz).
+    pub freq_low_hz: f64,
+    pub freq_high_hz: f64,
+    /// Normalized cross-correlation (−1–1).
+    pub score: f64,
+}
+
+fn to_db(m: f32) -> f32 {
+    20.0 * m.max(1e-12).log10()
+}
+
+/// Clamp to the dynamic range, remove the mean and scale to unit norm.
+/// Returns `None` for a flat patch (nothing to correlate against).
+fn normalize(values: &mut [f32], dynamic_range_db: f32) -> Option<()> {
+    let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let floor = max - dynamic_range_db;
+    for v in values.iter_mut() {
+        *v = v.max(floor);
+    }
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / values.len() as f64;
+    let norm = values.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>().sqrt();
+    if norm < 1e-9 {
+        return None;
+    }
+    for v in values.iter_mut() {
+        *v = ((*v as f64 - mean) / norm) as f32;
+    }
+    Some(())
+}
+
+/// Cut a template out of `spectrogram` for the box `(start, end)` seconds ×
+/// `(freq_low, freq_high)` Hz. Needs the columns in memory.
+pub fn template_from_spectrogram(
+    spectrogram: &SpectrogramData,
+    (start_time, end_time): (f64, f64),
+    (freq_low_hz, freq_high_hz): (f64, f64),
+    params: &TemplateMatchParams,
+) -> Option<Template> {
+    template_from_columns(
+        &spectrogram.columns,
+        spectrogram.freq_resolution,
+        spectrogram.time_resolution,
+        (start_time, end_time),
+        (freq_low_hz, freq_high_hz),
+        params,
+    )
+}
+
+/// Cut a template from raw samples (`samples[0]` at `samples_start_time`),
+/// computing STFT columns with the given FFT size and hop.
+pub fn template_from_samples(
+    samples: &[f32],
+    sample_rate: u32,
+    samples_start_time: f64,
+    (fft_size, hop_size): (usize, usize),
+    (start_time, end_time): (f64, f64),
+    (freq_low_hz, freq_high_hz): (f64, f64),
+    params: &TemplateMatchParams,
+) -> Option<Template> {
+    let columns = stft(samples, sample_rate, samples_start_time, fft_size, hop_size);
+    template_from_columns(
+        &columns,
+        sample_rate as f64 / fft_size as f64,
+        hop_size as f64 / sample_rate as f64,
+        (start_time, end_time),
+        (freq_low_hz, freq_high_hz),
+        params,
+    )
+}
+
+fn template_from_columns(
+    columns: &[SpectrogramColumn],
+    freq_resolution: f64,
+    time_resolution: f64,
+    (start_time, end_time): (f64, f64),
+    (freq_low_hz, freq_high_hz): (f64, f64),
+    params: &TemplateMatchParams,
+) -> Option<Template> {
+    if freq_resolution <= 0.0 || end_time <= start_time || freq_high_hz <= freq_low_hz {
+        return None;
+    }
+    // Column time_offset is the frame start; the frame centre is half a window later.
+    let half_window = 0.5 / freq_resolution;
+    let selected: Vec<&SpectrogramColumn> = columns
+        .iter()
+        .filter(|c| {
+            let t = c.time_offset + half_window;
+            t >= start_time && t <= end_time
+        })
+        .collect();
+    let first = selected.first()?;
+    let n_bins = first.magnitudes.len();
+    let lo = ((freq_low_hz / freq_resolution).round() as usize).max(1);
+    let hi = ((freq_high_hz / freq_resolution).round() as usize).min(n_bins.saturating_sub(1));
+    if hi <= lo || selected.len() < 2 {
+        return None;
+    }
+
+    let bins = hi - lo + 1;
+    let mut data: Vec<f32> = selected
+        .iter()
+        .flat_map(|c| c.magnitudes[lo..=hi].iter().map(|&m| to_db(m)))
+        .collect();
+    normalize(&mut data, params.dynamic_range_db)?;
+
+    Some(Template {
+        freq_low_hz: lo as f64 * freq_resolution,
+        freq_high_hz: hi as f64 * freq_resolution,
+        freq_resolution,
+        time_resolution,
+        duration: end_time - start_time,
+        lead: first.time_offset + half_window - start_time,
+        cols: selected.len(),
+        bins,
+        data,
+    })
+}
+
+/// Scan a spectrogram whose columns are in memory.
+pub fn match_template(
+    template: &Template,
+    spectrogram: &SpectrogramData,
+    params: &TemplateMatchParams,
+) -> Vec<TemplateHit> {
+    match_columns(template, &spectrogram.columns, spectrogram.freq_resolution, params)
+}
+
+/// Scan raw samples (`samples[0]` at `samples_start_time`), computing STFT
+/// columns on demand. FFT size and hop are chosen to reproduce the template's
+/// bin width and hop at this sample rate.
+pub fn match_template_samples(
+    template: &Template,
+    samples: &[f32],
+    sample_rate: u32,
+    samples_start_time: f64,
+    params: &TemplateMatchParams,
+) -> Vec<TemplateHit> {
+    if sample_rate == 0 || template.freq_resolution <= 0.0 {
+        return Vec::new();
+    }
+    let sr = sample_rate as f64;
+    let fft_size = ((sr / template.freq_resolution).round() as usize).max(2);
+    let hop_size = ((template.time_resolution * sr).round() as usize).max(1);
+    let columns = stft(samples, sample_rate, samples_start_time, fft_size, hop_size);
+    match_columns(template, &columns, sr / fft_size as f64, params)
+}
+
+fn stft(
+    samples: &[f32],
+    sample_rate: u32,
+    samples_start_time: f64,
+    fft_size: usize,
+    hop_size: usize,
+) -> Vec<SpectrogramColumn> {
+    if hop_size == 0 || samples.len() < fft_size {
+        return Vec::new();
+    }
+    let cols = (samples.len() - fft_size) / hop_size + 1;
+    let mut columns = compute_stft_columns(samples, sample_rate, fft_size, hop_size, 0, cols);
+    for c in columns.iter_mut() {
+        c.time_offset += samples_start_time;
+    }
+    columns
+}
+
+fn match_columns(
+    template: &Template,
+    columns: &[SpectrogramColumn],
+    freq_resolution: f64,
+    params: &TemplateMatchParams,
+) -> Vec<TemplateHit> {
+    let Some(n_bins) = columns.first().map(|c| c.magnitudes.len()) else {
+        return Vec::new();
+    };
+    if freq_resolution <= 0.0 || columns.len() < template.cols || template.cols == 0 {
+        return Vec::new();
+    }
+
+    // Target bin for each template row (template and target bin widths may differ slightly)
+    let rows: Vec<usize> = (0..template.bins)
+        .map(|b| ((template.freq_low_hz + b as f64 * template.freq_resolution) / freq_resolution).round() as usize)
+        .collect();
+    let max_shift = (params.max_freq_shift_hz / freq_resolution).round() as isize;
+    let row_lo = rows[0] as isize;
+    let row_hi = rows[rows.len() - 1] as isize;
+    let shifts: Vec<isize> = (-max_shift..=max_shift)
+        .filter(|s| row_lo + s >= 1 && row_hi + s < n_bins as isize)
+        .collect();
+    if shifts.is_empty() {
+        return Vec::new();
+    }
+
+    // dB values over the band covered by all shifts, computed once per column
+    let band_lo = (row_lo + shifts[0]) as usize;
+    let band_hi = (row_hi + shifts[shifts.len() - 1]) as usize;
+    let band: Vec<Vec<f32>> = columns
+        .iter()
+        .map(|c| c.magnitudes[band_lo..=band_hi].iter().map(|&m| to_db(m)).collect())
+        .collect();
+
+    let half_window = 0.5 / freq_resolution;
+    let n = (template.cols * template.bins) as f64;
+    let mut patch = vec![0.0f32; template.cols * template.bins];
+    let mut scored: Vec<(usize, f64, isize)> = Vec::new();
+    for offset in 0..=(columns.len() - template.cols) {
+        let mut best = (f64::NEG_INFINITY, 0isize);
+        for &shift in &shifts {
+            for col in 0..template.cols {
+                let db = &band[offset + col];
+                for (b, &row) in rows.iter().enumerate() {
+                    patch[col * template.bins + b] = db[(row as isize + shift) as usize - band_lo];
+                }
+            }
+            // Template is zero-mean/unit-norm, so only the patch needs normalizing
+            let max = patch.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            let floor = max - params.dynamic_range_db;
+            let (mut sum, mut sum_sq, mut dot) = (0.0f64, 0.0f64, 0.0f64);
+            for (&v, &t) in patch.iter().zip(template.data.iter()) {
+                let v = v.max(floor) as f64;
+                sum += v;
+                sum_sq += v * v;
+                dot += v * t as f64;
+            }
+            let var = sum_sq - sum * sum / n;
+            if var < 1e-9 {
+                continue;
+            }
+            let score = dot / var.sqrt();
+            if score > best.0 {
+                best = (score, shift);
+            }
+        }
+        if best.0.is_finite() {
+            scored.push((offset, best.0, best.1));
+        }
+    }
+
+    // Non-maximum suppression: strongest first, drop anything overlapping a kept hit
+    let mut candidates: Vec<&(usize, f64, isize)> = scored.iter().filter(|s| s.1 >= params.min_score).collect();
+    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    let mut kept: Vec<&(usize, f64, isize)> = Vec::new();
+    for c in candidates {
+        if kept.len() >= params.max_hits {
+            break;
+        }
+        if kept.iter().all(|k| k.0.abs_diff(c.0) >= template.cols) {
+            kept.push(c);
+        }
+    }
+    kept.sort_by_key(|k| k.0);
+
+    kept.into_iter()
+        .map(|&(offset, score, shift)| {
+            let start_time = columns[offset].time_offset + half_window - template.lead;
+            let shift_hz = shift as f64 * freq_resolution;
+            TemplateHit {
+                start_time,
+                end_time: start_time + template.duration,
+                freq_low_hz: template.freq_low_hz + shift_hz,
+                freq_high_hz: template.freq_high_hz + shift_hz,
+                score,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const SR: u32 = 256_000;
+
+    /// Add a linear chirp from f0 to f1 of `dur` seconds starting at `t0`.
+    fn add_chirp(buf: &mut [f32], f0: f64, f1: f64, t0: f64, dur: f64, amp: f64) {
+        let start = (t0 * SR as f64) as usize;
+        let n = (dur * SR as f64) as usize;
+        let mut phase = 0.0f64;
+        for i in 0..n {
+            let t = i as f64 / SR as f64;
+            let f = f0 + (f1 - f0) * t / dur;
+            phase += 2.0 * std::f64::consts::PI * f / SR as f64;
+            if let Some(s) = buf.get_mut(start + i) {
+                *s += (amp * phase.sin()) as f32;
+            }
+        }
+    }
+
+    /// Deterministic low-level noise so the background isn't perfectly flat.
+    fn noise(len: usize) -> Vec<f32> {
+        let mut x = 12345u32;
+        (0..len)
+            .map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                ((x >> 16) as f32 / 65536.0 - 0.5) * 0.002
+            })
+            .collect()
+    }
+
+    fn spectrogram(samples: &[f32], fft: usize, hop: usize) -> SpectrogramData {
+        let cols = (samples.len() - fft) / hop + 1;
+        let columns = compute_stft_columns(samples, SR, fft, hop, 0, cols);
+        SpectrogramData {
+            total_columns: columns.len(),
+            columns: Arc::new(columns),
+            freq_resolution: SR as f64 / fft as f64,
+            time_resolution: hop as f64 / SR as f64,
+            max_freq: SR as f64 / 2.0,
+            sample_rate: SR,
+        }
+    }
+
+    #[test]
+    fn test_finds_repeated_sweeps_but_not_cf_tone() {
+        let mut samples = noise((0.2 * SR as f64) as usize);
+        for t0 in [0.02, 0.07, 0.12] {
+            add_chirp(&mut samples, 70_000.0, 40_000.0, t0, 0.004, 0.5);
+        }
+        // Constant-frequency call in the same band: should not match an FM sweep
+        add_chirp(&mut samples, 55_000.0, 55_000.0, 0.165, 0.004, 0.5);
+        let spec = spectrogram(&samples, 256, 64);
+        let params = TemplateMatchParams::default();
+
+        let template = template_from_spectrogram(&spec, (0.019, 0.025), (35_000.0, 75_000.0), &params).unwrap();
+        let hits = match_template(&template, &spec, &params);
+        assert_eq!(hits.len(), 3, "{hits:?}");
+        for (hit, t0) in hits.iter().zip([0.02, 0.07, 0.12]) {
+            assert!((hit.start_time - (t0 - 0.001)).abs() < 0.001, "{hit:?}");
+            assert!(hit.score > 0.8);
+        }
+    }
+
+    #[test]
+    fn test_frequency_shift_tolerance() {
+        let mut samples = noise((0.1 * SR as f64) as usize);
+        add_chirp(&mut samples, 70_000.0, 40_000.0, 0.02, 0.004, 0.5);
+        add_chirp(&mut samples, 73_000.0, 43_000.0, 0.07, 0.004, 0.5);
+        let spec = spectrogram(&samples, 256, 64);
+
+        let strict = TemplateMatchParams { max_freq_shift_hz: 0.0, min_score: 0.9, ..Default::default() };
+        let loose = TemplateMatchParams { max_freq_shift_hz: 4000.0, min_score: 0.9, ..Default::default() };
+        let template = template_from_spectrogram(&spec, (0.019, 0.025), (35_000.0, 75_000.0), &strict).unwrap();
+        assert_eq!(match_template(&template, &spec, &strict).len(), 1);
+
+        let hits = match_template(&template, &spec, &loose);
+        assert_eq!(hits.len(), 2, "{hits:?}");
+        assert!(hits[1].freq_low_hz > template.freq_low_hz + 1000.0);
+    }
+
+    #[test]
+    fn test_samples_scan_reports_file_time() {
+        let mut samples = noise((0.1 * SR as f64) as usize);
+        add_chirp(&mut samples, 70_000.0, 40_000.0, 0.02, 0.004, 0.5);
+        add_chirp(&mut samples, 70_000.0, 40_000.0, 0.06, 0.004, 0.5);
+        let params = TemplateMatchParams::default();
+        let template = template_from_samples(
+            &samples, SR, 0.0, (256, 64), (0.019, 0.025), (35_000.0, 75_000.0), &params,
+        ).unwrap();
+
+        // Scan a later chunk, as if streamed: times come back in file time
+        let offset = (0.05 * SR as f64) as usize;
+        let hits = match_template_samples(&template, &samples[offset..], SR, 0.05, &params);
+        assert_eq!(hits.len(), 1, "{hits:?}");
+        assert!((hits[0].start_time - 0.059).abs() < 0.001, "{:?}", hits[0]);
+    }
+}
@@ -165,6 +165,52 @@ pub fn zc_rate_per_bin(
     bins
 }
 
+/// Times (seconds from `samples[0]`) of every `division_factor`-th upward
+/// zero crossing while the Schmitt trigger is armed — the event stream a
+/// frequency-division detector's ZC recorder stores, so consecutive events
+/// are `division_factor` cycles apart. Crossing times are interpolated
+/// between samples.
+///
+/// Uses the same band-pass, envelope and adaptive threshold as `zc_divide`.
+pub fn zc_event_times(samples: &[f32], sample_rate: u32, division_factor: u32, skip_bandpass: bool) -> Vec<f64> {
+    if samples.len() < 2 || division_factor == 0 || sample_rate == 0 {
+        return Vec::new();
+    }
+
+    let filtered = if skip_bandpass {
+        samples.to_vec()
+    } else {
+        bandpass_ultrasonic(samples, sample_rate)
+    };
+    let env_samples = ((sample_rate as f64 * 0.001) as usize).max(1);
+    let envelope = smooth_envelope(&filtered, env_samples);
+    let (threshold_high, threshold_low) = adaptive_threshold(&filtered);
+
+    let mut events = Vec::new();
+    let mut armed = false;
+    let mut cycle_count: u32 = 0;
+    for i in 1..filtered.len() {
+        let env = envelope[i];
+        if env > threshold_high {
+            armed = true;
+        } else if env < threshold_low {
+            armed = false;
+            cycle_count = 0;
+        }
+
+        let (prev, curr) = (filtered[i - 1], filtered[i]);
+        if armed && prev < 0.0 && curr >= 0.0 {
+            cycle_count += 1;
+            if cycle_count >= division_factor {
+                cycle_count = 0;
+                let frac = (-prev / (curr - prev)) as f64;
+                events.push((i as f64 - 1.0 + frac) / sample_rate as f64);
+            }
+        }
+    }
+    events
+}
+
 pub(crate) fn cascaded_lp(samples: &[f32], cutoff: f64, sample_rate: u32, passes: usize) -> Vec<f32> {
     let mut result = samples.to_vec();
     for _ in 0..passes {
@@ -234,6 +280,19 @@ mod tests {
         assert!(peak > 0.001, "Quiet bat calls should still produce clicks, peak={peak}");
     }
 
+    #[test]
+    fn test_event_times_are_division_cycles_apart() {
+        let sr = 384_000;
+        let input: Vec<f32> = make_sine(40_000.0, sr, 0.02).iter().map(|s| s * 0.5).collect();
+        let events = zc_event_times(&input, sr, 8, false);
+        assert!(events.len() > 50, "{} events", events.len());
+        // Skip the filter's settling time, then every interval is 8 cycles
+        for w in events[10..].windows(2) {
+            let freq = 8.0 / (w[1] - w[0]);
+            assert!((freq - 40_000.0).abs() < 200.0, "freq {freq}");
+        }
+    }
+
     #[test]
     fn test_silence_produces_no_output() {
         let input = vec![0.0f32; 19200];
@@ -13,6 +13,10 @@ pub struct FileMetadata {
     pub data_offset: Option<u64>,
     /// Byte length of audio data region. None for non-WAV.
     pub data_size: Option<u64>,
+    /// Time-expansion factor the file was recorded with (1.0 = real time).
+    /// When > 1, `AudioData::sample_rate` and `duration_secs` are already in
+    /// real-world units; the file's own rate is `sample_rate / time_expansion`.
+    pub time_expansion: f64,
 }
 
 #[derive(Clone)]
@@ -155,7 +155,7 @@ class MediaStorePlugin(private val activity: Activity) : Plugin(activity) {
                 val resolver = activity.contentResolver
                 val values = ContentValues().apply {
                     put(MediaStore.Audio.Media.DISPLAY_NAME, filename)
-                    put(MediaStore.Audio.Media.MIME_TYPE, "audio/wav")
+                    put(MediaStore.Audio.Media.MIME_TYPE, recordingMimeType(filename))
                     put(MediaStore.Audio.Media.RELATIVE_PATH, "Recordings/$SUBFOLDER")
                     put(MediaStore.Audio.Media.IS_PENDING, 1)
                 }
@@ -438,13 +438,17 @@ class MediaStorePlugin(private val activity: Activity) : Plugin(activity) {
 
     // ── Internal helpers ────────────────────────────────────────────────
 
+    /** Recordings are WAV unless saved as FLAC. */
+    private fun recordingMimeType(name: String): String =
+        if (name.endsWith(".flac", ignoreCase = true)) "audio/flac" else "audio/wav"
+
     /** API 29+: Save via MediaStore to Recordings/Oversample */
     private fun saveViaMediaStore(sourceFile: File, displayName: String): String {
         val resolver = activity.contentResolver
 
         val values = ContentValues().apply {
             put(MediaStore.Audio.Media.DISPLAY_NAME, displayName)
-            put(MediaStore.Audio.Media.MIME_TYPE, "audio/wav")
+            put(MediaStore.Audio.Media.MIME_TYPE, recordingMimeType(displayName))
             put(MediaStore.Audio.Media.RELATIVE_PATH, "Recordings/$SUBFOLDER")
             put(MediaStore.Audio.Media.IS_PENDING, 1)
         }
@@ -487,7 +491,7 @@ class MediaStorePlugin(private val activity: Activity) : Plugin(activity) {
         MediaScannerConnection.scanFile(
             activity,
             arrayOf(destFile.absolutePath),
-            arrayOf("audio/wav"),
+            arrayOf(recordingMimeType(displayName)),
             null
         )
 
@@ -501,7 +505,7 @@ class MediaStorePlugin(private val activity: Activity) : Plugin(activity) {
 
         val values = ContentValues().apply {
             put(MediaStore.Audio.Media.DISPLAY_NAME, displayName)
-            put(MediaStore.Audio.Media.MIME_TYPE, "audio/wav")
+            put(MediaStore.Audio.Media.MIME_TYPE, recordingMimeType(displayName))
             put(MediaStore.Audio.Media.RELATIVE_PATH, "Recordings/$SUBFOLDER")
             put(MediaStore.Audio.Media.IS_PENDING, 1)
         }
@@ -535,7 +539,7 @@ class MediaStorePlugin(private val activity: Activity) : Plugin(activity) {
         MediaScannerConnection.scanFile(
             activity,
             arrayOf(destFile.absolutePath),
-            arrayOf("audio/wav"),
+            arrayOf(recordingMimeType(displayName)),
             null
         )
 
@@ -4,6 +4,8 @@
 //! to mono f32 samples natively on a background thread, avoiding the
 //! need to pass entire file bytes through the WASM boundary.
 
+use oversample_core::audio::{aiff, caf, wavpack};
+use oversample_core::audio::loader::{decode_pcm, parse_wav_header_with_file_size, WavHeader};
 use serde::Serialize;
 use std::io::Cursor;
 use std::path::Path;
@@ -39,11 +41,15 @@ pub fn file_info(path: &str) -> Result<AudioFileInfo, String> {
     match &bytes[0..4] {
         b"RIFF" if is_w4v(&bytes) => w4v_info(&bytes, file_size),
         b"RIFF" => wav_info(&bytes, file_size),
+        _ if oversample_core::audio::riff::is_wave(&bytes) => Ok(pcm_info(&wide_wav_header(&bytes)?, "WAV", file_size)),
+        b"FORM" if aiff::is_aiff(&bytes) => Ok(pcm_info(&aiff::parse_aiff_header(&bytes)?, "AIFF", file_size)),
+        b"caff" => Ok(pcm_info(&caf::parse_caf_header(&bytes, Some(file_size as u64))?, "CAF", file_size)),
+        b"wvpk" => wavpack_info(&bytes, file_size),
         b"fLaC" => flac_info(&bytes, file_size),
         b"OggS" => ogg_info(&bytes, file_size),
         _ if is_m4a(&bytes) => m4a_info(&bytes, file_size),
         _ if is_mp3(&bytes) => mp3_info(&bytes, file_size),
-        _ => Err("Unknown audio format (expected WAV, W4V, FLAC, OGG, MP3, or M4A)".into()),
+        _ => Err("Unknown audio format (expected WAV, RF64, W64, W4V, WavPack, AIFF, CAF, FLAC, OGG, MP3, or M4A)".into()),
     }
 }
 
@@ -60,6 +66,8 @@ pub fn decode_full(path: &str) -> Result<FullDecodeResult, String> {
     match &bytes[0..4] {
         b"RIFF" if is_w4v(&bytes) => decode_w4v(&bytes, file_size),
         b"RIFF" => decode_wav(&bytes, file_size),
+        _ if oversample_core::audio::riff::is_wave(&bytes) => decode_wide_wav(&bytes, file_size),
+        b"FORM" | b"caff" | b"wvpk" => decode_with_core(&bytes, file_size),
         b"fLaC" => decode_flac(&bytes, file_size),
         b"OggS" => decode_ogg(&bytes, file_size),
         _ if is_m4a(&bytes) => decode_m4a(&bytes, file_size),
@@ -119,6 +127,37 @@ fn mix_to_mono(samples: &[f32], channels: u32) -> Vec<f32> {
 
 // ── WAV ─────────────────────────────────────────────────────────────
 
+/// RF64 and Wave64 header, with the frame count cut to the bytes on disk.
+/// These containers exist for files over 4 GB, so they're read directly
+/// rather than rewritten as RIFF for hound.
+fn wide_wav_header(bytes: &[u8]) -> Result<WavHeader, String> {
+    let mut header = parse_wav_header_with_file_size(bytes, Some(bytes.len() as u64))?;
+    let bytes_per_frame = header.channels as u64 * (header.bits_per_sample as u64 / 8);
+    if bytes_per_frame == 0 {
+        return Err("Invalid WAV: zero channels or bit depth".into());
+    }
+    let available = (bytes.len() as u64).saturating_sub(header.data_offset).min(header.data_size);
+    header.total_frames = available / bytes_per_frame;
+    header.data_size = header.total_frames * bytes_per_frame;
+    Ok(header)
+}
+
+fn decode_wide_wav(bytes: &[u8], file_size: usize) -> Result<FullDecodeResult, String> {
+    let header = wide_wav_header(bytes)?;
+    let start = header.data_offset as usize;
+    let data = &bytes[start..start + header.data_size as usize];
+    let all_samples = if header.bits_per_sample == 8 && header.unsigned_8bit {
+        data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect()
+    } else {
+        decode_pcm(data, header.bits_per_sample, header.is_float, header.big_endian)
+    };
+    let samples = mix_to_mono(&all_samples, header.channels as u32);
+    Ok(FullDecodeResult {
+        info: pcm_info(&header, "WAV", file_size),
+        samples,
+    })
+}
+
 fn wav_info(bytes: &[u8], file_size: usize) -> Result<AudioFileInfo, String> {
     let cursor = Cursor::new(bytes);
     let reader = hound::WavReader::new(cursor).map_err(|e| format!("WAV error: {e}"))?;
@@ -182,6 +221,60 @@ fn decode_wav(bytes: &[u8], file_size: usize) -> Result<FullDecodeResult, String
     })
 }
 
+// ── AIFF / CAF / WavPack ────────────────────────────────────────────
+
+fn pcm_info(header: &WavHeader, format: &str, file_size: usize) -> AudioFileInfo {
+    let mono_samples = header.total_frames as usize;
+    AudioFileInfo {
+        sample_rate: header.sample_rate,
+        channels: header.channels as u32,
+        duration_secs: mono_samples as f64 / header.sample_rate as f64,
+        total_mono_samples: mono_samples,
+        bits_per_sample: header.bits_per_sample,
+        is_float: header.is_float,
+        format: format.into(),
+        file_size,
+    }
+}
+
+fn wavpack_info(bytes: &[u8], file_size: usize) -> Result<AudioFileInfo, String> {
+    let header = wavpack::parse_wavpack_header(bytes)?;
+    // Streams written without a known length have to be decoded to count
+    let mono_samples = match header.total_frames {
+        Some(n) => n as usize,
+        None => wavpack::decode_wavpack(bytes)?.1.len() / header.channels.max(1) as usize,
+    };
+    Ok(AudioFileInfo {
+        sample_rate: header.sample_rate,
+        channels: header.channels as u32,
+        duration_secs: mono_samples as f64 / header.sample_rate as f64,
+        total_mono_samples: mono_samples,
+        bits_per_sample: header.bits_per_sample,
+        is_float: header.is_float,
+        format: "WavPack".into(),
+        file_size,
+    })
+}
+
+/// Formats whose only decoder lives in oversample-core.
+fn decode_with_core(bytes: &[u8], file_size: usize) -> Result<FullDecodeResult, String> {
+    let audio = oversample_core::audio::loader::load_audio(bytes)?;
+    let samples = audio.samples.to_vec();
+    Ok(FullDecodeResult {
+        info: AudioFileInfo {
+            sample_rate: audio.sample_rate,
+            channels: audio.channels,
+            duration_secs: audio.duration_secs,
+            total_mono_samples: samples.len(),
+            bits_per_sample: audio.metadata.bits_per_sample,
+            is_float: audio.metadata.is_float,
+            format: audio.metadata.format.into(),
+            file_size,
+        },
+        samples,
+    })
+}
+
 // ── FLAC ────────────────────────────────────────────────────────────
 
 fn flac_info(bytes: &[u8], file_size: usize) -> Result<AudioFileInfo, String> {
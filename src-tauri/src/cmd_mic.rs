@@ -201,6 +201,7 @@ pub fn mic_stop_recording(
     device_model: Option<String>,
     app_version: Option<String>,
     skip_native_save: Option<bool>,
+    record_format: Option<String>,
 ) -> Result<RecordingResult, String> {
     let mic = state.lock().map_err(|e| e.to_string())?;
     let m = mic.as_ref().ok_or("Microphone not open")?;
@@ -250,7 +251,10 @@ pub fn mic_stop_recording(
 
     // Build the GUANO chunk for either path below.
     let now = chrono::Local::now();
-    let filename_ts = now.format("batcap_%Y%m%d_%H%M%S.wav").to_string();
+    let record_format = recording::RecordFormat::from_arg(record_format.as_deref());
+    let filename_ts = now
+        .format(&format!("batcap_%Y%m%d_%H%M%S.{}", record_format.extension()))
+        .to_string();
     let location = match (loc_latitude, loc_longitude) {
         (Some(lat), Some(lon)) => Some(recording::RecordingLocation {
             latitude: lat,
@@ -287,11 +291,14 @@ pub fn mic_stop_recording(
             let mut buf = m.buffer.lock().unwrap();
             recovery::drain_cpal_bytes(&mut buf)
         };
-        let finalized_path = recovery::finalize_in_place_and_take(
+        let mut finalized_path = recovery::finalize_in_place_and_take(
             writer,
             &final_bytes,
             &guano_text,
         ).map_err(|e| format!("recovery finalize failed: {}", e))?;
+        if record_format == recording::RecordFormat::Flac {
+            finalized_path = recording::replace_with_flac(&finalized_path, &guano)?;
+        }
 
         let final_size = finalized_path.metadata().map(|m| m.len()).unwrap_or(0);
 
@@ -303,7 +310,7 @@ pub fn mic_stop_recording(
             let _ = std::fs::remove_file(&finalized_path);
             "shared://recording".to_string()
         } else {
-            // Move .wav.part → recordings/<name>.wav
+            // Move .wav.part (or its FLAC) → recordings/<name>
             let dir = app
                 .path()
                 .app_data_dir()
@@ -324,7 +331,11 @@ pub fn mic_stop_recording(
         let samples_f32 = recording::get_samples_f32(&buf);
         let mut wav_data = recording::encode_native_wav(&buf)?;
         drop(buf);
-        oversample_core::audio::guano::append_guano_chunk(&mut wav_data, &guano_text);
+        if record_format == recording::RecordFormat::Flac {
+            wav_data = recording::wav_bytes_to_flac(&wav_data, &guano)?;
+        } else {
+            oversample_core::audio::guano::append_guano_chunk(&mut wav_data, &guano_text);
+        }
         let file_size_bytes = wav_data.len();
 
         let path = if let Some(fd) = shared_fd {
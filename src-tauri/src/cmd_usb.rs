@@ -133,6 +133,7 @@ pub fn usb_stop_recording(
     device_model: Option<String>,
     app_version: Option<String>,
     skip_native_save: Option<bool>,
+    record_format: Option<String>,
 ) -> Result<RecordingResult, String> {
     let usb = state.lock().map_err(|e| e.to_string())?;
     let s = usb.as_ref().ok_or("USB stream not open")?;
@@ -182,7 +183,10 @@ pub fn usb_stop_recording(
     }
 
     let now = chrono::Local::now();
-    let filename = now.format("batcap_%Y%m%d_%H%M%S.wav").to_string();
+    let record_format = recording::RecordFormat::from_arg(record_format.as_deref());
+    let filename = now
+        .format(&format!("batcap_%Y%m%d_%H%M%S.{}", record_format.extension()))
+        .to_string();
     let location = match (loc_latitude, loc_longitude) {
         (Some(lat), Some(lon)) => Some(recording::RecordingLocation {
             latitude: lat,
@@ -226,8 +230,11 @@ pub fn usb_stop_recording(
             let mut buf = s.buffer.lock().unwrap();
             usb_audio::drain_usb_recovery_bytes(&mut buf)
         };
-        let finalized_path = recovery::finalize_in_place_and_take(writer, &final_bytes, &guano_text)
+        let mut finalized_path = recovery::finalize_in_place_and_take(writer, &final_bytes, &guano_text)
             .map_err(|e| format!("recovery finalize failed: {}", e))?;
+        if record_format == recording::RecordFormat::Flac {
+            finalized_path = recording::replace_with_flac(&finalized_path, &guano)?;
+        }
         let final_size = finalized_path.metadata().map(|m| m.len()).unwrap_or(0) as usize;
 
         let saved_path = if let Some(fd) = shared_fd {
@@ -251,7 +258,11 @@ pub fn usb_stop_recording(
         // To-memory mode: encode the accumulated i16 buffer, return samples.
         let samples_f32 = usb_audio::get_usb_samples_f32(s);
         let mut wav_data = usb_audio::encode_usb_wav(s)?;
-        oversample_core::audio::guano::append_guano_chunk(&mut wav_data, &guano_text);
+        if record_format == recording::RecordFormat::Flac {
+            wav_data = recording::wav_bytes_to_flac(&wav_data, &guano)?;
+        } else {
+            oversample_core::audio::guano::append_guano_chunk(&mut wav_data, &guano_text);
+        }
         let file_size_bytes = wav_data.len();
         let path = if let Some(fd) = shared_fd {
             recording::write_wav_to_fd(fd, &wav_data)?;
@@ -619,6 +619,111 @@ pub fn encode_native_wav(buffer: &RecordingBuffer) -> Result<Vec<u8>, String> {
     Ok(cursor.into_inner())
 }
 
+/// File format for saved recordings, chosen on the WASM side (`recordFormat`).
+/// Recording itself always streams WAV; FLAC is encoded from it at stop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+    Wav,
+    Flac,
+}
+
+impl RecordFormat {
+    pub fn from_arg(arg: Option<&str>) -> Self {
+        match arg {
+            Some("flac") => Self::Flac,
+            _ => Self::Wav,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+        }
+    }
+}
+
+/// Re-encode a WAV stream (RIFF or RF64) as FLAC carrying `guano`.
+/// 8/16-bit stays 16-bit; 24-bit, 32-bit and float recordings become
+/// 24-bit FLAC. PCM is read in blocks, so the input is never fully in memory.
+fn wav_to_flac<R: std::io::Read + std::io::Seek, W: std::io::Write + std::io::Seek>(
+    mut input: R,
+    out: W,
+    guano: &oversample_core::audio::guano::GuanoMetadata,
+) -> Result<W, String> {
+    use oversample_core::audio::flac::{self, FlacSpec, FlacWriter, BLOCK_SIZE};
+    use oversample_core::audio::loader::parse_wav_header_with_file_size;
+    use std::io::{Read, SeekFrom};
+
+    let read_err = |e: std::io::Error| format!("WAV read error: {}", e);
+    let file_size = input.seek(SeekFrom::End(0)).map_err(read_err)?;
+    input.seek(SeekFrom::Start(0)).map_err(read_err)?;
+    let mut head = Vec::new();
+    (&mut input).take(65536).read_to_end(&mut head).map_err(read_err)?;
+    let header = parse_wav_header_with_file_size(&head, Some(file_size))?;
+
+    let in_bits = header.bits_per_sample;
+    let bits = if !header.is_float && in_bits <= 16 { 16 } else { 24 };
+    let flac_spec = FlacSpec { sample_rate: header.sample_rate, channels: header.channels, bits_per_sample: bits };
+    let mut writer = FlacWriter::new(out, flac_spec, Some(guano), &[])?;
+
+    let sample_bytes = in_bits as usize / 8;
+    let frame_bytes = sample_bytes * header.channels as usize;
+    input.seek(SeekFrom::Start(header.data_offset)).map_err(read_err)?;
+    let mut pcm = std::io::BufReader::new(input).take(header.data_size);
+    let mut raw = vec![0u8; BLOCK_SIZE * frame_bytes];
+    let mut block = Vec::with_capacity(BLOCK_SIZE * header.channels as usize);
+    loop {
+        let mut filled = 0;
+        while filled < raw.len() {
+            match pcm.read(&mut raw[filled..]).map_err(read_err)? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        block.clear();
+        block.extend(raw[..filled / frame_bytes * frame_bytes].chunks_exact(sample_bytes).map(|b| {
+            match (header.is_float, in_bits) {
+                (true, _) => flac::f32_to_pcm(f32::from_le_bytes([b[0], b[1], b[2], b[3]]), 24),
+                (false, 8) => (b[0] as i32 - 128) << 8,
+                (false, 16) => i16::from_le_bytes([b[0], b[1]]) as i32,
+                (false, 24) => i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8,
+                _ => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) >> 8,
+            }
+        }));
+        writer.write_samples(&block)?;
+        if filled < raw.len() {
+            break;
+        }
+    }
+    writer.finish()
+}
+
+/// Encode an in-memory WAV recording as FLAC.
+pub fn wav_bytes_to_flac(wav_data: &[u8], guano: &oversample_core::audio::guano::GuanoMetadata) -> Result<Vec<u8>, String> {
+    Ok(wav_to_flac(Cursor::new(wav_data), Cursor::new(Vec::new()), guano)?.into_inner())
+}
+
+/// Encode a finalized WAV file as FLAC next to it (same name, `.flac`
+/// extension) and delete the WAV. Streams through both files, so overnight
+/// recordings never sit in memory. Returns the FLAC path.
+pub fn replace_with_flac(
+    wav_path: &std::path::Path,
+    guano: &oversample_core::audio::guano::GuanoMetadata,
+) -> Result<std::path::PathBuf, String> {
+    let flac_path = wav_path.with_extension("flac");
+    let input = std::fs::File::open(wav_path).map_err(|e| format!("WAV read error: {}", e))?;
+    let out = std::fs::File::create(&flac_path).map_err(|e| format!("FLAC create error: {}", e))?;
+    let out = wav_to_flac(input, std::io::BufWriter::new(out), guano)
+        .inspect_err(|_| { let _ = std::fs::remove_file(&flac_path); })?;
+    out.into_inner()
+        .map_err(|e| format!("FLAC write error: {}", e))?
+        .sync_data()
+        .map_err(|e| format!("FLAC write error: {}", e))?;
+    let _ = std::fs::remove_file(wav_path);
+    Ok(flac_path)
+}
+
 /// Optional GPS location for GUANO metadata.
 pub struct RecordingLocation {
     pub latitude: f64,
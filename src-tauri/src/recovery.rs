@@ -1,13 +1,17 @@
 //! Crash-recovery for in-progress recordings.
 //!
 //! During a recording, raw PCM samples are streamed to a `<name>.wav.part`
-//! file with a placeholder WAV header, and a `<name>.wav.meta.json` sidecar
+//! file with a placeholder WAV header (with a `JUNK` chunk reserved for an
+//! RF64 `ds64` chunk, used once the file passes the 4 GB RIFF limit), and a `<name>.wav.meta.json` sidecar
 //! captures the GUANO-relevant context (mic info, device, location, start
 //! timestamp). On a clean stop the final encode is written and the partial
 //! files are deleted. If the app crashes or is killed, the partial + sidecar
 //! remain on disk; `recover_leftover_recordings` is called on next launch to
 //! patch the WAV header from the file size, reattach GUANO from the sidecar,
 //! and promote the file into the recordings directory.
+//!
+//! Recordings saved as FLAC still stream WAV PCM to `<name>.flac.part`; the
+//! FLAC is encoded from the finished WAV at stop (or at recovery).
 
 use std::fs::{File, OpenOptions};
 use std::io::{Seek, SeekFrom, Write};
@@ -17,11 +21,16 @@ use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 use tauri::Manager;
 
+use oversample_core::audio::riff::{ds64_body, DS64_BODY_LEN};
+
 use crate::recording::NativeSampleFormat;
 
 /// Bumped whenever the sidecar schema changes.
 const META_VERSION: u32 = 1;
 
+/// Placeholder header length: RIFF (12) + JUNK (8 + 28) + fmt (24) + data (8).
+const HEADER_LEN: u64 = 80;
+
 /// Sidecar file written at recording start. Stores everything needed to
 /// reconstruct a full GUANO chunk during recovery.
 #[derive(Serialize, Deserialize, Clone)]
@@ -83,6 +92,8 @@ pub struct RecoveryWriter {
     pub meta_path: PathBuf,
     pub wav_file: File,
     pub data_bytes_written: u64,
+    /// Bytes per sample frame, for the RF64 sample count.
+    pub block_align: u64,
 }
 
 /// Container for recovery state that lives on MicState. Cloning bumps the Arc
@@ -239,6 +250,7 @@ pub fn create(
         meta_path,
         wav_file,
         data_bytes_written: 0,
+        block_align: channels as u64 * (format.bits_per_sample() as u64 / 8),
     })
 }
 
@@ -355,22 +367,15 @@ pub fn finalize_in_place_and_take(
     }
 
     // Append the GUANO chunk at the end of file.
-    writer.wav_file.seek(SeekFrom::End(0))?;
-    let text_bytes = guano_text.as_bytes();
-    let pad = if text_bytes.len() % 2 == 1 { 1 } else { 0 };
-    let chunk_total_bytes = 8 + text_bytes.len() as u64 + pad as u64;
-    writer.wav_file.write_all(b"guan")?;
-    writer.wav_file.write_all(&(text_bytes.len() as u32).to_le_bytes())?;
-    writer.wav_file.write_all(text_bytes)?;
-    if pad == 1 {
-        writer.wav_file.write_all(&[0u8])?;
-    }
+    let chunk_total_bytes = append_guano_chunk(&mut writer.wav_file, guano_text)?;
 
     // Patch header with new sizes (RIFF accounts for the guan chunk too).
     patch_header_with_extra(
         &mut writer.wav_file,
+        HEADER_LEN,
         writer.data_bytes_written,
         chunk_total_bytes,
+        writer.block_align,
     )?;
 
     // Durability: force kernel buffers to the device so a crash immediately
@@ -413,6 +418,10 @@ fn write_placeholder_header(
     f.write_all(b"RIFF")?;
     f.write_all(&0u32.to_le_bytes())?; // placeholder: file_size - 8
     f.write_all(b"WAVE")?;
+    // JUNK chunk (36 bytes), rewritten as ds64 if the recording passes 4 GB
+    f.write_all(b"JUNK")?;
+    f.write_all(&(DS64_BODY_LEN as u32).to_le_bytes())?;
+    f.write_all(&[0u8; DS64_BODY_LEN])?;
     // fmt chunk (24 bytes)
     f.write_all(b"fmt ")?;
     f.write_all(&16u32.to_le_bytes())?; // fmt chunk body size
@@ -428,30 +437,63 @@ fn write_placeholder_header(
     Ok(())
 }
 
-fn patch_header(f: &mut File, data_bytes: u64) -> std::io::Result<()> {
-    patch_header_with_extra(f, data_bytes, 0)
+/// Append a GUANO `guan` chunk (word-aligned) at the end of the file and
+/// return its total size including header and padding.
+fn append_guano_chunk(f: &mut File, guano_text: &str) -> std::io::Result<u64> {
+    f.seek(SeekFrom::End(0))?;
+    let text_bytes = guano_text.as_bytes();
+    let pad = if text_bytes.len() % 2 == 1 { 1 } else { 0 };
+    f.write_all(b"guan")?;
+    f.write_all(&(text_bytes.len() as u32).to_le_bytes())?;
+    f.write_all(text_bytes)?;
+    if pad == 1 {
+        f.write_all(&[0u8])?;
+    }
+    Ok(8 + text_bytes.len() as u64 + pad)
 }
 
-/// Patch the RIFF + data size fields. `extra_bytes_after_data` is the size of
-/// any chunks appended after the data chunk (e.g. the GUANO `guan` chunk at
-/// finalize time) so the RIFF size covers them.
+/// Patch the RIFF + data size fields for a data chunk starting at
+/// `data_offset`. `extra_bytes_after_data` is the size of any chunks appended
+/// after the data chunk (e.g. the GUANO `guan` chunk at finalize time) so the
+/// RIFF size covers them.
+///
+/// Past the 4 GB RIFF limit the header becomes RF64: the reserved `JUNK`
+/// chunk is rewritten as `ds64` carrying the 64-bit sizes, and the 32-bit
+/// fields are set to 0xFFFFFFFF.
 fn patch_header_with_extra(
     f: &mut File,
+    data_offset: u64,
     data_bytes: u64,
     extra_bytes_after_data: u64,
+    block_align: u64,
 ) -> std::io::Result<()> {
-    // WAV with PCM/IEEE data size fields are u32. Clamp to u32::MAX so we at
-    // least write a valid (possibly truncated) header for huge recoveries.
-    let data_size: u32 = data_bytes.min(u32::MAX as u64) as u32;
-    // RIFF size = 36 (everything before the data bytes, excluding "RIFF<size>")
-    // + data_size + extra chunks after data.
-    let riff_size: u32 = 36u32
-        .saturating_add(data_size)
-        .saturating_add(extra_bytes_after_data.min(u32::MAX as u64) as u32);
-    f.seek(SeekFrom::Start(4))?;
-    f.write_all(&riff_size.to_le_bytes())?;
-    f.seek(SeekFrom::Start(40))?;
-    f.write_all(&data_size.to_le_bytes())?;
+    // RIFF size = everything after "RIFF<size>": header chunks, data, extras.
+    let riff_size = (data_offset - 8) + data_bytes + extra_bytes_after_data;
+    if riff_size <= u32::MAX as u64 {
+        f.seek(SeekFrom::Start(4))?;
+        f.write_all(&(riff_size as u32).to_le_bytes())?;
+        f.seek(SeekFrom::Start(data_offset - 4))?;
+        f.write_all(&(data_bytes as u32).to_le_bytes())?;
+    } else if data_offset == HEADER_LEN {
+        let ds64 = ds64_body(riff_size, data_bytes, data_bytes / block_align.max(1));
+        f.seek(SeekFrom::Start(0))?;
+        f.write_all(b"RF64")?;
+        f.write_all(&u32::MAX.to_le_bytes())?;
+        f.seek(SeekFrom::Start(12))?;
+        f.write_all(b"ds64")?;
+        f.write_all(&(DS64_BODY_LEN as u32).to_le_bytes())?;
+        f.write_all(&ds64)?;
+        f.seek(SeekFrom::Start(data_offset - 4))?;
+        f.write_all(&u32::MAX.to_le_bytes())?;
+    } else {
+        // Older 44-byte partials have no room for ds64. Clamp to u32::MAX so
+        // we at least write a valid header; readers take the real data size
+        // from the file size.
+        f.seek(SeekFrom::Start(4))?;
+        f.write_all(&(riff_size.min(u32::MAX as u64) as u32).to_le_bytes())?;
+        f.seek(SeekFrom::Start(data_offset - 4))?;
+        f.write_all(&(data_bytes.min(u32::MAX as u64) as u32).to_le_bytes())?;
+    }
     f.flush()?;
     Ok(())
 }
@@ -492,7 +534,7 @@ pub fn recover_leftover_recordings(app_data: &Path) -> Vec<RecoveredRecording> {
         let is_part = path
             .file_name()
             .and_then(|n| n.to_str())
-            .map(|n| n.ends_with(".wav.part"))
+            .map(|n| n.ends_with(".wav.part") || n.ends_with(".flac.part"))
             .unwrap_or(false);
         if is_part {
             parts.push(path);
@@ -523,17 +565,34 @@ fn recover_one(
         .and_then(|n| n.to_str())
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "bad name"))?
         .to_string();
-    // Strip ".part" → "<name>.wav"
+    // Strip ".part" → "<name>.wav" (or "<name>.flac" when saving as FLAC)
     let wav_name = part_name.trim_end_matches(".part").to_string();
     let meta_path = rec_dir.join(format!("{}.meta.json", wav_name));
 
-    // Less than header = nothing useful. Delete and move on.
-    if file_size <= 44 {
+    // Less than a header = nothing useful. Delete and move on.
+    let discard = || {
         let _ = std::fs::remove_file(part_path);
         let _ = std::fs::remove_file(&meta_path);
-        return Ok(None);
+        Ok(None)
+    };
+    if file_size <= 44 {
+        return discard();
     }
 
+    // The placeholder header is 80 bytes with a reserved ds64 slot, or 44
+    // bytes for partials written by older versions.
+    let header = {
+        use std::io::Read;
+        let mut head = Vec::new();
+        File::open(part_path)?.take(HEADER_LEN).read_to_end(&mut head)?;
+        oversample_core::audio::loader::parse_wav_header(&head)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+    };
+    if file_size <= header.data_offset {
+        return discard();
+    }
+    let data_offset = header.data_offset;
+
     // Load sidecar if present.
     let meta: Option<RecoveryMeta> = if meta_path.exists() {
         std::fs::read_to_string(&meta_path)
@@ -544,23 +603,12 @@ fn recover_one(
         None
     };
 
-    // Read block_align from the fmt chunk (offset 32, u16 LE) so we can round
-    // the captured byte count down to a whole-sample boundary. A crash in the
-    // middle of a write can leave a torn last sample (e.g. 1 byte of a 2-byte
-    // i16, or 3 bytes of a 4-byte f32). Playing that would produce a single
-    // garbled sample at the end.
-    let raw_data_bytes = file_size.saturating_sub(44);
-    let block_align: u64 = {
-        let mut f = File::open(part_path)?;
-        use std::io::Read;
-        f.seek(SeekFrom::Start(32))?;
-        let mut buf = [0u8; 2];
-        let ba = match f.read_exact(&mut buf) {
-            Ok(_) => u16::from_le_bytes(buf).max(1) as u64,
-            Err(_) => 1,
-        };
-        ba
-    };
+    // Round the captured byte count down to a whole-sample boundary. A crash
+    // in the middle of a write can leave a torn last sample (e.g. 1 byte of a
+    // 2-byte i16, or 3 bytes of a 4-byte f32). Playing that would produce a
+    // single garbled sample at the end.
+    let raw_data_bytes = file_size - data_offset;
+    let block_align = (header.channels as u64 * (header.bits_per_sample as u64 / 8)).max(1);
     let data_bytes = (raw_data_bytes / block_align) * block_align;
     if data_bytes < raw_data_bytes {
         eprintln!(
@@ -570,27 +618,13 @@ fn recover_one(
         );
     }
 
-    // Truncate off the torn tail (if any) and patch header sizes.
-    {
-        let mut f = OpenOptions::new().read(true).write(true).open(part_path)?;
-        if data_bytes < raw_data_bytes {
-            f.set_len(44 + data_bytes)?;
-        }
-        patch_header(&mut f, data_bytes)?;
-    }
-
-    // Read the patched WAV and append GUANO.
-    let mut wav_data = std::fs::read(part_path)?;
-
     let (sample_count, sample_rate, bits_per_sample) = if let Some(ref m) = meta {
         let bps = m.bits_per_sample.max(1) as u64;
         (data_bytes * 8 / bps, m.sample_rate, m.bits_per_sample)
     } else {
-        // No sidecar — parse fmt chunk we just wrote.
-        let sr = u32::from_le_bytes(wav_data[24..28].try_into().unwrap_or([0; 4]));
-        let bps = u16::from_le_bytes(wav_data[34..36].try_into().unwrap_or([0; 2]));
-        let bps_u64 = bps.max(1) as u64;
-        (data_bytes * 8 / bps_u64, sr, bps)
+        // No sidecar — use the fmt chunk we wrote.
+        let bps = header.bits_per_sample;
+        (data_bytes * 8 / bps.max(1) as u64, header.sample_rate, bps)
     };
 
     let duration_secs = if sample_rate > 0 {
@@ -624,17 +658,37 @@ fn recover_one(
             sample_rate, duration_secs, bits_per_sample,
         )
     };
-    oversample_core::audio::guano::append_guano_chunk(&mut wav_data, &guano_text);
-    let final_size = wav_data.len() as u64;
+    let is_flac = wav_name.ends_with(".flac");
 
-    // Write to recordings dir with a "recovered_" prefix so the user can tell.
+    // Finalize in place (the file may be far larger than RAM): truncate the
+    // torn tail (if any), append GUANO, and patch header sizes — switching to
+    // RF64 past 4 GB.
+    {
+        let mut f = OpenOptions::new().read(true).write(true).open(part_path)?;
+        if data_bytes < raw_data_bytes {
+            f.set_len(data_offset + data_bytes)?;
+        }
+        let extra = if is_flac { 0 } else { append_guano_chunk(&mut f, &guano_text)? };
+        patch_header_with_extra(&mut f, data_offset, data_bytes, extra, block_align)?;
+        f.sync_data()?;
+    }
+
+    // Move into the recordings dir with a "recovered_" prefix so the user can tell.
     std::fs::create_dir_all(target_dir)?;
     let final_name = format!("recovered_{}", wav_name);
     let final_path = target_dir.join(&final_name);
-    std::fs::write(&final_path, &wav_data)?;
+    if is_flac {
+        let guano = oversample_core::audio::guano::parse_guano_chunk(guano_text.as_bytes())
+            .unwrap_or_default();
+        let flac_path = crate::recording::replace_with_flac(part_path, &guano)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::rename(&flac_path, &final_path)?;
+    } else {
+        std::fs::rename(part_path, &final_path)?;
+    }
+    let final_size = final_path.metadata()?.len();
 
-    // Remove the partial + sidecar now that we have a good final file.
-    let _ = std::fs::remove_file(part_path);
+    // Remove the sidecar now that we have a good final file.
     let _ = std::fs::remove_file(&meta_path);
 
     Ok(Some(RecoveredRecording {
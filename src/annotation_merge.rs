@@ -0,0 +1,378 @@
+//! Three-way merge of annotation lists.
+//!
+//! Two copies of a file's annotations (e.g. the sidecar edited on the phone
+//! and the one edited on the desktop) are merged per annotation `id`. An
+//! annotation counts as edited on a side when its `modified_at` is not one of
+//! the versions the last merge already saw, recorded in a [`MergeBase`] that
+//! is kept with the merged result. Without a base every difference between
+//! the sides is a conflict, so nothing is thrown away silently.
+//!
+//! Deletions are only recognised on a side that carries a base: a side with
+//! no base can't tell "never had it" from "deleted it", so the annotation is
+//! kept.
+
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Serialize, Deserialize};
+use crate::annotations::{Annotation, AnnotationId, generate_uuid, now_iso8601};
+
+/// What a previous merge saw of one annotation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BaseEntry {
+    /// `modified_at` of the version the merge kept. None = deleted.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub kept: Option<String>,
+    /// `modified_at` of versions the merge replaced or dropped.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub superseded: Vec<String>,
+}
+
+impl BaseEntry {
+    fn knows(&self, version: &str) -> bool {
+        self.kept.as_deref() == Some(version) || self.superseded.iter().any(|v| v == version)
+    }
+}
+
+/// Common ancestor of two annotation lists, as left by the last merge.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MergeBase {
+    /// When the merge happened.
+    pub merged_at: String,
+    #[serde(default)]
+    pub entries: BTreeMap<AnnotationId, BaseEntry>,
+}
+
+impl MergeBase {
+    /// Combine the bases of both sides. Where they disagree on the kept
+    /// version, the newer merge wins and the other version is superseded.
+    fn union(a: Option<&MergeBase>, b: Option<&MergeBase>) -> MergeBase {
+        let (older, newer) = match (a, b) {
+            (Some(a), Some(b)) if a.merged_at > b.merged_at => (Some(b), Some(a)),
+            (a, b) => (a, b),
+        };
+        let mut out = older.cloned().unwrap_or_default();
+        let Some(newer) = newer else { return out };
+        out.merged_at = newer.merged_at.clone();
+        for (id, entry) in &newer.entries {
+            let merged = out.entries.entry(id.clone()).or_default();
+            let mut seen: BTreeSet<String> = merged.superseded.drain(..).collect();
+            seen.extend(merged.kept.take());
+            seen.extend(entry.superseded.iter().cloned());
+            if let Some(ref k) = entry.kept {
+                seen.remove(k);
+            }
+            merged.kept = entry.kept.clone();
+            merged.superseded = seen.into_iter().collect();
+        }
+        out
+    }
+}
+
+/// An annotation edited differently on both sides, or edited on one side and
+/// deleted on the other.
+#[derive(Clone, Debug)]
+pub struct MergeConflict {
+    pub id: AnnotationId,
+    /// Our version. None = we deleted it.
+    pub ours: Option<Annotation>,
+    /// Their version. None = they deleted it.
+    pub theirs: Option<Annotation>,
+}
+
+/// How the user settled a conflict.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ConflictChoice {
+    #[default]
+    Ours,
+    Theirs,
+    /// Keep both versions; theirs gets a new id.
+    Both,
+}
+
+/// Result of [`three_way_merge`], before conflicts are resolved.
+#[derive(Clone, Debug, Default)]
+pub struct MergeOutcome {
+    /// Annotations merged without conflict, ours first, in list order.
+    pub merged: Vec<Annotation>,
+    pub conflicts: Vec<MergeConflict>,
+    /// Annotations taken from their side (new or edited only there).
+    pub taken: usize,
+    /// Annotations dropped because one side deleted them.
+    pub deleted: usize,
+    base: MergeBase,
+    /// Versions seen this merge, per id, for the new base.
+    seen: BTreeMap<AnnotationId, BTreeSet<String>>,
+}
+
+impl MergeOutcome {
+    /// True when the merge changes nothing on our side.
+    pub fn is_noop(&self) -> bool {
+        self.conflicts.is_empty() && self.taken == 0 && self.deleted == 0
+    }
+
+    /// Apply `choices` (one per conflict, missing = [`ConflictChoice::Ours`])
+    /// and return the merged annotations with the base to store alongside.
+    pub fn resolve(self, choices: &[ConflictChoice]) -> (Vec<Annotation>, MergeBase) {
+        self.resolve_at(choices, now_iso8601())
+    }
+
+    fn resolve_at(self, choices: &[ConflictChoice], merged_at: String) -> (Vec<Annotation>, MergeBase) {
+        let MergeOutcome { mut merged, conflicts, base, mut seen, .. } = self;
+        for (i, c) in conflicts.into_iter().enumerate() {
+            let choice = choices.get(i).copied().unwrap_or_default();
+            let (keep, copy) = match choice {
+                ConflictChoice::Ours => (c.ours, None),
+                ConflictChoice::Theirs => (c.theirs, None),
+                ConflictChoice::Both => match (c.ours, c.theirs) {
+                    (Some(o), Some(t)) => (Some(o), Some(t)),
+                    (o, t) => (o.or(t), None),
+                },
+            };
+            merged.extend(keep);
+            if let Some(mut t) = copy {
+                t.id = generate_uuid();
+                seen.entry(t.id.clone()).or_default().insert(t.modified_at.clone());
+                merged.push(t);
+            }
+        }
+
+        let kept: BTreeMap<&str, &str> = merged.iter()
+            .map(|a| (a.id.as_str(), a.modified_at.as_str()))
+            .collect();
+        let mut entries = base.entries;
+        let ids: BTreeSet<AnnotationId> = entries.keys().chain(seen.keys()).cloned().collect();
+        for id in ids {
+            let entry = entries.entry(id.clone()).or_default();
+            let mut all: BTreeSet<String> = entry.superseded.drain(..).collect();
+            all.extend(entry.kept.take());
+            all.extend(seen.remove(&id).unwrap_or_default());
+            entry.kept = kept.get(id.as_str()).map(|v| v.to_string());
+            if let Some(ref k) = entry.kept {
+                all.remove(k);
+            }
+            entry.superseded = all.into_iter().collect();
+        }
+        (merged, MergeBase { merged_at, entries })
+    }
+}
+
+/// Carry `choices` made for the conflicts in `from` over to the conflicts in
+/// `to` (a later merge of the same sides), matching by annotation id.
+/// Conflicts that weren't in `from` get [`ConflictChoice::Ours`].
+pub fn remap_choices(from: &[MergeConflict], choices: &[ConflictChoice], to: &[MergeConflict]) -> Vec<ConflictChoice> {
+    let by_id: BTreeMap<&str, ConflictChoice> = from.iter()
+        .zip(choices)
+        .map(|(c, choice)| (c.id.as_str(), *choice))
+        .collect();
+    to.iter().map(|c| by_id.get(c.id.as_str()).copied().unwrap_or_default()).collect()
+}
+
+/// True when two versions differ in nothing but `modified_at`. Always
+/// compares content: an edit that didn't bump `modified_at` must still count.
+fn same_content(a: &Annotation, b: &Annotation) -> bool {
+    let mut b = b.clone();
+    b.modified_at = a.modified_at.clone();
+    serde_json::to_value(a).ok() == serde_json::to_value(&b).ok()
+}
+
+/// Merge `theirs` into `ours`. Each side passes the base it was last merged
+/// with, if any.
+pub fn three_way_merge(
+    ours: &[Annotation],
+    ours_base: Option<&MergeBase>,
+    theirs: &[Annotation],
+    theirs_base: Option<&MergeBase>,
+) -> MergeOutcome {
+    let base = MergeBase::union(ours_base, theirs_base);
+    let edited = |a: &Annotation| base.entries.get(&a.id).is_none_or(|e| !e.knows(&a.modified_at));
+    let deleted_by = |side_base: Option<&MergeBase>, id: &str| {
+        side_base.is_some_and(|b| b.entries.contains_key(id))
+    };
+    let their_by_id: BTreeMap<&str, &Annotation> = theirs.iter().map(|a| (a.id.as_str(), a)).collect();
+    let our_ids: BTreeSet<&str> = ours.iter().map(|a| a.id.as_str()).collect();
+
+    let mut out = MergeOutcome::default();
+    for o in ours {
+        out.seen.entry(o.id.clone()).or_default().insert(o.modified_at.clone());
+        match their_by_id.get(o.id.as_str()) {
+            Some(t) => {
+                out.seen.entry(o.id.clone()).or_default().insert(t.modified_at.clone());
+                if same_content(o, t) {
+                    out.merged.push(o.clone());
+                    continue;
+                }
+                let their_kept = base.entries.get(&t.id)
+                    .is_some_and(|e| e.kept.as_deref() == Some(t.modified_at.as_str()));
+                // Same timestamp, different content: an edit that didn't
+                // bump modified_at, so we can't tell which side is newer
+                let (eo, et) = if o.modified_at == t.modified_at { (true, true) } else { (edited(o), edited(t)) };
+                match (eo, et) {
+                    (true, false) => out.merged.push(o.clone()),
+                    // Neither side edited: one of them still has a version
+                    // the last merge replaced
+                    (false, false) if !their_kept => out.merged.push(o.clone()),
+                    (false, _) => {
+                        out.merged.push((*t).clone());
+                        out.taken += 1;
+                    }
+                    (true, true) => out.conflicts.push(MergeConflict {
+                        id: o.id.clone(),
+                        ours: Some(o.clone()),
+                        theirs: Some((*t).clone()),
+                    }),
+                }
+            }
+            None if deleted_by(theirs_base, &o.id) => {
+                if edited(o) {
+                    out.conflicts.push(MergeConflict { id: o.id.clone(), ours: Some(o.clone()), theirs: None });
+                } else {
+                    out.deleted += 1;
+                }
+            }
+            None => out.merged.push(o.clone()),
+        }
+    }
+    for t in theirs {
+        if our_ids.contains(t.id.as_str()) {
+            continue;
+        }
+        out.seen.entry(t.id.clone()).or_default().insert(t.modified_at.clone());
+        if deleted_by(ours_base, &t.id) {
+            if edited(t) {
+                out.conflicts.push(MergeConflict { id: t.id.clone(), ours: None, theirs: Some(t.clone()) });
+            }
+            // else: we deleted it and they didn't touch it since
+        } else {
+            out.merged.push(t.clone());
+            out.taken += 1;
+        }
+    }
+    out.base = base;
+    out
+}
+
+/// Where a merge with conflicts will be stored once resolved.
+#[derive(Clone, Debug)]
+pub enum MergeTarget {
+    /// A loaded file's annotation set (index into `AppState::files`).
+    File(usize),
+    /// A project file entry, merged from the .batm at `batm_key`.
+    Project { file_index: usize, batm_key: String },
+}
+
+/// A merge waiting for the user to resolve its conflicts.
+#[derive(Clone, Debug)]
+pub struct PendingMerge {
+    pub target: MergeTarget,
+    /// Audio filename, for display.
+    pub filename: String,
+    /// Where their side came from, for display (e.g. "sidecar next to the audio file").
+    pub source: String,
+    pub outcome: MergeOutcome,
+    /// Their sidecar, for the noise profile and metadata.
+    pub theirs: crate::annotations::AnnotationSet,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::{AnnotationKind, Marker};
+
+    fn marker(id: &str, time: f64, modified_at: &str) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            kind: AnnotationKind::Marker(Marker { time, label: None, color: None }),
+            created_at: "2026-01-01T00:00:00.000Z".to_string(),
+            modified_at: modified_at.to_string(),
+            notes: None,
+            parent_id: None,
+            sort_order: None,
+            tags: Vec::new(),
+            label_default: None,
+            confidence: None,
+            review: None,
+        }
+    }
+
+    /// Merge two identical copies to get a shared base.
+    fn synced(annotations: &[Annotation]) -> MergeBase {
+        three_way_merge(annotations, None, annotations, None)
+            .resolve_at(&[], "2026-01-02T00:00:00.000Z".to_string())
+            .1
+    }
+
+    #[test]
+    fn edit_on_both_sides_conflicts() {
+        let base = synced(&[marker("a", 1.0, "t0")]);
+        let ours = [marker("a", 2.0, "t1")];
+        let theirs = [marker("a", 3.0, "t2")];
+        let out = three_way_merge(&ours, Some(&base), &theirs, Some(&base));
+        assert_eq!(out.conflicts.len(), 1);
+        assert!(out.conflicts[0].ours.is_some() && out.conflicts[0].theirs.is_some());
+    }
+
+    #[test]
+    fn edit_on_one_side_is_taken() {
+        let base = synced(&[marker("a", 1.0, "t0")]);
+        let ours = [marker("a", 1.0, "t0")];
+        let theirs = [marker("a", 3.0, "t2")];
+        let out = three_way_merge(&ours, Some(&base), &theirs, Some(&base));
+        assert!(out.conflicts.is_empty());
+        assert_eq!(out.taken, 1);
+        assert_eq!(out.merged[0].modified_at, "t2");
+    }
+
+    #[test]
+    fn edit_against_delete_conflicts() {
+        let base = synced(&[marker("a", 1.0, "t0"), marker("b", 1.0, "t0")]);
+        let ours = [marker("a", 2.0, "t1"), marker("b", 1.0, "t0")];
+        let theirs: [Annotation; 0] = [];
+        let out = three_way_merge(&ours, Some(&base), &theirs, Some(&base));
+        assert_eq!(out.conflicts.len(), 1);
+        assert_eq!(out.conflicts[0].id, "a");
+        assert!(out.conflicts[0].theirs.is_none());
+        // b was untouched here, so their delete goes through
+        assert_eq!(out.deleted, 1);
+    }
+
+    #[test]
+    fn without_a_base_differences_conflict() {
+        let ours = [marker("a", 1.0, "t1"), marker("b", 1.0, "t1")];
+        let theirs = [marker("a", 2.0, "t2"), marker("b", 1.0, "t1"), marker("c", 1.0, "t1")];
+        let out = three_way_merge(&ours, None, &theirs, None);
+        assert_eq!(out.conflicts.len(), 1);
+        assert_eq!(out.conflicts[0].id, "a");
+        assert_eq!(out.taken, 1);
+        assert_eq!(out.merged.len(), 2);
+    }
+
+    #[test]
+    fn same_timestamp_with_different_content_conflicts() {
+        let base = synced(&[marker("a", 1.0, "t0")]);
+        let ours = [marker("a", 2.0, "t0")];
+        let theirs = [marker("a", 1.0, "t0")];
+        let out = three_way_merge(&ours, Some(&base), &theirs, Some(&base));
+        assert_eq!(out.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn repeat_merge_is_a_noop() {
+        let ours = [marker("a", 1.0, "t1"), marker("b", 1.0, "t1")];
+        let theirs = [marker("a", 2.0, "t2"), marker("c", 1.0, "t1")];
+        let first = three_way_merge(&ours, None, &theirs, None);
+        assert_eq!(first.conflicts.len(), 1);
+        let (merged, base) = first.resolve_at(&[ConflictChoice::Ours], "2026-01-02T00:00:00.000Z".to_string());
+
+        let again = three_way_merge(&merged, Some(&base), &theirs, None);
+        assert!(again.is_noop());
+        assert_eq!(again.merged.len(), merged.len());
+    }
+
+    #[test]
+    fn choices_follow_ids() {
+        let c = |id: &str| MergeConflict { id: id.to_string(), ours: None, theirs: None };
+        let from = [c("a"), c("b")];
+        let to = [c("b"), c("new")];
+        let out = remap_choices(&from, &[ConflictChoice::Ours, ConflictChoice::Theirs], &to);
+        assert_eq!(out, vec![ConflictChoice::Theirs, ConflictChoice::Ours]);
+    }
+}
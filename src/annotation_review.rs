@@ -0,0 +1,221 @@
+//! QA review queue: which annotations to review, in what order, and how the
+//! queue moves on.
+//!
+//! Regions, segments and markers nobody has reviewed yet are queued file by
+//! file, in time order within a file. Project files that aren't loaded are
+//! queued from the project's copy of their annotations; when their turn
+//! comes the file is loaded and its items are pointed at the loaded file.
+
+use std::collections::HashSet;
+use crate::annotation_table::RowSource;
+use crate::annotations::{Annotation, AnnotationId, AnnotationKind, ReviewStatus};
+
+/// Review queue: unreviewed annotations across the project (or the loaded
+/// files), stepped through one at a time.
+#[derive(Clone, Debug, Default)]
+pub struct ReviewQueue {
+    /// (file, annotation id) in review order. Project files that aren't
+    /// loaded are loaded when their turn comes.
+    pub items: Vec<(RowSource, AnnotationId)>,
+    /// Index into `items` of the annotation under review.
+    pub pos: usize,
+    /// Decisions recorded this session.
+    pub decided: usize,
+}
+
+fn start_time(a: &Annotation) -> f64 {
+    match &a.kind {
+        AnnotationKind::Region(r) => r.time_start,
+        AnnotationKind::Marker(m) => m.time,
+        AnnotationKind::Measurement(m) => m.start_time,
+        AnnotationKind::Group(_) => 0.0,
+    }
+}
+
+/// Ids of the unreviewed regions, segments and markers, in time order.
+pub fn unreviewed(annotations: &[Annotation]) -> Vec<AnnotationId> {
+    let mut anns: Vec<&Annotation> = annotations.iter()
+        .filter(|a| matches!(a.kind, AnnotationKind::Region(_) | AnnotationKind::Marker(_)))
+        .filter(|a| a.review_status() == ReviewStatus::Unreviewed)
+        .collect();
+    anns.sort_by(|a, b| start_time(a).total_cmp(&start_time(b)));
+    anns.into_iter().map(|a| a.id.clone()).collect()
+}
+
+impl ReviewQueue {
+    /// Queue the unreviewed annotations of `files`, in the order given.
+    /// None when there's nothing to review.
+    pub fn build<'a>(files: impl IntoIterator<Item = (RowSource, &'a [Annotation])>) -> Option<Self> {
+        let items: Vec<(RowSource, AnnotationId)> = files.into_iter()
+            .flat_map(|(source, annotations)| unreviewed(annotations).into_iter().map(move |id| (source, id)))
+            .collect();
+        (!items.is_empty()).then_some(Self { items, pos: 0, decided: 0 })
+    }
+
+    pub fn current(&self) -> Option<&(RowSource, AnnotationId)> {
+        self.items.get(self.pos)
+    }
+
+    /// Move past the current item (counting it if `decided`) to the next one
+    /// still unreviewed, per `status`; items reviewed or deleted elsewhere
+    /// since the queue was built are skipped. Returns false at the end.
+    pub fn advance(&mut self, decided: bool, status: impl Fn(RowSource, &AnnotationId) -> Option<ReviewStatus>) -> bool {
+        if decided {
+            self.decided += 1;
+        }
+        self.pos += 1;
+        while let Some((source, id)) = self.items.get(self.pos) {
+            if status(*source, id) == Some(ReviewStatus::Unreviewed) {
+                return true;
+            }
+            self.pos += 1;
+        }
+        false
+    }
+
+    /// Project file whose turn it is but which isn't loaded yet.
+    pub fn waiting_on(&self) -> Option<usize> {
+        match self.current() {
+            Some((RowSource::Project(pi), _)) => Some(*pi),
+            _ => None,
+        }
+    }
+
+    /// Point project file `pi`'s items at loaded file `file_idx`. Returns
+    /// the ids that moved.
+    pub fn relink(&mut self, pi: usize, file_idx: usize) -> HashSet<AnnotationId> {
+        let mut ids = HashSet::new();
+        for item in &mut self.items {
+            if item.0 == RowSource::Project(pi) {
+                item.0 = RowSource::Loaded(file_idx);
+                ids.insert(item.1.clone());
+            }
+        }
+        ids
+    }
+}
+
+/// Annotations of `project_copy` queued as `ids` that the loaded file's
+/// sidecar (`loaded`) lacks, to be copied across so they can be reviewed.
+pub fn missing_from_loaded(
+    project_copy: Vec<Annotation>,
+    ids: &HashSet<AnnotationId>,
+    loaded: Option<&[Annotation]>,
+) -> Vec<Annotation> {
+    project_copy.into_iter()
+        .filter(|a| ids.contains(&a.id))
+        .filter(|a| !loaded.is_some_and(|anns| anns.iter().any(|b| b.id == a.id)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::{Marker, Measurement, Review};
+
+    fn annotation(id: &str, kind: AnnotationKind) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            kind,
+            created_at: "2026-01-01T00:00:00.000Z".to_string(),
+            modified_at: "2026-01-01T00:00:00.000Z".to_string(),
+            notes: None,
+            parent_id: None,
+            sort_order: None,
+            tags: Vec::new(),
+            label_default: None,
+            confidence: None,
+            review: None,
+        }
+    }
+
+    fn marker(id: &str, time: f64) -> Annotation {
+        annotation(id, AnnotationKind::Marker(Marker { time, label: None, color: None }))
+    }
+
+    fn reviewed(mut a: Annotation, status: ReviewStatus) -> Annotation {
+        a.review = Some(Review { status, reviewer: None, reviewed_at: None });
+        a
+    }
+
+    fn ids(queue: &ReviewQueue) -> Vec<(RowSource, &str)> {
+        queue.items.iter().map(|(s, id)| (*s, id.as_str())).collect()
+    }
+
+    #[test]
+    fn test_build_orders_by_file_then_time() {
+        let first = [
+            marker("late", 9.0),
+            reviewed(marker("done", 1.0), ReviewStatus::Accepted),
+            annotation("ruler", AnnotationKind::Measurement(Measurement {
+                start_time: 0.5, start_freq: 40_000.0, end_time: 0.6, end_freq: 30_000.0, label: None,
+            })),
+            marker("early", 2.0),
+        ];
+        let second = [marker("other", 0.1)];
+        let queue = ReviewQueue::build([
+            (RowSource::Project(1), &second[..]),
+            (RowSource::Loaded(0), &first[..]),
+        ]).unwrap();
+        assert_eq!(ids(&queue), [
+            (RowSource::Project(1), "other"),
+            (RowSource::Loaded(0), "early"),
+            (RowSource::Loaded(0), "late"),
+        ]);
+        assert_eq!(queue.pos, 0);
+
+        let all_done = [reviewed(marker("a", 1.0), ReviewStatus::Rejected)];
+        assert!(ReviewQueue::build([(RowSource::Loaded(0), &all_done[..])]).is_none());
+        assert!(ReviewQueue::build(std::iter::empty()).is_none());
+    }
+
+    #[test]
+    fn test_advance_skips_items_reviewed_elsewhere() {
+        let anns = [marker("a", 1.0), marker("b", 2.0), marker("c", 3.0), marker("d", 4.0)];
+        let mut queue = ReviewQueue::build([(RowSource::Loaded(0), &anns[..])]).unwrap();
+        // "b" was accepted in the annotation table, "c" deleted
+        let status = |_: RowSource, id: &AnnotationId| match id.as_str() {
+            "b" => Some(ReviewStatus::Accepted),
+            "c" => None,
+            _ => Some(ReviewStatus::Unreviewed),
+        };
+        assert!(queue.advance(true, status));
+        assert_eq!(queue.current().map(|c| c.1.as_str()), Some("d"));
+        assert_eq!(queue.decided, 1);
+
+        // Skipping doesn't count as a decision
+        assert!(!queue.advance(false, status));
+        assert_eq!(queue.current(), None);
+        assert_eq!(queue.decided, 1);
+    }
+
+    #[test]
+    fn test_unloaded_project_files_in_turn() {
+        let loaded = [marker("a", 1.0)];
+        let project = [marker("p1", 1.0), marker("p2", 2.0)];
+        let mut queue = ReviewQueue::build([
+            (RowSource::Loaded(0), &loaded[..]),
+            (RowSource::Project(3), &project[..]),
+        ]).unwrap();
+        assert_eq!(queue.waiting_on(), None);
+        assert!(queue.advance(true, |_, _| Some(ReviewStatus::Unreviewed)));
+        assert_eq!(queue.waiting_on(), Some(3));
+
+        // Project file 3 is now loaded as file 2
+        let moved = queue.relink(3, 2);
+        assert_eq!(moved, HashSet::from(["p1".to_string(), "p2".to_string()]));
+        assert_eq!(queue.waiting_on(), None);
+        assert_eq!(ids(&queue), [
+            (RowSource::Loaded(0), "a"),
+            (RowSource::Loaded(2), "p1"),
+            (RowSource::Loaded(2), "p2"),
+        ]);
+        assert!(queue.relink(3, 2).is_empty());
+
+        // Its sidecar already had "p1"; only "p2" is copied across
+        let sidecar = [marker("p1", 1.0)];
+        let missing = missing_from_loaded(project.to_vec(), &moved, Some(&sidecar));
+        assert_eq!(missing.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["p2"]);
+        assert_eq!(missing_from_loaded(project.to_vec(), &moved, None).len(), 2);
+    }
+}
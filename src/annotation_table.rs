@@ -0,0 +1,593 @@
+//! Project-wide annotation table: one row per annotation, filter
+//! expressions and sorting.
+//!
+//! A filter is a boolean expression over row fields, e.g.
+//! `tag = "Myotis" and freq_low > 40k`. Comparisons are `=`, `!=`, `<`,
+//! `<=`, `>`, `>=` and `~` (contains); they combine with `and`, `or`, `not`
+//! and parentheses. Text compares case-insensitively. Numbers take `k`/`kHz`
+//! (×1000), `Hz`, `ms` (÷1000) and `s` suffixes. `tag` matches if any tag
+//! does. A comparison on a missing value (a segment's `freq_low`, say) is
+//! false.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use crate::annotations::{Annotation, AnnotationId, AnnotationKind, kind_name, label_of, now_iso8601};
+
+/// Where a row's annotation lives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RowSource {
+    /// A loaded file (index into `AppState::files`); edits go to the
+    /// annotation store.
+    Loaded(usize),
+    /// A project file that isn't loaded (index into `BatProject::files`);
+    /// edits go to the project copy.
+    Project(usize),
+}
+
+/// One annotation in the table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotationRow {
+    pub source: RowSource,
+    pub id: AnnotationId,
+    pub file: String,
+    pub kind: &'static str,
+    pub start: f64,
+    pub end: f64,
+    pub freq_low: Option<f64>,
+    pub freq_high: Option<f64>,
+    pub label: Option<String>,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+    /// Absolute start time (ms since epoch), if the file's start is known.
+    pub clock_ms: Option<f64>,
+    /// `clock_ms` as local wall-clock time: ms since 1970-01-01 00:00 in
+    /// the viewer's time zone. Equal to `clock_ms` until the UI shifts it.
+    pub clock_local_ms: Option<f64>,
+    pub status: &'static str,
+    pub confidence: Option<f64>,
+}
+
+impl AnnotationRow {
+    /// Row for `a`; `file_start_ms` is the file's recording start, if known.
+    /// Groups have no extent and give None.
+    pub fn new(source: RowSource, file: &str, file_start_ms: Option<f64>, a: &Annotation) -> Option<Self> {
+        let (start, end, freq_low, freq_high) = match &a.kind {
+            AnnotationKind::Region(r) => (r.time_start, r.time_end, r.freq_low, r.freq_high),
+            AnnotationKind::Marker(m) => (m.time, m.time, None, None),
+            AnnotationKind::Measurement(m) => (
+                m.start_time.min(m.end_time),
+                m.start_time.max(m.end_time),
+                Some(m.start_freq.min(m.end_freq)),
+                Some(m.start_freq.max(m.end_freq)),
+            ),
+            AnnotationKind::Group(_) => return None,
+        };
+        Some(Self {
+            source,
+            id: a.id.clone(),
+            file: file.to_string(),
+            kind: kind_name(&a.kind),
+            start,
+            end,
+            freq_low,
+            freq_high,
+            label: label_of(&a.kind).map(str::to_string),
+            tags: a.tags.clone(),
+            notes: a.notes.clone(),
+            clock_ms: file_start_ms.map(|ms| ms + start * 1000.0),
+            clock_local_ms: file_start_ms.map(|ms| ms + start * 1000.0),
+            status: a.review_status().label(),
+            confidence: a.confidence,
+        })
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Sortable / filterable columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    File,
+    Kind,
+    Start,
+    Clock,
+    Duration,
+    FreqLow,
+    FreqHigh,
+    Label,
+    Tags,
+    Notes,
+    Status,
+    Confidence,
+}
+
+enum Value<'a> {
+    Num(Option<f64>),
+    Text(Option<&'a str>),
+    List(&'a [String]),
+}
+
+impl Column {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "file" | "filename" => Self::File,
+            "kind" | "type" => Self::Kind,
+            "time" | "start" => Self::Start,
+            "clock" => Self::Clock,
+            "duration" | "dur" => Self::Duration,
+            "freq_low" | "low" | "fmin" => Self::FreqLow,
+            "freq_high" | "high" | "fmax" => Self::FreqHigh,
+            "label" => Self::Label,
+            "tag" | "tags" => Self::Tags,
+            "notes" | "note" => Self::Notes,
+            "status" | "review" => Self::Status,
+            "confidence" => Self::Confidence,
+            _ => return None,
+        })
+    }
+
+    fn value(self, row: &AnnotationRow) -> Value<'_> {
+        match self {
+            Self::File => Value::Text(Some(&row.file)),
+            Self::Kind => Value::Text(Some(row.kind)),
+            Self::Start => Value::Num(Some(row.start)),
+            Self::Clock => Value::Num(row.clock_local_ms),
+            Self::Duration => Value::Num(Some(row.duration())),
+            Self::FreqLow => Value::Num(row.freq_low),
+            Self::FreqHigh => Value::Num(row.freq_high),
+            Self::Label => Value::Text(row.label.as_deref()),
+            Self::Tags => Value::List(&row.tags),
+            Self::Notes => Value::Text(row.notes.as_deref()),
+            Self::Status => Value::Text(Some(row.status)),
+            Self::Confidence => Value::Num(row.confidence),
+        }
+    }
+}
+
+/// Order rows by `column`; missing values sort last.
+pub fn sort_rows(rows: &mut [AnnotationRow], column: Column, ascending: bool) {
+    rows.sort_by(|a, b| {
+        let ord = match (column.value(a), column.value(b)) {
+            (Value::Num(x), Value::Num(y)) => match (x, y) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            (Value::Text(x), Value::Text(y)) => match (x, y) {
+                (Some(x), Some(y)) => x.to_lowercase().cmp(&y.to_lowercase()),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            (Value::List(x), Value::List(y)) => x.join(",").to_lowercase().cmp(&y.join(",").to_lowercase()),
+            _ => Ordering::Equal,
+        };
+        let ord = if ascending { ord } else { ord.reverse() };
+        // Keep equal rows in file/time order
+        ord.then_with(|| a.file.cmp(&b.file)).then_with(|| a.start.total_cmp(&b.start))
+    });
+}
+
+// ── Filter expressions ──────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+/// A clock-time filter value: the span of local time it names, in ms since
+/// 1970-01-01 00:00 for dates, or since midnight for a bare `HH:MM[:SS]`.
+/// `clock = 2024-07-14` matches the whole day, `clock > 22:30` from 22:31.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockSpan {
+    pub time_of_day: bool,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// A parsed filter expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    Cmp { column: Column, op: Op, text: String, num: Option<f64> },
+    Clock { op: Op, span: ClockSpan },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut out = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => { chars.next(); }
+            '(' => { chars.next(); out.push(Token::LParen); }
+            ')' => { chars.next(); out.push(Token::RParen); }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(ch) if ch == c => break,
+                        Some(ch) => text.push(ch),
+                        None => return Err("Unclosed quote".to_string()),
+                    }
+                }
+                out.push(Token::Str(text));
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                chars.next();
+                let eq = chars.peek() == Some(&'=');
+                if eq { chars.next(); }
+                let op = match (c, eq) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    ('~', _) => Op::Contains,
+                    _ => return Err("Expected !=".to_string()),
+                };
+                out.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()\"'=!<>~".contains(ch) { break; }
+                    word.push(ch);
+                    chars.next();
+                }
+                out.push(Token::Ident(word));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Parse a number with an optional unit suffix (`40k`, `40kHz`, `5ms`, `0.2s`).
+fn parse_number(s: &str) -> Option<f64> {
+    let lower = s.to_ascii_lowercase();
+    let (num, scale) = if let Some(n) = lower.strip_suffix("khz") {
+        (n, 1000.0)
+    } else if let Some(n) = lower.strip_suffix("hz") {
+        (n, 1.0)
+    } else if let Some(n) = lower.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = lower.strip_suffix('k') {
+        (n, 1000.0)
+    } else if let Some(n) = lower.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = lower.strip_suffix('%') {
+        (n, 0.01)
+    } else {
+        (lower.as_str(), 1.0)
+    };
+    num.trim().parse::<f64>().ok().map(|v| v * scale)
+}
+
+/// Days from 1970-01-01 to a proleptic Gregorian date.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// `HH:MM[:SS]` as (ms since midnight, ms the given precision spans).
+fn parse_time_of_day(s: &str) -> Option<(f64, f64)> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || parts.iter().any(|p| p.is_empty() || p.len() > 2 || !p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let n: Vec<u32> = parts.iter().map(|p| p.parse().unwrap_or(0)).collect();
+    if n[0] > 23 || n[1] > 59 || n.get(2).is_some_and(|&s| s > 59) {
+        return None;
+    }
+    let ms = (n[0] * 3600 + n[1] * 60 + n.get(2).copied().unwrap_or(0)) as f64 * 1000.0;
+    Some((ms, if parts.len() == 3 { 1000.0 } else { 60_000.0 }))
+}
+
+/// Parse a clock-time filter value: `HH:MM[:SS]`, `YYYY-MM-DD`, or a date
+/// and time joined by `T` or a space.
+fn parse_clock(s: &str) -> Option<ClockSpan> {
+    if let Some((start, len)) = parse_time_of_day(s) {
+        return Some(ClockSpan { time_of_day: true, start, end: start + len });
+    }
+    let (date, time) = match s.split_once(['T', 't', ' ']) {
+        Some((d, t)) => (d, Some(t.trim())),
+        None => (s, None),
+    };
+    let ymd: Vec<&str> = date.split('-').collect();
+    if ymd.len() != 3 || ymd.iter().any(|p| p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let (y, m, d): (i64, i64, i64) = (ymd[0].parse().ok()?, ymd[1].parse().ok()?, ymd[2].parse().ok()?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    let day_ms = days_from_civil(y, m, d) as f64 * 86_400_000.0;
+    let (offset, len) = match time {
+        Some(t) => parse_time_of_day(t)?,
+        None => (0.0, 86_400_000.0),
+    };
+    Some(ClockSpan { time_of_day: false, start: day_ms + offset, end: day_ms + offset + len })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Ident(w)) if w.eq_ignore_ascii_case(word))
+    }
+
+    fn or_expr(&mut self) -> Result<Filter, String> {
+        let mut left = self.and_expr()?;
+        while self.peek_word("or") {
+            self.pos += 1;
+            left = Filter::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Filter, String> {
+        let mut left = self.unary()?;
+        while self.peek_word("and") {
+            self.pos += 1;
+            left = Filter::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        if self.peek_word("not") {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let inner = self.or_expr()?;
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    return Err("Expected )".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) => {
+                let column = Column::from_name(&name).ok_or_else(|| format!("Unknown field '{name}'"))?;
+                self.pos += 1;
+                let Some(Token::Op(op)) = self.tokens.get(self.pos).cloned() else {
+                    return Err(format!("Expected a comparison after '{name}'"));
+                };
+                self.pos += 1;
+                let text = match self.tokens.get(self.pos).cloned() {
+                    Some(Token::Str(s)) | Some(Token::Ident(s)) => s,
+                    _ => return Err(format!("Expected a value after '{name}'")),
+                };
+                self.pos += 1;
+                if column == Column::Clock {
+                    if let Some(span) = parse_clock(&text) {
+                        return Ok(Filter::Clock { op, span });
+                    }
+                }
+                let num = parse_number(&text);
+                let numeric = matches!(column.value_kind(), ValueKind::Num);
+                if numeric && num.is_none() {
+                    return Err(if column == Column::Clock {
+                        format!("'{text}' is not a time (HH:MM) or date (YYYY-MM-DD)")
+                    } else {
+                        format!("'{text}' is not a number")
+                    });
+                }
+                Ok(Filter::Cmp { column, op, text: text.to_lowercase(), num })
+            }
+            Some(t) => Err(format!("Unexpected {t:?}")),
+            None => Err("Incomplete expression".to_string()),
+        }
+    }
+}
+
+enum ValueKind {
+    Num,
+    Text,
+}
+
+impl Column {
+    fn value_kind(self) -> ValueKind {
+        match self {
+            Self::Start | Self::Clock | Self::Duration | Self::FreqLow | Self::FreqHigh | Self::Confidence => ValueKind::Num,
+            _ => ValueKind::Text,
+        }
+    }
+}
+
+impl Filter {
+    /// Parse a filter expression. An empty string gives None (match all).
+    pub fn parse(s: &str) -> Result<Option<Filter>, String> {
+        let tokens = tokenize(s)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        let mut p = Parser { tokens, pos: 0 };
+        let f = p.or_expr()?;
+        if p.pos < p.tokens.len() {
+            return Err(format!("Unexpected {:?}", p.tokens[p.pos]));
+        }
+        Ok(Some(f))
+    }
+
+    pub fn matches(&self, row: &AnnotationRow) -> bool {
+        match self {
+            Filter::And(a, b) => a.matches(row) && b.matches(row),
+            Filter::Or(a, b) => a.matches(row) || b.matches(row),
+            Filter::Not(a) => !a.matches(row),
+            Filter::Clock { op, span } => row.clock_local_ms.is_some_and(|ms| {
+                let v = if span.time_of_day { ms.rem_euclid(86_400_000.0) } else { ms };
+                match op {
+                    Op::Eq => v >= span.start && v < span.end,
+                    Op::Ne => v < span.start || v >= span.end,
+                    Op::Lt => v < span.start,
+                    Op::Le => v < span.end,
+                    Op::Gt => v >= span.end,
+                    Op::Ge => v >= span.start,
+                    Op::Contains => false,
+                }
+            }),
+            Filter::Cmp { column, op, text, num } => match column.value(row) {
+                Value::Num(v) => match (v, num) {
+                    (Some(v), Some(n)) => match op {
+                        Op::Eq => v == *n,
+                        Op::Ne => v != *n,
+                        Op::Lt => v < *n,
+                        Op::Le => v <= *n,
+                        Op::Gt => v > *n,
+                        Op::Ge => v >= *n,
+                        Op::Contains => false,
+                    },
+                    _ => false,
+                },
+                Value::Text(v) => v.is_some_and(|v| cmp_text(&v.to_lowercase(), *op, text)),
+                Value::List(tags) => {
+                    if *op == Op::Ne {
+                        !tags.iter().any(|t| t.to_lowercase() == *text)
+                    } else {
+                        tags.iter().any(|t| cmp_text(&t.to_lowercase(), *op, text))
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn cmp_text(v: &str, op: Op, text: &str) -> bool {
+    match op {
+        Op::Eq => v == text,
+        Op::Ne => v != text,
+        Op::Lt => v < text,
+        Op::Le => v <= text,
+        Op::Gt => v > text,
+        Op::Ge => v >= text,
+        Op::Contains => v.contains(text),
+    }
+}
+
+// ── Bulk edits ──────────────────────────────────────────────────────────
+
+/// An edit applied to every selected row.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BulkEdit {
+    AddTag(String),
+    RemoveTag(String),
+    Relabel(String),
+    Delete,
+}
+
+/// Apply `edit` to the annotations in `ids`. Returns how many changed.
+pub fn apply_bulk_edit(annotations: &mut Vec<Annotation>, ids: &HashSet<AnnotationId>, edit: &BulkEdit) -> usize {
+    if *edit == BulkEdit::Delete {
+        let before = annotations.len();
+        annotations.retain(|a| !ids.contains(&a.id));
+        return before - annotations.len();
+    }
+    let now = now_iso8601();
+    let mut changed = 0;
+    for a in annotations.iter_mut().filter(|a| ids.contains(&a.id)) {
+        let did = match edit {
+            BulkEdit::AddTag(tag) => {
+                let has = a.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+                if !has { a.tags.push(tag.clone()); }
+                !has
+            }
+            BulkEdit::RemoveTag(tag) => {
+                let before = a.tags.len();
+                a.tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+                a.tags.len() != before
+            }
+            BulkEdit::Relabel(label) => {
+                let slot = match a.kind {
+                    AnnotationKind::Region(ref mut r) => &mut r.label,
+                    AnnotationKind::Marker(ref mut m) => &mut m.label,
+                    AnnotationKind::Group(ref mut g) => &mut g.label,
+                    AnnotationKind::Measurement(ref mut m) => &mut m.label,
+                };
+                let did = slot.as_deref() != Some(label.as_str()) || a.label_default.is_some();
+                *slot = Some(label.clone());
+                a.label_default = None;
+                did
+            }
+            BulkEdit::Delete => false,
+        };
+        if did {
+            a.modified_at = now.clone();
+            changed += 1;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_at(clock_local_ms: f64) -> AnnotationRow {
+        AnnotationRow {
+            source: RowSource::Loaded(0),
+            id: "a".into(),
+            file: "f.wav".into(),
+            kind: "Region",
+            start: 0.0,
+            end: 1.0,
+            freq_low: None,
+            freq_high: None,
+            label: None,
+            tags: Vec::new(),
+            notes: None,
+            clock_ms: Some(clock_local_ms),
+            clock_local_ms: Some(clock_local_ms),
+            status: "",
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn test_clock_filters() {
+        // 2024-07-14 22:30:15 local
+        let row = row_at(days_from_civil(2024, 7, 14) as f64 * 86_400_000.0 + 81_015_000.0);
+        let matches = |expr: &str| Filter::parse(expr).unwrap().unwrap().matches(&row);
+        assert!(matches("clock >= 22:00"));
+        assert!(matches("clock = 22:30"));
+        assert!(!matches("clock > 22:30"));
+        assert!(matches("clock > 22:00 or clock < 04:00"));
+        assert!(matches("clock = 2024-07-14"));
+        assert!(matches("clock < 2024-07-15 and clock > 2024-07-13"));
+        assert!(matches("clock >= 2024-07-14T22:30:15"));
+        assert!(matches("clock < '2024-07-14 22:31'"));
+        assert!(Filter::parse("clock > dusk").is_err());
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+    }
+}
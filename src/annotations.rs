@@ -136,6 +136,12 @@ pub struct Region {
     /// Whether this region is locked (cannot be resized/moved via handles).
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub locked: Option<bool>,
+    /// Traced frequency contour of the call inside this region (see `dsp::contour`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub contour: Option<Vec<crate::dsp::contour::ContourPoint>>,
+    /// Source position from multi-channel TDOA localisation (see `dsp::tdoa`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<crate::dsp::tdoa::SourcePosition>,
 }
 
 impl Region {
@@ -154,7 +160,8 @@ pub struct Marker {
     pub color: Option<String>,
 }
 
-/// A measurement between two time+frequency points (future).
+/// A measurement between two time+frequency points, e.g. along a call to
+/// get its slope or between two pulses to get the inter-pulse interval.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Measurement {
     pub start_time: f64,
@@ -165,6 +172,48 @@ pub struct Measurement {
     pub label: Option<String>,
 }
 
+impl Measurement {
+    /// Elapsed time in seconds (negative if the end precedes the start).
+    pub fn delta_time(&self) -> f64 {
+        self.end_time - self.start_time
+    }
+
+    /// Frequency change in Hz (negative for a downward sweep).
+    pub fn delta_freq(&self) -> f64 {
+        self.end_freq - self.start_freq
+    }
+
+    /// Frequency slope in kHz/ms; None when both points share a time.
+    pub fn slope_khz_per_ms(&self) -> Option<f64> {
+        let dt_ms = self.delta_time() * 1000.0;
+        // A flat line drawn right to left would otherwise read "-0.00 kHz/ms"
+        (dt_ms.abs() > 1e-9).then(|| self.delta_freq() / 1000.0 / dt_ms + 0.0)
+    }
+
+    /// Repetition rate implied by taking the span as one period, in Hz
+    /// (pulses per second for an inter-pulse interval).
+    pub fn rate_hz(&self) -> Option<f64> {
+        let dt = self.delta_time().abs();
+        (dt > 1e-9).then(|| 1.0 / dt)
+    }
+
+    /// One-line readout: "Δt 12.4 ms  Δf -18.2 kHz  -1.47 kHz/ms  80.6 Hz".
+    pub fn readout(&self) -> String {
+        let mut out = format!(
+            "\u{0394}t {:.1} ms  \u{0394}f {:+.1} kHz",
+            self.delta_time().abs() * 1000.0,
+            self.delta_freq() / 1000.0,
+        );
+        if let Some(slope) = self.slope_khz_per_ms() {
+            out.push_str(&format!("  {slope:+.2} kHz/ms"));
+        }
+        if let Some(rate) = self.rate_hz() {
+            out.push_str(&format!("  {rate:.1} Hz"));
+        }
+        out
+    }
+}
+
 /// A named group that can contain other annotations or nested groups.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Group {
@@ -211,6 +260,62 @@ pub struct Annotation {
     /// means the label (if any) is user-authored.
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub label_default: Option<bool>,
+    /// How sure the labeller is of the identification, 0–1.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub confidence: Option<f64>,
+    /// Second reviewer's verdict. None = not reviewed yet.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub review: Option<Review>,
+}
+
+impl Annotation {
+    pub fn review_status(&self) -> ReviewStatus {
+        self.review.as_ref().map(|r| r.status).unwrap_or_default()
+    }
+}
+
+/// Where an annotation is in the QA review.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    #[default]
+    Unreviewed,
+    Accepted,
+    Rejected,
+    Disputed,
+}
+
+impl ReviewStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Unreviewed => "Unreviewed",
+            Self::Accepted => "Accepted",
+            Self::Rejected => "Rejected",
+            Self::Disputed => "Disputed",
+        }
+    }
+
+    /// Short glyph for the annotation tree.
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Unreviewed => "",
+            Self::Accepted => "\u{2713}",
+            Self::Rejected => "\u{2717}",
+            Self::Disputed => "?",
+        }
+    }
+}
+
+/// A reviewer's decision on an annotation.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Review {
+    #[serde(default)]
+    pub status: ReviewStatus,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reviewer: Option<String>,
+    /// When the decision was recorded (ISO 8601).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reviewed_at: Option<String>,
 }
 
 /// Human-readable kind name used as the prefix for auto-generated labels
@@ -225,7 +330,7 @@ pub fn kind_name(kind: &AnnotationKind) -> &'static str {
     }
 }
 
-fn label_of(kind: &AnnotationKind) -> Option<&str> {
+pub fn label_of(kind: &AnnotationKind) -> Option<&str> {
     match kind {
         AnnotationKind::Region(r) => r.label.as_deref(),
         AnnotationKind::Marker(m) => m.label.as_deref(),
@@ -280,6 +385,11 @@ pub struct AudioFileMetadata {
     /// Peak level (dBFS) of entire unmodified audio file.
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub peak_db_full: Option<f64>,
+    /// Time-expansion factor the annotations were made with. `sample_rate`
+    /// and `duration_secs` are in real-world units when this is > 1.
+    /// Missing in sidecars written before it was recorded, which means 1.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub time_expansion: Option<f64>,
 }
 
 /// Per-file annotation collection — serialized to .batm sidecar files (YAML).
@@ -309,6 +419,11 @@ pub struct AnnotationSet {
     #[serde(default)]
     pub annotations: Vec<Annotation>,
 
+    /// What the last merge with another copy of this sidecar saw; the common
+    /// ancestor for the next three-way merge.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub merge_base: Option<crate::annotation_merge::MergeBase>,
+
     /// Noise reduction profile (notch bands + spectral floor). Kept near the end
     /// because noise_floor.bin_magnitudes can be very long.
     #[serde(skip_serializing_if = "Option::is_none", default)]
@@ -327,6 +442,7 @@ impl AnnotationSet {
             file_identity,
             audio_metadata: None,
             annotations: Vec::new(),
+            merge_base: None,
             noise_profile: None,
         }
     }
@@ -350,10 +466,47 @@ impl AnnotationSet {
             data_size: audio.metadata.data_size,
             peak_db_30s,
             peak_db_full,
+            time_expansion: Some(audio.metadata.time_expansion),
         });
         set
     }
 
+    /// Move annotations made at time-expansion factor `old` to factor `new`
+    /// so they stay on the same calls: times shrink and frequencies grow by
+    /// `new / old`.
+    pub fn rescale_time_expansion(&mut self, old: f64, new: f64) {
+        let ratio = new / old;
+        let t = |v: &mut f64| *v /= ratio;
+        let f = |v: &mut f64| *v *= ratio;
+        for a in &mut self.annotations {
+            match &mut a.kind {
+                AnnotationKind::Region(r) => {
+                    t(&mut r.time_start);
+                    t(&mut r.time_end);
+                    if let Some(v) = r.freq_low.as_mut() { f(v); }
+                    if let Some(v) = r.freq_high.as_mut() { f(v); }
+                    for p in r.contour.iter_mut().flatten() {
+                        t(&mut p.time);
+                        f(&mut p.freq);
+                    }
+                }
+                AnnotationKind::Marker(m) => t(&mut m.time),
+                AnnotationKind::Measurement(m) => {
+                    t(&mut m.start_time);
+                    t(&mut m.end_time);
+                    f(&mut m.start_freq);
+                    f(&mut m.end_freq);
+                }
+                AnnotationKind::Group(_) => {}
+            }
+        }
+        if let Some(meta) = self.audio_metadata.as_mut() {
+            meta.sample_rate = (meta.sample_rate as f64 * ratio).round() as u32;
+            meta.duration_secs /= ratio;
+            meta.time_expansion = Some(new);
+        }
+    }
+
     /// Touch the modified_at timestamp and app_version.
     pub fn touch(&mut self) {
         self.modified_at = Some(now_iso8601());
@@ -492,6 +645,8 @@ pub fn collect_descendants(annotations: &[Annotation], parent_id: &str) -> Vec<A
 }
 
 /// Assign consecutive sort_order values to annotations at a given parent level.
+/// Annotations whose order changes get a new `modified_at`, so sidecar
+/// merges see the move.
 pub fn renumber_children(annotations: &mut [Annotation], parent_id: Option<&str>) {
     let mut indices: Vec<usize> = annotations.iter().enumerate()
         .filter(|(_, a)| a.parent_id.as_deref() == parent_id)
@@ -506,7 +661,72 @@ pub fn renumber_children(annotations: &mut [Annotation], parent_id: Option<&str>
         oa.partial_cmp(&ob).unwrap_or(std::cmp::Ordering::Equal)
             .then_with(|| a.created_at.cmp(&b.created_at))
     });
+    let now = now_iso8601();
     for (rank, &idx) in indices.iter().enumerate() {
-        annotations[idx].sort_order = Some(rank as f64);
+        let a = &mut annotations[idx];
+        if a.sort_order != Some(rank as f64) {
+            a.sort_order = Some(rank as f64);
+            a.modified_at = now.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(start_time: f64, start_khz: f64, end_time: f64, end_khz: f64) -> Measurement {
+        Measurement {
+            start_time,
+            start_freq: start_khz * 1000.0,
+            end_time,
+            end_freq: end_khz * 1000.0,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_measurement_slope() {
+        let sweep = measurement(0.100, 60.0, 0.105, 40.0);
+        assert!((sweep.slope_khz_per_ms().unwrap() + 4.0).abs() < 1e-6);
+        assert!((sweep.rate_hz().unwrap() - 200.0).abs() < 1e-6);
+        assert_eq!(sweep.readout(), "\u{0394}t 5.0 ms  \u{0394}f -20.0 kHz  -4.00 kHz/ms  200.0 Hz");
+
+        // The same line dragged from its end back to its start has the same
+        // slope and duration; only the sign of Δf follows the drag.
+        let reversed = measurement(0.105, 40.0, 0.100, 60.0);
+        assert!(reversed.delta_time() < 0.0);
+        assert!((reversed.slope_khz_per_ms().unwrap() + 4.0).abs() < 1e-6);
+        assert!((reversed.rate_hz().unwrap() - 200.0).abs() < 1e-6);
+        assert_eq!(reversed.readout(), "\u{0394}t 5.0 ms  \u{0394}f +20.0 kHz  -4.00 kHz/ms  200.0 Hz");
+
+        let upward = measurement(0.0, 20.0, 0.002, 21.0);
+        assert!((upward.slope_khz_per_ms().unwrap() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_measurement_pulse_interval() {
+        // Start of one pulse to the start of the next at the same frequency.
+        let interval = measurement(0.200, 45.0, 0.285, 45.0);
+        assert_eq!(interval.slope_khz_per_ms(), Some(0.0));
+        assert!((interval.rate_hz().unwrap() - 1000.0 / 85.0).abs() < 1e-6);
+        assert_eq!(interval.readout(), "\u{0394}t 85.0 ms  \u{0394}f +0.0 kHz  +0.00 kHz/ms  11.8 Hz");
+
+        let reversed = measurement(0.285, 45.0, 0.200, 45.0);
+        assert!((reversed.rate_hz().unwrap() - 1000.0 / 85.0).abs() < 1e-6);
+        assert_eq!(reversed.readout(), interval.readout());
+    }
+
+    #[test]
+    fn test_measurement_zero_duration() {
+        // A vertical line measures bandwidth only: no slope and no rate.
+        let vertical = measurement(0.150, 80.0, 0.150, 30.0);
+        assert_eq!(vertical.slope_khz_per_ms(), None);
+        assert_eq!(vertical.rate_hz(), None);
+        assert_eq!(vertical.readout(), "\u{0394}t 0.0 ms  \u{0394}f -50.0 kHz");
+
+        let point = measurement(0.150, 40.0, 0.150, 40.0);
+        assert_eq!(point.slope_khz_per_ms(), None);
+        assert_eq!(point.readout(), "\u{0394}t 0.0 ms  \u{0394}f +0.0 kHz");
     }
 }
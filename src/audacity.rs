@@ -0,0 +1,301 @@
+//! Audacity label tracks.
+//!
+//! A label file has one line per label, `start<TAB>end<TAB>text`, with times
+//! in seconds. A label with a spectral selection is followed by a line
+//! `\<TAB>low<TAB>high` in Hz (Audacity writes -1 for an open bound).
+//!
+//! Labels import as segments, or regions when they have a frequency line,
+//! and point labels (start == end) as markers. Audacity has nowhere to keep
+//! tags, so they ride in the label text as a trailing `[#tag; #tag]`.
+//!
+//! Measurements export as the time and frequency span they cover, with a
+//! `measure:<Hz>:<Hz>` tag giving the line's frequency at the label's start
+//! and end so it imports as a measurement again.
+
+use crate::annotations::{generate_uuid, now_iso8601, Annotation, AnnotationKind, Marker, Measurement, Region};
+
+/// Tag carrying a measurement's start and end frequency.
+const MEASURE_TAG_PREFIX: &str = "measure:";
+
+fn format_secs(v: f64) -> String {
+    format!("{v:.6}")
+}
+
+fn clean_text(s: &str) -> String {
+    s.replace(['\t', '\r', '\n'], " ").trim().to_string()
+}
+
+/// Append `[#tag; #tag]` to a label. `\`, `;`, `[` and `]` in a tag are
+/// escaped with `\`.
+fn label_with_tags(label: Option<&str>, tags: &[String]) -> String {
+    let label = label.map(clean_text).unwrap_or_default();
+    if tags.is_empty() {
+        return label;
+    }
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|t| {
+            let mut out = String::from("#");
+            for c in clean_text(t).chars() {
+                if matches!(c, '\\' | ';' | '[' | ']') {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+            out
+        })
+        .collect();
+    let tags = format!("[{}]", tags.join("; "));
+    if label.is_empty() { tags } else { format!("{label} {tags}") }
+}
+
+/// Split a trailing `[#tag; #tag]` off a label. Anything else in brackets,
+/// like `Myotis [?]`, stays part of the label.
+fn split_tags(text: &str) -> (Option<String>, Vec<String>) {
+    let text = text.trim();
+    let escaped = |i: usize| text[..i].bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1;
+    let parsed = text.strip_suffix(']').and_then(|rest| {
+        // Tags escape `[`, so the last unescaped `[#` opens them
+        let (open, _) = rest.rmatch_indices("[#").find(|&(i, _)| !escaped(i))?;
+        let mut tags = Vec::new();
+        let mut tag: Option<String> = None;
+        let mut chars = rest[open + 1..].chars();
+        while let Some(c) = chars.next() {
+            match (c, tag.as_mut()) {
+                ('#', None) => tag = Some(String::new()),
+                (' ', None) => {}
+                ('\\', Some(t)) => t.push(chars.next()?),
+                (';', Some(t)) => {
+                    let t = t.trim().to_string();
+                    if !t.is_empty() {
+                        tags.push(t);
+                    }
+                    tag = None;
+                }
+                ('[' | ']', Some(_)) => return None,
+                (c, Some(t)) => t.push(c),
+                (_, None) => return None,
+            }
+        }
+        let last = tag?.trim().to_string();
+        if !last.is_empty() {
+            tags.push(last);
+        }
+        Some((rest[..open].trim(), tags))
+    });
+    let (label, tags) = parsed.unwrap_or((text, Vec::new()));
+    ((!label.is_empty()).then(|| label.to_string()), tags)
+}
+
+/// Write annotations as an Audacity label track. Regions and segments keep
+/// their time span, markers become point labels and measurements the box
+/// they span; groups are skipped.
+pub fn export_labels(annotations: &[Annotation]) -> String {
+    let mut rows: Vec<(f64, String)> = Vec::new();
+    for a in annotations {
+        let mut tags = a.tags.clone();
+        let (start, end, freqs, label) = match &a.kind {
+            AnnotationKind::Region(r) => {
+                let freqs = (r.freq_low.is_some() || r.freq_high.is_some())
+                    .then(|| (r.freq_low.unwrap_or(-1.0), r.freq_high.unwrap_or(-1.0)));
+                (r.time_start, r.time_end, freqs, r.label.as_deref())
+            }
+            AnnotationKind::Marker(m) => (m.time, m.time, None, m.label.as_deref()),
+            AnnotationKind::Measurement(m) => {
+                let (left, right) = if m.start_time <= m.end_time {
+                    (m.start_freq, m.end_freq)
+                } else {
+                    (m.end_freq, m.start_freq)
+                };
+                tags.push(format!("{MEASURE_TAG_PREFIX}{left:.1}:{right:.1}"));
+                (
+                    m.start_time.min(m.end_time),
+                    m.start_time.max(m.end_time),
+                    Some((left.min(right), left.max(right))),
+                    m.label.as_deref(),
+                )
+            }
+            AnnotationKind::Group(_) => continue,
+        };
+        let mut row = format!(
+            "{}\t{}\t{}\n",
+            format_secs(start),
+            format_secs(end),
+            label_with_tags(label, &tags),
+        );
+        if let Some((low, high)) = freqs {
+            row.push_str(&format!("\\\t{low:.6}\t{high:.6}\n"));
+        }
+        rows.push((start, row));
+    }
+    rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+    rows.into_iter().map(|(_, r)| r).collect()
+}
+
+/// Parse an Audacity label track.
+pub fn parse_labels(text: &str) -> Result<Vec<Annotation>, String> {
+    let mut out: Vec<Annotation> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r').trim_start_matches('\u{feff}');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut fields = line.splitn(3, '\t');
+        let first = fields.next().unwrap_or("").trim();
+
+        if first == "\\" {
+            // Frequency range of the previous label
+            let bound = |s: Option<&str>| s.and_then(|s| s.trim().parse::<f64>().ok()).filter(|v| *v >= 0.0);
+            let low = bound(fields.next());
+            let high = bound(fields.next());
+            let Some(prev) = out.last_mut() else {
+                return Err(format!("Line {}: frequency range without a label", n + 1));
+            };
+            if let AnnotationKind::Marker(m) = &prev.kind {
+                // A point label with a frequency range is still a point in
+                // time; keep it as a zero-length region so the range is kept
+                if low.is_some() || high.is_some() {
+                    prev.kind = AnnotationKind::Region(Region {
+                        time_start: m.time,
+                        time_end: m.time,
+                        freq_low: low,
+                        freq_high: high,
+                        label: m.label.clone(),
+                        color: None,
+                        locked: None,
+                        contour: None,
+                        position: None,
+                    });
+                }
+            } else if let AnnotationKind::Region(r) = &mut prev.kind {
+                r.freq_low = low;
+                r.freq_high = high;
+            }
+            continue;
+        }
+
+        let start: f64 = first.parse().map_err(|_| format!("Line {}: not an Audacity label file", n + 1))?;
+        let end: f64 = fields
+            .next()
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| format!("Line {}: missing end time", n + 1))?;
+        let (label, tags) = split_tags(fields.next().unwrap_or(""));
+        let kind = if end <= start {
+            AnnotationKind::Marker(Marker { time: start, label, color: None })
+        } else {
+            AnnotationKind::Region(Region {
+                time_start: start,
+                time_end: end,
+                freq_low: None,
+                freq_high: None,
+                label,
+                color: None,
+                locked: None,
+                contour: None,
+                position: None,
+            })
+        };
+        let now = now_iso8601();
+        out.push(Annotation {
+            id: generate_uuid(),
+            kind,
+            created_at: now.clone(),
+            modified_at: now,
+            notes: None,
+            parent_id: None,
+            sort_order: None,
+            tags,
+            label_default: None,
+            confidence: None,
+            review: None,
+        });
+    }
+    for a in &mut out {
+        restore_measurement(a);
+    }
+    Ok(out)
+}
+
+/// Turn a label carrying a `measure:` tag back into a measurement.
+fn restore_measurement(a: &mut Annotation) {
+    let Some(pos) = a.tags.iter().position(|t| t.starts_with(MEASURE_TAG_PREFIX)) else { return };
+    let Some((left, right)) = a.tags[pos][MEASURE_TAG_PREFIX.len()..]
+        .split_once(':')
+        .and_then(|(l, r)| Some((l.parse::<f64>().ok()?, r.parse::<f64>().ok()?)))
+    else {
+        return;
+    };
+    let (start_time, end_time, label) = match &a.kind {
+        AnnotationKind::Region(r) => (r.time_start, r.time_end, r.label.clone()),
+        AnnotationKind::Marker(m) => (m.time, m.time, m.label.clone()),
+        _ => return,
+    };
+    a.tags.remove(pos);
+    a.kind = AnnotationKind::Measurement(Measurement { start_time, start_freq: left, end_time, end_freq: right, label });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_round_trip() {
+        let tags = vec!["feeding buzz".to_string(), "a;b [c]\\".to_string()];
+        let text = label_with_tags(Some("Myotis [?]"), &tags);
+        assert_eq!(text, r"Myotis [?] [#feeding buzz; #a\;b \[c\]\\]");
+        assert_eq!(split_tags(&text), (Some("Myotis [?]".to_string()), tags));
+
+        // Plain bracketed text is part of the label
+        assert_eq!(split_tags("Myotis [?]"), (Some("Myotis [?]".to_string()), Vec::new()));
+        assert_eq!(split_tags("[a; b]"), (Some("[a; b]".to_string()), Vec::new()));
+        assert_eq!(split_tags("[#social]"), (None, vec!["social".to_string()]));
+    }
+
+    #[test]
+    fn test_measurement_round_trip() {
+        let mut a = Annotation {
+            id: "m".into(),
+            kind: AnnotationKind::Measurement(Measurement {
+                start_time: 0.25,
+                start_freq: 30_000.0,
+                end_time: 0.2,
+                end_freq: 45_000.0,
+                label: Some("FM".into()),
+            }),
+            created_at: String::new(),
+            modified_at: String::new(),
+            notes: None,
+            parent_id: None,
+            sort_order: None,
+            tags: Vec::new(),
+            label_default: None,
+            confidence: None,
+            review: None,
+        };
+        let text = export_labels(std::slice::from_ref(&a));
+        assert_eq!(
+            text,
+            "0.200000\t0.250000\tFM [#measure:45000.0:30000.0]\n\\\t30000.000000\t45000.000000\n"
+        );
+
+        // What parse_labels makes of those two lines
+        let (label, tags) = split_tags("FM [#measure:45000.0:30000.0]");
+        a.tags = tags;
+        a.kind = AnnotationKind::Region(Region {
+            time_start: 0.2,
+            time_end: 0.25,
+            freq_low: Some(30_000.0),
+            freq_high: Some(45_000.0),
+            label,
+            color: None,
+            locked: None,
+            contour: None,
+            position: None,
+        });
+        restore_measurement(&mut a);
+        assert!(a.tags.is_empty());
+        let AnnotationKind::Measurement(m) = &a.kind else { panic!("not a measurement") };
+        assert_eq!((m.start_time, m.start_freq, m.end_time, m.end_freq), (0.2, 45_000.0, 0.25, 30_000.0));
+        assert_eq!(m.label.as_deref(), Some("FM"));
+    }
+}
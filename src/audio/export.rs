@@ -1,4 +1,5 @@
-//! WAV export: process audio regions through the DSP pipeline and download as WAV files.
+//! WAV/FLAC export: process audio regions through the DSP pipeline and download as WAV or FLAC files.
+//! ZC export: run regions through the zero-crossing trigger and download Anabat files.
 
 use leptos::prelude::*;
 use wasm_bindgen::JsCast;
@@ -9,7 +10,9 @@ use crate::audio::playback::snapshot_params;
 use crate::audio::source::{AudioSource, ChannelView};
 use crate::audio::streaming_playback::{apply_dsp_mode, apply_filters, PlaybackParams, PV_MODE_BOOST_DB};
 use crate::audio::playback::apply_gain;
-use crate::state::{AppState, PlaybackMode, Selection};
+use crate::audio::anabat::{self, AnabatHeader};
+use crate::dsp::zc_divide::zc_event_times;
+use crate::state::{AppState, ExportFormat, LoadedFile, PlaybackMode, Selection};
 
 /// Number of source samples per export chunk (same as streaming playback).
 const CHUNK_SAMPLES: usize = 96_000;
@@ -20,6 +23,35 @@ const FILTER_WARMUP: usize = 4096;
 /// Overlap samples for PV/PS crossfade (same as streaming_playback::PV_HQ_OVERLAP).
 const PV_HQ_OVERLAP: usize = 8192;
 
+/// Seconds of source audio run through the ZC trigger at once (the trigger
+/// threshold adapts per chunk, as it would on a detector).
+const ZC_CHUNK_SECS: f64 = 30.0;
+
+/// Output encoding shared by every file written by one export action.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ExportOptions {
+    /// Encode FLAC at this bit depth; `None` writes 16-bit WAV.
+    pub flac_bits: Option<u16>,
+    /// Add a BWF `bext` chunk to WAV output (FLAC has nowhere to put one).
+    pub write_bext: bool,
+}
+
+impl ExportOptions {
+    /// Read the export settings from `state` for a region of `file`.
+    pub(crate) fn from_state(state: &AppState, file: &LoadedFile) -> Self {
+        // FLAC keeps 24-bit depth from high-resolution sources; WAV stays 16-bit
+        let flac_bits = (state.export_format.get_untracked() == ExportFormat::Flac)
+            .then_some(if file.audio.metadata.bits_per_sample > 16 { 24 } else { 16 });
+        let write_bext = flac_bits.is_none() && state.export_write_bext.get_untracked();
+        Self { flac_bits, write_bext }
+    }
+
+    /// File extension for the chosen format.
+    pub(crate) fn extension(&self) -> &'static str {
+        if self.flac_bits.is_some() { "flac" } else { "wav" }
+    }
+}
+
 /// Build PlaybackParams for exporting a region.
 /// When `use_region_focus` is true and the region has frequency bounds,
 /// those bounds drive the selection-based bandpass/heterodyne.
@@ -199,7 +231,7 @@ pub(crate) fn trigger_browser_download(data: &[u8], filename: &str) {
     web_sys::Url::revoke_object_url(&url).ok();
 }
 
-/// Export a single region as a WAV file and trigger browser download.
+/// Export a single region as a WAV or FLAC file, per `options`, and trigger browser download.
 pub(crate) fn export_one_region(
     source: &dyn AudioSource,
     sample_rate: u32,
@@ -209,6 +241,7 @@ pub(crate) fn export_one_region(
     filename: &str,
     source_filename: &str,
     source_guano: Option<&crate::audio::guano::GuanoMetadata>,
+    options: &ExportOptions,
 ) {
     let samples = process_region(source, sample_rate, start_time, end_time, params);
 
@@ -221,12 +254,27 @@ pub(crate) fn export_one_region(
         _ => sample_rate,
     };
 
-    let mut wav_data = encode_wav(&samples, output_rate);
-
     // Build GUANO metadata for the export
     let guano = build_export_guano(
         output_rate, &samples, params, filename, source_filename, source_guano,
     );
+
+    if let Some(bits) = options.flac_bits {
+        match crate::audio::flac::encode_flac(&samples, output_rate, bits, Some(&guano), &[]) {
+            Ok(flac_data) => trigger_browser_download(&flac_data, filename),
+            Err(e) => log::error!("FLAC export failed: {e}"),
+        }
+        return;
+    }
+
+    let mut wav_data = encode_wav(&samples, output_rate);
+    if options.write_bext {
+        use crate::audio::bwf;
+        match source_guano.and_then(|g| bwf::Bext::from_guano(g, start_time, output_rate)) {
+            Some(bext) => bwf::insert_bext_chunk(&mut wav_data, &bext),
+            None => log::warn!("No source timestamp; exporting {filename} without a bext chunk"),
+        }
+    }
     crate::audio::guano::append_guano_chunk(&mut wav_data, &guano.to_text());
 
     trigger_browser_download(&wav_data, filename);
@@ -445,33 +493,22 @@ pub fn get_selected_regions(state: &AppState) -> Vec<(Annotation, Region)> {
         .collect()
 }
 
-/// Export all selected regions (or current selection) as WAV files.
+/// Export all selected regions (or current selection) as WAV or FLAC files,
+/// following `state.export_format`.
 pub fn export_selected(state: &AppState) {
     let file = match state.current_file() {
         Some(f) => f,
         None => return,
     };
+    let options = ExportOptions::from_state(state, &file);
+    let ext = options.extension();
     let source = &file.audio.source;
     let sample_rate = file.audio.sample_rate;
     let use_region_focus = state.export_use_region_focus.get_untracked();
     let source_filename = &file.name;
     let source_guano = file.audio.metadata.guano.as_ref();
 
-    // Strip extension from source filename for export naming
-    let base_name = file.name.trim_end_matches(".wav")
-        .trim_end_matches(".WAV")
-        .trim_end_matches(".w4v")
-        .trim_end_matches(".W4V")
-        .trim_end_matches(".flac")
-        .trim_end_matches(".FLAC")
-        .trim_end_matches(".ogg")
-        .trim_end_matches(".OGG")
-        .trim_end_matches(".mp3")
-        .trim_end_matches(".MP3")
-        .trim_end_matches(".m4a")
-        .trim_end_matches(".M4A")
-        .trim_end_matches(".m4b")
-        .trim_end_matches(".M4B");
+    let base_name = export_base_name(&file.name);
 
     let regions = get_selected_regions(state);
 
@@ -491,34 +528,161 @@ pub fn export_selected(state: &AppState) {
                     }
                 }
             };
-            let filename = format!("{base_name}{suffix}.wav");
+            let filename = format!("{base_name}{suffix}.{ext}");
             export_one_region(
                 source.as_ref(), sample_rate,
                 region.time_start, region.time_end,
                 &params, &filename,
-                source_filename, source_guano,
+                source_filename, source_guano, &options,
             );
         }
     } else if let Some(sel) = state.selection.get_untracked() {
         // Export current selection
         let params = build_export_params(state, None, false, sample_rate);
-        let filename = format!("{base_name}_selection.wav");
+        let filename = format!("{base_name}_selection.{ext}");
         export_one_region(
             source.as_ref(), sample_rate,
             sel.time_start, sel.time_end,
             &params, &filename,
-            source_filename, source_guano,
+            source_filename, source_guano, &options,
         );
     } else {
         // No selection — export the whole file
         let params = build_export_params(state, None, false, sample_rate);
         let duration = file.audio.source.duration_secs();
-        let filename = format!("{base_name}_export.wav");
+        let filename = format!("{base_name}_export.{ext}");
         export_one_region(
             source.as_ref(), sample_rate,
             0.0, duration,
             &params, &filename,
-            source_filename, source_guano,
+            source_filename, source_guano, &options,
         );
     }
 }
+
+/// Strip the audio extension from a source filename for export naming.
+fn export_base_name(name: &str) -> &str {
+    name.trim_end_matches(".wav")
+        .trim_end_matches(".WAV")
+        .trim_end_matches(".w4v")
+        .trim_end_matches(".W4V")
+        .trim_end_matches(".flac")
+        .trim_end_matches(".FLAC")
+        .trim_end_matches(".ogg")
+        .trim_end_matches(".OGG")
+        .trim_end_matches(".mp3")
+        .trim_end_matches(".MP3")
+        .trim_end_matches(".m4a")
+        .trim_end_matches(".M4A")
+        .trim_end_matches(".m4b")
+        .trim_end_matches(".M4B")
+        .trim_end_matches(".zc")
+        .trim_end_matches(".ZC")
+}
+
+/// Division ratio for ZC export: the ZC playback factor, as a whole number.
+pub fn zc_export_division(state: &AppState) -> u32 {
+    state.zc_factor.get_untracked().round().clamp(1.0, 255.0) as u32
+}
+
+/// Divided zero-crossing event times within `[start, end)` of a file,
+/// relative to `start`. Files loaded from ZC data reuse their own dots.
+fn zc_events_for_span(file: &LoadedFile, start: f64, end: f64, division: u32) -> Vec<f64> {
+    if let Some(dots) = &file.zc_dots {
+        return dots.iter()
+            .filter(|d| d.time_secs >= start && d.time_secs < end)
+            .map(|d| d.time_secs - start)
+            .collect();
+    }
+    let sr = file.audio.sample_rate;
+    let total = file.audio.source.total_samples();
+    let end_sample = ((end * sr as f64) as u64).min(total);
+    let chunk = ((ZC_CHUNK_SECS * sr as f64) as u64).max(1);
+    let mut events = Vec::new();
+    let mut pos = (start.max(0.0) * sr as f64) as u64;
+    while pos < end_sample {
+        let len = chunk.min(end_sample - pos) as usize;
+        let samples = file.audio.source.read_region(ChannelView::MonoMix, pos, len);
+        let offset = pos as f64 / sr as f64 - start;
+        events.extend(zc_event_times(&samples, sr, division, false).into_iter().map(|t| t + offset));
+        pos += len as u64;
+    }
+    events
+}
+
+/// `stem` plus `.zc`, with `_2`, `_3`, … added if an earlier file in the
+/// same export already took that name.
+fn unique_zc_filename(used: &mut std::collections::HashSet<String>, stem: &str) -> String {
+    let mut name = format!("{stem}.zc");
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{stem}_{n}.zc");
+        n += 1;
+    }
+    name
+}
+
+/// Export `[start, end)` of a file as an Anabat ZC file and trigger a download.
+fn export_zc_span(file: &LoadedFile, start: f64, end: f64, division: u32, filename: &str) -> usize {
+    let events = zc_events_for_span(file, start, end, division);
+    let mut header = AnabatHeader::from_guano(file.audio.metadata.guano.as_ref(), start, division as u8);
+    if header.notes1.is_empty() {
+        header.notes1 = file.name.clone();
+    }
+    trigger_browser_download(&anabat::write_anabat(&header, &events), filename);
+    events.len()
+}
+
+/// Export the selected regions, the selection, or the whole file — or, with
+/// a timeline active, each file under the selection (or the whole timeline) —
+/// as Anabat zero-crossing files.
+pub fn export_selected_zc(state: &AppState) {
+    let division = zc_export_division(state);
+    let selection = state.selection.get_untracked();
+
+    if let Some(tl) = state.active_timeline.get_untracked() {
+        let (range_start, range_end) = selection
+            .map(|s| (s.time_start, s.time_end))
+            .unwrap_or((0.0, tl.total_duration_secs));
+        let files = state.files.get_untracked();
+        let mut used = std::collections::HashSet::new();
+        let mut exported = 0;
+        for seg in &tl.segments {
+            let start = (range_start - seg.timeline_offset_secs).max(0.0);
+            let end = (range_end - seg.timeline_offset_secs).min(seg.duration_secs);
+            let Some(file) = files.get(seg.file_index) else { continue };
+            if end <= start {
+                continue;
+            }
+            let filename = unique_zc_filename(&mut used, export_base_name(&file.name));
+            export_zc_span(file, start, end, division, &filename);
+            exported += 1;
+        }
+        state.show_info_toast(format!("Exported {} ZC file(s) (\u{00f7}{})", exported, division));
+        return;
+    }
+
+    let Some(file) = state.current_file() else { return };
+    let base_name = export_base_name(&file.name);
+    let regions = get_selected_regions(state);
+    let mut dots = 0;
+    if !regions.is_empty() {
+        let mut used = std::collections::HashSet::new();
+        for (i, (_annotation, region)) in regions.iter().enumerate() {
+            let suffix = match region.label.as_deref() {
+                Some(l) if !l.is_empty() => format!("_{}", l.replace(' ', "_")),
+                _ if regions.len() > 1 => format!("_{}", i + 1),
+                _ => String::new(),
+            };
+            let filename = unique_zc_filename(&mut used, &format!("{base_name}{suffix}"));
+            dots += export_zc_span(&file, region.time_start, region.time_end, division, &filename);
+        }
+    } else if let Some(sel) = selection {
+        dots = export_zc_span(&file, sel.time_start, sel.time_end, division, &format!("{base_name}_selection.zc"));
+    } else {
+        dots = export_zc_span(&file, 0.0, file.audio.duration_secs, division, &format!("{base_name}.zc"));
+    }
+    if dots == 0 {
+        state.show_info_toast("ZC export: no signal above the trigger threshold");
+    }
+}
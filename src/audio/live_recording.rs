@@ -64,13 +64,14 @@ pub(crate) fn cleanup_failed_recording(state: &AppState) {
 pub(crate) fn start_live_recording(state: &AppState, sample_rate: u32) -> usize {
     let now = js_sys::Date::new_0();
     let name = format!(
-        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.wav",
+        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.{}",
         now.get_full_year(),
         now.get_month() + 1,
         now.get_date(),
         now.get_hours(),
         now.get_minutes(),
         now.get_seconds(),
+        state.record_format.get_untracked().extension(),
     );
 
     let samples: Arc<Vec<f32>> = Arc::new(Vec::new());
@@ -94,6 +95,7 @@ pub(crate) fn start_live_recording(state: &AppState, sample_rate: u32) -> usize
             guano: None,
             data_offset: None,
             data_size: None,
+            time_expansion: 1.0,
         },
     };
 
@@ -135,6 +137,7 @@ pub(crate) fn start_live_recording(state: &AppState, sample_rate: u32) -> usize
             loading_id: None,
             min_display_freq: None,
             max_display_freq: None,
+            zc_dots: None,
         });
     });
 
@@ -185,6 +188,7 @@ pub(crate) fn start_live_armed(state: &AppState, sample_rate: u32) -> usize {
             guano: None,
             data_offset: None,
             data_size: None,
+            time_expansion: 1.0,
         },
     };
 
@@ -226,6 +230,7 @@ pub(crate) fn start_live_armed(state: &AppState, sample_rate: u32) -> usize {
             loading_id: None,
             min_display_freq: None,
             max_display_freq: None,
+            zc_dots: None,
         });
     });
 
@@ -253,11 +258,12 @@ pub(crate) fn set_live_recording_zoom(state: &AppState, sample_rate: u32) {
 
 /// True when the file at `idx` looks like an armed-but-empty live doc — no
 /// samples written, neither listening nor recording. Used to decide whether
-/// Listen/Record should reuse it instead of creating a new file.
+/// Listen/Record should reuse it instead of creating a new file. (Anabat
+/// files also have no samples in memory, but carry their dots.)
 pub(crate) fn is_armed_live_doc(state: &AppState, idx: usize) -> bool {
     state.files.with_untracked(|files| {
         files.get(idx).map_or(false, |f| {
-            !f.is_recording && !f.is_live_listen && f.audio.samples.is_empty()
+            !f.is_recording && !f.is_live_listen && f.audio.samples.is_empty() && f.zc_dots.is_none()
         })
     })
 }
@@ -291,13 +297,14 @@ pub(crate) fn promote_armed_to_listening(state: &AppState, idx: usize) {
 pub(crate) fn promote_armed_to_recording(state: &AppState, idx: usize) {
     let now = js_sys::Date::new_0();
     let new_name = format!(
-        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.wav",
+        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.{}",
         now.get_full_year(),
         now.get_month() + 1,
         now.get_date(),
         now.get_hours(),
         now.get_minutes(),
         now.get_seconds(),
+        state.record_format.get_untracked().extension(),
     );
     state.files.update(|files| {
         if let Some(f) = files.get_mut(idx) {
@@ -355,6 +362,7 @@ pub(crate) fn start_live_listening(state: &AppState, sample_rate: u32) -> usize
             guano: None,
             data_offset: None,
             data_size: None,
+            time_expansion: 1.0,
         },
     };
 
@@ -396,6 +404,7 @@ pub(crate) fn start_live_listening(state: &AppState, sample_rate: u32) -> usize
             loading_id: None,
             min_display_freq: None,
             max_display_freq: None,
+            zc_dots: None,
         });
     });
 
@@ -462,13 +471,14 @@ pub(crate) fn convert_listen_to_recording(state: &AppState, sample_rate: u32) ->
         js_sys::Date::now() - preroll_ms,
     ));
     let name = format!(
-        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.wav",
+        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.{}",
         ts.get_full_year(),
         ts.get_month() + 1,
         ts.get_date(),
         ts.get_hours(),
         ts.get_minutes(),
         ts.get_seconds(),
+        state.record_format.get_untracked().extension(),
     );
 
     state.files.update(|files| {
@@ -921,7 +931,7 @@ fn update_or_create_file(
 
         (idx, name)
     } else {
-        let name = generate_recording_name();
+        let name = generate_recording_name(&state);
         let total_cols = if audio.samples.len() >= 2048 {
             (audio.samples.len() - 2048) / 512 + 1
         } else { 0 };
@@ -964,6 +974,7 @@ fn update_or_create_file(
                 loading_id: None,
                 min_display_freq: None,
                 max_display_freq: None,
+                zc_dots: None,
             });
         });
         state.current_file_index.set(Some(idx));
@@ -989,18 +1000,19 @@ fn persist_and_identify(
     file_index: usize,
     filename: String,
     wav_bytes: Vec<u8>,
+    data_offset: u64,
     audio_data_size: u64,
     needs_save: bool,
-    is_mobile: bool,
 ) {
     let exact_file_size = wav_bytes.len() as u64;
+    let is_mobile = state.is_mobile.get_untracked();
 
     // If we also need to save, clone the bytes before identity computation consumes them.
     let wav_bytes_for_save = if needs_save { Some(wav_bytes.clone()) } else { None };
 
     crate::file_identity::start_identity_computation(
         state, file_index, filename.clone(), exact_file_size, Some(wav_bytes),
-        Some(44), Some(audio_data_size), None,
+        Some(data_offset), Some(audio_data_size), None,
     );
 
     if let Some(wav_data) = wav_bytes_for_save {
@@ -1039,6 +1051,26 @@ pub(crate) fn finalize_recording(params: FinalizeParams, state: AppState) {
     if samples.is_empty() && has_native_path {
         state.mic_live_file_idx.set(None);
         live_waterfall::clear();
+        // FLAC recordings were re-encoded from the streamed WAV at stop;
+        // the live entry can't be patched into a WAV stream, so reload the
+        // finished file through the regular native loader instead.
+        if saved_path.ends_with(".flac") {
+            if let Some(idx) = live_idx {
+                state.files.update(|files| {
+                    if idx < files.len() { files.remove(idx); }
+                });
+            }
+            let name = saved_path.rsplit(['/', '\\']).next().unwrap_or(&saved_path).to_string();
+            let load_id = state.loading_start(&name);
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(e) = crate::components::file_sidebar::load_native_file(saved_path, state, load_id).await {
+                    log::error!("FLAC recording load failed: {}", e);
+                    state.show_error_toast(format!("Recording save succeeded but load failed: {}", e));
+                }
+                state.loading_done(load_id);
+            });
+            return;
+        }
         let path = saved_path.clone();
         let live_idx_for_async = live_idx;
         let fsize = file_size.unwrap_or(0) as u64;
@@ -1090,10 +1122,10 @@ pub(crate) fn finalize_recording(params: FinalizeParams, state: AppState) {
     // ── Phase 1: Build metadata (GUANO + WAV markers) from state ────────
     let recording_name = live_idx
         .and_then(|idx| state.files.with_untracked(|f| f.get(idx).map(|f| f.name.clone())))
-        .unwrap_or_else(generate_recording_name);
+        .unwrap_or_else(|| generate_recording_name(&state));
     let meta = build_recording_meta(&state, sample_rate, duration_secs, &recording_name);
 
-    // ── Phase 2: Encode WAV bytes (single pass for size, hash, and save) ─
+    // ── Phase 2: Encode WAV/FLAC bytes (single pass for size, hash, and save)
     let samples: Arc<Vec<f32>> = samples.into();
     let source = Arc::new(InMemorySource {
         samples: samples.clone(),
@@ -1101,12 +1133,15 @@ pub(crate) fn finalize_recording(params: FinalizeParams, state: AppState) {
         sample_rate,
         channels: 1,
     });
-    let wav_bytes = crate::audio::wav_encoder::encode_wav_complete(
-        &samples, sample_rate, Some(&meta.guano), &meta.wav_markers,
+    let wav_bytes = crate::audio::wav_encoder::encode_recording(
+        &samples, sample_rate, bits_per_sample, &recording_name, Some(&meta.guano), &meta.wav_markers,
     );
     let exact_file_size = file_size.unwrap_or(wav_bytes.len());
     let num_samples = samples.len() as u64;
-    let audio_data_size = num_samples * (bits_per_sample as u64 / 8);
+    let (data_offset, audio_data_size) = match crate::audio::loader::parse_flac_header(&wav_bytes) {
+        Ok(h) => (h.first_frame_offset, (wav_bytes.len() as u64).saturating_sub(h.first_frame_offset)),
+        Err(_) => (44, num_samples * (bits_per_sample as u64 / 8)),
+    };
 
     let audio = AudioData {
         samples,
@@ -1120,8 +1155,9 @@ pub(crate) fn finalize_recording(params: FinalizeParams, state: AppState) {
             bits_per_sample,
             is_float,
             guano: Some(meta.guano),
-            data_offset: Some(44),
+            data_offset: Some(data_offset),
             data_size: Some(audio_data_size),
+            time_expansion: 1.0,
         },
     };
 
@@ -1166,7 +1202,7 @@ pub(crate) fn finalize_recording(params: FinalizeParams, state: AppState) {
 
     persist_and_identify(
         state, file_index, name_check.clone(), wav_bytes,
-        audio_data_size, needs_save, is_mobile,
+        data_offset, audio_data_size, needs_save,
     );
 
     // ── Phase 5: Reset preroll + zoom + spectrogram ─────────────────────
@@ -1223,12 +1259,7 @@ async fn finalize_streaming_tauri_recording(
     let head_pcm_bytes = crate::tauri_bridge::read_file_range(
         &path, header.data_offset, head_byte_len,
     ).await?;
-    let head_interleaved = decode_head_pcm(
-        &head_pcm_bytes,
-        header.bits_per_sample,
-        header.is_float,
-        header.channels,
-    );
+    let head_interleaved = decode_head_pcm(&head_pcm_bytes, &header);
     let channels = header.channels as usize;
     let (head_mono, head_raw) = if channels == 1 {
         (head_interleaved, None)
@@ -1248,7 +1279,7 @@ async fn finalize_streaming_tauri_recording(
         if data_end < file_size {
             let tail_len = (file_size - data_end).min(65536);
             if let Ok(tail_bytes) = crate::tauri_bridge::read_file_range(&path, data_end, tail_len).await {
-                guano = scan_tail_for_guano(&tail_bytes);
+                guano = scan_tail_for_guano(&tail_bytes, false);
             }
         }
     }
@@ -1276,6 +1307,7 @@ async fn finalize_streaming_tauri_recording(
             guano,
             data_offset: Some(header.data_offset),
             data_size: Some(header.data_size),
+            time_expansion: 1.0,
         },
     };
     let audio_for_stft = audio.clone();
@@ -1316,22 +1348,23 @@ async fn finalize_streaming_tauri_recording(
     // and future sidecar resolution stay consistent.
     crate::file_identity::start_identity_computation(
         state, file_index, name, file_size, None,
-        Some(44), Some(header.data_size), None,
+        Some(header.data_offset), Some(header.data_size), None,
     );
 
     Ok(())
 }
 
-fn generate_recording_name() -> String {
+fn generate_recording_name(state: &AppState) -> String {
     let now = js_sys::Date::new_0();
     format!(
-        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.wav",
+        "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.{}",
         now.get_full_year(),
         now.get_month() + 1,
         now.get_date(),
         now.get_hours(),
         now.get_minutes(),
         now.get_seconds(),
+        state.record_format.get_untracked().extension(),
     )
 }
 
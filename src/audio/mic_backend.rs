@@ -116,6 +116,7 @@ fn build_stop_recording_args(state: &AppState) -> JsValue {
     if state.mic_preroll_samples.get_untracked() > 0 {
         let _ = js_sys::Reflect::set(&args, &JsValue::from_str("skipNativeSave"), &JsValue::TRUE);
     }
+    let _ = js_sys::Reflect::set(&args, &JsValue::from_str("recordFormat"), &JsValue::from_str(state.record_format.get_untracked().extension()));
     args.into()
 }
 
@@ -650,9 +651,10 @@ async fn try_create_shared_fd(state: &AppState) -> Option<i32> {
         .unwrap_or_else(|| {
             let now = js_sys::Date::new_0();
             format!(
-                "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.wav",
+                "batcap_{:04}{:02}{:02}_{:02}{:02}{:02}.{}",
                 now.get_full_year(), now.get_month() + 1, now.get_date(),
                 now.get_hours(), now.get_minutes(), now.get_seconds(),
+                state.record_format.get_untracked().extension(),
             )
         });
 
@@ -1,5 +1,5 @@
 // Re-export modules from oversample-core.
-pub use oversample_core::audio::{source, guano, loader};
+pub use oversample_core::audio::{source, guano, anabat, flac, loader, riff, bwf, audiomoth, aiff, caf, wavpack, time_expansion};
 
 pub mod browser_decode;
 pub mod export;
@@ -1,6 +1,6 @@
 use leptos::prelude::*;
 use crate::annotations::AnnotationKind;
-use crate::state::{ActiveFocus, AppState, GainMode, Selection, PlaybackMode};
+use crate::state::{ActiveFocus, AppState, GainMode, Selection};
 use crate::audio::streaming_playback::{self, PlaybackParams};
 use crate::audio::source::{AudioSource, TimelineAudioSource};
 use crate::viewport;
@@ -221,7 +221,7 @@ pub fn replay_live(state: &AppState) {
     let remaining_duration = (end_sample - start_sample) as f64 / sr as f64;
     let channel_view = state.channel_view.get_untracked();
 
-    let Some(_) = streaming_playback::start_stream(
+    let Some(final_rate) = streaming_playback::start_stream(
         target.source,
         channel_view,
         sr,
@@ -233,15 +233,7 @@ pub fn replay_live(state: &AppState) {
         return;
     };
 
-    // Compute correct playback speed for the current mode
-    let te_factor = state.te_factor.get_untracked();
-    let playback_speed = match state.playback_mode.get_untracked() {
-        PlaybackMode::TimeExpansion => {
-            let abs_f = te_factor.abs().max(1.0);
-            if te_factor > 0.0 { 1.0 / abs_f } else { abs_f }
-        }
-        _ => 1.0,
-    };
+    let playback_speed = final_rate as f64 / sr as f64;
 
     start_playhead(*state, current_time, remaining_duration, playback_speed);
 }
@@ -347,7 +339,7 @@ fn play_from_time_inner(state: &AppState, start_secs: f64, selection: Option<Sel
     let params = snapshot_params(state, selection, sr);
     let channel_view = state.channel_view.get_untracked();
 
-    let Some(_) = streaming_playback::start_stream(
+    let Some(final_rate) = streaming_playback::start_stream(
         target.source,
         channel_view,
         sr,
@@ -360,14 +352,7 @@ fn play_from_time_inner(state: &AppState, start_secs: f64, selection: Option<Sel
     };
 
     let play_duration = (end_sample - start_sample) as f64 / sr as f64;
-    let te_factor = state.te_factor.get_untracked();
-    let playback_speed = match state.playback_mode.get_untracked() {
-        PlaybackMode::TimeExpansion => {
-            let abs_f = te_factor.abs().max(1.0);
-            if te_factor > 0.0 { 1.0 / abs_f } else { abs_f }
-        }
-        _ => 1.0,
-    };
+    let playback_speed = final_rate as f64 / sr as f64;
 
     state.active_playback_selection.set(selection);
     state.is_playing.set(true);
@@ -403,7 +388,7 @@ pub fn play(state: &AppState) {
     let play_duration = (end_sample - start_sample) as f64 / sr as f64;
     let channel_view = state.channel_view.get_untracked();
 
-    let Some(_) = streaming_playback::start_stream(
+    let Some(final_rate) = streaming_playback::start_stream(
         target.source,
         channel_view,
         sr,
@@ -412,14 +397,7 @@ pub fn play(state: &AppState) {
         params,
     ) else { return };
 
-    let te_factor = state.te_factor.get_untracked();
-    let playback_speed = match state.playback_mode.get_untracked() {
-        PlaybackMode::TimeExpansion => {
-            let abs_f = te_factor.abs().max(1.0);
-            if te_factor > 0.0 { 1.0 / abs_f } else { abs_f }
-        }
-        _ => 1.0,
-    };
+    let playback_speed = final_rate as f64 / sr as f64;
 
     state.push_nav(); // save pre-play position so the back button can return here
     state.pre_play_scroll.set(state.scroll_offset.get_untracked());
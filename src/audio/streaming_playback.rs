@@ -189,7 +189,9 @@ pub(crate) fn start_stream(
             };
             (rate as u32).clamp(8000, 384_000)
         }
-        _ => sample_rate,
+        // A time-expanded recording is audible as stored: play it at the
+        // recorded rate, not the real-world one
+        _ => crate::audio::time_expansion::base_source(source.as_ref()).sample_rate(),
     };
 
     // Stereo output: stereo source + Stereo view (all modes, not just Normal)
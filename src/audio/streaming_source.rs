@@ -37,6 +37,10 @@ struct WavFormatInfo {
     channels: u32,
     bits_per_sample: u16,
     is_float: bool,
+    /// AIFF and most CAF files store samples big-endian.
+    big_endian: bool,
+    /// WAV's 8-bit samples are offset-binary; AIFF-sowt and CAF are signed.
+    unsigned_8bit: bool,
     data_offset: u64,
     bytes_per_frame: u32, // channels * (bits_per_sample / 8)
 }
@@ -212,6 +216,8 @@ impl StreamingWavSource {
                 channels: header.channels as u32,
                 bits_per_sample: header.bits_per_sample,
                 is_float: header.is_float,
+                big_endian: header.big_endian,
+                unsigned_8bit: header.unsigned_8bit,
                 data_offset: header.data_offset,
                 bytes_per_frame: header.channels as u32 * (header.bits_per_sample as u32 / 8),
             },
@@ -483,7 +489,13 @@ impl AudioSource for StreamingWavSource {
 
 /// Decode raw PCM bytes into interleaved f32 samples.
 fn decode_pcm_bytes(bytes: &[u8], info: &WavFormatInfo) -> Vec<f32> {
+    if info.big_endian {
+        return crate::audio::loader::decode_pcm(bytes, info.bits_per_sample, info.is_float, true);
+    }
     match (info.is_float, info.bits_per_sample) {
+        (false, 8) if info.unsigned_8bit => {
+            bytes.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect()
+        }
         (true, 32) => {
             bytes
                 .chunks_exact(4)
@@ -516,14 +528,8 @@ fn decode_pcm_bytes(bytes: &[u8], info: &WavFormatInfo) -> Vec<f32> {
                 .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / max)
                 .collect()
         }
-        _ => {
-            log::warn!(
-                "Unsupported PCM format: {}bit {}",
-                info.bits_per_sample,
-                if info.is_float { "float" } else { "int" }
-            );
-            vec![0.0; bytes.len() / (info.bits_per_sample as usize / 8)]
-        }
+        // Signed 8-bit (AIFF-sowt, little-endian CAF) and 64-bit float
+        _ => crate::audio::loader::decode_pcm(bytes, info.bits_per_sample, info.is_float, false),
     }
 }
 
@@ -1002,10 +1008,11 @@ impl AudioSource for StreamingFlacSource {
 
 // ─── Streaming helpers (format-agnostic) ────────────────────────────────────
 
-/// Prefetch a sample region from a streaming source (WAV, FLAC, MP3, or OGG).
-/// No-op for in-memory sources.
+/// Prefetch a sample region from a streaming source (WAV, FLAC, MP3, or OGG),
+/// looking through any time-expansion wrapper. No-op for in-memory sources.
 /// Returns `(did_seek_skip, is_vbr)` for MP3 sources; `(false, false)` otherwise.
 pub async fn prefetch_streaming(source: &dyn AudioSource, start: u64, len: usize) -> (bool, bool) {
+    let source = crate::audio::time_expansion::base_source(source);
     if let Some(s) = source.as_any().downcast_ref::<StreamingWavSource>() {
         s.prefetch_region(start, len).await;
     } else if let Some(s) = source.as_any().downcast_ref::<StreamingFlacSource>() {
@@ -1026,6 +1033,7 @@ pub async fn prefetch_streaming(source: &dyn AudioSource, start: u64, len: usize
 
 /// Check if a source is a streaming (non-in-memory) source.
 pub fn is_streaming(source: &dyn AudioSource) -> bool {
+    let source = crate::audio::time_expansion::base_source(source);
     source.as_any().downcast_ref::<StreamingWavSource>().is_some()
         || source.as_any().downcast_ref::<StreamingFlacSource>().is_some()
         || source.as_any().downcast_ref::<StreamingMp3Source>().is_some()
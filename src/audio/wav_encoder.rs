@@ -5,78 +5,7 @@ use wasm_bindgen_futures::JsFuture;
 
 use crate::types::WavMarker;
 
-/// Build WAV `cue ` and `LIST`/`adtl` chunks for the given markers.
-/// Returns the raw bytes to insert into the RIFF stream (before the GUANO chunk).
-pub fn encode_wav_cue_chunks(markers: &[WavMarker]) -> Vec<u8> {
-    if markers.is_empty() {
-        return Vec::new();
-    }
-
-    let mut buf = Vec::new();
-
-    // ── cue chunk ──
-    // cue chunk body: u32 num_cue_points, then per point:
-    //   u32 id, u32 position, 4-byte data_id ("data"), u32 chunk_start, u32 block_start, u32 sample_offset
-    let num_points = markers.len() as u32;
-    let cue_body_size = 4 + num_points * 24;
-    buf.extend_from_slice(b"cue ");
-    buf.extend_from_slice(&cue_body_size.to_le_bytes());
-    buf.extend_from_slice(&num_points.to_le_bytes());
-    for m in markers {
-        buf.extend_from_slice(&m.id.to_le_bytes());          // id
-        buf.extend_from_slice(&(m.position as u32).to_le_bytes()); // position
-        buf.extend_from_slice(b"data");                       // fcc_chunk
-        buf.extend_from_slice(&0u32.to_le_bytes());           // chunk_start
-        buf.extend_from_slice(&0u32.to_le_bytes());           // block_start
-        buf.extend_from_slice(&(m.position as u32).to_le_bytes()); // sample_offset
-    }
-
-    // ── LIST/adtl chunk with labl and note sub-chunks ──
-    let mut adtl_body = Vec::new();
-    adtl_body.extend_from_slice(b"adtl");
-    for m in markers {
-        if let Some(ref label) = m.label {
-            let text_bytes = label.as_bytes();
-            // labl sub-chunk: u32 cue_id + null-terminated string
-            let sub_size = 4 + text_bytes.len() as u32 + 1; // +1 for null terminator
-            adtl_body.extend_from_slice(b"labl");
-            adtl_body.extend_from_slice(&sub_size.to_le_bytes());
-            adtl_body.extend_from_slice(&m.id.to_le_bytes());
-            adtl_body.extend_from_slice(text_bytes);
-            adtl_body.push(0); // null terminator
-            // RIFF word-alignment padding
-            if sub_size % 2 != 0 {
-                adtl_body.push(0);
-            }
-        }
-        if let Some(ref note) = m.note {
-            let text_bytes = note.as_bytes();
-            let sub_size = 4 + text_bytes.len() as u32 + 1;
-            adtl_body.extend_from_slice(b"note");
-            adtl_body.extend_from_slice(&sub_size.to_le_bytes());
-            adtl_body.extend_from_slice(&m.id.to_le_bytes());
-            adtl_body.extend_from_slice(text_bytes);
-            adtl_body.push(0);
-            if sub_size % 2 != 0 {
-                adtl_body.push(0);
-            }
-        }
-    }
-
-    // Only write the LIST chunk if there are labl/note sub-chunks
-    if adtl_body.len() > 4 {
-        let list_size = adtl_body.len() as u32;
-        buf.extend_from_slice(b"LIST");
-        buf.extend_from_slice(&list_size.to_le_bytes());
-        buf.extend_from_slice(&adtl_body);
-        // Word-align the LIST chunk
-        if list_size % 2 != 0 {
-            buf.push(0);
-        }
-    }
-
-    buf
-}
+pub use crate::audio::loader::encode_wav_cue_chunks;
 
 /// Insert cue marker chunks into an already-encoded WAV byte buffer.
 /// The chunks are inserted just before the GUANO chunk (if present) or at end of RIFF.
@@ -166,6 +95,29 @@ pub fn encode_wav_complete(
     wav_data
 }
 
+/// Encode a recording in the format its filename asks for: FLAC for `.flac`
+/// names (24-bit when the recording is deeper than 16 bits, else 16-bit),
+/// otherwise the same WAV as `encode_wav_complete`. Falls back to WAV if
+/// FLAC encoding fails.
+pub fn encode_recording(
+    samples: &[f32],
+    sample_rate: u32,
+    bits_per_sample: u16,
+    filename: &str,
+    guano: Option<&crate::audio::guano::GuanoMetadata>,
+    markers: &[WavMarker],
+) -> Vec<u8> {
+    let ext = filename.rsplit('.').next().unwrap_or_default();
+    if crate::state::RecordFormat::from_extension(ext) == crate::state::RecordFormat::Flac {
+        let bits = if bits_per_sample > 16 { 24 } else { 16 };
+        match crate::audio::flac::encode_flac(samples, sample_rate, bits, guano, markers) {
+            Ok(bytes) => return bytes,
+            Err(e) => log::warn!("FLAC encode failed, saving WAV instead: {}", e),
+        }
+    }
+    encode_wav_complete(samples, sample_rate, guano, markers)
+}
+
 /// Trigger a browser download of raw WAV bytes.
 pub(crate) fn trigger_browser_wav_download(wav_data: &[u8], filename: &str) {
     let array = js_sys::Uint8Array::new_with_length(wav_data.len() as u32);
@@ -204,16 +156,18 @@ pub(crate) fn trigger_browser_wav_download(wav_data: &[u8], filename: &str) {
     web_sys::Url::revoke_object_url(&url).ok();
 }
 
-/// Build and download a recording WAV preserving all metadata (GUANO + cue markers).
-pub fn download_recording_wav(
+/// Build and download a recording (WAV or FLAC, by filename) preserving all
+/// metadata (GUANO + cue markers).
+pub fn download_recording(
     samples: &[f32],
     sample_rate: u32,
+    bits_per_sample: u16,
     filename: &str,
     guano: Option<&crate::audio::guano::GuanoMetadata>,
     markers: &[WavMarker],
 ) {
-    let wav_data = encode_wav_complete(samples, sample_rate, guano, markers);
-    trigger_browser_wav_download(&wav_data, filename);
+    let data = encode_recording(samples, sample_rate, bits_per_sample, filename, guano, markers);
+    trigger_browser_wav_download(&data, filename);
 }
 
 /// Save WAV bytes directly to shared storage (Recordings/Oversample)
@@ -0,0 +1,217 @@
+use crate::audio::source::ChannelView;
+use crate::audio::streaming_source;
+use crate::dsp::bat_passes::{self, ActivitySummary, BatPass, PassParams, TimedPulse};
+use crate::dsp::pulse_detect::{self, DetectedPulse, PulseDetectionParams};
+use crate::project::BatProject;
+use crate::state::LoadedFile;
+
+/// Seconds of a streamed file run through pulse detection at once (the
+/// detection threshold adapts per chunk).
+const PULSE_CHUNK_SECS: f64 = 30.0;
+
+/// Audio read either side of each chunk so calls crossing its edges are seen
+/// whole. Longer than any pulse `PulseDetectionParams` accepts.
+const PULSE_CHUNK_PAD_SECS: f64 = 0.1;
+
+/// Pass segmentation + activity summary for a set of files (e.g. one night's sequence).
+#[derive(Clone, Debug)]
+pub struct SequenceActivity {
+    pub passes: Vec<BatPass>,
+    pub summary: ActivitySummary,
+    /// Local UTC offset used for hour/night bucketing (minutes).
+    pub utc_offset_minutes: i32,
+    pub files_analyzed: usize,
+    /// Files left out because no recording start time could be resolved.
+    pub files_without_time: usize,
+    /// Project files left out because they aren't loaded.
+    pub files_not_loaded: usize,
+}
+
+/// Recording start (ms since epoch) for a loaded file, honouring the project's
+/// per-file override and time offset when the file belongs to the project.
+pub fn file_start_epoch_ms(file: &LoadedFile, project: Option<&BatProject>) -> Option<f64> {
+    let detected = file.recording_start_epoch_ms();
+    let proj_idx = project.zip(file.identity.as_ref())
+        .and_then(|(proj, id)| proj.find_file(id));
+    match (project, proj_idx) {
+        (Some(proj), Some(idx)) => proj.file_start_epoch_ms(idx, detected),
+        _ => detected,
+    }
+}
+
+/// Pulses in a loaded file. Streamed files have no whole-file spectrogram and
+/// only hold the parts viewed so far, so they are read from the audio source
+/// chunk by chunk (their pulses have no peak frequency).
+pub async fn detect_file_pulses(file: &LoadedFile, params: &PulseDetectionParams) -> Vec<DetectedPulse> {
+    let source = file.audio.source.as_ref();
+    if !streaming_source::is_streaming(source) {
+        return pulse_detect::detect_pulses(&file.audio, &file.spectrogram, params);
+    }
+    let sr = file.audio.sample_rate;
+    let mut pulses = Vec::new();
+    for chunk in pulse_chunks(source.total_samples(), sr) {
+        streaming_source::prefetch_streaming(source, chunk.read_start, chunk.read_len).await;
+        let samples = source.read_region(ChannelView::MonoMix, chunk.read_start, chunk.read_len);
+        chunk.take_pulses(&mut pulses, pulse_detect::detect_pulses_in_samples(&samples, sr, None, params), sr);
+    }
+    pulses
+}
+
+/// One step of chunked pulse detection: the padded sample range to read and
+/// the part of it whose pulses the chunk keeps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PulseChunk {
+    read_start: u64,
+    read_len: usize,
+    own_start: u64,
+    own_end: u64,
+}
+
+impl PulseChunk {
+    /// Add the pulses found in this chunk's samples that start in the part it
+    /// owns to `out`, in file time and numbered on from `out`. Pulses
+    /// starting in the padding belong to the neighbouring chunk.
+    fn take_pulses(&self, out: &mut Vec<DetectedPulse>, found: Vec<DetectedPulse>, sr: u32) {
+        let offset = self.read_start as f64 / sr as f64;
+        let (own_start, own_end) = (self.own_start as f64 / sr as f64, self.own_end as f64 / sr as f64);
+        for mut p in found {
+            p.start_time += offset;
+            if p.start_time < own_start || p.start_time >= own_end {
+                continue;
+            }
+            p.end_time += offset;
+            p.peak_time += offset;
+            p.index = out.len() + 1;
+            out.push(p);
+        }
+    }
+}
+
+/// Split `total` samples into padded chunks for pulse detection.
+fn pulse_chunks(total: u64, sr: u32) -> Vec<PulseChunk> {
+    let chunk = ((PULSE_CHUNK_SECS * sr as f64) as u64).max(1);
+    let pad = (PULSE_CHUNK_PAD_SECS * sr as f64) as u64;
+    (0..total).step_by(chunk as usize).map(|own_start| {
+        let own_end = (own_start + chunk).min(total);
+        let read_start = own_start.saturating_sub(pad);
+        let read_end = (own_end + pad).min(total);
+        PulseChunk { read_start, read_len: (read_end - read_start) as usize, own_start, own_end }
+    }).collect()
+}
+
+/// Local UTC offset (minutes, east positive) at the given time, from the browser.
+pub fn local_utc_offset_minutes(epoch_ms: f64) -> i32 {
+    let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(epoch_ms));
+    -(date.get_timezone_offset() as i32)
+}
+
+/// Build the activity summary from per-file pulse lists.
+///
+/// `per_file` holds `(file_index, start_epoch_ms, duration_secs, pulses)`;
+/// files with no resolved start time are counted but skipped.
+/// `files_not_loaded` counts the files that couldn't be analysed at all.
+pub fn sequence_activity(
+    per_file: &[(usize, Option<f64>, f64, Vec<DetectedPulse>)],
+    files_not_loaded: usize,
+    params: &PassParams,
+) -> SequenceActivity {
+    let mut timed = Vec::new();
+    let mut spans = Vec::new();
+    let mut files_analyzed = 0;
+    let mut files_without_time = 0;
+
+    for (file_index, start_ms, duration_secs, pulses) in per_file {
+        let Some(start_ms) = *start_ms else {
+            files_without_time += 1;
+            continue;
+        };
+        files_analyzed += 1;
+        spans.push((start_ms, start_ms + duration_secs * 1000.0));
+        timed.extend(pulses.iter().map(|p| TimedPulse {
+            source: *file_index,
+            start_epoch_ms: start_ms + p.start_time * 1000.0,
+            end_epoch_ms: start_ms + p.end_time * 1000.0,
+        }));
+    }
+
+    let utc_offset_minutes = spans.first().map(|(s, _)| local_utc_offset_minutes(*s)).unwrap_or(0);
+    let passes = bat_passes::segment_passes(&timed, params);
+    let summary = bat_passes::activity_summary(&timed, &passes, &spans, utc_offset_minutes);
+    SequenceActivity { passes, summary, utc_offset_minutes, files_analyzed, files_without_time, files_not_loaded }
+}
+
+/// Format an epoch-ms time as local "YYYY-MM-DD HH:MM".
+pub fn format_local_datetime(epoch_ms: f64) -> String {
+    let d = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(epoch_ms));
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        d.get_full_year(), d.get_month() + 1, d.get_date(), d.get_hours(), d.get_minutes(),
+    )
+}
+
+/// Tab-separated per-hour table (with a per-night block) for spreadsheets.
+pub fn activity_tsv(activity: &SequenceActivity) -> String {
+    let mut out = String::from("hour_start\tpasses\tpulses\tactive_minutes\trecorded_minutes\n");
+    for h in &activity.summary.hours {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{:.1}\n",
+            format_local_datetime(h.hour_start_epoch_ms), h.passes, h.pulses, h.active_minutes, h.recorded_minutes,
+        ));
+    }
+    out.push_str("\nnight_start\tpasses\tpulses\tactive_minutes\trecorded_minutes\tpasses_per_hour\n");
+    for n in &activity.summary.nights {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{:.1}\t{:.2}\n",
+            format_local_datetime(n.night_start_epoch_ms), n.passes, n.pulses, n.active_minutes,
+            n.recorded_minutes, n.passes_per_hour,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pulse(start_time: f64) -> DetectedPulse {
+        DetectedPulse {
+            index: 1,
+            start_time,
+            end_time: start_time + 0.005,
+            peak_time: start_time + 0.002,
+            peak_freq: 0.0,
+            snr_db: 30.0,
+            peak_amplitude: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_pulse_chunks_cover_the_file_once() {
+        let sr = 1000;
+        let chunks = pulse_chunks(70_000, sr);
+        let owned: Vec<(u64, u64)> = chunks.iter().map(|c| (c.own_start, c.own_end)).collect();
+        assert_eq!(owned, [(0, 30_000), (30_000, 60_000), (60_000, 70_000)]);
+        let read: Vec<(u64, usize)> = chunks.iter().map(|c| (c.read_start, c.read_len)).collect();
+        assert_eq!(read, [(0, 30_100), (29_900, 30_200), (59_900, 10_100)]);
+        assert!(pulse_chunks(0, sr).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_keeps_pulses_starting_in_its_own_span() {
+        let sr = 1000;
+        let chunk = pulse_chunks(70_000, sr)[1];
+        let mut out = vec![pulse(10.0)];
+        let found = vec![
+            pulse(0.0),    // tail of a call from the previous chunk (29.9 s)
+            pulse(0.15),   // 30.05 s
+            pulse(30.05),  // 59.95 s, runs into the padding
+            pulse(30.15),  // 60.05 s, the next chunk's
+        ];
+        chunk.take_pulses(&mut out, found, sr);
+        let starts: Vec<f64> = out.iter().map(|p| (p.start_time * 1000.0).round() / 1000.0).collect();
+        assert_eq!(starts, [10.0, 30.05, 59.95]);
+        assert_eq!(out.iter().map(|p| p.index).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!((out[2].end_time - 59.955).abs() < 1e-9);
+        assert!((out[2].peak_time - 59.952).abs() < 1e-9);
+    }
+}
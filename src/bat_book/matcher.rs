@@ -0,0 +1,331 @@
+//! Rank bat book entries against measured calls.
+//!
+//! A suggestion aid only: scores each entry of the regional book by how well
+//! the detected calls' characteristic frequency and shape fit the entry's
+//! published range and call type, and explains why. It does not identify
+//! species.
+
+use super::auto_resolve::resolve_auto;
+use super::data::get_manifest;
+use super::types::{AutoResolved, BatBookEntry, BatBookManifest, BatBookRegion};
+use crate::dsp::call_params::CallParameters;
+use crate::dsp::call_shape::{CallShape, ShapeClassification};
+use crate::state::LoadedFile;
+
+/// Score falls to ~37% this far (Hz) outside an entry's frequency range.
+const FREQ_FALLOFF_HZ: f64 = 4000.0;
+/// Relative weight of the shape score against the frequency score.
+const SHAPE_WEIGHT: f64 = 0.4;
+/// Entries scoring below this are not suggested.
+const MIN_SCORE: f64 = 0.15;
+
+/// Summary of the calls being matched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallSummary {
+    pub call_count: usize,
+    /// Median characteristic frequency (Hz).
+    pub f_char_hz: f64,
+    /// Median peak-energy frequency (Hz).
+    pub f_max_energy_hz: f64,
+    /// Most common shape (weighted by confidence) and its share of the calls.
+    pub dominant_shape: Option<(CallShape, f64)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeciesCandidate {
+    pub entry: BatBookEntry,
+    /// 0..1, higher is a better fit.
+    pub score: f64,
+    /// Human-readable reasons, e.g. "Fchar 45 kHz inside 42–48 kHz".
+    pub reasons: Vec<String>,
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Summarise the calls whose start falls inside `time_range` (or all calls).
+pub fn summarize_calls(
+    calls: &[CallParameters],
+    shapes: &[ShapeClassification],
+    time_range: Option<(f64, f64)>,
+) -> Option<CallSummary> {
+    let in_range = |t: f64| time_range.is_none_or(|(lo, hi)| t >= lo && t <= hi);
+    let selected: Vec<&CallParameters> = calls.iter().filter(|c| in_range(c.start_time)).collect();
+    if selected.is_empty() {
+        return None;
+    }
+
+    let mut shape_weights: Vec<(CallShape, f64)> = Vec::new();
+    let mut total_weight = 0.0;
+    for call in &selected {
+        if let Some(s) = shapes.iter().find(|s| s.index == call.index) {
+            total_weight += s.confidence;
+            match shape_weights.iter_mut().find(|(shape, _)| *shape == s.shape) {
+                Some((_, w)) => *w += s.confidence,
+                None => shape_weights.push((s.shape, s.confidence)),
+            }
+        }
+    }
+    let dominant_shape = shape_weights
+        .into_iter()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .filter(|_| total_weight > 0.0)
+        .map(|(shape, w)| (shape, w / total_weight));
+
+    Some(CallSummary {
+        call_count: selected.len(),
+        f_char_hz: median(selected.iter().map(|c| c.f_char_hz).collect()),
+        f_max_energy_hz: median(selected.iter().map(|c| c.f_max_energy_hz).collect()),
+        dominant_shape,
+    })
+}
+
+fn khz(hz: f64) -> String {
+    format!("{:.0}", hz / 1000.0)
+}
+
+fn score_entry(entry: &BatBookEntry, summary: &CallSummary) -> Option<SpeciesCandidate> {
+    if !entry.echolocates || entry.freq_hi_hz <= 0.0 {
+        return None;
+    }
+
+    let mut reasons = Vec::new();
+    let range = format!("{}\u{2013}{} kHz", khz(entry.freq_lo_hz), khz(entry.freq_hi_hz));
+
+    // Frequency: Fchar is the usual identification frequency; FmaxE as a second opinion.
+    let distance = |f: f64| {
+        if f < entry.freq_lo_hz {
+            entry.freq_lo_hz - f
+        } else if f > entry.freq_hi_hz {
+            f - entry.freq_hi_hz
+        } else {
+            0.0
+        }
+    };
+    let d_char = distance(summary.f_char_hz);
+    let d_energy = distance(summary.f_max_energy_hz);
+    let freq_score = (-d_char / FREQ_FALLOFF_HZ).exp() * 0.75 + (-d_energy / FREQ_FALLOFF_HZ).exp() * 0.25;
+    if d_char == 0.0 {
+        reasons.push(format!("Fchar {} kHz inside {}", khz(summary.f_char_hz), range));
+    } else {
+        let side = if summary.f_char_hz < entry.freq_lo_hz { "below" } else { "above" };
+        reasons.push(format!(
+            "Fchar {} kHz is {:.1} kHz {} {}",
+            khz(summary.f_char_hz), d_char / 1000.0, side, range
+        ));
+    }
+
+    // Shape: compare the dominant shape with the book's call type.
+    let shape_score = match summary.dominant_shape {
+        Some((shape, share)) => {
+            let compat = shape.book_compatibility(entry.call_type);
+            if compat >= 1.0 {
+                reasons.push(format!("{} shape matches", shape.label()));
+            } else if compat > 0.0 {
+                reasons.push(format!("{} shape partly matches {}", shape.label(), entry.call_type));
+            } else {
+                reasons.push(format!("{} shape, book lists {}", shape.label(), entry.call_type));
+            }
+            compat * share
+        }
+        None => 0.5,
+    };
+
+    let score = freq_score * (1.0 - SHAPE_WEIGHT) + shape_score * SHAPE_WEIGHT;
+    if score < MIN_SCORE {
+        return None;
+    }
+    Some(SpeciesCandidate { entry: entry.clone(), score, reasons })
+}
+
+/// Rank the entries of `manifest` for the given call summary, best first.
+pub fn rank_entries(manifest: &BatBookManifest, summary: &CallSummary) -> Vec<SpeciesCandidate> {
+    let mut candidates: Vec<SpeciesCandidate> = manifest
+        .entries
+        .iter()
+        .filter_map(|e| score_entry(e, summary))
+        .collect();
+    // Species-level entries before family-level ones on ties.
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.entry.scientific_name.is_empty().cmp(&b.entry.scientific_name.is_empty()))
+    });
+    candidates
+}
+
+/// Resolve the regional book for `file` and rank its entries against the calls.
+pub fn rank_for_file(
+    file: Option<&LoadedFile>,
+    favourites: &[BatBookRegion],
+    calls: &[CallParameters],
+    shapes: &[ShapeClassification],
+    time_range: Option<(f64, f64)>,
+) -> (AutoResolved, Option<CallSummary>, Vec<SpeciesCandidate>) {
+    let resolved = resolve_auto(file, favourites);
+    let Some(summary) = summarize_calls(calls, shapes, time_range) else {
+        return (resolved, None, Vec::new());
+    };
+    let manifest = get_manifest(resolved.region);
+    let candidates = rank_entries(&manifest, &summary);
+    (resolved, Some(summary), candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::call_shape::ShapeFeatures;
+
+    fn entry(id: &'static str, scientific_name: &'static str, call_type: &'static str, lo_khz: f64, hi_khz: f64) -> BatBookEntry {
+        BatBookEntry {
+            id,
+            name: id,
+            scientific_name,
+            family: "Vespertilionidae",
+            call_type,
+            freq_lo_hz: lo_khz * 1000.0,
+            freq_hi_hz: hi_khz * 1000.0,
+            description: "",
+            commonness: None,
+            echolocates: true,
+        }
+    }
+
+    fn book(entries: Vec<BatBookEntry>) -> BatBookManifest {
+        BatBookManifest { region: "Test".into(), entries }
+    }
+
+    fn call(index: usize, start_time: f64, f_char_khz: f64, f_max_energy_khz: f64) -> CallParameters {
+        CallParameters {
+            index,
+            start_time,
+            duration_ms: 5.0,
+            f_start_hz: 80_000.0,
+            f_end_hz: f_char_khz * 1000.0,
+            f_max_hz: 80_000.0,
+            f_min_hz: f_char_khz * 1000.0,
+            f_char_hz: f_char_khz * 1000.0,
+            f_knee_hz: f_char_khz * 1000.0,
+            f_max_energy_hz: f_max_energy_khz * 1000.0,
+            bandwidth_hz: 80_000.0 - f_char_khz * 1000.0,
+            mean_slope_khz_per_ms: -1.0,
+            ipi_ms: None,
+            ridge_points: 10,
+        }
+    }
+
+    fn shape(index: usize, shape: CallShape, confidence: f64) -> ShapeClassification {
+        ShapeClassification {
+            index,
+            shape,
+            confidence,
+            features: ShapeFeatures {
+                duration_ms: 5.0,
+                bandwidth_khz: 30.0,
+                mean_abs_slope: 1.0,
+                head_abs_slope: 2.0,
+                cf_fraction: 0.0,
+                flat_fraction: 0.5,
+                tail_flat_fraction: 1.0,
+            },
+        }
+    }
+
+    fn summary(f_char_khz: f64, dominant_shape: Option<(CallShape, f64)>) -> CallSummary {
+        CallSummary {
+            call_count: 3,
+            f_char_hz: f_char_khz * 1000.0,
+            f_max_energy_hz: f_char_khz * 1000.0,
+            dominant_shape,
+        }
+    }
+
+    #[test]
+    fn test_rank_puts_matching_species_first() {
+        let manifest = book(vec![
+            entry("low_fm", "Lowus fm", "FM", 20.0, 30.0),
+            entry("family", "", "FM-QCF", 42.0, 48.0),
+            entry("target", "Targetus qcf", "FM-QCF", 42.0, 48.0),
+            entry("high_cf", "Highus cf", "CF", 80.0, 90.0),
+        ]);
+        let calls = [call(1, 0.1, 45.0, 46.0), call(2, 0.2, 44.5, 45.0), call(3, 0.3, 45.5, 47.0)];
+        let shapes = [shape(1, CallShape::FmQcf, 0.9), shape(2, CallShape::FmQcf, 0.8), shape(3, CallShape::Fm, 0.3)];
+        let summary = summarize_calls(&calls, &shapes, None).unwrap();
+        assert_eq!(summary.call_count, 3);
+        assert_eq!(summary.f_char_hz, 45_000.0);
+        assert_eq!(summary.f_max_energy_hz, 46_000.0);
+        let (dominant, share) = summary.dominant_shape.unwrap();
+        assert_eq!(dominant, CallShape::FmQcf);
+        assert!((share - 1.7 / 2.0).abs() < 1e-9);
+
+        let ranked = rank_entries(&manifest, &summary);
+        let ids: Vec<&str> = ranked.iter().map(|c| c.entry.id).collect();
+        // Species before the equally scored family entry; the CF entry 35 kHz away is dropped.
+        assert_eq!(ids, ["target", "family", "low_fm"]);
+        assert_eq!(ranked[0].reasons[0], "Fchar 45 kHz inside 42\u{2013}48 kHz");
+        assert_eq!(ranked[0].reasons[1], "FM-QCF shape matches");
+        assert_eq!(ranked[2].reasons[0], "Fchar 45 kHz is 15.0 kHz above 20\u{2013}30 kHz");
+    }
+
+    #[test]
+    fn test_call_type_weighting() {
+        let manifest = book(vec![
+            entry("cf", "Cf bat", "CF", 40.0, 50.0),
+            entry("fm", "Fm bat", "FM", 40.0, 50.0),
+            entry("fm_qcf", "Fmqcf bat", "FM-QCF", 40.0, 50.0),
+        ]);
+        // Same frequency fit for all three, so only the call type separates them.
+        let ranked = rank_entries(&manifest, &summary(45.0, Some((CallShape::FmQcf, 1.0))));
+        let scores: Vec<(&str, f64)> = ranked.iter().map(|c| (c.entry.id, c.score)).collect();
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores[0].0, "fm_qcf");
+        assert!((scores[0].1 - 1.0).abs() < 1e-9);
+        assert_eq!(scores[1].0, "fm");
+        assert!((scores[1].1 - (1.0 - SHAPE_WEIGHT + 0.5 * SHAPE_WEIGHT)).abs() < 1e-9);
+        assert_eq!(scores[2].0, "cf");
+        assert!((scores[2].1 - (1.0 - SHAPE_WEIGHT)).abs() < 1e-9);
+        assert_eq!(ranked[1].reasons[1], "FM-QCF shape partly matches FM");
+        assert_eq!(ranked[2].reasons[1], "FM-QCF shape, book lists CF");
+
+        // A less certain shape pulls the matching entry back towards the others.
+        let unsure = rank_entries(&manifest, &summary(45.0, Some((CallShape::FmQcf, 0.5))));
+        assert!((unsure[0].score - (1.0 - SHAPE_WEIGHT + 0.5 * SHAPE_WEIGHT)).abs() < 1e-9);
+
+        // Without a shape every entry gets the neutral shape score.
+        let no_shape = rank_entries(&manifest, &summary(45.0, None));
+        assert!(no_shape.iter().all(|c| (c.score - (1.0 - SHAPE_WEIGHT + 0.5 * SHAPE_WEIGHT)).abs() < 1e-9));
+        assert!(no_shape.iter().all(|c| c.reasons.len() == 1));
+    }
+
+    #[test]
+    fn test_no_calls_or_no_echolocators() {
+        assert_eq!(summarize_calls(&[], &[], None), None);
+        let calls = [call(1, 0.1, 45.0, 46.0), call(2, 2.5, 30.0, 31.0)];
+        assert_eq!(summarize_calls(&calls, &[], Some((1.0, 2.0))), None);
+
+        // Only the call inside the range counts; unclassified calls leave no dominant shape.
+        let in_range = summarize_calls(&calls, &[], Some((2.0, 3.0))).unwrap();
+        assert_eq!(in_range.call_count, 1);
+        assert_eq!(in_range.f_char_hz, 30_000.0);
+        assert_eq!(in_range.dominant_shape, None);
+
+        let mut flying_fox = entry("flying_fox", "Pteropus sp.", "none", 0.0, 0.0);
+        flying_fox.echolocates = false;
+        let mut silent = entry("silent", "Silentus", "FM", 40.0, 50.0);
+        silent.echolocates = false;
+        let manifest = book(vec![flying_fox, silent]);
+        assert!(rank_entries(&manifest, &summary(45.0, None)).is_empty());
+        assert!(rank_entries(&book(Vec::new()), &summary(45.0, None)).is_empty());
+    }
+}
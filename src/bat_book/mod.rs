@@ -3,3 +3,4 @@ pub mod species;
 pub mod data;
 pub mod country_map;
 pub mod auto_resolve;
+pub mod matcher;
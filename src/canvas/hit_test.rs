@@ -88,6 +88,8 @@ pub fn is_in_band_ff_drag_zone(mouse_x: f64, canvas_width: f64) -> bool {
 pub const ANNOTATION_HANDLE_HIT_RADIUS: f64 = 8.0;
 /// Pixel radius for annotation resize handle hit detection (touch/mobile).
 pub const ANNOTATION_HANDLE_HIT_RADIUS_TOUCH: f64 = 22.0;
+/// Pixel distance from a measurement line that counts as a click on it.
+const MEASUREMENT_HIT_DISTANCE: f64 = 5.0;
 
 /// Compute the 8 (or 2 for segments) resize handle positions in pixel space for an annotation.
 /// Returns a list of (handle_position, px_x, px_y).
@@ -188,7 +190,8 @@ pub fn hit_test_annotation_handles(
 }
 
 /// Hit-test annotation bodies (click inside an annotation region).
-/// Prioritizes: (1) label area clicks, (2) smallest-area annotation when overlapping.
+/// Prioritizes: (1) label area clicks and measurement lines, (2) smallest-area
+/// annotation when overlapping.
 /// Returns the annotation ID if the click is inside any annotation.
 pub fn hit_test_annotation_body(
     annotation_set: &AnnotationSet,
@@ -212,6 +215,16 @@ pub fn hit_test_annotation_body(
     for ann in &annotation_set.annotations {
         let region = match &ann.kind {
             AnnotationKind::Region(r) => r,
+            AnnotationKind::Measurement(m) => {
+                let a = ((m.start_time - start_time) * px_per_sec,
+                    spectrogram_renderer::freq_to_y(m.start_freq, min_freq, max_freq, canvas_height));
+                let b = ((m.end_time - start_time) * px_per_sec,
+                    spectrogram_renderer::freq_to_y(m.end_freq, min_freq, max_freq, canvas_height));
+                if distance_to_segment((px_x, px_y), a, b) <= MEASUREMENT_HIT_DISTANCE {
+                    label_hit = Some(ann.id.clone());
+                }
+                continue;
+            }
             _ => continue,
         };
 
@@ -250,6 +263,19 @@ pub fn hit_test_annotation_body(
     label_hit.or_else(|| best_body.map(|(id, _)| id))
 }
 
+/// Distance in pixels from point `p` to the segment `a`–`b`.
+fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - cx).powi(2) + (p.1 - cy).powi(2)).sqrt()
+}
+
 /// Get the pixel positions of resize handles for a specific annotation.
 /// Used by both hit-testing and rendering.
 pub fn get_annotation_handle_positions(
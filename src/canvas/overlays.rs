@@ -772,21 +772,44 @@ pub fn draw_het_overlay(
     }
 }
 
-/// Draw detected pulse markers as vertical bands on the spectrogram.
+/// Draw a call contour polyline. Segment opacity follows the contour level.
+/// `to_xy` maps (time, frequency) to canvas coordinates.
+fn draw_contour(
+    ctx: &CanvasRenderingContext2d,
+    points: &[crate::dsp::contour::ContourPoint],
+    rgb: &str,
+    to_xy: impl Fn(f64, f64) -> (f64, f64),
+) {
+    ctx.set_line_width(1.5);
+    for w in points.windows(2) {
+        let level = w[0].level_db.max(w[1].level_db);
+        let alpha = (1.0 + level as f64 / 30.0).clamp(0.25, 1.0);
+        ctx.set_stroke_style_str(&format!("rgba({rgb}, {alpha:.2})"));
+        ctx.begin_path();
+        let (x0, y0) = to_xy(w[0].time, w[0].freq);
+        let (x1, y1) = to_xy(w[1].time, w[1].freq);
+        ctx.move_to(x0, y0);
+        ctx.line_to(x1, y1);
+        ctx.stroke();
+    }
+}
+
+/// Draw detected pulse markers as vertical bands on the spectrogram,
+/// with each call's traced contour on top.
 pub fn draw_pulses(
     ctx: &CanvasRenderingContext2d,
     pulses: &[crate::dsp::pulse_detect::DetectedPulse],
+    contours: &[crate::dsp::contour::CallContour],
     selected_index: Option<usize>,
-    scroll_offset: f64,
-    time_resolution: f64,
-    zoom: f64,
-    canvas_width: f64,
-    canvas_height: f64,
+    view: &OverlayView,
 ) {
     if pulses.is_empty() {
         return;
     }
 
+    let OverlayView {
+        min_freq, max_freq, scroll_offset, time_resolution, zoom, canvas_width, canvas_height,
+    } = *view;
     let visible_time = (canvas_width / zoom) * time_resolution;
     let start_time = scroll_offset;
     let end_time = start_time + visible_time;
@@ -838,7 +861,45 @@ pub fn draw_pulses(
             ctx.set_text_baseline("top");
             let _ = ctx.fill_text(&format!("{}", pulse.index), x0 + 2.0, 2.0);
         }
+
+        if let Some(c) = contours.iter().find(|c| c.index == pulse.index) {
+            let rgb = if is_selected { "255, 220, 100" } else { "120, 255, 190" };
+            draw_contour(ctx, &c.points, rgb, |t, f| {
+                ((t - start_time) * px_per_sec, freq_to_y(f, min_freq, max_freq, canvas_height))
+            });
+        }
+    }
+}
+
+/// Draw zero-crossing dots from an Anabat ZC file over the (silent)
+/// spectrogram, on the same time/frequency axes.
+pub fn draw_zc_dots(ctx: &CanvasRenderingContext2d, dots: &[crate::audio::anabat::ZcDot], view: &OverlayView) {
+    let OverlayView {
+        min_freq, max_freq, scroll_offset, time_resolution, zoom, canvas_width, canvas_height,
+    } = *view;
+    let visible_time = (canvas_width / zoom) * time_resolution;
+    if dots.is_empty() || visible_time <= 0.0 {
+        return;
+    }
+    let start_time = scroll_offset;
+    let end_time = start_time + visible_time;
+    let px_per_sec = canvas_width / visible_time;
+    let first = dots.partition_point(|d| d.time_secs < start_time);
+    let last = dots.partition_point(|d| d.time_secs <= end_time);
+    let radius = (0.0005 * px_per_sec).clamp(1.0, 2.5);
+
+    ctx.set_fill_style_str("rgba(120, 255, 120, 0.9)");
+    ctx.begin_path();
+    for d in &dots[first..last] {
+        if d.freq_hz < min_freq || d.freq_hz > max_freq {
+            continue;
+        }
+        let x = (d.time_secs - start_time) * px_per_sec;
+        let y = freq_to_y(d.freq_hz, min_freq, max_freq, canvas_height);
+        ctx.move_to(x + radius, y);
+        let _ = ctx.arc(x, y, radius, 0.0, std::f64::consts::TAU);
     }
+    ctx.fill();
 }
 
 /// Draw selection rectangle overlay on spectrogram.
@@ -1402,6 +1463,14 @@ pub fn draw_annotations(
     for annotation in &annotation_set.annotations {
         let sel = match &annotation.kind {
             crate::annotations::AnnotationKind::Region(s) => s,
+            crate::annotations::AnnotationKind::Measurement(m) => {
+                let is_selected = selected_ids.contains(&annotation.id);
+                let view = OverlayView {
+                    min_freq, max_freq, scroll_offset, time_resolution, zoom, canvas_width, canvas_height,
+                };
+                draw_measurement(ctx, m, is_selected, &view);
+                continue;
+            }
             _ => continue,
         };
 
@@ -1451,6 +1520,14 @@ pub fn draw_annotations(
         ctx.set_line_width(1.0);
         ctx.stroke_rect(x0, y0, x1 - x0, y1 - y0);
 
+        // Stored call contour
+        if let Some(ref points) = sel.contour {
+            let rgb = if is_selected { "255, 220, 100" } else { "150, 255, 200" };
+            draw_contour(ctx, points, rgb, |t, f| {
+                ((t - start_time) * px_per_sec, freq_to_y(f, min_freq, max_freq, canvas_height))
+            });
+        }
+
         // Label
         if let Some(ref label) = sel.label {
             let is_default = annotation.label_default.unwrap_or(false);
@@ -1503,6 +1580,86 @@ pub fn draw_annotations(
     }
 }
 
+/// Visible time and frequency range of the spectrogram canvas an overlay is
+/// drawn on.
+#[derive(Clone, Copy, Debug)]
+pub struct OverlayView {
+    pub min_freq: f64,
+    pub max_freq: f64,
+    pub scroll_offset: f64,
+    pub time_resolution: f64,
+    pub zoom: f64,
+    pub canvas_width: f64,
+    pub canvas_height: f64,
+}
+
+/// Draw a two-point measurement as a line with a readout of Δt, Δf, slope
+/// and implied rate. Also used for the measurement being dragged out, with
+/// `highlighted` set.
+pub fn draw_measurement(
+    ctx: &CanvasRenderingContext2d,
+    measurement: &crate::annotations::Measurement,
+    highlighted: bool,
+    view: &OverlayView,
+) {
+    let OverlayView {
+        min_freq, max_freq, scroll_offset, time_resolution, zoom, canvas_width, canvas_height,
+    } = *view;
+    let visible_time = (canvas_width / zoom) * time_resolution;
+    if visible_time <= 0.0 { return; }
+    let start_time = scroll_offset;
+    let px_per_sec = canvas_width / visible_time;
+
+    let x0 = (measurement.start_time - start_time) * px_per_sec;
+    let x1 = (measurement.end_time - start_time) * px_per_sec;
+    if x0.max(x1) < 0.0 || x0.min(x1) > canvas_width { return; }
+    let y0 = freq_to_y(measurement.start_freq, min_freq, max_freq, canvas_height);
+    let y1 = freq_to_y(measurement.end_freq, min_freq, max_freq, canvas_height);
+
+    let (line, fg, bg) = if highlighted {
+        ("rgba(255, 200, 80, 0.95)", "rgba(255, 235, 180, 0.98)", "rgba(60, 45, 10, 0.85)")
+    } else {
+        ("rgba(120, 220, 255, 0.9)", "rgba(210, 240, 255, 0.95)", "rgba(15, 35, 50, 0.8)")
+    };
+
+    ctx.save();
+    ctx.set_stroke_style_str(line);
+    ctx.set_fill_style_str(line);
+    ctx.set_line_width(1.5);
+    ctx.begin_path();
+    ctx.move_to(x0, y0);
+    ctx.line_to(x1, y1);
+    ctx.stroke();
+    for (x, y) in [(x0, y0), (x1, y1)] {
+        ctx.begin_path();
+        let _ = ctx.arc(x, y, 3.0, 0.0, std::f64::consts::TAU);
+        ctx.fill();
+    }
+
+    // Readout box beside the right-hand end, flipped left when clipped
+    let mut lines = Vec::new();
+    if let Some(label) = measurement.label.as_ref().filter(|l| !l.is_empty()) {
+        lines.push(label.clone());
+    }
+    lines.push(measurement.readout());
+    ctx.set_font("11px monospace");
+    let text_w = lines.iter()
+        .filter_map(|l| ctx.measure_text(l).ok().map(|m| m.width()))
+        .fold(0.0, f64::max);
+    let box_w = text_w + 8.0;
+    let box_h = lines.len() as f64 * 14.0 + 4.0;
+    let (ax, ay) = if x1 >= x0 { (x1, y1) } else { (x0, y0) };
+    let box_x = if ax + 6.0 + box_w <= canvas_width { ax + 6.0 } else { (ax - 6.0 - box_w).max(0.0) };
+    let box_y = (ay - box_h / 2.0).clamp(0.0, (canvas_height - box_h).max(0.0));
+    ctx.set_fill_style_str(bg);
+    ctx.fill_rect(box_x, box_y, box_w, box_h);
+    ctx.set_fill_style_str(fg);
+    for (i, text) in lines.iter().enumerate() {
+        let _ = ctx.fill_text(text, box_x + 4.0, box_y + 13.0 + i as f64 * 14.0);
+    }
+    ctx.restore();
+}
+
 /// Style for a time-position marker drawn as a vertical line + optional label.
 #[derive(Clone, Copy, PartialEq)]
 pub enum TimeMarkerStyle {
@@ -18,9 +18,9 @@ pub use crate::canvas::flow::{FlowAlgo, FlowData, compute_flow_data, composite_f
 pub use crate::canvas::overlays::{
     FreqShiftMode, FreqMarkerState, TimeMarkerStyle, DebugTileKind,
     draw_freq_markers, draw_time_markers, draw_band_ff_overlay, draw_het_overlay,
-    draw_pulses, draw_selection, draw_harmonic_shadows, draw_filter_overlay,
+    draw_pulses, draw_zc_dots, draw_selection, draw_harmonic_shadows, draw_filter_overlay,
     pixel_to_time_freq, draw_notch_bands, draw_tile_debug_overlay, draw_annotations,
-    draw_time_marker_lines,
+    draw_measurement, draw_time_marker_lines, OverlayView,
 };
 
 // PreRendered and SpectDisplaySettings are defined in oversample-core::types.
@@ -132,6 +132,7 @@ pub fn AnalysisPanel() -> impl IntoView {
                     let msg = match state.canvas_tool.get() {
                         CanvasTool::Hand => "Panning...",
                         CanvasTool::Selection => "Selecting...",
+                        CanvasTool::Measure => "Measuring...",
                     };
                     return view! {
                         <span style="color: #888">{msg}</span>
@@ -683,8 +683,9 @@ pub fn App() -> impl IntoView {
                 };
 
                 // Save to the outgoing file and all files in its sequence group
-                let names: Vec<String> = state.files.get_untracked().iter().map(|f| f.name.clone()).collect();
-                let groups = crate::components::file_sidebar::file_groups::compute_file_groups(&names);
+                let files = state.files.get_untracked();
+                let names: Vec<String> = files.iter().map(|f| f.name.clone()).collect();
+                let groups = crate::components::file_sidebar::file_groups::compute_file_groups(&names, &files);
                 let group_key = groups.get(oi).and_then(|g| g.as_ref()).map(|ti| ti.group_key.clone());
 
                 state.files.update(|files| {
@@ -762,6 +763,26 @@ pub fn App() -> impl IntoView {
                 }
             }
         }
+        // Review queue: A/X/D record a decision, N skips, P replays, Esc stops
+        if state_kb.review_queue.with_untracked(|q| q.is_some())
+            && !ev.ctrl_key() && !ev.meta_key() && !ev.alt_key()
+        {
+            use crate::annotations::ReviewStatus;
+            use crate::components::review_bar;
+            let handled = match ev.key().to_ascii_lowercase().as_str() {
+                "a" => { review_bar::record_decision(state_kb, ReviewStatus::Accepted); true }
+                "x" => { review_bar::record_decision(state_kb, ReviewStatus::Rejected); true }
+                "d" => { review_bar::record_decision(state_kb, ReviewStatus::Disputed); true }
+                "n" => { review_bar::skip(state_kb); true }
+                "p" => { review_bar::show_current(state_kb); true }
+                "escape" => { review_bar::stop_review(state_kb); true }
+                _ => false,
+            };
+            if handled {
+                ev.prevent_default();
+                return;
+            }
+        }
         if ev.key() == " " {
             ev.prevent_default();
             if state_kb.current_file_index.get_untracked().is_some() {
@@ -930,14 +951,14 @@ pub fn App() -> impl IntoView {
         if (ev.key() == "z" || ev.key() == "Z") && (ev.ctrl_key() || ev.meta_key()) && !ev.alt_key() {
             ev.prevent_default();
             if ev.shift_key() {
-                state_kb.redo_annotations();
+                state_kb.redo();
             } else {
-                state_kb.undo_annotations();
+                state_kb.undo();
             }
         }
         if ev.key() == "y" && (ev.ctrl_key() || ev.meta_key()) && !ev.shift_key() && !ev.alt_key() {
             ev.prevent_default();
-            state_kb.redo_annotations();
+            state_kb.redo();
         }
         // Navigation: arrow keys, PgUp/PgDn, Ctrl+Home/End
         let is_ctrl = ev.ctrl_key() || ev.meta_key();
@@ -1089,7 +1110,8 @@ pub fn App() -> impl IntoView {
                 let name = path.rsplit(['/', '\\']).next().unwrap_or(&path).to_string();
                 // Filter to audio-ish extensions
                 let ext = name.rsplit('.').next().unwrap_or("").to_lowercase();
-                if !matches!(ext.as_str(), "wav" | "w4v" | "flac" | "ogg" | "mp3" | "m4a" | "m4b") {
+                // Anabat ZC files use `.zc` or a `.NN#` extension
+                if !matches!(ext.as_str(), "wav" | "rf64" | "w64" | "w4v" | "wv" | "aif" | "aiff" | "aifc" | "caf" | "flac" | "ogg" | "mp3" | "m4a" | "m4b" | "zc") && !ext.ends_with('#') {
                     log::info!("Skipping non-audio drop: {name}");
                     continue;
                 }
@@ -1340,8 +1362,8 @@ fn MainArea() -> impl IntoView {
                                                     if let Some(f) = files.get(i) {
                                                         let total = f.audio.source.total_samples() as usize;
                                                         let samples = f.audio.source.read_region(crate::audio::source::ChannelView::MonoMix, 0, total);
-                                                        crate::audio::wav_encoder::download_recording_wav(
-                                                            &samples, f.audio.sample_rate, &name,
+                                                        crate::audio::wav_encoder::download_recording(
+                                                            &samples, f.audio.sample_rate, f.audio.metadata.bits_per_sample, &name,
                                                             f.audio.metadata.guano.as_ref(), &f.wav_markers,
                                                         );
                                                     }
@@ -1437,6 +1459,21 @@ fn MainArea() -> impl IntoView {
                 <crate::components::file_sidebar::privacy_settings::PrivacySettingsModal />
             })}
 
+            // Review queue bar
+            {move || state.review_queue.with(|q| q.is_some()).then(|| view! {
+                <crate::components::review_bar::ReviewBar />
+            })}
+
+            // Project-wide annotation table
+            {move || state.show_annotation_table.get().then(|| view! {
+                <crate::components::file_sidebar::annotation_table::AnnotationTableModal />
+            })}
+
+            // Sidecar merge conflicts
+            {move || (!state.pending_merges.with(|list| list.is_empty())).then(|| view! {
+                <crate::components::file_sidebar::merge_conflicts::MergeConflictModal />
+            })}
+
             // "Ready to record" modal
             {move || (state.record_ready_state.get() == crate::state::RecordReadyState::AwaitingConfirmation).then(|| {
                 let on_ok = move |_: web_sys::MouseEvent| {
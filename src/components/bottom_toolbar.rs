@@ -6,7 +6,7 @@
 
 use leptos::prelude::*;
 use wasm_bindgen::prelude::*;
-use crate::state::{AppState, Bar, ChannelMode, LayerPanel, MicStrategy, PlayStartMode, RecordMode};
+use crate::state::{AppState, Bar, ChannelMode, LayerPanel, MicStrategy, PlayStartMode, RecordFormat, RecordMode};
 use crate::audio::{microphone, playback};
 use crate::audio::source::ChannelView;
 use crate::components::combo_button::ComboButton;
@@ -561,6 +561,22 @@ pub fn BottomToolbar() -> impl IntoView {
                     }
                 >"Listen only"</button>
 
+                // ── File format ──
+                <hr />
+                <div class="layer-panel-title">"File format"</div>
+                <div style="display: flex; gap: 2px; padding: 0 6px 4px;">
+                    {[(RecordFormat::Wav, "WAV"), (RecordFormat::Flac, "FLAC")].into_iter().map(|(format, label)| view! {
+                        <button class=move || layer_opt_class(state.record_format.get() == format)
+                            on:click=move |_| {
+                                state.record_format.set(format);
+                                if let Some(ls) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+                                    let _ = ls.set_item("oversample_record_format", format.extension());
+                                }
+                            }
+                        >{label}</button>
+                    }).collect_view()}
+                </div>
+
                 // ── Microphone ──
                 <hr />
                 <div class="layer-panel-title">"Microphone"</div>
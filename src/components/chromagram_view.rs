@@ -398,7 +398,7 @@ pub fn ChromagramView() -> impl IntoView {
                     } else {
                         format!("cursor: grab; touch-action: {ta};")
                     },
-                    CanvasTool::Selection => format!("cursor: crosshair; touch-action: {ta};"),
+                    CanvasTool::Selection | CanvasTool::Measure => format!("cursor: crosshair; touch-action: {ta};"),
                 }
             }
         >
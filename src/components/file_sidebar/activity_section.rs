@@ -0,0 +1,174 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use crate::state::AppState;
+use crate::project::SequenceDefinition;
+use crate::bat_activity::{self, SequenceActivity};
+use crate::dsp::bat_passes::PassParams;
+use crate::dsp::pulse_detect::PulseDetectionParams;
+
+/// Bat pass segmentation + activity index over a project sequence or the
+/// selected project files.
+#[component]
+pub(super) fn ActivitySection(
+    sequences: Vec<SequenceDefinition>,
+    selected_proj_indices: RwSignal<Vec<usize>>,
+    proj_to_runtime: StoredValue<Vec<Option<usize>>>,
+) -> impl IntoView {
+    let state = expect_context::<AppState>();
+
+    // "sel" = selected files (all loaded files when nothing is selected), "seq:N" = sequence N
+    let source = RwSignal::new("sel".to_string());
+    let max_gap_secs = RwSignal::new(1.0f64);
+    let running = RwSignal::new(false);
+    let result: RwSignal<Option<SequenceActivity>> = RwSignal::new(None);
+    let sequences = StoredValue::new(sequences);
+
+    let on_compute = move |_: web_sys::MouseEvent| {
+        if running.get_untracked() { return; }
+
+        let src = source.get_untracked();
+        let proj_indices: Vec<usize> = match src.strip_prefix("seq:").and_then(|n| n.parse::<usize>().ok()) {
+            Some(n) => sequences.with_value(|seqs| seqs.get(n).map(|s| s.file_indices.clone()).unwrap_or_default()),
+            None => {
+                let sel = selected_proj_indices.get_untracked();
+                if sel.is_empty() {
+                    proj_to_runtime.with_value(|p2r| (0..p2r.len()).collect())
+                } else {
+                    sel
+                }
+            }
+        };
+        let runtime_indices: Vec<usize> = proj_indices.iter()
+            .filter_map(|&pi| proj_to_runtime.with_value(|p2r| p2r.get(pi).copied().flatten()))
+            .collect();
+        if runtime_indices.is_empty() {
+            state.show_info_toast("No loaded files to analyse");
+            return;
+        }
+        let files_not_loaded = proj_indices.len() - runtime_indices.len();
+
+        running.set(true);
+        let params = PassParams { max_gap_secs: max_gap_secs.get_untracked(), ..PassParams::default() };
+        spawn_local(async move {
+            let project = state.current_project.get_untracked();
+            let mut per_file = Vec::new();
+            for idx in runtime_indices {
+                let file = state.files.with_untracked(|files| files.get(idx).cloned());
+                let Some(file) = file else { continue };
+                crate::canvas::tile_cache::yield_to_browser().await;
+                let pulses = bat_activity::detect_file_pulses(&file, &PulseDetectionParams::default()).await;
+                let start = bat_activity::file_start_epoch_ms(&file, project.as_ref());
+                per_file.push((idx, start, file.audio.duration_secs, pulses));
+            }
+            result.set(Some(bat_activity::sequence_activity(&per_file, files_not_loaded, &params)));
+            running.set(false);
+        });
+    };
+
+    let seq_options: Vec<_> = sequences.with_value(|seqs| seqs.iter().enumerate().map(|(i, s)| {
+        let label = s.label.clone().unwrap_or_else(|| format!("Sequence {} ({} files)", i + 1, s.file_indices.len()));
+        let value = format!("seq:{i}");
+        let selected = {
+            let value = value.clone();
+            move || source.get() == value
+        };
+        view! { <option value=value selected=selected>{label}</option> }
+    }).collect());
+
+    view! {
+        <div class="project-activity">
+            <div class="project-section-header">"Bat activity"</div>
+            <div class="setting-row">
+                <select
+                    on:change=move |ev: web_sys::Event| {
+                        let target = ev.target().unwrap();
+                        let select: web_sys::HtmlSelectElement = target.unchecked_into();
+                        source.set(select.value());
+                    }
+                >
+                    <option value="sel" selected=move || source.get() == "sel">"Selected files"</option>
+                    {seq_options}
+                </select>
+            </div>
+            <div class="setting-row">
+                <span class="setting-label" title="Pulses separated by more than this start a new pass">"Pass gap"</span>
+                <span class="setting-value">{move || format!("{:.1} s", max_gap_secs.get())}</span>
+            </div>
+            <div class="setting-row">
+                <input
+                    type="range"
+                    class="setting-range"
+                    min="0.2" max="5.0" step="0.1"
+                    prop:value=move || max_gap_secs.get().to_string()
+                    on:input=move |ev: web_sys::Event| {
+                        let target = ev.target().unwrap();
+                        let input: web_sys::HtmlInputElement = target.unchecked_into();
+                        if let Ok(v) = input.value().parse::<f64>() {
+                            max_gap_secs.set(v);
+                        }
+                    }
+                />
+            </div>
+            <div class="setting-row">
+                <button class="project-btn-inline" on:click=on_compute disabled=move || running.get()>
+                    {move || if running.get() { "Analysing\u{2026}" } else { "Count passes" }}
+                </button>
+            </div>
+            {move || result.get().map(activity_view)}
+        </div>
+    }
+}
+
+fn activity_view(activity: SequenceActivity) -> impl IntoView {
+    let s = &activity.summary;
+    let headline = format!(
+        "{} passes, {} pulses, {} active min over {} file(s)",
+        s.total_passes, s.total_pulses, s.total_active_minutes, activity.files_analyzed,
+    );
+    let skipped = (activity.files_without_time > 0).then(|| view! {
+        <div class="project-meta-warn">
+            {format!("{} file(s) skipped: no recording start time", activity.files_without_time)}
+        </div>
+    });
+    let not_loaded = (activity.files_not_loaded > 0).then(|| view! {
+        <div class="project-meta-warn">
+            {format!("{} file(s) skipped: not loaded", activity.files_not_loaded)}
+        </div>
+    });
+    let night_rows: Vec<_> = s.nights.iter().map(|n| view! {
+        <tr>
+            <td>{bat_activity::format_local_datetime(n.night_start_epoch_ms).get(..10).unwrap_or("").to_string()}</td>
+            <td>{n.passes}</td>
+            <td>{n.active_minutes}</td>
+            <td>{format!("{:.1}", n.passes_per_hour)}</td>
+        </tr>
+    }).collect();
+    let hour_rows: Vec<_> = s.hours.iter().map(|h| view! {
+        <tr>
+            <td>{bat_activity::format_local_datetime(h.hour_start_epoch_ms)}</td>
+            <td>{h.passes}</td>
+            <td>{h.active_minutes}</td>
+            <td>{format!("{:.0}", h.recorded_minutes)}</td>
+        </tr>
+    }).collect();
+    let tsv = bat_activity::activity_tsv(&activity);
+    let on_copy = move |_: web_sys::MouseEvent| super::copy_to_clipboard(&tsv);
+
+    view! {
+        <div class="project-activity-result">
+            <div class="project-groupings">{headline}</div>
+            {skipped}
+            {not_loaded}
+            <table class="project-activity-table">
+                <tr><th>"Night"</th><th>"Passes"</th><th>"Act. min"</th><th>"Passes/h"</th></tr>
+                {night_rows}
+            </table>
+            <table class="project-activity-table">
+                <tr><th>"Hour"</th><th>"Passes"</th><th>"Act. min"</th><th>"Rec. min"</th></tr>
+                {hour_rows}
+            </table>
+            <button class="project-btn-inline" on:click=on_copy>"Copy table"</button>
+        </div>
+    }
+}
@@ -5,6 +5,7 @@ use crate::audio::source::{ChannelView, DEFAULT_ANALYSIS_WINDOW_SECS};
 use crate::state::{AppState, RightSidebarTab};
 use crate::dsp::bit_analysis::{self, BitAnalysis, BitCaution};
 use crate::dsp::wsnr;
+use crate::dsp::acoustic_indices::{self, AcousticIndices, IndexBands, IndexParams};
 use std::sync::Arc;
 
 #[component]
@@ -14,6 +15,9 @@ pub(crate) fn AnalysisPanel() -> impl IntoView {
     // Async analysis results — None means "not yet computed" or "computing"
     let analysis: RwSignal<Option<BitAnalysis>> = RwSignal::new(None);
     let wsnr_result: RwSignal<Option<wsnr::WsnrResult>> = RwSignal::new(None);
+    let indices: RwSignal<Option<AcousticIndices>> = RwSignal::new(None);
+    // Soundscape index bands: "auto" (by sample rate), "audible" or "ultrasonic"
+    let index_bands = RwSignal::new("auto".to_string());
     let is_computing = RwSignal::new(false);
     let last_computed_idx: RwSignal<Option<usize>> = RwSignal::new(None);
     let compute_gen = RwSignal::new(0u32);
@@ -31,6 +35,7 @@ pub(crate) fn AnalysisPanel() -> impl IntoView {
 
         analysis.set(None);
         wsnr_result.set(None);
+        indices.set(None);
         is_computing.set(true);
         last_computed_idx.set(idx);
         analysis_is_full.set(full_file);
@@ -57,6 +62,14 @@ pub(crate) fn AnalysisPanel() -> impl IntoView {
             Arc::new(file.audio.source.read_region(ChannelView::MonoMix, 0, max_samples))
         };
         let duration_secs = samples.len() as f64 / sample_rate as f64;
+        let index_params = IndexParams {
+            bands: match index_bands.get_untracked().as_str() {
+                "audible" => IndexBands::audible(),
+                "ultrasonic" => IndexBands::ultrasonic(),
+                _ => IndexBands::for_sample_rate(sample_rate),
+            },
+            ..IndexParams::for_sample_rate(sample_rate)
+        };
 
         spawn_local(async move {
             yield_to_browser().await;
@@ -75,6 +88,13 @@ pub(crate) fn AnalysisPanel() -> impl IntoView {
             if compute_gen.get_untracked() != generation { return; }
             wsnr_result.set(Some(wsnr_res));
 
+            yield_to_browser().await;
+            if compute_gen.get_untracked() != generation { return; }
+
+            let indices_res = acoustic_indices::analyze_indices(&samples, sample_rate, 0.0, &index_params);
+            if compute_gen.get_untracked() != generation { return; }
+            indices.set(Some(indices_res));
+
             is_computing.set(false);
         });
     };
@@ -99,6 +119,7 @@ pub(crate) fn AnalysisPanel() -> impl IntoView {
         if file.is_none() {
             analysis.set(None);
             wsnr_result.set(None);
+            indices.set(None);
             last_computed_idx.set(None);
             is_computing.set(false);
             return;
@@ -195,6 +216,21 @@ pub(crate) fn AnalysisPanel() -> impl IntoView {
             }
         }
 
+        // Soundscape indices
+        if let Some(ref ix) = indices.get() {
+            let o = &ix.overall;
+            report.push_str(&format!(
+                "\nSoundscape Indices\n  ACI: {:.2}\n  NDSI: {:.3}\n  Entropy H: {:.3} (Hf {:.3}, Ht {:.3})\n  BI: {:.2}\n",
+                o.aci, o.ndsi, o.entropy(), o.spectral_entropy, o.temporal_entropy, o.bi
+            ));
+            if ix.windows.len() > 1 {
+                report.push_str("  Per window:\n");
+                for line in acoustic_indices::indices_tsv(&ix.windows).lines() {
+                    report.push_str(&format!("    {}\n", line));
+                }
+            }
+        }
+
         // Bit analysis
         if let Some(ref a) = analysis.get() {
             let total = a.total_samples;
@@ -507,6 +543,74 @@ pub(crate) fn AnalysisPanel() -> impl IntoView {
                     }
                 }
             }}
+            // Soundscape indices section
+            {move || {
+                indices.get().map(|ix| {
+                    let o = ix.overall.clone();
+                    let window_rows: Vec<_> = if ix.windows.len() > 1 {
+                        ix.windows.iter().map(|w| view! {
+                            <tr>
+                                <td>{crate::format_time::format_time_display(w.start_time, 0)}</td>
+                                <td>{format!("{:.1}", w.aci)}</td>
+                                <td>{format!("{:.2}", w.ndsi)}</td>
+                                <td>{format!("{:.2}", w.entropy())}</td>
+                                <td>{format!("{:.1}", w.bi)}</td>
+                            </tr>
+                        }).collect()
+                    } else {
+                        Vec::new()
+                    };
+                    let has_windows = !window_rows.is_empty();
+                    view! {
+                        <div class="setting-group">
+                            <div class="setting-group-title">"Soundscape Indices"</div>
+                            <div class="setting-row">
+                                <span class="setting-label" title="Anthrophony / biophony bands for NDSI and BI">"Bands"</span>
+                                <select
+                                    on:change=move |ev: web_sys::Event| {
+                                        let target = ev.target().unwrap();
+                                        let select: web_sys::HtmlSelectElement = target.unchecked_into();
+                                        index_bands.set(select.value());
+                                        run_analysis(analysis_is_full.get_untracked());
+                                    }
+                                >
+                                    <option value="auto" selected=move || index_bands.get() == "auto">"Auto"</option>
+                                    <option value="audible" selected=move || index_bands.get() == "audible">"Audible (1\u{2013}2 / 2\u{2013}11 kHz)"</option>
+                                    <option value="ultrasonic" selected=move || index_bands.get() == "ultrasonic">"Ultrasonic (1\u{2013}15 / 15\u{2013}120 kHz)"</option>
+                                </select>
+                            </div>
+                            <div class="analysis-stats">
+                                <div class="analysis-stat">
+                                    <span class="analysis-stat-value">{format!("{:.1}", o.aci)}</span>
+                                    <span class="analysis-stat-label" title="Acoustic Complexity Index (mean per window)">"ACI"</span>
+                                </div>
+                                <div class="analysis-stat">
+                                    <span class="analysis-stat-value">{format!("{:.2}", o.ndsi)}</span>
+                                    <span class="analysis-stat-label" title="Normalized Difference Soundscape Index: \u{2212}1 anthrophony \u{2026} +1 biophony">"NDSI"</span>
+                                </div>
+                                <div class="analysis-stat">
+                                    <span class="analysis-stat-value">{format!("{:.2}", o.spectral_entropy)}</span>
+                                    <span class="analysis-stat-label" title="Spectral entropy">"Hf"</span>
+                                </div>
+                                <div class="analysis-stat">
+                                    <span class="analysis-stat-value">{format!("{:.2}", o.temporal_entropy)}</span>
+                                    <span class="analysis-stat-label" title="Temporal entropy">"Ht"</span>
+                                </div>
+                                <div class="analysis-stat">
+                                    <span class="analysis-stat-value">{format!("{:.1}", o.bi)}</span>
+                                    <span class="analysis-stat-label" title="Bioacoustic Index (dB\u{00b7}kHz)">"BI"</span>
+                                </div>
+                            </div>
+                            {has_windows.then(|| view! {
+                                <table class="cf-pass-table">
+                                    <tr><th>"Start"</th><th>"ACI"</th><th>"NDSI"</th><th>"H"</th><th>"BI"</th></tr>
+                                    {window_rows}
+                                </table>
+                            })}
+                        </div>
+                    }
+                })
+            }}
             // Bit analysis section
             {move || {
                 match analysis.get().as_ref() {
@@ -0,0 +1,353 @@
+use std::collections::{HashMap, HashSet};
+use leptos::prelude::*;
+use crate::annotation_table::{AnnotationRow, BulkEdit, Column, Filter, RowSource, apply_bulk_edit, sort_rows};
+use crate::annotations::AnnotationId;
+use crate::bat_activity::file_start_epoch_ms;
+use crate::components::file_sidebar::settings_panel::restore_selection;
+use crate::format_time::{format_duration, format_time_display};
+use crate::state::AppState;
+
+/// Rows shown at once; the rest are reachable by filtering or sorting.
+const MAX_SHOWN: usize = 500;
+
+type RowKey = (RowSource, AnnotationId);
+
+fn row_key(row: &AnnotationRow) -> RowKey {
+    (row.source, row.id.clone())
+}
+
+/// Every annotation in the open project (or all loaded files when there is
+/// no project). Loaded files are read from the annotation store; project
+/// files that aren't loaded from the project's copy.
+fn collect_rows(state: AppState) -> Vec<AnnotationRow> {
+    let files = state.files.get();
+    let store = state.annotation_store.get();
+    state.current_project.with(|project| {
+        let mut rows = Vec::new();
+        let push_loaded = |rows: &mut Vec<AnnotationRow>, i: usize| {
+            let Some(Some(set)) = store.sets.get(i) else { return };
+            let start = file_start_epoch_ms(&files[i], project.as_ref());
+            rows.extend(set.annotations.iter()
+                .filter_map(|a| AnnotationRow::new(RowSource::Loaded(i), &files[i].name, start, a)));
+        };
+        match project {
+            Some(proj) => {
+                let loaded: HashMap<usize, usize> = files.iter().enumerate()
+                    .filter_map(|(i, f)| Some((proj.find_file(f.identity.as_ref()?)?, i)))
+                    .collect();
+                for (pi, pf) in proj.files.iter().enumerate() {
+                    if let Some(&i) = loaded.get(&pi) {
+                        push_loaded(&mut rows, i);
+                    } else {
+                        let start = proj.file_start_epoch_ms(pi, pf.creation_time_ms);
+                        rows.extend(pf.annotations.iter().filter_map(|a| {
+                            AnnotationRow::new(RowSource::Project(pi), &pf.identity.filename, start, a)
+                        }));
+                    }
+                }
+            }
+            None => (0..files.len()).for_each(|i| push_loaded(&mut rows, i)),
+        }
+        for row in &mut rows {
+            row.clock_local_ms = row.clock_ms.map(to_local_ms);
+        }
+        rows
+    })
+}
+
+/// Shift epoch ms by the local UTC offset at that moment, for clock filters.
+fn to_local_ms(epoch_ms: f64) -> f64 {
+    let d = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(epoch_ms));
+    epoch_ms - d.get_timezone_offset() * 60_000.0
+}
+
+/// Apply `edit` to the rows in `keys`, one undo step per loaded file.
+fn bulk_edit(state: AppState, keys: &HashSet<RowKey>, edit: &BulkEdit) -> usize {
+    let mut by_source: HashMap<RowSource, HashSet<AnnotationId>> = HashMap::new();
+    for (source, id) in keys {
+        by_source.entry(*source).or_default().insert(id.clone());
+    }
+    let current = state.current_file_index.get_untracked();
+    let mut changed = 0;
+    let mut project_changed = false;
+    for (source, ids) in by_source {
+        match source {
+            RowSource::Loaded(i) => {
+                // Edit a copy so files the edit doesn't touch get no undo step
+                let Some(mut annotations) = state.annotation_store.with_untracked(|store| {
+                    store.sets.get(i).and_then(|s| s.as_ref()).map(|set| set.annotations.clone())
+                }) else { continue };
+                let n = apply_bulk_edit(&mut annotations, &ids, edit);
+                if n == 0 { continue; }
+                state.snapshot_file_annotations(i);
+                state.annotation_store.update(|store| {
+                    if let Some(Some(set)) = store.sets.get_mut(i) {
+                        set.annotations = annotations;
+                    }
+                });
+                changed += n;
+                if current == Some(i) {
+                    state.annotations_dirty.set(true);
+                } else {
+                    crate::opfs::save_annotations(state, i);
+                }
+            }
+            RowSource::Project(pi) => {
+                state.current_project.update(|p| {
+                    if let Some(pf) = p.as_mut().and_then(|p| p.files.get_mut(pi)) {
+                        let n = apply_bulk_edit(&mut pf.annotations, &ids, edit);
+                        changed += n;
+                        project_changed |= n > 0;
+                    }
+                });
+            }
+        }
+    }
+    if let (BulkEdit::Delete, Some(cur)) = (edit, current) {
+        state.selected_annotation_ids.update(|sel| {
+            sel.retain(|id| !keys.contains(&(RowSource::Loaded(cur), id.clone())));
+        });
+    }
+    if project_changed {
+        state.current_project.update(|p| if let Some(p) = p { p.touch(); });
+        state.project_dirty.set(true);
+    }
+    changed
+}
+
+/// Open the row's file and select the annotation.
+fn navigate(state: AppState, row: &AnnotationRow) {
+    let RowSource::Loaded(i) = row.source else {
+        state.show_info_toast(format!("{} isn't loaded", row.file));
+        return;
+    };
+    if state.current_file_index.get_untracked() != Some(i) {
+        state.current_file_index.set(Some(i));
+    }
+    let id = row.id.clone();
+    // Let the file-switch effect restore the incoming file's settings first
+    wasm_bindgen_futures::spawn_local(async move {
+        crate::canvas::tile_cache::yield_to_browser().await;
+        state.selected_annotation_ids.set(vec![id.clone()]);
+        restore_selection(state, &id);
+    });
+}
+
+/// Local date and time with seconds.
+fn format_clock(epoch_ms: f64) -> String {
+    let d = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(epoch_ms));
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        d.get_full_year(), d.get_month() + 1, d.get_date(),
+        d.get_hours(), d.get_minutes(), d.get_seconds(),
+    )
+}
+
+fn format_khz(hz: Option<f64>) -> String {
+    hz.map(|f| format!("{:.1}", f / 1000.0)).unwrap_or_default()
+}
+
+const COLUMNS: &[(Column, &str)] = &[
+    (Column::File, "File"),
+    (Column::Start, "Time"),
+    (Column::Clock, "Clock time"),
+    (Column::Duration, "Duration"),
+    (Column::FreqLow, "Low kHz"),
+    (Column::FreqHigh, "High kHz"),
+    (Column::Label, "Label"),
+    (Column::Tags, "Tags"),
+    (Column::Notes, "Notes"),
+    (Column::Status, "Status"),
+];
+
+/// Table of every annotation in the project, with a filter expression,
+/// sortable columns and bulk tag / relabel / delete.
+#[component]
+pub fn AnnotationTableModal() -> impl IntoView {
+    let state = expect_context::<AppState>();
+    let filter_text = RwSignal::new(String::new());
+    let sort = RwSignal::new((Column::File, true));
+    let checked = RwSignal::new(HashSet::<RowKey>::new());
+    let edit_text = RwSignal::new(String::new());
+    let confirm_delete = RwSignal::new(false);
+
+    let rows = Memo::new(move |_| collect_rows(state));
+    let parsed = Memo::new(move |_| Filter::parse(&filter_text.get()));
+    let visible = Memo::new(move |_| {
+        let filter = parsed.get().ok().flatten();
+        let mut out: Vec<AnnotationRow> = rows.get().into_iter()
+            .filter(|r| filter.as_ref().is_none_or(|f| f.matches(r)))
+            .collect();
+        let (column, ascending) = sort.get();
+        sort_rows(&mut out, column, ascending);
+        out
+    });
+
+    // Drop checks for rows that no longer exist
+    Effect::new(move |_| {
+        let live: HashSet<RowKey> = rows.with(|r| r.iter().map(row_key).collect());
+        if checked.with_untracked(|c| c.iter().any(|k| !live.contains(k))) {
+            checked.update(|c| c.retain(|k| live.contains(k)));
+        }
+    });
+
+    let close = move || state.show_annotation_table.set(false);
+
+    let all_checked = move || {
+        visible.with(|v| !v.is_empty() && checked.with(|c| v.iter().all(|r| c.contains(&row_key(r)))))
+    };
+    let toggle_all = move |_: web_sys::Event| {
+        let keys: Vec<RowKey> = visible.with_untracked(|v| v.iter().map(row_key).collect());
+        if all_checked() {
+            checked.update(|c| keys.iter().for_each(|k| { c.remove(k); }));
+        } else {
+            checked.update(|c| c.extend(keys));
+        }
+    };
+
+    let run = move |edit: BulkEdit| {
+        let keys = checked.get_untracked();
+        if keys.is_empty() { return; }
+        let n = bulk_edit(state, &keys, &edit);
+        confirm_delete.set(false);
+        if edit == BulkEdit::Delete {
+            checked.set(HashSet::new());
+            state.show_info_toast(format!("Deleted {n} annotation(s)"));
+        } else {
+            state.show_info_toast(format!("Updated {n} annotation(s)"));
+        }
+    };
+    let run_text = move |make: fn(String) -> BulkEdit| {
+        let text = edit_text.get_untracked().trim().to_string();
+        if text.is_empty() {
+            state.show_info_toast("Enter a tag or label first");
+            return;
+        }
+        run(make(text));
+    };
+
+    let header = COLUMNS.iter().map(|&(column, title)| {
+        let arrow = move || match sort.get() {
+            (c, true) if c == column => " \u{25B2}",
+            (c, false) if c == column => " \u{25BC}",
+            _ => "",
+        };
+        view! {
+            <th class="annotation-table-sortable"
+                on:click=move |_| sort.update(|s| *s = if s.0 == column { (column, !s.1) } else { (column, true) })
+            >{title}{arrow}</th>
+        }
+    }).collect::<Vec<_>>();
+
+    let body = move || {
+        visible.with(|v| v.iter().take(MAX_SHOWN).map(|row| {
+            let key = row_key(row);
+            let key_for_check = key.clone();
+            let is_checked = move || checked.with(|c| c.contains(&key_for_check));
+            let nav_row = row.clone();
+            let unloaded = matches!(row.source, RowSource::Project(_));
+            view! {
+                <tr class:annotation-table-unloaded=unloaded
+                    on:click=move |_| navigate(state, &nav_row)
+                >
+                    <td on:click=|ev: web_sys::MouseEvent| ev.stop_propagation()>
+                        <input type="checkbox"
+                            prop:checked=is_checked
+                            on:change=move |_| checked.update(|c| if !c.remove(&key) { c.insert(key.clone()); })
+                        />
+                    </td>
+                    <td class="annotation-table-file" title=row.file.clone()>{row.file.clone()}</td>
+                    <td class="annotation-table-num">{format_time_display(row.start, 3)}</td>
+                    <td>{row.clock_ms.map(format_clock).unwrap_or_default()}</td>
+                    <td class="annotation-table-num">{format_duration(row.duration(), 3)}</td>
+                    <td class="annotation-table-num">{format_khz(row.freq_low)}</td>
+                    <td class="annotation-table-num">{format_khz(row.freq_high)}</td>
+                    <td>{row.label.clone().unwrap_or_else(|| row.kind.to_string())}</td>
+                    <td>{row.tags.join(", ")}</td>
+                    <td class="annotation-table-notes">{row.notes.clone().unwrap_or_default()}</td>
+                    <td>{row.status}</td>
+                </tr>
+            }
+        }).collect::<Vec<_>>())
+    };
+
+    let summary = move || {
+        let (shown, total) = (visible.with(|v| v.len()), rows.with(|r| r.len()));
+        let n_checked = checked.with(|c| c.len());
+        let mut s = if shown == total { format!("{total} annotation(s)") } else { format!("{shown} of {total} match") };
+        if shown > MAX_SHOWN {
+            s.push_str(&format!(", first {MAX_SHOWN} shown"));
+        }
+        if n_checked > 0 {
+            s.push_str(&format!(", {n_checked} selected"));
+        }
+        s
+    };
+
+    view! {
+        <div class="xc-modal-overlay" on:click=move |_| close()>
+            <div class="xc-modal annotation-table-modal" on:click=|ev: web_sys::MouseEvent| ev.stop_propagation()>
+                <div class="xc-modal-header">
+                    <span class="xc-modal-title">
+                        {move || state.current_project.with(|p| match p.as_ref().and_then(|p| p.name.clone()) {
+                            Some(name) => format!("Annotations \u{2014} {name}"),
+                            None => "Annotations".to_string(),
+                        })}
+                    </span>
+                    <button class="xc-modal-close" on:click=move |_| close()>{"\u{00D7}"}</button>
+                </div>
+                <div class="annotation-table-filter">
+                    <input type="text" spellcheck="false"
+                        placeholder="Filter, e.g. tag = \"Myotis\" and freq_low > 40k"
+                        title="Fields: file, label, tag, notes, kind, status, time, clock (HH:MM or YYYY-MM-DD), duration, freq_low, freq_high, confidence. Operators: = != < <= > >= ~ (contains), and, or, not, ( )"
+                        prop:value=move || filter_text.get()
+                        on:input=move |ev| filter_text.set(event_target_value(&ev))
+                    />
+                    {move || parsed.with(|p| p.as_ref().err().cloned()).map(|e| view! {
+                        <span class="annotation-table-error">{e}</span>
+                    })}
+                </div>
+                <div class="annotation-table-scroll">
+                    <table class="annotation-table">
+                        <thead>
+                            <tr>
+                                <th>
+                                    <input type="checkbox" title="Select all matching"
+                                        prop:checked=all_checked
+                                        on:change=toggle_all
+                                    />
+                                </th>
+                                {header}
+                            </tr>
+                        </thead>
+                        <tbody>{body}</tbody>
+                    </table>
+                </div>
+                <div class="annotation-table-actions">
+                    <span class="annotation-table-summary">{summary}</span>
+                    <span style="flex: 1;"></span>
+                    <input type="text" class="annotation-table-edit" placeholder="Tag or label"
+                        prop:value=move || edit_text.get()
+                        on:input=move |ev| edit_text.set(event_target_value(&ev))
+                    />
+                    <button class="layer-panel-opt" on:click=move |_| run_text(BulkEdit::AddTag)>"Add tag"</button>
+                    <button class="layer-panel-opt" on:click=move |_| run_text(BulkEdit::RemoveTag)>"Remove tag"</button>
+                    <button class="layer-panel-opt" on:click=move |_| run_text(BulkEdit::Relabel)>"Relabel"</button>
+                    {move || if confirm_delete.get() {
+                        view! {
+                            <button class="layer-panel-opt annotation-table-delete" on:click=move |_| run(BulkEdit::Delete)>"Confirm delete"</button>
+                            <button class="layer-panel-opt" on:click=move |_| confirm_delete.set(false)>"Cancel"</button>
+                        }.into_any()
+                    } else {
+                        view! {
+                            <button class="layer-panel-opt"
+                                disabled=move || checked.with(|c| c.is_empty())
+                                on:click=move |_| confirm_delete.set(true)
+                            >"Delete"</button>
+                        }.into_any()
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
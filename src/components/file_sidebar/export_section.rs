@@ -1,7 +1,8 @@
-//! Collapsible export section: WAV / MP4 export with format radio buttons,
-//! video settings, progress bar, and .batm import/export.
+//! Collapsible export section: WAV / FLAC / MP4 / ZC export with format radio buttons,
+//! video settings, progress bar, .batm import/export, Raven selection tables and Audacity labels.
 
 use leptos::prelude::*;
+use wasm_bindgen::JsCast;
 
 use crate::audio::export;
 use crate::audio::video_export;
@@ -15,6 +16,10 @@ pub fn ExportSection(
     on_export_batm: Callback<()>,
     on_save_sidecar: Callback<()>,
     on_import_batm: Callback<()>,
+    on_export_raven: Callback<()>,
+    on_import_raven: Callback<()>,
+    on_export_audacity: Callback<()>,
+    on_import_audacity: Callback<()>,
     has_annotations: Signal<Option<bool>>,
     has_file_path: Signal<Option<bool>>,
 ) -> impl IntoView {
@@ -41,8 +46,22 @@ pub fn ExportSection(
         let format = state.export_format.get();
         let ext = match format {
             ExportFormat::Wav => ".wav",
+            ExportFormat::Flac => ".flac",
             ExportFormat::Mp4 => ".mp4",
+            ExportFormat::Zc => ".zc",
         };
+        if format == ExportFormat::Zc {
+            // ZC export ignores the playback mode; it always runs the ZC trigger
+            state.zc_factor.track();
+            let division = export::zc_export_division(&state);
+            if state.active_timeline.get().is_some() {
+                return format!("Export timeline files to {ext} (\u{00f7}{division})");
+            }
+            return match export::get_export_info(&state) {
+                Some(info) => format!("Export {} {} to {ext} (\u{00f7}{division})", info.count, info.source_label),
+                None => format!("Export to {ext}"),
+            };
+        }
         match export::get_export_info(&state) {
             Some(info) => {
                 let mode_suffix = info.mode_label
@@ -61,12 +80,15 @@ pub fn ExportSection(
 
     let on_export_click = move |_: web_sys::MouseEvent| {
         match state.export_format.get_untracked() {
-            ExportFormat::Wav => {
+            ExportFormat::Wav | ExportFormat::Flac => {
                 export::export_selected(&state);
             }
             ExportFormat::Mp4 => {
                 video_export::start_export(&state);
             }
+            ExportFormat::Zc => {
+                export::export_selected_zc(&state);
+            }
         }
     };
 
@@ -107,6 +129,15 @@ pub fn ExportSection(
                         />
                         " WAV"
                     </label>
+                    <label class="export-radio" title="Lossless compressed audio with GUANO metadata">
+                        <input
+                            type="radio"
+                            name="export-format"
+                            checked=move || state.export_format.get() == ExportFormat::Flac
+                            on:change=move |_| on_format_change(ExportFormat::Flac)
+                        />
+                        " FLAC"
+                    </label>
                     <label class=move || if webcodecs_available {
                         "export-radio"
                     } else {
@@ -128,8 +159,46 @@ pub fn ExportSection(
                             None
                         }}
                     </label>
+                    <label class="export-radio" title="Anabat zero-crossing file, divided by the ZC playback factor">
+                        <input
+                            type="radio"
+                            name="export-format"
+                            checked=move || state.export_format.get() == ExportFormat::Zc
+                            on:change=move |_| on_format_change(ExportFormat::Zc)
+                        />
+                        " ZC"
+                    </label>
                 </div>
 
+                // WAV-specific options
+                {move || {
+                    (state.export_format.get() == ExportFormat::Wav).then(|| view! {
+                        <div class="setting-row" style="gap: 4px; align-items: center;">
+                            <label
+                                class="export-radio"
+                                title="Add a Broadcast WAV bext chunk with the region's start time, taken from the GUANO Timestamp"
+                            >
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || state.export_write_bext.get()
+                                    on:change=move |ev: web_sys::Event| {
+                                        let target = ev.target().unwrap();
+                                        let input: web_sys::HtmlInputElement = target.unchecked_into();
+                                        let write = input.checked();
+                                        state.export_write_bext.set(write);
+                                        if let Some(ls) = web_sys::window()
+                                            .and_then(|w| w.local_storage().ok().flatten())
+                                        {
+                                            let _ = ls.set_item("oversample_export_write_bext", if write { "true" } else { "false" });
+                                        }
+                                    }
+                                />
+                                " Write BWF bext chunk"
+                            </label>
+                        </div>
+                    })
+                }}
+
                 // MP4-specific options (shown when MP4 selected)
                 {move || {
                     if state.export_format.get() == ExportFormat::Mp4 && webcodecs_available {
@@ -389,6 +458,60 @@ pub fn ExportSection(
                         "Import .batm"
                     </button>
                 </div>
+
+                // Raven Pro selection tables (whole timeline when one is active)
+                <div class="setting-row" style="gap: 4px; margin-top: 4px;">
+                    <button
+                        class="sidebar-btn"
+                        style="flex: 1;"
+                        on:click={
+                            let cb = on_export_raven;
+                            move |_: web_sys::MouseEvent| cb.run(())
+                        }
+                        disabled=move || has_annotations.get().is_none() && state.active_timeline.get().is_none()
+                        title="Export annotations as a Raven Pro selection table"
+                    >
+                        "Export Raven"
+                    </button>
+                    <button
+                        class="sidebar-btn"
+                        style="flex: 1;"
+                        on:click={
+                            let cb = on_import_raven;
+                            move |_: web_sys::MouseEvent| cb.run(())
+                        }
+                        title="Import a Raven Pro selection table"
+                    >
+                        "Import Raven"
+                    </button>
+                </div>
+
+                // Audacity label track
+                <div class="setting-row" style="gap: 4px; margin-top: 4px;">
+                    <button
+                        class="sidebar-btn"
+                        style="flex: 1;"
+                        on:click={
+                            let cb = on_export_audacity;
+                            move |_: web_sys::MouseEvent| cb.run(())
+                        }
+                        disabled=move || has_annotations.get().is_none()
+                        title="Export annotations as an Audacity label track"
+                    >
+                        "Export labels"
+                    </button>
+                    <button
+                        class="sidebar-btn"
+                        style="flex: 1;"
+                        on:click={
+                            let cb = on_import_audacity;
+                            move |_: web_sys::MouseEvent| cb.run(())
+                        }
+                        title="Import an Audacity label track"
+                    >
+                        "Import labels"
+                    </button>
+                </div>
             </div>
         </div>
     }
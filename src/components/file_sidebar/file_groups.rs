@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use crate::audio::audiomoth;
 use crate::state::LoadedFile;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -95,12 +96,34 @@ pub fn parse_track_suffix(filename: &str) -> Option<TrackInfo> {
     None
 }
 
+/// Whether the file's metadata names an AudioMoth as the recorder.
+pub fn is_audiomoth(file: &LoadedFile) -> bool {
+    file.audio.metadata.guano.as_ref().is_some_and(|g| {
+        g.fields.iter().any(|(k, v)| k == "Model" && v == audiomoth::MODEL)
+    })
+}
+
+/// [`parse_track_suffix`] for a loaded file. AudioMoth `YYYYMMDD_HHMMSS`
+/// names are timestamps, not tracks.
+pub fn file_track_suffix(file: &LoadedFile) -> Option<TrackInfo> {
+    if is_audiomoth(file) {
+        return None;
+    }
+    parse_track_suffix(&file.name)
+}
+
 /// Compute file groups from a list of filenames.
 ///
 /// Returns a parallel Vec: `Some(TrackInfo)` for files that belong to a group
 /// of 2+ files sharing the same `group_key`, `None` for singletons.
-pub fn compute_file_groups(names: &[String]) -> Vec<Option<TrackInfo>> {
-    let parsed: Vec<Option<TrackInfo>> = names.iter().map(|n| parse_track_suffix(n)).collect();
+/// `files` runs parallel to `names`, to leave AudioMoth files out.
+pub fn compute_file_groups(names: &[String], files: &[LoadedFile]) -> Vec<Option<TrackInfo>> {
+    let parsed: Vec<Option<TrackInfo>> = names.iter().enumerate().map(|(i, n)| {
+        if files.get(i).is_some_and(is_audiomoth) {
+            return None;
+        }
+        parse_track_suffix(n)
+    }).collect();
 
     // Count occurrences per group_key
     let mut counts: HashMap<String, usize> = HashMap::new();
@@ -150,6 +173,29 @@ fn sequence_stem(filename: &str) -> String {
     }
 }
 
+/// Folder a file was opened from, when the platform tells us.
+fn source_folder(file: &LoadedFile) -> Option<&str> {
+    let path = file.identity.as_ref().and_then(|id| id.file_path.as_deref()).or(match &file.file_handle {
+        Some(crate::audio::streaming_source::FileHandle::TauriPath(p)) => Some(p.as_str()),
+        _ => None,
+    })?;
+    path.rsplit_once(['/', '\\']).map(|(dir, _)| dir)
+}
+
+/// Sequence key for an AudioMoth file: one sequence per device (by the
+/// `Serial` from its comment), or per folder when the serial is unknown.
+fn audiomoth_sequence_key(file: Option<&LoadedFile>) -> Option<String> {
+    let file = file.filter(|f| is_audiomoth(f))?;
+    let serial = file.audio.metadata.guano.as_ref()
+        .and_then(|g| g.fields.iter().find(|(k, _)| k == "Serial"))
+        .map(|(_, v)| v.as_str());
+    Some(match (serial, source_folder(file)) {
+        (Some(serial), _) => format!("AudioMoth {serial}"),
+        (None, Some(folder)) => format!("AudioMoth {folder}"),
+        (None, None) => "AudioMoth".to_string(),
+    })
+}
+
 /// Compute combined file group info (multitrack + sequence) for all loaded files.
 ///
 /// Sequence detection:
@@ -158,20 +204,45 @@ fn sequence_stem(filename: &str) -> String {
 /// 3. Group files sharing the same prefix (and same track label, if multitrack)
 /// 4. Require 2+ files to form a sequence
 /// 5. Compute gaps between consecutive files using recording timestamps
+///
+/// AudioMoth files have no sequence counter; they are grouped per device and
+/// numbered in order of their recording start times.
 pub fn compute_all_groups(
     names: &[String],
     files: &[LoadedFile],
 ) -> Vec<FileGroupInfo> {
-    let tracks = compute_file_groups(names);
+    let tracks = compute_file_groups(names, files);
+
+    // AudioMoth files: device key → file indices
+    let mut moth_groups: HashMap<String, Vec<usize>> = HashMap::new();
+    let moth_keys: Vec<Option<String>> = (0..names.len()).map(|i| {
+        audiomoth_sequence_key(files.get(i))
+    }).collect();
 
     // Parse sequence stems for each file
-    let seq_parses: Vec<Option<(String, u32)>> = names.iter().map(|name| {
+    let seq_parses: Vec<Option<(String, u32)>> = names.iter().zip(&moth_keys).map(|(name, moth)| {
+        if moth.is_some() {
+            return None;
+        }
         let stem = sequence_stem(name);
         parse_sequence_stem(&stem)
     }).collect();
 
     // Build sequence key: (prefix, track_label_or_empty) → list of (file_index, seq_number)
     let mut seq_groups: HashMap<(String, String), Vec<(usize, u32)>> = HashMap::new();
+    for (i, key) in moth_keys.into_iter().enumerate() {
+        if let Some(key) = key {
+            moth_groups.entry(key).or_default().push(i);
+        }
+    }
+    for (key, mut members) in moth_groups {
+        let start = |i: usize| files.get(i)
+            .and_then(|f| f.recording_start_epoch_ms())
+            .unwrap_or(f64::MAX);
+        members.sort_by(|&a, &b| start(a).total_cmp(&start(b)).then_with(|| names[a].cmp(&names[b])));
+        let numbered = members.into_iter().enumerate().map(|(n, i)| (i, n as u32 + 1)).collect();
+        seq_groups.insert((key, String::new()), numbered);
+    }
     for (i, sp) in seq_parses.iter().enumerate() {
         if let Some((prefix, num)) = sp {
             let track_label = tracks[i].as_ref()
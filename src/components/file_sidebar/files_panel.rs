@@ -15,6 +15,16 @@ use crate::format_time::format_duration_compact;
 use super::loading::{read_and_load_file, load_native_file, DemoEntry, fetch_demo_index, load_single_demo};
 use super::suggestions::BatsForYou;
 
+/// `accept` list for the file picker. Anabat ZC files use `.zc` or a
+/// `.NN#` extension, which `accept` can't wildcard, so those are listed out.
+fn audio_accept() -> String {
+    let mut accept = String::from(".wav,.rf64,.w64,.w4v,.wv,.aif,.aiff,.aifc,.caf,.flac,.mp3,.ogg,.m4a,.m4b,.zc");
+    for n in 0..100 {
+        accept.push_str(&format!(",.{n:02}#"));
+    }
+    accept
+}
+
 /// Remove the file at `idx` from the list and fix up index-tracking signals
 /// (current_file_index, mic_live_file_idx) plus the per-file viewport state
 /// when the closed file was the current one. Stops playback if the closed
@@ -234,7 +244,7 @@ pub(super) fn FilesPanel() -> impl IntoView {
             <input
                 node_ref=file_input_ref
                 type="file"
-                accept=".wav,.w4v,.flac,.mp3,.ogg,.m4a,.m4b"
+                accept=audio_accept()
                 multiple=true
                 style="display:none"
                 on:change=on_file_input_change
@@ -393,8 +403,8 @@ pub(super) fn FilesPanel() -> impl IntoView {
                             if let Some(f) = files.get(i) {
                                 let total = f.audio.source.total_samples() as usize;
                                 let samples = f.audio.source.read_region(crate::audio::source::ChannelView::MonoMix, 0, total);
-                                crate::audio::wav_encoder::download_recording_wav(
-                                    &samples, f.audio.sample_rate, &name_dl,
+                                crate::audio::wav_encoder::download_recording(
+                                    &samples, f.audio.sample_rate, f.audio.metadata.bits_per_sample, &name_dl,
                                     f.audio.metadata.guano.as_ref(), &f.wav_markers,
                                 );
                             }
@@ -0,0 +1,146 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use crate::state::AppState;
+use crate::audio::source::ChannelView;
+use crate::dsp::acoustic_indices::{self, IndexBands, IndexParams, IndexWindow};
+
+#[derive(Clone, Debug)]
+struct FileIndices {
+    name: String,
+    indices: IndexWindow,
+}
+
+/// Acoustic indices (ACI, NDSI, entropy, BI) per project file, for triaging
+/// long deployments before listening.
+#[component]
+pub(super) fn IndicesSection(
+    selected_proj_indices: RwSignal<Vec<usize>>,
+    proj_to_runtime: StoredValue<Vec<Option<usize>>>,
+) -> impl IntoView {
+    let state = expect_context::<AppState>();
+
+    // "auto" picks bands by sample rate; "audible" / "ultrasonic" force a preset
+    let bands = RwSignal::new("auto".to_string());
+    let running = RwSignal::new(false);
+    let result: RwSignal<Vec<FileIndices>> = RwSignal::new(Vec::new());
+
+    let on_compute = move |_: web_sys::MouseEvent| {
+        if running.get_untracked() { return; }
+
+        let sel = selected_proj_indices.get_untracked();
+        let proj_indices: Vec<usize> = if sel.is_empty() {
+            proj_to_runtime.with_value(|p2r| (0..p2r.len()).collect())
+        } else {
+            sel
+        };
+        let runtime_indices: Vec<usize> = proj_indices.iter()
+            .filter_map(|&pi| proj_to_runtime.with_value(|p2r| p2r.get(pi).copied().flatten()))
+            .collect();
+        if runtime_indices.is_empty() {
+            state.show_info_toast("No loaded files to analyse");
+            return;
+        }
+
+        running.set(true);
+        let preset = bands.get_untracked();
+        spawn_local(async move {
+            let mut rows = Vec::new();
+            for idx in runtime_indices {
+                let file = state.files.with_untracked(|files| files.get(idx).cloned());
+                let Some(file) = file else { continue };
+                let sr = file.audio.sample_rate;
+                let params = IndexParams {
+                    bands: match preset.as_str() {
+                        "audible" => IndexBands::audible(),
+                        "ultrasonic" => IndexBands::ultrasonic(),
+                        _ => IndexBands::for_sample_rate(sr),
+                    },
+                    ..IndexParams::for_sample_rate(sr)
+                };
+                // Read one window at a time so long files never sit in memory whole
+                let total = file.audio.source.total_samples();
+                let mut windows = Vec::new();
+                for (start, end) in acoustic_indices::window_bounds(total, sr, &params) {
+                    crate::canvas::tile_cache::yield_to_browser().await;
+                    let samples = file.audio.source.read_region(ChannelView::MonoMix, start, (end - start) as usize);
+                    windows.push(acoustic_indices::compute_window(&samples, sr, start as f64 / sr as f64, &params));
+                }
+                rows.push(FileIndices { name: file.name.clone(), indices: acoustic_indices::aggregate(&windows) });
+            }
+            result.set(rows);
+            running.set(false);
+        });
+    };
+
+    view! {
+        <div class="project-activity">
+            <div class="project-section-header">"Soundscape indices"</div>
+            <div class="setting-row">
+                <select
+                    on:change=move |ev: web_sys::Event| {
+                        let target = ev.target().unwrap();
+                        let select: web_sys::HtmlSelectElement = target.unchecked_into();
+                        bands.set(select.value());
+                    }
+                >
+                    <option value="auto" selected=move || bands.get() == "auto">"Bands: auto"</option>
+                    <option value="audible" selected=move || bands.get() == "audible">"Bands: audible"</option>
+                    <option value="ultrasonic" selected=move || bands.get() == "ultrasonic">"Bands: ultrasonic"</option>
+                </select>
+            </div>
+            <div class="setting-row">
+                <button class="project-btn-inline" on:click=on_compute disabled=move || running.get()>
+                    {move || if running.get() { "Analysing\u{2026}" } else { "Compute indices" }}
+                </button>
+            </div>
+            {move || {
+                let rows = result.get();
+                (!rows.is_empty()).then(|| indices_view(rows))
+            }}
+        </div>
+    }
+}
+
+fn indices_view(rows: Vec<FileIndices>) -> impl IntoView {
+    let mean = acoustic_indices::aggregate(&rows.iter().map(|r| r.indices.clone()).collect::<Vec<_>>());
+    let mut tsv = String::from("file\tduration_s\taci\tndsi\th_spectral\th_temporal\th_total\tbi\n");
+    for r in &rows {
+        let w = &r.indices;
+        tsv.push_str(&format!(
+            "{}\t{:.1}\t{:.2}\t{:.3}\t{:.3}\t{:.3}\t{:.3}\t{:.2}\n",
+            r.name, w.end_time - w.start_time, w.aci, w.ndsi, w.spectral_entropy, w.temporal_entropy, w.entropy(), w.bi,
+        ));
+    }
+    let on_copy = move |_: web_sys::MouseEvent| super::copy_to_clipboard(&tsv);
+
+    let file_rows: Vec<_> = rows.iter().map(|r| {
+        let w = &r.indices;
+        view! {
+            <tr>
+                <td title=r.name.clone()>{r.name.clone()}</td>
+                <td>{format!("{:.1}", w.aci)}</td>
+                <td>{format!("{:.2}", w.ndsi)}</td>
+                <td>{format!("{:.2}", w.entropy())}</td>
+                <td>{format!("{:.1}", w.bi)}</td>
+            </tr>
+        }
+    }).collect();
+
+    view! {
+        <div class="project-activity-result">
+            <table class="project-activity-table">
+                <tr><th>"File"</th><th>"ACI"</th><th>"NDSI"</th><th>"H"</th><th>"BI"</th></tr>
+                {file_rows}
+                <tr>
+                    <th>"Mean"</th>
+                    <th>{format!("{:.1}", mean.aci)}</th>
+                    <th>{format!("{:.2}", mean.ndsi)}</th>
+                    <th>{format!("{:.2}", mean.entropy())}</th>
+                    <th>{format!("{:.1}", mean.bi)}</th>
+                </tr>
+            </table>
+            <button class="project-btn-inline" on:click=on_copy>"Copy table"</button>
+        </div>
+    }
+}
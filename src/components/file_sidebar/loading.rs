@@ -101,7 +101,7 @@ pub(super) async fn read_and_load_file(file: File, state: AppState, load_id: u64
 
     if size > MAX_FILE_SIZE {
         let msg = format!(
-            "File too large ({:.1} GB) — only WAV, FLAC, MP3, OGG, and M4A files can be streamed above 2 GB",
+            "File too large ({:.1} GB) — only WAV, AIFF, CAF, FLAC, MP3, OGG, and M4A files can be streamed above 2 GB",
             size / 1_000_000_000.0
         );
         state.show_error_toast(&msg);
@@ -117,6 +117,11 @@ pub(super) async fn read_and_load_file(file: File, state: AppState, load_id: u64
 
 pub(crate) async fn load_named_bytes(name: String, bytes: &[u8], xc_metadata: Option<Vec<(String, String)>>, xc_hashes: Option<crate::state::SidecarHashes>, state: AppState, load_id: u64, is_demo: bool) -> Result<(), String> {
     let mut wav_markers = crate::audio::loader::parse_wav_markers(bytes);
+    // Anabat ZC files decode to silence; their dots are kept alongside
+    let zc_dots = crate::audio::anabat::is_anabat(bytes)
+        .then(|| crate::audio::anabat::parse_anabat(bytes).ok())
+        .flatten()
+        .map(|zc| Arc::new(zc.dots));
     let is_m4a = crate::audio::loader::is_m4a(bytes);
     // For M4A, prefer the browser's AudioContext decoder: it handles every AAC
     // variant the OS media stack supports (HE-AAC, PS, ELD, and odd ffmpeg
@@ -156,6 +161,9 @@ pub(crate) async fn load_named_bytes(name: String, bytes: &[u8], xc_metadata: Op
             }
         }
     }
+    if let Some(te) = crate::audio::time_expansion::detect_and_apply(&mut audio, &name) {
+        log::info!("{name}: time-expanded ×{te}, showing real-world time and frequency");
+    }
     log::info!(
         "Loaded {}: {} samples, {} Hz, {:.2}s",
         name,
@@ -173,8 +181,10 @@ pub(crate) async fn load_named_bytes(name: String, bytes: &[u8], xc_metadata: Op
     const HOP_SIZE: usize = 512; // baseline LOD hop
     let fft_size: usize = state.spect_fft_mode.get_untracked().fft_for_lod(crate::canvas::tile_cache::LOD_BASELINE);
 
-    // Check for silent/quiet files — scan first 30s only
-    let (silence_check, cached_peak_db) = {
+    // Check for silent/quiet files — scan first 30s only (ZC carriers are silent by design)
+    let (silence_check, cached_peak_db) = if zc_dots.is_some() {
+        (None, None)
+    } else {
         use crate::audio::source::{ChannelView, DEFAULT_ANALYSIS_WINDOW_SECS};
         let total_len = audio.source.total_samples() as usize;
         let scan_end = total_len.min(
@@ -241,6 +251,7 @@ pub(crate) async fn load_named_bytes(name: String, bytes: &[u8], xc_metadata: Op
                 loading_id: Some(load_id),
                 min_display_freq: None,
                 max_display_freq: None,
+                zc_dots,
             });
             state.current_file_index.set(Some(idx));
         });
@@ -318,6 +329,58 @@ pub(crate) async fn load_named_bytes(name: String, bytes: &[u8], xc_metadata: Op
     Ok(())
 }
 
+/// Change the time-expansion factor of a loaded file (1.0 = real time).
+///
+/// The samples are untouched; the file's sample rate, spectrogram axes,
+/// vertical zoom, scroll position and annotations are rescaled so the view
+/// stays on the same calls, now in real-world time and frequency.
+pub(crate) fn set_time_expansion(state: AppState, file_index: usize, factor: f64) {
+    let Some(old) = state.files.with_untracked(|files| {
+        files.get(file_index).map(|f| f.audio.metadata.time_expansion)
+    }) else {
+        return;
+    };
+    if (old - factor).abs() < 1e-9 {
+        return;
+    }
+    let mut ratio = 1.0;
+    state.files.update(|files| {
+        let Some(f) = files.get_mut(file_index) else { return };
+        let old_rate = f.audio.sample_rate as f64;
+        crate::audio::time_expansion::apply_time_expansion(&mut f.audio, factor);
+        ratio = f.audio.sample_rate as f64 / old_rate;
+        let spec = &mut f.spectrogram;
+        spec.freq_resolution *= ratio;
+        spec.time_resolution /= ratio;
+        spec.max_freq *= ratio;
+        spec.sample_rate = f.audio.sample_rate;
+        f.min_display_freq = f.min_display_freq.map(|v| v * ratio);
+        f.max_display_freq = f.max_display_freq.map(|v| v * ratio);
+    });
+
+    state.annotation_store.update(|store| {
+        if let Some(Some(set)) = store.sets.get_mut(file_index) {
+            set.rescale_time_expansion(old, factor);
+        }
+    });
+    state.annotations_dirty.set(true);
+
+    if state.current_file_index.get_untracked() == Some(file_index) {
+        state.selection.set(None);
+        state.scroll_offset.update(|s| *s /= ratio);
+        state.min_display_freq.update(|v| *v = v.map(|f| f * ratio));
+        state.max_display_freq.update(|v| *v = v.map(|f| f * ratio));
+    }
+
+    use crate::canvas::tile_cache;
+    tile_cache::clear_file(file_index);
+    tile_cache::clear_flow_file(file_index);
+    tile_cache::clear_reassign_file(file_index);
+    tile_cache::clear_chroma_cache();
+    crate::canvas::spectrogram_renderer::clear_tile_canvas_cache();
+    state.tile_ready_signal.update(|n| *n = n.wrapping_add(1));
+}
+
 const DEMO_SOUNDS_BASE: &str = "https://archive.oversample.com";
 const DEMO_SOUNDS_FALLBACK_BASE: &str =
     "https://cdn.jsdelivr.net/gh/pengowray/bat-demo-sounds@main";
@@ -0,0 +1,240 @@
+use leptos::prelude::*;
+use crate::annotation_merge::{ConflictChoice, MergeTarget, PendingMerge, remap_choices, three_way_merge};
+use crate::annotations::{Annotation, AnnotationKind, AnnotationSet};
+use crate::format_time::format_time_range;
+use crate::state::AppState;
+
+/// Three-way merge `theirs` into loaded file `file_idx`. Clean merges are
+/// applied straight away; merges with conflicts wait in
+/// `AppState::pending_merges` for the conflict dialog.
+pub(crate) fn merge_into_file(state: AppState, file_idx: usize, theirs: AnnotationSet, source: &str) {
+    let ours = state.annotation_store.with_untracked(|store| {
+        store.sets.get(file_idx).and_then(|s| s.as_ref())
+            .map(|set| (set.annotations.clone(), set.merge_base.clone()))
+    });
+    let Some((ours, ours_base)) = ours else {
+        state.snapshot_file_annotations(file_idx);
+        state.annotation_store.update(|store| {
+            store.ensure_len(file_idx + 1);
+            store.sets[file_idx] = Some(theirs);
+        });
+        persist(state, file_idx);
+        state.show_info_toast("Annotations imported");
+        return;
+    };
+    let outcome = three_way_merge(&ours, ours_base.as_ref(), &theirs.annotations, theirs.merge_base.as_ref());
+    let filename = state.files.with_untracked(|files| {
+        files.get(file_idx).map(|f| f.name.clone()).unwrap_or_default()
+    });
+    let pending = PendingMerge {
+        target: MergeTarget::File(file_idx),
+        filename,
+        source: source.to_string(),
+        outcome,
+        theirs,
+    };
+    if pending.outcome.conflicts.is_empty() {
+        apply_pending(state, pending, &[]);
+    } else {
+        state.pending_merges.update(|list| list.push(pending));
+    }
+}
+
+fn persist(state: AppState, file_idx: usize) {
+    if state.current_file_index.get_untracked() == Some(file_idx) {
+        state.annotations_dirty.set(true);
+    } else {
+        crate::opfs::save_annotations(state, file_idx);
+    }
+}
+
+/// Resolve a merge with `choices` and store the result.
+pub(crate) fn apply_pending(state: AppState, pending: PendingMerge, choices: &[ConflictChoice]) {
+    let PendingMerge { target, filename, outcome, theirs, .. } = pending;
+    // Merge again against the current annotations: they may have been edited
+    // while the dialog was waiting. The user's choices carry over by id.
+    let (ours, ours_base) = match target {
+        MergeTarget::File(file_idx) => state.annotation_store.with_untracked(|store| {
+            store.sets.get(file_idx).and_then(|s| s.as_ref())
+                .map(|set| (set.annotations.clone(), set.merge_base.clone()))
+        }),
+        MergeTarget::Project { file_index, .. } => state.current_project.with_untracked(|p| {
+            p.as_ref().and_then(|proj| proj.files.get(file_index))
+                .map(|pf| (pf.annotations.clone(), pf.merge_base.clone()))
+        }),
+    }.unwrap_or_default();
+    let fresh = three_way_merge(&ours, ours_base.as_ref(), &theirs.annotations, theirs.merge_base.as_ref());
+    let choices = remap_choices(&outcome.conflicts, choices, &fresh.conflicts);
+    let outcome = fresh;
+    let (taken, deleted, conflicts) = (outcome.taken, outcome.deleted, outcome.conflicts.len());
+    let noop = outcome.is_noop();
+    let (annotations, base) = outcome.resolve(&choices);
+    match target {
+        MergeTarget::File(file_idx) => {
+            if !noop {
+                state.snapshot_file_annotations(file_idx);
+            }
+            state.annotation_store.update(|store| {
+                store.ensure_len(file_idx + 1);
+                let set = store.sets[file_idx].get_or_insert_with(|| theirs.clone());
+                set.annotations = annotations;
+                set.merge_base = Some(base);
+                if set.noise_profile.is_none() {
+                    set.noise_profile = theirs.noise_profile.clone();
+                }
+            });
+            persist(state, file_idx);
+        }
+        MergeTarget::Project { file_index, batm_key } => {
+            state.current_project.update(|p| {
+                if let Some(proj) = p {
+                    proj.apply_batm_merge(file_index, &theirs, annotations, base, &batm_key);
+                }
+            });
+            state.project_dirty.set(true);
+        }
+    }
+    if noop {
+        return;
+    }
+    let mut parts = Vec::new();
+    if taken > 0 { parts.push(format!("{taken} taken")); }
+    if deleted > 0 { parts.push(format!("{deleted} deleted")); }
+    if conflicts > 0 { parts.push(format!("{conflicts} conflict(s) resolved")); }
+    state.show_info_toast(format!("Merged {filename}: {}", parts.join(", ")));
+}
+
+/// One-line summary of an annotation version for the conflict dialog.
+fn describe(a: &Annotation) -> String {
+    let label = crate::annotations::label_of(&a.kind)
+        .map(str::to_string)
+        .unwrap_or_else(|| crate::annotations::kind_name(&a.kind).to_string());
+    let extent = match &a.kind {
+        AnnotationKind::Region(r) => {
+            let mut s = format_time_range(r.time_start, r.time_end, 3);
+            if let (Some(lo), Some(hi)) = (r.freq_low, r.freq_high) {
+                s.push_str(&format!(", {:.1}\u{2013}{:.1} kHz", lo / 1000.0, hi / 1000.0));
+            }
+            s
+        }
+        AnnotationKind::Marker(m) => format!("{:.3}s", m.time),
+        AnnotationKind::Measurement(m) => format_time_range(m.start_time, m.end_time, 3),
+        AnnotationKind::Group(_) => String::new(),
+    };
+    let mut out = label;
+    if !extent.is_empty() {
+        out.push_str(&format!(" \u{00B7} {extent}"));
+    }
+    if !a.tags.is_empty() {
+        out.push_str(&format!(" \u{00B7} [{}]", a.tags.join(", ")));
+    }
+    if let Some(ref n) = a.notes {
+        out.push_str(&format!(" \u{00B7} \u{201C}{n}\u{201D}"));
+    }
+    out
+}
+
+fn version_cell(a: Option<&Annotation>) -> impl IntoView + use<> {
+    match a {
+        Some(a) => {
+            let when = a.modified_at.get(..16).unwrap_or(&a.modified_at).replace('T', " ");
+            view! {
+                <div class="merge-conflict-version">
+                    <div>{describe(a)}</div>
+                    <div class="merge-conflict-when">{format!("edited {when}")}</div>
+                </div>
+            }.into_any()
+        }
+        None => view! {
+            <div class="merge-conflict-version merge-conflict-deleted">"deleted"</div>
+        }.into_any(),
+    }
+}
+
+/// Dialog for the first pending merge: pick a side for each conflicting
+/// annotation, then apply.
+#[component]
+pub fn MergeConflictModal() -> impl IntoView {
+    let state = expect_context::<AppState>();
+    let Some(pending) = state.pending_merges.with_untracked(|list| list.first().cloned()) else {
+        return view! { <span></span> }.into_any();
+    };
+    let choices = RwSignal::new(vec![ConflictChoice::Ours; pending.outcome.conflicts.len()]);
+    let remaining = state.pending_merges.with_untracked(|list| list.len()) - 1;
+
+    let pop_first = move || {
+        let mut first = None;
+        state.pending_merges.update(|list| {
+            if !list.is_empty() {
+                first = Some(list.remove(0));
+            }
+        });
+        first
+    };
+    let on_skip = move |_: web_sys::MouseEvent| {
+        pop_first();
+    };
+    let on_apply = move |_: web_sys::MouseEvent| {
+        if let Some(p) = pop_first() {
+            apply_pending(state, p, &choices.get_untracked());
+        }
+    };
+    let set_all = move |c: ConflictChoice| choices.update(|v| v.iter_mut().for_each(|x| *x = c));
+
+    let summary = format!(
+        "{} conflicting annotation(s) between this copy and the {}. {} other change(s) merged cleanly.",
+        pending.outcome.conflicts.len(),
+        pending.source,
+        pending.outcome.taken + pending.outcome.deleted,
+    );
+
+    let rows = pending.outcome.conflicts.iter().enumerate().map(|(i, c)| {
+        let choice_btn = move |c: ConflictChoice, text: &'static str| view! {
+            <button
+                class=move || if choices.get().get(i) == Some(&c) { "layer-panel-opt sel" } else { "layer-panel-opt" }
+                on:click=move |_| choices.update(|v| if let Some(x) = v.get_mut(i) { *x = c; })
+            >{text}</button>
+        };
+        let both = c.ours.is_some() && c.theirs.is_some();
+        view! {
+            <div class="merge-conflict-row">
+                <div class="merge-conflict-sides">
+                    {version_cell(c.ours.as_ref())}
+                    {version_cell(c.theirs.as_ref())}
+                </div>
+                <div class="merge-conflict-choices">
+                    {choice_btn(ConflictChoice::Ours, "Keep mine")}
+                    {choice_btn(ConflictChoice::Theirs, "Keep theirs")}
+                    {both.then(|| choice_btn(ConflictChoice::Both, "Keep both"))}
+                </div>
+            </div>
+        }
+    }).collect::<Vec<_>>();
+
+    view! {
+        <div class="xc-modal-overlay">
+            <div class="xc-modal" style="width: min(92vw, 640px);">
+                <div class="xc-modal-header">
+                    <span class="xc-modal-title">{format!("Merge conflicts \u{2014} {}", pending.filename)}</span>
+                    <button class="xc-modal-close" title="Skip this file" on:click=on_skip>{"\u{00D7}"}</button>
+                </div>
+                <div class="merge-conflict-summary">{summary}</div>
+                <div class="merge-conflict-sides merge-conflict-heading">
+                    <span>"Mine"</span>
+                    <span>"Theirs"</span>
+                </div>
+                <div class="merge-conflict-list">{rows}</div>
+                <div class="merge-conflict-actions">
+                    <button class="layer-panel-opt" on:click=move |_| set_all(ConflictChoice::Ours)>"All mine"</button>
+                    <button class="layer-panel-opt" on:click=move |_| set_all(ConflictChoice::Theirs)>"All theirs"</button>
+                    <span style="flex: 1;"></span>
+                    {(remaining > 0).then(|| view! {
+                        <span class="merge-conflict-when">{format!("{remaining} more file(s)")}</span>
+                    })}
+                    <button class="layer-panel-opt" on:click=on_skip>"Skip"</button>
+                    <button class="layer-panel-opt sel" on:click=on_apply>"Apply"</button>
+                </div>
+            </div>
+        </div>
+    }.into_any()
+}
@@ -93,6 +93,36 @@ fn hash_row(label: &str, hash: &str, reference: Option<&str>, from_reference: bo
     }
 }
 
+/// Time-expansion factors offered in the selector; detectors use 10, 20 or 32.
+const TIME_EXPANSION_PRESETS: [f64; 4] = [1.0, 10.0, 20.0, 32.0];
+
+/// Time-expansion selector. Changing it rescales the file to real-world units.
+fn time_expansion_row(state: AppState, file_idx: usize, current: f64) -> impl IntoView {
+    let mut factors = TIME_EXPANSION_PRESETS.to_vec();
+    if !factors.iter().any(|f| (f - current).abs() < 1e-9) {
+        factors.push(current);
+    }
+    let options: Vec<_> = factors.into_iter().map(|f| {
+        let label = if f == 1.0 { "Off (real time)".to_string() } else { format!("\u{00d7}{f}") };
+        view! { <option value=f.to_string() selected={(f - current).abs() < 1e-9}>{label}</option> }
+    }).collect();
+    let on_change = move |ev: web_sys::Event| {
+        use wasm_bindgen::JsCast;
+        let select: web_sys::HtmlSelectElement = ev.target().unwrap().unchecked_into();
+        if let Ok(factor) = select.value().parse::<f64>() {
+            super::set_time_expansion(state, file_idx, factor);
+        }
+    };
+    view! {
+        <div class="setting-row metadata-row">
+            <span class="setting-label" title="Factor the recorder slowed the audio down by. Times and frequencies are shown in real-world units.">"Time expansion"</span>
+            <select class="setting-select" on:change=on_change>
+                {options}
+            </select>
+        </div>
+    }
+}
+
 fn format_file_size(bytes: usize) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
@@ -292,6 +322,12 @@ pub(crate) fn MetadataPanel() -> impl IntoView {
                             .map(|g| g.fields.clone())
                             .unwrap_or_default();
                         let has_guano = !guano_fields.is_empty();
+                        let sample_rate_text = if meta.time_expansion > 1.0 {
+                            let file_rate = f.audio.sample_rate as f64 / meta.time_expansion / 1000.0;
+                            format!("{} kHz ({file_rate:.1} kHz in file)", f.audio.sample_rate / 1000)
+                        } else {
+                            format!("{} kHz", f.audio.sample_rate / 1000)
+                        };
 
                         view! {
                             <div class="setting-group">
@@ -299,7 +335,8 @@ pub(crate) fn MetadataPanel() -> impl IntoView {
                                 {metadata_row("Name".into(), f.name.clone(), None)}
                                 {metadata_row("Format".into(), meta.format.to_string(), None)}
                                 {metadata_row("Duration".into(), crate::format_time::format_duration(f.audio.duration_secs, 3), None)}
-                                {metadata_row("Sample rate".into(), format!("{} kHz", f.audio.sample_rate / 1000), None)}
+                                {metadata_row("Sample rate".into(), sample_rate_text, None)}
+                                {time_expansion_row(state, idx.unwrap_or(0), meta.time_expansion)}
                                 {metadata_row("Channels".into(), f.audio.channels.to_string(), None)}
                                 {metadata_row("Bit depth".into(), format!("{}-bit", meta.bits_per_sample), None)}
                                 {metadata_row(size_label, size_str, None)}
@@ -318,7 +355,7 @@ pub(crate) fn MetadataPanel() -> impl IntoView {
                                 view! { <span></span> }.into_any()
                             }}
                             {if has_guano {
-                                let is_guano_source = matches!(meta.format, "WAV" | "W4V");
+                                let is_guano_source = matches!(meta.format, "WAV" | "W4V" | "WavPack" | "AIFF" | "CAF" | "FLAC" | "Anabat ZC");
                                 let default_section: &str = if is_guano_source {
                                     "Guano metadata"
                                 } else {
@@ -337,11 +374,16 @@ pub(crate) fn MetadataPanel() -> impl IntoView {
                                     if current_section.as_ref() != Some(&section) {
                                         let heading = section.clone();
                                         let show_badge = is_guano_source && heading != default_section;
+                                        let badge = if matches!(heading.as_str(), "BWF" | "iXML" | "AudioMoth" | "AIFF" | "CAF") {
+                                            heading.clone()
+                                        } else {
+                                            "GUANO".to_string()
+                                        };
                                         items.push(view! {
                                             <div class="setting-group-title">
                                                 {heading}
                                                 {if show_badge {
-                                                    view! { <span class="metadata-source-badge">"GUANO"</span> }.into_any()
+                                                    view! { <span class="metadata-source-badge">{badge}</span> }.into_any()
                                                 } else {
                                                     view! { <span></span> }.into_any()
                                                 }}
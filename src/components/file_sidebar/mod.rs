@@ -4,6 +4,9 @@ mod files_panel;
 mod config_panel;
 mod export_section;
 mod project_panel;
+mod activity_section;
+mod indices_section;
+mod tdoa_section;
 pub(crate) use project_panel::save_project_async;
 pub mod settings_panel;
 pub mod analysis;
@@ -16,6 +19,8 @@ pub(crate) mod streaming_load;
 mod suggestions;
 pub mod mic_chooser;
 pub mod privacy_settings;
+pub(crate) mod merge_conflicts;
+pub(crate) mod annotation_table;
 
 use leptos::prelude::*;
 use wasm_bindgen::prelude::*;
@@ -34,7 +39,7 @@ pub(crate) use metadata_panel::MetadataPanel;
 pub(crate) use harmonics::HarmonicsPanel;
 pub(crate) use psd_panel::PsdPanel;
 pub(crate) use pulse_panel::PulsePanel;
-pub(crate) use loading::{load_named_bytes, load_native_file, fetch_demo_index, load_single_demo};
+pub(crate) use loading::{load_named_bytes, load_native_file, fetch_demo_index, load_single_demo, set_time_expansion};
 
 fn copy_to_clipboard(text: &str) {
     if let Some(window) = web_sys::window() {
@@ -8,6 +8,10 @@ use crate::annotations::AudioFileMetadata;
 use crate::opfs;
 use crate::format_time::format_duration_compact;
 use crate::viewport;
+use crate::annotation_merge::{MergeTarget, PendingMerge};
+use super::activity_section::ActivitySection;
+use super::indices_section::IndicesSection;
+use super::merge_conflicts;
 
 /// Helper: build AudioFileMetadata from a LoadedFile.
 fn audio_meta_from_loaded(f: &crate::state::LoadedFile) -> AudioFileMetadata {
@@ -22,6 +26,7 @@ fn audio_meta_from_loaded(f: &crate::state::LoadedFile) -> AudioFileMetadata {
         data_size: f.audio.metadata.data_size,
         peak_db_30s: f.cached_peak_db,
         peak_db_full: f.cached_full_peak_db,
+        time_expansion: Some(f.audio.metadata.time_expansion),
     }
 }
 
@@ -268,6 +273,7 @@ fn ProjectView(project: BatProject) -> impl IntoView {
     let mt_count = project.multitrack_groups.len();
     let _timeline_count = project.timelines.len();
     let timelines_clone = project.timelines.clone();
+    let sequences_clone = project.sequences.clone();
 
     // Track which project files are currently loaded
     let loaded_files = state.files.get_untracked();
@@ -355,35 +361,52 @@ fn ProjectView(project: BatProject) -> impl IntoView {
         spawn_local(async move {
             let loaded = state.files.get_untracked();
             let mut merged_count = 0u32;
+            let mut conflicted = 0u32;
             let mut skipped = 0u32;
 
             for f in loaded.iter() {
                 let Some(ref identity) = f.identity else { continue };
                 let key = opfs::opfs_key(identity);
 
-                // Check if already merged
+                // Try to load the .batm
+                let set = match opfs::load_batm_by_key(&key).await {
+                    Ok(Some(set)) => set,
+                    Ok(None) => continue, // No sidecar for this file
+                    Err(e) => {
+                        log::warn!("Failed to load .batm {key}: {e}");
+                        continue;
+                    }
+                };
+
+                // Check if already merged since the sidecar last changed
                 let already = state.current_project.with_untracked(|p| {
-                    p.as_ref().is_some_and(|proj| proj.was_merged(&key))
+                    p.as_ref().is_some_and(|proj| proj.was_merged(&key, set.modified_at.as_deref()))
                 });
                 if already { skipped += 1; continue; }
 
-                // Try to load the .batm
-                match opfs::load_batm_by_key(&key).await {
-                    Ok(Some(set)) => {
-                        let did_merge = state.current_project.try_update(|p| {
-                            p.as_mut().is_some_and(|proj| proj.merge_batm(&set, &key))
-                        }).unwrap_or(false);
-                        if did_merge {
-                            merged_count += 1;
-                            state.project_dirty.set(true);
-                        }
-                    }
-                    Ok(None) => {} // No sidecar for this file
-                    Err(e) => log::warn!("Failed to load .batm {key}: {e}"),
+                let plan = state.current_project.with_untracked(|p| {
+                    p.as_ref().and_then(|proj| proj.plan_batm_merge(&set))
+                });
+                let Some((file_index, outcome)) = plan else { continue };
+                let pending = PendingMerge {
+                    target: MergeTarget::Project { file_index, batm_key: key },
+                    filename: set.file_identity.filename.clone(),
+                    source: "sidecar in browser storage".to_string(),
+                    outcome,
+                    theirs: set,
+                };
+                if pending.outcome.conflicts.is_empty() {
+                    merge_conflicts::apply_pending(state, pending, &[]);
+                    merged_count += 1;
+                } else {
+                    state.pending_merges.update(|list| list.push(pending));
+                    conflicted += 1;
                 }
             }
 
-            let msg = if merged_count > 0 {
+            let msg = if conflicted > 0 {
+                format!("Merged {merged_count} sidecar(s), {conflicted} with conflicts to resolve")
+            } else if merged_count > 0 {
                 format!("Merged {merged_count} sidecar(s)")
             } else if skipped > 0 {
                 "Already merged".to_string()
@@ -776,6 +799,19 @@ fn ProjectView(project: BatProject) -> impl IntoView {
                 }
             }
 
+            // Bat passes / activity index
+            <ActivitySection
+                sequences=sequences_clone
+                selected_proj_indices=selected_proj_indices
+                proj_to_runtime=proj_to_runtime
+            />
+
+            // Soundscape indices per file
+            <IndicesSection
+                selected_proj_indices=selected_proj_indices
+                proj_to_runtime=proj_to_runtime
+            />
+
             // Notes
             <div class="project-notes-section">
                 <div class="project-section-header">"Notes"</div>
@@ -799,6 +835,16 @@ fn ProjectView(project: BatProject) -> impl IntoView {
                         None
                     }}
                 </div>
+                <div class="project-merge-row">
+                    <button class="project-btn-inline"
+                        on:click=move |_| crate::components::review_bar::start_review(state)
+                        title="Step through unreviewed annotations in the project's loaded files, playing each one"
+                    >"Review queue"</button>
+                    <button class="project-btn-inline"
+                        on:click=move |_| state.show_annotation_table.set(true)
+                        title="Filter, sort and bulk-edit every annotation in the project"
+                    >"Table"</button>
+                </div>
                 {move || {
                     merge_status.get().map(|msg| view! {
                         <div class="project-merge-status">{msg}</div>
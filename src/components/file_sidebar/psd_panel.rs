@@ -153,6 +153,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                 sort_order: None,
                 tags: Vec::new(),
                 label_default: None,
+                confidence: None,
+                review: None,
             }];
 
             // Add selection bounds as the first child annotation
@@ -168,6 +170,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                             label: Some(format!("Selection {:.1}\u{2013}{:.1} kHz", flo / 1000.0, fhi / 1000.0)),
                             color: Some("#ffcc33".to_string()),
                             locked: None,
+                            contour: None,
+                            position: None,
                         }),
                         created_at: now_iso8601(),
                         modified_at: now_iso8601(),
@@ -176,6 +180,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                         sort_order: Some(-1.0),
                         tags: Vec::new(),
                         label_default: None,
+                        confidence: None,
+                        review: None,
                     });
                 }
             }
@@ -196,6 +202,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                         label: Some(format!("F{} {:.1} kHz ({:.1} dB)", i + 1, peak.freq_hz / 1000.0, peak.power_db)),
                         color: Some(color.clone()),
                         locked: None,
+                        contour: None,
+                        position: None,
                     }),
                     created_at: now_iso8601(),
                     modified_at: now_iso8601(),
@@ -204,6 +212,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                     sort_order: Some(sort),
                     tags: Vec::new(),
                     label_default: None,
+                    confidence: None,
+                    review: None,
                 });
 
                 if let Some((lo, hi)) = peak.bw_6db {
@@ -217,6 +227,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                             label: Some(format!("F{} -6 dB: {:.1} kHz", i + 1, (hi - lo) / 1000.0)),
                             color: Some("#44aa66".to_string()),
                             locked: None,
+                            contour: None,
+                            position: None,
                         }),
                         created_at: now_iso8601(),
                         modified_at: now_iso8601(),
@@ -225,6 +237,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                         sort_order: Some(sort + 1.0),
                         tags: Vec::new(),
                         label_default: None,
+                        confidence: None,
+                        review: None,
                     });
                 }
 
@@ -239,6 +253,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                             label: Some(format!("F{} -10 dB: {:.1} kHz", i + 1, (hi - lo) / 1000.0)),
                             color: Some("#aaaa44".to_string()),
                             locked: None,
+                            contour: None,
+                            position: None,
                         }),
                         created_at: now_iso8601(),
                         modified_at: now_iso8601(),
@@ -247,6 +263,8 @@ pub(crate) fn PsdPanel() -> impl IntoView {
                         sort_order: Some(sort + 2.0),
                         tags: Vec::new(),
                         label_default: None,
+                        confidence: None,
+                        review: None,
                     });
                 }
             }
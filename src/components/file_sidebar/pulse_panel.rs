@@ -3,6 +3,20 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use crate::state::{AppState, RightSidebarTab};
 use crate::dsp::pulse_detect::{self, DetectedPulse, PulseDetectionParams};
+use crate::dsp::call_params::{self, CallMeasurementParams, CallParameters};
+use crate::dsp::call_shape::{self, CallShape};
+use crate::dsp::call_events::{self, CallEvent, CallEventKind, CallEventParams};
+use crate::dsp::contour::{self, CallContour, ContourParams};
+use crate::dsp::cf_analysis::{self, CfAnalysis, CfCall, CfParams};
+use crate::dsp::bat_passes::PassParams;
+use super::tdoa_section::TdoaSection;
+use crate::audio::source::ChannelView;
+use crate::types::{AudioData, SpectrogramData};
+use crate::bat_book::matcher::{self, CallSummary, SpeciesCandidate};
+use crate::annotations::{
+    Annotation, AnnotationKind, Group, Region,
+    generate_uuid, now_iso8601,
+};
 
 #[component]
 pub(crate) fn PulsePanel() -> impl IntoView {
@@ -13,6 +27,10 @@ pub(crate) fn PulsePanel() -> impl IntoView {
     let min_duration_ms = RwSignal::new(0.3f64);
     let max_duration_ms = RwSignal::new(50.0f64);
     let min_gap_ms = RwSignal::new(3.0f64);
+    // Show only pulses of this shape (None = all)
+    let shape_filter: RwSignal<Option<CallShape>> = RwSignal::new(None);
+    // CF mode: list CF frequencies and show the Doppler / histogram section
+    let cf_mode = RwSignal::new(false);
 
     // Generation counter for cancellation
     let compute_gen = RwSignal::new(0u32);
@@ -21,6 +39,20 @@ pub(crate) fn PulsePanel() -> impl IntoView {
     // Bumped by Re-detect to force the Effect to re-run without remounting the component
     let redetect_trigger = RwSignal::new(0u32);
 
+    // CF analysis reads the audio around every call, so only run it once CF
+    // mode is on and detection has finished
+    Effect::new(move || {
+        if !cf_mode.get() || state.pulse_detecting.get() || state.cf_analysis.with_untracked(Option::is_some) {
+            return;
+        }
+        let audio = last_computed_idx.get_untracked()
+            .and_then(|i| state.files.with_untracked(|files| files.get(i).map(|f| f.audio.clone())));
+        let Some(audio) = audio else { return };
+        let channel = state.channel_view.get_untracked();
+        let cf = state.call_contours.with_untracked(|contours| analyse_cf_calls(&audio, contours, channel));
+        state.cf_analysis.set(Some(cf));
+    });
+
     // Trigger pulse detection when tab is active and file changes
     Effect::new(move || {
         let tab = state.right_sidebar_tab.get();
@@ -46,6 +78,11 @@ pub(crate) fn PulsePanel() -> impl IntoView {
         let file = idx.and_then(|i| files.get(i).cloned());
         let Some(file) = file else {
             state.detected_pulses.set(Vec::new());
+            state.call_parameters.set(Vec::new());
+            state.call_shapes.set(Vec::new());
+            state.call_events.set(Vec::new());
+            state.call_contours.set(Vec::new());
+            state.cf_analysis.set(None);
             state.pulse_detecting.set(false);
             last_computed_idx.set(None);
             return;
@@ -53,6 +90,11 @@ pub(crate) fn PulsePanel() -> impl IntoView {
 
         // Start detection
         state.detected_pulses.set(Vec::new());
+        state.call_parameters.set(Vec::new());
+        state.call_shapes.set(Vec::new());
+        state.call_events.set(Vec::new());
+        state.call_contours.set(Vec::new());
+        state.cf_analysis.set(None);
         state.selected_pulse_index.set(None);
         state.pulse_detecting.set(true);
         last_computed_idx.set(idx);
@@ -66,6 +108,8 @@ pub(crate) fn PulsePanel() -> impl IntoView {
         let min_dur = min_duration_ms.get_untracked();
         let max_dur = max_duration_ms.get_untracked();
         let gap = min_gap_ms.get_untracked();
+        let reassign = state.reassign_enabled.get_untracked();
+        let channel = state.channel_view.get_untracked();
 
         spawn_local(async move {
             yield_to_browser().await;
@@ -83,7 +127,25 @@ pub(crate) fn PulsePanel() -> impl IntoView {
             let pulses = pulse_detect::detect_pulses(&audio, &spectrogram, &params);
 
             if compute_gen.get_untracked() != generation { return; }
+            let measure_params = CallMeasurementParams {
+                freq_low_hz: params.bandpass_low_hz,
+                freq_high_hz: params.bandpass_high_hz,
+                ..CallMeasurementParams::default()
+            };
+            let (calls, ridges) = call_params::measure_calls_with_ridges(&pulses, &spectrogram, &measure_params);
+            let shapes = call_shape::classify_calls(&calls, &ridges);
+            let events = call_events::detect_call_events(&pulses, &calls, &CallEventParams::default());
+            let contour_params = ContourParams {
+                freq_low_hz: params.bandpass_low_hz,
+                freq_high_hz: params.bandpass_high_hz,
+                ..ContourParams::default()
+            };
+            let contours = trace_contours(&audio, &spectrogram, &pulses, &contour_params, reassign, channel);
             state.detected_pulses.set(pulses);
+            state.call_parameters.set(calls);
+            state.call_shapes.set(shapes);
+            state.call_events.set(events);
+            state.call_contours.set(contours);
             state.pulse_detecting.set(false);
         });
     });
@@ -184,10 +246,21 @@ pub(crate) fn PulsePanel() -> impl IntoView {
                         " Show overlay"
                     </label>
                 </div>
+                <div class="setting-row">
+                    <label class="setting-label" title="Constant-frequency calls (horseshoe and leaf-nosed bats): CF per call, Doppler range per pass, CF histogram">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || cf_mode.get()
+                            on:change=move |ev| cf_mode.set(event_target_checked(&ev))
+                        />
+                        " CF analysis"
+                    </label>
+                </div>
                 <div class="setting-row">
                     <button class="setting-button" on:click=on_redetect>"Re-detect"</button>
                 </div>
             </div>
+            <TdoaSection/>
             // Status / Results
             {move || {
                 let files = state.files.get();
@@ -215,8 +288,20 @@ pub(crate) fn PulsePanel() -> impl IntoView {
                     }.into_any();
                 }
 
-                let count = pulses.len();
-                let pulse_items: Vec<_> = pulses.iter().map(|p| {
+                let shapes = state.call_shapes.get();
+                let filter = shape_filter.get();
+                let cf = cf_mode.get().then(|| state.cf_analysis.get()).flatten();
+                let cf_of = |index: usize| cf.as_ref().and_then(|a| a.calls.iter().find(|c| c.index == index));
+                let shape_of = |index: usize| shapes.iter().find(|s| s.index == index);
+                let visible: Vec<&DetectedPulse> = pulses.iter()
+                    .filter(|p| filter.is_none() || shape_of(p.index).map(|s| s.shape) == filter)
+                    .collect();
+                let count_text = if filter.is_some() {
+                    format!("Pulses ({} of {})", visible.len(), pulses.len())
+                } else {
+                    format!("Pulses ({})", pulses.len())
+                };
+                let pulse_items: Vec<_> = visible.into_iter().map(|p| {
                     let pulse = p.clone();
                     let pulse2 = p.clone();
                     let is_selected = selected == Some(p.index);
@@ -225,12 +310,34 @@ pub(crate) fn PulsePanel() -> impl IntoView {
                     let freq_khz = p.peak_freq / 1000.0;
                     let time_text = crate::format_time::format_time_display(p.start_time, 3);
                     let dur_text = format!("{:.1}ms", dur_ms);
-                    let freq_text = format!("{:.1}kHz", freq_khz);
+                    let cf_call = cf_of(p.index);
+                    let freq_text = match cf_call {
+                        Some(c) => format!("CF {:.2}kHz", c.cf_hz / 1000.0),
+                        None => format!("{:.1}kHz", freq_khz),
+                    };
                     let snr_text = format!("{:.0}dB", p.snr_db);
-                    let tooltip = format!(
+                    let shape = shape_of(p.index);
+                    let shape_text = shape.map(|s| s.shape.label()).unwrap_or("");
+                    let shape_class = match shape {
+                        Some(s) if s.confidence < 0.5 => "pulse-shape low-confidence",
+                        _ => "pulse-shape",
+                    };
+                    let mut tooltip = format!(
                         "Pulse #{}: {:.4}s \u{2013} {:.4}s ({:.2}ms)\nPeak freq: {:.1} kHz\nSNR: {:.1} dB",
                         p.index, p.start_time, p.end_time, dur_ms, freq_khz, p.snr_db
                     );
+                    if let Some(s) = shape {
+                        tooltip.push_str(&format!(
+                            "\nShape: {} ({:.0}% confidence)",
+                            s.shape.label(), s.confidence * 100.0
+                        ));
+                    }
+                    if let Some(c) = cf_call {
+                        tooltip.push_str(&format!(
+                            "\nCF: {:.3} kHz, {:.1} ms\ntFM: {:.1} ms, {:.1} kHz wide",
+                            c.cf_hz / 1000.0, c.cf_duration_ms(), c.tfm_duration_ms, c.tfm_bandwidth_hz() / 1000.0
+                        ));
+                    }
 
                     view! {
                         <div
@@ -242,14 +349,75 @@ pub(crate) fn PulsePanel() -> impl IntoView {
                             <span class="pulse-time">{time_text}</span>
                             <span class="pulse-dur">{dur_text}</span>
                             <span class="pulse-freq">{freq_text}</span>
+                            <span class=shape_class>{shape_text}</span>
                             <span class="pulse-snr">{snr_text}</span>
                         </div>
                     }
                 }).collect();
 
+                let calls = state.call_parameters.get();
+                let selected_call = selected
+                    .and_then(|i| calls.iter().find(|c| c.index == i).cloned());
+                let call_table = call_params::call_parameters_tsv(&calls);
+                let on_copy_table = move |_: web_sys::MouseEvent| {
+                    super::copy_to_clipboard(&call_table);
+                };
+
+                // Rank the regional bat book against the calls in the selection (or whole file)
+                let time_range = state.selection.get().map(|s| (s.time_start, s.time_end));
+                let favourites = state.bat_book_favourites.get();
+                let (resolved, summary, candidates) = matcher::rank_for_file(
+                    idx.and_then(|i| files.get(i)),
+                    &favourites,
+                    &calls,
+                    &shapes,
+                    time_range,
+                );
+                let species_view = summary.map(|summary| {
+                    species_candidates_view(resolved.source_label, summary, candidates, time_range.is_some())
+                });
+
+                let cf_view = cf.clone().map(cf_analysis_view);
+
+                let events = state.call_events.get();
+                let events_view = (!events.is_empty()).then(|| call_events_view(events, on_pulse_click));
+
+                let selected_contour = selected.and_then(|i| {
+                    state.call_contours.get().into_iter().find(|c| c.index == i)
+                });
+                let selected_view = selected_call.map(|c| call_parameters_view(c, selected_contour));
+
                 view! {
+                    {selected_view}
+                    {cf_view}
+                    {species_view}
+                    {events_view}
                     <div class="setting-group">
-                        <div class="setting-group-title">{format!("Pulses ({})", count)}</div>
+                        <div class="setting-group-title">{count_text}</div>
+                        <div class="setting-row">
+                            <span class="setting-label">"Shape"</span>
+                            <select
+                                on:change=move |ev: web_sys::Event| {
+                                    let target = ev.target().unwrap();
+                                    let select: web_sys::HtmlSelectElement = target.unchecked_into();
+                                    shape_filter.set(CallShape::from_label(&select.value()));
+                                }
+                            >
+                                <option value="" selected=filter.is_none()>"All"</option>
+                                {CallShape::ALL.iter().map(|&s| {
+                                    view! {
+                                        <option value=s.label() selected=filter == Some(s)>{s.label()}</option>
+                                    }
+                                }).collect::<Vec<_>>()}
+                            </select>
+                        </div>
+                        <div class="copy-report-row">
+                            <button
+                                class="copy-report-btn"
+                                on:click=on_copy_table
+                                title="Copy per-call measurements as a tab-separated table"
+                            >"Copy call table"</button>
+                        </div>
                         <div class="pulse-list">
                             {pulse_items}
                         </div>
@@ -260,6 +428,406 @@ pub(crate) fn PulsePanel() -> impl IntoView {
     }
 }
 
+/// Trace a contour per pulse. Uses the reassigned spectrogram when the
+/// reassigned view is on (sharper FM sweeps), otherwise the STFT columns.
+fn trace_contours(
+    audio: &AudioData,
+    spectrogram: &SpectrogramData,
+    pulses: &[DetectedPulse],
+    params: &ContourParams,
+    reassign: bool,
+    channel: ChannelView,
+) -> Vec<CallContour> {
+    if !reassign {
+        return contour::trace_calls(pulses, spectrogram, params);
+    }
+    let sr = audio.sample_rate;
+    // ~2 ms window, 1/16 hop
+    let fft_size = ((sr as usize) / 500).next_power_of_two().clamp(128, 1024);
+    let hop_size = fft_size / 16;
+    let total = audio.source.total_samples();
+    pulses.iter().map(|p| {
+        let start = ((p.start_time * sr as f64) as u64).saturating_sub(fft_size as u64);
+        let end = (((p.end_time * sr as f64) as u64) + fft_size as u64).min(total);
+        let samples = if end > start {
+            audio.source.read_region(channel, start, (end - start) as usize)
+        } else {
+            Vec::new()
+        };
+        let mut points = contour::trace_contour_reassigned(
+            &samples, sr, start as f64 / sr as f64, fft_size, hop_size,
+            (p.start_time, p.end_time), params,
+        );
+        if points.is_empty() {
+            points = contour::trace_contour(spectrogram, p.start_time, p.end_time, params);
+        }
+        CallContour { index: p.index, points }
+    }).collect()
+}
+
+/// CF component, resting frequency and terminal FM per call, measured on a
+/// zero-padded FFT of the CF samples; calls without a CF part are skipped.
+fn analyse_cf_calls(audio: &AudioData, contours: &[CallContour], channel: ChannelView) -> CfAnalysis {
+    let params = CfParams::default();
+    let sr = audio.sample_rate;
+    let total = audio.source.total_samples();
+    let calls: Vec<CfCall> = contours.iter().filter_map(|c| {
+        let (first, last) = (c.points.first()?, c.points.last()?);
+        let start = (first.time * sr as f64).max(0.0) as u64;
+        let end = ((last.time * sr as f64).ceil() as u64 + 1).min(total);
+        if end <= start {
+            return None;
+        }
+        let samples = audio.source.read_region(channel, start, (end - start) as usize);
+        cf_analysis::analyse_call(c.index, &c.points, &samples, sr, start as f64 / sr as f64, &params)
+    }).collect();
+    cf_analysis::summarize(calls, &PassParams::default(), &params)
+}
+
+/// Measurement grid for the selected call.
+fn call_parameters_view(c: CallParameters, contour: Option<CallContour>) -> impl IntoView {
+    let state = expect_context::<AppState>();
+    let khz = |hz: f64| format!("{:.1} kHz", hz / 1000.0);
+    let ipi_text = c.ipi_ms.map(|v| format!("{:.1} ms", v)).unwrap_or_else(|| "\u{2014}".into());
+    let stats: Vec<(String, &'static str, &'static str)> = vec![
+        (khz(c.f_start_hz), "Fstart", "Frequency at call start"),
+        (khz(c.f_end_hz), "Fend", "Frequency at call end"),
+        (khz(c.f_max_hz), "Fmax", "Highest frequency"),
+        (khz(c.f_min_hz), "Fmin", "Lowest frequency"),
+        (khz(c.f_char_hz), "Fchar", "Characteristic frequency (flattest part of the final 40%)"),
+        (khz(c.f_knee_hz), "Fknee", "Knee frequency (turn from steep FM into the tail)"),
+        (khz(c.f_max_energy_hz), "FmaxE", "Frequency of maximum energy"),
+        (khz(c.bandwidth_hz), "Bandwidth", "Fmax \u{2212} Fmin"),
+        (format!("{:.2} kHz/ms", c.mean_slope_khz_per_ms), "Slope", "Mean slope, start to end"),
+        (format!("{:.2} ms", c.duration_ms), "Duration", "Call duration"),
+        (ipi_text, "IPI", "Inter-pulse interval (start to start)"),
+    ];
+    let cells: Vec<_> = stats.into_iter().map(|(value, label, tip)| view! {
+        <div class="analysis-stat">
+            <span class="analysis-stat-value">{value}</span>
+            <span class="analysis-stat-label" title=tip>{label}</span>
+        </div>
+    }).collect();
+    let contour = contour.filter(|c| c.points.len() >= 2);
+    let save_button = contour.map(|contour| {
+        let on_save = move |_: web_sys::MouseEvent| save_contour_annotation(state, &contour);
+        view! {
+            <div class="copy-report-row">
+                <button
+                    class="copy-report-btn"
+                    on:click=on_save
+                    title="Save this call's frequency contour as a region annotation"
+                >"Save contour"</button>
+            </div>
+        }
+    });
+    view! {
+        <div class="setting-group">
+            <div class="setting-group-title">{format!("Call #{}", c.index)}</div>
+            <div class="analysis-stats">{cells}</div>
+            {save_button}
+        </div>
+    }
+}
+
+/// Store a call contour as a region annotation bounding the contour.
+fn save_contour_annotation(state: AppState, call: &CallContour) {
+    /// Keeps sidecar files small for long CF calls.
+    const MAX_STORED_POINTS: usize = 200;
+    let Some(idx) = state.current_file_index.get_untracked() else { return };
+    let (Some(first), Some(last)) = (call.points.first(), call.points.last()) else { return };
+    let f_lo = call.points.iter().map(|p| p.freq).fold(f64::INFINITY, f64::min);
+    let f_hi = call.points.iter().map(|p| p.freq).fold(0.0, f64::max);
+    let margin = ((f_hi - f_lo) * 0.1).max(1000.0);
+
+    state.snapshot_annotations();
+    state.push_annotations(idx, vec![Annotation {
+        id: generate_uuid(),
+        kind: AnnotationKind::Region(Region {
+            time_start: first.time,
+            time_end: last.time,
+            freq_low: Some((f_lo - margin).max(0.0)),
+            freq_high: Some(f_hi + margin),
+            label: Some(format!("Call #{}", call.index)),
+            color: None,
+            locked: None,
+            contour: Some(contour::simplify(&call.points, MAX_STORED_POINTS)),
+            position: None,
+        }),
+        created_at: now_iso8601(),
+        modified_at: now_iso8601(),
+        notes: None,
+        parent_id: None,
+        sort_order: None,
+        tags: Vec::new(),
+        label_default: None,
+        confidence: None,
+        review: None,
+    }]);
+    state.show_info_toast(format!("Contour of call #{} saved", call.index));
+}
+
+/// Buzz / social-call list with a button to turn them into annotations.
+fn call_events_view(
+    events: Vec<CallEvent>,
+    on_pulse_click: impl Fn(DetectedPulse) + Copy + Send + Sync + 'static,
+) -> impl IntoView {
+    let state = expect_context::<AppState>();
+    let buzzes = events.iter().filter(|e| e.kind == CallEventKind::FeedingBuzz).count();
+    let social = events.len() - buzzes;
+    let title = format!("Events ({} buzz, {} social)", buzzes, social);
+
+    let rows: Vec<_> = events.iter().map(|e| {
+        let event = e.clone();
+        let time_text = crate::format_time::format_time_display(e.start_time, 3);
+        let dur_text = format!("{:.0}ms", (e.end_time - e.start_time) * 1000.0);
+        let detail = match e.min_ipi_ms {
+            Some(ipi) => format!("{} pulses, IPI {:.1}ms", e.pulse_indices.len(), ipi),
+            None => format!("{:.1}kHz", e.freq_high_hz / 1000.0),
+        };
+        view! {
+            <div
+                class="pulse-item"
+                on:click=move |_| {
+                    // Navigate to the first pulse of the event
+                    let first = event.pulse_indices.first().copied();
+                    let pulse = first.and_then(|i| {
+                        state.detected_pulses.get_untracked().into_iter().find(|p| p.index == i)
+                    });
+                    if let Some(pulse) = pulse {
+                        on_pulse_click(pulse);
+                    }
+                }
+            >
+                <span class="pulse-shape">{e.kind.label()}</span>
+                <span class="pulse-time">{time_text}</span>
+                <span class="pulse-dur">{dur_text}</span>
+                <span class="pulse-snr">{detail}</span>
+            </div>
+        }
+    }).collect();
+
+    let on_annotate = move |_: web_sys::MouseEvent| {
+        annotate_call_events(state, &events);
+    };
+
+    view! {
+        <div class="setting-group">
+            <div class="setting-group-title">{title}</div>
+            <div class="copy-report-row">
+                <button
+                    class="copy-report-btn"
+                    on:click=on_annotate
+                    title="Add each feeding buzz and social call as a tagged region annotation"
+                >"Add as regions"</button>
+            </div>
+            <div class="pulse-list">{rows}</div>
+        </div>
+    }
+}
+
+/// Add call events to the current file's annotations as tagged regions,
+/// grouped under a single "Call events" group.
+fn annotate_call_events(state: AppState, events: &[CallEvent]) {
+    let Some(idx) = state.current_file_index.get_untracked() else { return };
+    if events.is_empty() {
+        return;
+    }
+
+    state.snapshot_annotations();
+
+    let group_id = generate_uuid();
+    let mut annotations = vec![Annotation {
+        id: group_id.clone(),
+        kind: AnnotationKind::Group(Group {
+            label: Some("Call events".to_string()),
+            color: None,
+            collapsed: Some(false),
+        }),
+        created_at: now_iso8601(),
+        modified_at: now_iso8601(),
+        notes: None,
+        parent_id: None,
+        sort_order: None,
+        tags: Vec::new(),
+        label_default: None,
+        confidence: None,
+        review: None,
+    }];
+
+    for (i, e) in events.iter().enumerate() {
+        let (color, label) = match e.kind {
+            CallEventKind::FeedingBuzz => ("#ff8844", format!("Buzz ({} pulses)", e.pulse_indices.len())),
+            CallEventKind::SocialCall => ("#cc66ff", "Social call".to_string()),
+        };
+        let notes = match e.min_ipi_ms {
+            Some(ipi) => format!("Pulses #{}\u{2013}#{}, min IPI {:.1} ms",
+                e.pulse_indices.first().unwrap_or(&0), e.pulse_indices.last().unwrap_or(&0), ipi),
+            None => format!("Pulse #{}", e.pulse_indices.first().unwrap_or(&0)),
+        };
+        annotations.push(Annotation {
+            id: generate_uuid(),
+            kind: AnnotationKind::Region(Region {
+                time_start: e.start_time,
+                time_end: e.end_time,
+                freq_low: None,
+                freq_high: None,
+                label: Some(label),
+                color: Some(color.to_string()),
+                locked: None,
+                contour: None,
+                position: None,
+            }),
+            created_at: now_iso8601(),
+            modified_at: now_iso8601(),
+            notes: Some(notes),
+            parent_id: Some(group_id.clone()),
+            sort_order: Some(i as f64),
+            tags: vec![e.kind.tag().to_string()],
+            label_default: None,
+            confidence: None,
+            review: None,
+        });
+    }
+
+    state.push_annotations(idx, annotations);
+    state.show_info_toast(format!("{} call events annotated", events.len()));
+}
+
+/// Top bat book suggestions for the measured calls.
+fn species_candidates_view(
+    book_label: String,
+    summary: CallSummary,
+    candidates: Vec<SpeciesCandidate>,
+    in_selection: bool,
+) -> impl IntoView {
+    const MAX_SHOWN: usize = 5;
+    let scope = if in_selection { "in selection" } else { "in file" };
+    let shape_text = summary
+        .dominant_shape
+        .map(|(shape, share)| format!(", mostly {} ({:.0}%)", shape.label(), share * 100.0))
+        .unwrap_or_default();
+    let summary_text = format!(
+        "{} calls {}: Fchar {:.1} kHz{}",
+        summary.call_count, scope, summary.f_char_hz / 1000.0, shape_text
+    );
+    let rows: Vec<_> = candidates.into_iter().take(MAX_SHOWN).map(|c| {
+        let name = if c.entry.scientific_name.is_empty() {
+            c.entry.name.to_string()
+        } else {
+            format!("{} ({})", c.entry.name, c.entry.scientific_name)
+        };
+        let score_text = format!("{:.0}%", c.score * 100.0);
+        let reasons = c.reasons.join(", ");
+        view! {
+            <div class="species-candidate">
+                <div class="species-candidate-head">
+                    <span class="species-candidate-name">{name}</span>
+                    <span class="species-candidate-score">{score_text}</span>
+                </div>
+                <div class="species-candidate-reasons">{reasons}</div>
+            </div>
+        }
+    }).collect();
+    let empty = rows.is_empty();
+    view! {
+        <div class="setting-group">
+            <div class="setting-group-title">{format!("Suggestions \u{2014} {}", book_label)}</div>
+            <div class="species-candidate-summary">{summary_text}</div>
+            {rows}
+            {empty.then(|| view! { <div class="sidebar-panel-empty">"No close matches in this book"</div> })}
+            <div class="species-candidate-note">"Suggestions only \u{2014} not an identification."</div>
+        </div>
+    }
+}
+
+/// CF summary: per-pass Doppler compensation and a histogram of call CFs.
+fn cf_analysis_view(analysis: CfAnalysis) -> impl IntoView {
+    if analysis.calls.is_empty() {
+        return view! {
+            <div class="setting-group">
+                <div class="setting-group-title">"CF analysis"</div>
+                <div class="sidebar-panel-empty">"No calls with a CF component"</div>
+            </div>
+        }.into_any();
+    }
+
+    let mut cfs: Vec<f64> = analysis.calls.iter().map(|c| c.cf_hz).collect();
+    cfs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let median = cfs[cfs.len() / 2];
+    let resting = analysis.passes.iter().map(|p| p.resting_hz).fold(f64::NAN, f64::max);
+    let mean_cf_ms = analysis.calls.iter().map(|c| c.cf_duration_ms()).sum::<f64>() / analysis.calls.len() as f64;
+    let stats: Vec<(String, &'static str, &'static str)> = vec![
+        (analysis.calls.len().to_string(), "CF calls", "Calls with a constant-frequency component"),
+        (format!("{:.2} kHz", median / 1000.0), "Median CF", "Median CF over all calls"),
+        (
+            if resting.is_nan() { "\u{2014}".into() } else { format!("{:.2} kHz", resting / 1000.0) },
+            "Resting",
+            "Highest pass CF: estimate of the resting frequency",
+        ),
+        (format!("{:.1} ms", mean_cf_ms), "CF dur.", "Mean duration of the CF component"),
+    ];
+    let stat_items: Vec<_> = stats.into_iter().map(|(value, label, title)| view! {
+        <div class="analysis-stat" title=title>
+            <span class="analysis-stat-value">{value}</span>
+            <span class="analysis-stat-label">{label}</span>
+        </div>
+    }).collect();
+
+    let pass_rows: Vec<_> = analysis.passes.iter().map(|p| view! {
+        <tr>
+            <td>{crate::format_time::format_time_display(p.start_time, 1)}</td>
+            <td>{p.indices.len()}</td>
+            <td>{format!("{:.2}", p.resting_hz / 1000.0)}</td>
+            <td>{format!("{:.0}", p.doppler_range_hz)}</td>
+            <td>{format!("{:.1}", p.doppler_speed_m_s)}</td>
+        </tr>
+    }).collect();
+    let has_passes = !pass_rows.is_empty();
+
+    let max_count = analysis.histogram.iter().map(|(_, n)| *n).max().unwrap_or(1).max(1);
+    let bin_khz = CfParams::default().histogram_bin_hz / 1000.0;
+    let bars: Vec<_> = analysis.histogram.iter().map(|&(f, n)| {
+        let title = format!("{:.1}\u{2013}{:.1} kHz: {} call(s)", f / 1000.0, f / 1000.0 + bin_khz, n);
+        let height = format!("height: {:.0}%", n as f64 / max_count as f64 * 100.0);
+        view! { <div class="cf-histogram-bar" title=title><div style=height></div></div> }
+    }).collect();
+    let hist_range = match (analysis.histogram.first(), analysis.histogram.last()) {
+        (Some(a), Some(b)) => format!("{:.1} \u{2013} {:.1} kHz", a.0 / 1000.0, b.0 / 1000.0 + bin_khz),
+        _ => String::new(),
+    };
+
+    let tsv = cf_analysis::cf_calls_tsv(&analysis.calls);
+    let on_copy = move |_: web_sys::MouseEvent| super::copy_to_clipboard(&tsv);
+
+    view! {
+        <div class="setting-group">
+            <div class="setting-group-title">"CF analysis"</div>
+            <div class="analysis-stats">{stat_items}</div>
+            {has_passes.then(|| view! {
+                <table class="cf-pass-table">
+                    <tr>
+                        <th>"Pass"</th>
+                        <th>"Calls"</th>
+                        <th title="Highest CF in the pass (kHz)">"Rest kHz"</th>
+                        <th title="Doppler compensation range (Hz)">"\u{0394}f Hz"</th>
+                        <th title="Flight speed implied by the range (m/s)">"m/s"</th>
+                    </tr>
+                    {pass_rows}
+                </table>
+            })}
+            <div class="cf-histogram">{bars}</div>
+            <div class="species-candidate-note">{hist_range}</div>
+            <div class="copy-report-row">
+                <button
+                    class="copy-report-btn"
+                    on:click=on_copy
+                    title="Copy per-call CF measurements as a tab-separated table"
+                >"Copy CF table"</button>
+            </div>
+        </div>
+    }.into_any()
+}
 
 fn event_target_value(ev: &web_sys::Event) -> String {
     use wasm_bindgen::JsCast;
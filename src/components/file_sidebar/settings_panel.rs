@@ -4,7 +4,7 @@ use crate::state::{
     AppState, FlowColorScheme, MainView, ResonatorFftMode, ResonatorLayout, SpectrogramDisplay,
     RESONATOR_BW_SLIDER_MAX, resonator_bw_to_slider, resonator_slider_to_bw,
 };
-use crate::annotations::{Annotation, AnnotationKind, AnnotationSet, Group, generate_uuid, now_iso8601, build_annotation_tree, AnnotationNode, collect_descendants, renumber_children};
+use crate::annotations::{Annotation, AnnotationKind, AnnotationSet, Group, generate_uuid, now_iso8601, build_annotation_tree, AnnotationNode, collect_descendants, renumber_children, ReviewStatus};
 
 /// Sample rate used to annotate the Resonators settings panel — the live
 /// waterfall's rate when mic is active, otherwise the current file's, with a
@@ -668,7 +668,7 @@ fn WavMarkersList() -> impl IntoView {
 /// Get the display label for an annotation.
 /// Returns (display_text, stored_label, is_default).
 /// is_default is true when the label was auto-generated (render italic).
-fn annotation_display(a: &Annotation) -> (String, Option<String>, bool) {
+pub(crate) fn annotation_display(a: &Annotation) -> (String, Option<String>, bool) {
     let is_default = a.label_default.unwrap_or(false);
     match &a.kind {
         AnnotationKind::Region(reg) => {
@@ -807,6 +807,10 @@ fn AnnotationsList() -> impl IntoView {
                 }
             })
             on_import_batm=Callback::new(move |()| import_annotations(state))
+            on_export_raven=Callback::new(move |()| export_raven(state))
+            on_import_raven=Callback::new(move |()| import_raven(state))
+            on_export_audacity=Callback::new(move |()| export_audacity(state))
+            on_import_audacity=Callback::new(move |()| import_audacity(state))
             has_annotations=Signal::derive(has_annotations)
             has_file_path=Signal::derive(has_file_path)
         />
@@ -824,6 +828,14 @@ fn render_tree_nodes(nodes: Vec<AnnotationNode>, state: AppState) -> impl IntoVi
             _ => false,
         };
         let existing_tags = node.annotation.tags.clone();
+        let review_status = node.annotation.review_status();
+        let review_title = node.annotation.review.as_ref().map(|r| {
+            let mut t = r.status.label().to_string();
+            if let Some(ref who) = r.reviewer { t.push_str(&format!(" by {who}")); }
+            if let Some(ref when) = r.reviewed_at { t.push_str(&format!(", {}", when.get(..10).unwrap_or(when))); }
+            t
+        });
+        let confidence = node.annotation.confidence;
         let depth = node.depth;
         let children = node.children;
 
@@ -833,6 +845,10 @@ fn render_tree_nodes(nodes: Vec<AnnotationNode>, state: AppState) -> impl IntoVi
             _ => false,
         };
         let locked_signal = RwSignal::new(initial_locked);
+        let readout = match &node.annotation.kind {
+            AnnotationKind::Measurement(m) => Some(m.readout()),
+            _ => None,
+        };
 
         let id_click = id.clone();
         let id_delete = id.clone();
@@ -850,6 +866,7 @@ fn render_tree_nodes(nodes: Vec<AnnotationNode>, state: AppState) -> impl IntoVi
         let initial_edit = if label_is_default { String::new() } else { existing_label.unwrap_or_default() };
         let edit_value = RwSignal::new(initial_edit);
         let tags_value = RwSignal::new(existing_tags.join(", "));
+        let confidence_value = RwSignal::new(confidence.map(|c| format!("{:.0}", c * 100.0)).unwrap_or_default());
 
         let indent_px = depth * 16;
 
@@ -866,6 +883,7 @@ fn render_tree_nodes(nodes: Vec<AnnotationNode>, state: AppState) -> impl IntoVi
                 class:annotation-group-item=is_group
                 style:padding-left=format!("{}px", 8 + indent_px)
                 draggable="true"
+                title=readout
                 on:click=move |ev: web_sys::MouseEvent| {
                     let click_id = id.clone();
                     let ctrl = ev.ctrl_key() || ev.meta_key();
@@ -995,7 +1013,13 @@ fn render_tree_nodes(nodes: Vec<AnnotationNode>, state: AppState) -> impl IntoVi
                                 .filter(|s| !s.is_empty())
                                 .collect();
                             update_annotation_tags(state, id_t, tags);
+                            let confidence = confidence_value.try_get_untracked().unwrap_or_default()
+                                .trim().trim_end_matches('%').parse::<f64>().ok()
+                                .map(|pct| (pct / 100.0).clamp(0.0, 1.0));
+                            update_annotation_confidence(state, id_t, confidence);
                         };
+                        let id_l_conf = id_save.clone();
+                        let id_t_conf = id_tags_save.clone();
                         let id_l_enter = id_save.clone();
                         let id_t_enter = id_tags_save.clone();
                         let id_l_blur = id_save2.clone();
@@ -1055,14 +1079,41 @@ fn render_tree_nodes(nodes: Vec<AnnotationNode>, state: AppState) -> impl IntoVi
                                         }
                                     }
                                 />
+                                <input
+                                    class="annotation-label-input annotation-confidence-input"
+                                    type="text"
+                                    inputmode="numeric"
+                                    prop:value=move || confidence_value.try_get().unwrap_or_default()
+                                    placeholder="Confidence %"
+                                    on:input=move |ev| {
+                                        let _ = confidence_value.try_set(leptos::prelude::event_target_value(&ev));
+                                    }
+                                    on:keydown=move |ev| {
+                                        if ev.key() == "Enter" {
+                                            save_all(&id_l_conf, &id_t_conf);
+                                            let _ = editing.try_set(false);
+                                        } else if ev.key() == "Escape" {
+                                            let _ = editing.try_set(false);
+                                        }
+                                    }
+                                />
                             </div>
                         }.into_any()
                     } else {
                         let tags_pills = tags_display.clone();
                         let label_class = if label_is_default { "annotation-label default" } else { "annotation-label" };
+                        let review_badge = (review_status != ReviewStatus::Unreviewed).then(|| {
+                            let class = format!("annotation-review {}", review_status.label().to_lowercase());
+                            view! { <span class=class title=review_title.clone()>{review_status.icon()}</span> }
+                        });
+                        let confidence_badge = confidence.map(|c| view! {
+                            <span class="annotation-confidence" title="Identification confidence">{format!("{:.0}%", c * 100.0)}</span>
+                        });
                         view! {
                             <span class=label_class>
+                                {review_badge}
                                 {display.clone()}
+                                {confidence_badge}
                                 {if !tags_pills.is_empty() {
                                     view! {
                                         <span class="annotation-tags">
@@ -1142,7 +1193,7 @@ fn render_tree_nodes(nodes: Vec<AnnotationNode>, state: AppState) -> impl IntoVi
 
 // --- Helper functions ---
 
-fn restore_selection(state: AppState, annotation_id: &str) {
+pub(crate) fn restore_selection(state: AppState, annotation_id: &str) {
     let idx = match state.current_file_index.get_untracked() {
         Some(i) => i,
         None => return,
@@ -1201,6 +1252,7 @@ pub(crate) fn toggle_annotation_lock(state: AppState, annotation_id: &str, locke
         Some(i) => i,
         None => return,
     };
+    state.snapshot_annotations();
     state.annotation_store.update(|store| {
         if let Some(Some(ref mut set)) = store.sets.get_mut(idx) {
             if let Some(ann) = set.annotations.iter_mut().find(|a| a.id == annotation_id) {
@@ -1283,6 +1335,16 @@ pub(crate) fn update_annotation_tags(state: AppState, annotation_id: &str, tags:
         Some(i) => i,
         None => return,
     };
+    // Saved together with the label; only record an undo step if the tags changed
+    let unchanged = state.annotation_store.with_untracked(|store| {
+        store.sets.get(idx).and_then(|s| s.as_ref())
+            .and_then(|set| set.annotations.iter().find(|a| a.id == annotation_id))
+            .is_some_and(|a| a.tags == tags)
+    });
+    if unchanged {
+        return;
+    }
+    state.snapshot_annotations();
     state.annotation_store.update(|store| {
         if let Some(Some(ref mut set)) = store.sets.get_mut(idx) {
             if let Some(a) = set.annotations.iter_mut().find(|a| a.id == annotation_id) {
@@ -1294,6 +1356,32 @@ pub(crate) fn update_annotation_tags(state: AppState, annotation_id: &str, tags:
     state.annotations_dirty.set(true);
 }
 
+pub(crate) fn update_annotation_confidence(state: AppState, annotation_id: &str, confidence: Option<f64>) {
+    let idx = match state.current_file_index.get_untracked() {
+        Some(i) => i,
+        None => return,
+    };
+    // Saved together with the label; only record an undo step if it changed
+    let unchanged = state.annotation_store.with_untracked(|store| {
+        store.sets.get(idx).and_then(|s| s.as_ref())
+            .and_then(|set| set.annotations.iter().find(|a| a.id == annotation_id))
+            .is_some_and(|a| a.confidence == confidence)
+    });
+    if unchanged {
+        return;
+    }
+    state.snapshot_annotations();
+    state.annotation_store.update(|store| {
+        if let Some(Some(ref mut set)) = store.sets.get_mut(idx) {
+            if let Some(a) = set.annotations.iter_mut().find(|a| a.id == annotation_id) {
+                a.confidence = confidence;
+                a.modified_at = now_iso8601();
+            }
+        }
+    });
+    state.annotations_dirty.set(true);
+}
+
 fn toggle_group_collapsed(state: AppState, annotation_id: &str) {
     let idx = match state.current_file_index.get_untracked() {
         Some(i) => i,
@@ -1305,6 +1393,7 @@ fn toggle_group_collapsed(state: AppState, annotation_id: &str) {
                 if let AnnotationKind::Group(ref mut g) = a.kind {
                     let cur = g.collapsed.unwrap_or(false);
                     g.collapsed = Some(!cur);
+                    a.modified_at = now_iso8601();
                 }
             }
         }
@@ -1348,12 +1437,14 @@ fn group_selected(state: AppState) {
                 id: group_id.clone(),
                 kind,
                 created_at: now.clone(),
-                modified_at: now,
+                modified_at: now.clone(),
                 notes: None,
                 parent_id: parent,
                 sort_order: order,
                 tags: Vec::new(),
                 label_default: Some(true),
+                confidence: None,
+                review: None,
             };
             set.annotations.push(group);
 
@@ -1362,6 +1453,7 @@ fn group_selected(state: AppState) {
                 if sel_ids.contains(&a.id) {
                     a.parent_id = Some(group_id.clone());
                     a.sort_order = Some(i as f64);
+                    a.modified_at = now.clone();
                 }
             }
 
@@ -1397,9 +1489,11 @@ fn ungroup_selected(state: AppState) {
             };
 
             // Move all direct children to the group's parent level
+            let now = now_iso8601();
             for a in set.annotations.iter_mut() {
                 if a.parent_id.as_deref() == Some(group_id.as_str()) {
                     a.parent_id = group_parent.clone();
+                    a.modified_at = now.clone();
                 }
             }
 
@@ -1457,6 +1551,7 @@ fn perform_drop(state: AppState) {
                     if let Some(a) = set.annotations.iter_mut().find(|a| a.id == dragged_id) {
                         a.parent_id = Some(target_id.clone());
                         a.sort_order = Some(f64::MAX); // append to end
+                        a.modified_at = now_iso8601();
                     }
                     renumber_children(&mut set.annotations, Some(target_id.as_str()));
                 }
@@ -1464,6 +1559,7 @@ fn perform_drop(state: AppState) {
                     if let Some(a) = set.annotations.iter_mut().find(|a| a.id == dragged_id) {
                         a.parent_id = target_parent.clone();
                         a.sort_order = Some(target_order - 0.5);
+                        a.modified_at = now_iso8601();
                     }
                     renumber_children(&mut set.annotations, target_parent.as_deref());
                 }
@@ -1471,6 +1567,7 @@ fn perform_drop(state: AppState) {
                     if let Some(a) = set.annotations.iter_mut().find(|a| a.id == dragged_id) {
                         a.parent_id = target_parent.clone();
                         a.sort_order = Some(target_order + 0.5);
+                        a.modified_at = now_iso8601();
                     }
                     renumber_children(&mut set.annotations, target_parent.as_deref());
                 }
@@ -1532,11 +1629,67 @@ fn export_annotations(state: AppState) {
     }
 }
 
-fn import_annotations(state: AppState) {
+/// Files covered by a Raven table: the active timeline's files at their
+/// offsets, or the current file on its own. Returns `(file index, placement)`.
+fn raven_files(state: AppState) -> Vec<(usize, crate::raven::RavenFile)> {
+    let files = state.files.get_untracked();
+    let placement = |idx: usize, offset_secs: f64| {
+        files.get(idx).map(|f| (idx, crate::raven::RavenFile {
+            name: f.name.clone(),
+            offset_secs,
+            duration_secs: f.audio.duration_secs,
+            nyquist: f.audio.sample_rate as f64 / 2.0,
+        }))
+    };
+    if let Some(tl) = state.active_timeline.get_untracked() {
+        tl.segments.iter().filter_map(|seg| placement(seg.file_index, seg.timeline_offset_secs)).collect()
+    } else {
+        state.current_file_index.get_untracked()
+            .and_then(|idx| placement(idx, 0.0))
+            .into_iter()
+            .collect()
+    }
+}
+
+fn export_raven(state: AppState) {
+    let files = raven_files(state);
+    if files.is_empty() {
+        state.show_error_toast("No file selected");
+        return;
+    }
+    let store = state.annotation_store.get_untracked();
+    let empty: Vec<Annotation> = Vec::new();
+    let tables: Vec<(crate::raven::RavenFile, &[Annotation])> = files.iter()
+        .map(|(idx, file)| {
+            let annotations = store.sets.get(*idx)
+                .and_then(|s| s.as_ref())
+                .map(|set| set.annotations.as_slice())
+                .unwrap_or(&empty);
+            (file.clone(), annotations)
+        })
+        .collect();
+    if tables.iter().all(|(_, a)| a.is_empty()) {
+        state.show_error_toast("No annotations to export");
+        return;
+    }
+    let text = crate::raven::export_selection_table(&tables);
+    let base = if files.len() > 1 {
+        "timeline".to_string()
+    } else {
+        let name = &files[0].1.name;
+        name.rsplit_once('.').map_or(name.as_str(), |(b, _)| b).to_string()
+    };
+    crate::audio::export::trigger_browser_download(text.as_bytes(), &format!("{base}.selections.txt"));
+    state.show_info_toast("Raven selection table exported");
+}
+
+/// Ask the user for a text file and pass its contents to `on_text`.
+fn pick_text_file(accept: &str, on_text: impl Fn(String) + 'static) {
+    let on_text = std::rc::Rc::new(on_text);
     let doc = web_sys::window().unwrap().document().unwrap();
     let input: web_sys::HtmlInputElement = doc.create_element("input").unwrap().unchecked_into();
     input.set_type("file");
-    input.set_attribute("accept", ".batm,.yaml,.yml").unwrap();
+    input.set_attribute("accept", accept).unwrap();
 
     let on_change = wasm_bindgen::closure::Closure::<dyn FnMut(web_sys::Event)>::new(move |ev: web_sys::Event| {
         let target: web_sys::HtmlInputElement = ev.target().unwrap().unchecked_into();
@@ -1545,28 +1698,9 @@ fn import_annotations(state: AppState) {
 
         let reader = web_sys::FileReader::new().unwrap();
         let reader_clone = reader.clone();
+        let on_text = on_text.clone();
         let on_load = wasm_bindgen::closure::Closure::<dyn FnMut(web_sys::Event)>::new(move |_: web_sys::Event| {
-            let result = reader_clone.result().unwrap();
-            let text = result.as_string().unwrap_or_default();
-            match yaml_serde::from_str::<AnnotationSet>(&text) {
-                Ok(imported) => {
-                    let idx = state.current_file_index.get_untracked().unwrap_or(0);
-                    state.snapshot_annotations();
-                    state.annotation_store.update(|store| {
-                        store.ensure_len(idx + 1);
-                        if let Some(Some(ref mut existing)) = store.sets.get_mut(idx) {
-                            existing.annotations.extend(imported.annotations);
-                        } else {
-                            store.sets[idx] = Some(imported);
-                        }
-                    });
-                    state.annotations_dirty.set(true);
-                    state.show_info_toast("Annotations imported");
-                }
-                Err(e) => {
-                    state.show_error_toast(format!("Import error: {e}"));
-                }
-            }
+            on_text(reader_clone.result().unwrap().as_string().unwrap_or_default());
         });
         reader.set_onload(Some(on_load.as_ref().unchecked_ref()));
         on_load.forget();
@@ -1576,3 +1710,89 @@ fn import_annotations(state: AppState) {
     on_change.forget();
     input.click();
 }
+
+fn import_raven(state: AppState) {
+    pick_text_file(".txt,.tsv", move |text| {
+        let files = raven_files(state);
+        if files.is_empty() {
+            state.show_error_toast("No file selected");
+            return;
+        }
+        let placements: Vec<_> = files.iter().map(|(_, f)| f.clone()).collect();
+        let imported = match crate::raven::parse_selection_table(&text, &placements) {
+            Ok(r) => r,
+            Err(e) => { state.show_error_toast(format!("Import error: {e}")); return; }
+        };
+        let count = imported.annotations.len();
+        let mut per_file: Vec<Vec<Annotation>> = vec![Vec::new(); files.len()];
+        for (i, a) in imported.annotations {
+            per_file[i].push(a);
+        }
+        let current = state.current_file_index.get_untracked();
+        for ((idx, _), annotations) in files.iter().zip(per_file) {
+            if annotations.is_empty() {
+                continue;
+            }
+            state.snapshot_file_annotations(*idx);
+            state.push_annotations(*idx, annotations);
+            if Some(*idx) != current {
+                crate::opfs::save_annotations(state, *idx);
+            }
+        }
+        if imported.skipped > 0 {
+            state.show_info_toast(format!("Imported {count} selections ({} rows skipped)", imported.skipped));
+        } else {
+            state.show_info_toast(format!("Imported {count} selections"));
+        }
+    });
+}
+
+fn export_audacity(state: AppState) {
+    let Some(idx) = state.current_file_index.get_untracked() else {
+        state.show_error_toast("No file selected");
+        return;
+    };
+    let store = state.annotation_store.get_untracked();
+    let Some(set) = store.sets.get(idx).and_then(|s| s.as_ref()).filter(|s| !s.annotations.is_empty()) else {
+        state.show_error_toast("No annotations to export");
+        return;
+    };
+    let text = crate::audacity::export_labels(&set.annotations);
+    let name = &set.file_identity.filename;
+    let base = name.rsplit_once('.').map_or(name.as_str(), |(b, _)| b);
+    crate::audio::export::trigger_browser_download(text.as_bytes(), &format!("{base}_labels.txt"));
+    state.show_info_toast("Audacity labels exported");
+}
+
+fn import_audacity(state: AppState) {
+    pick_text_file(".txt", move |text| {
+        let Some(idx) = state.current_file_index.get_untracked() else {
+            state.show_error_toast("No file selected");
+            return;
+        };
+        match crate::audacity::parse_labels(&text) {
+            Ok(labels) if labels.is_empty() => state.show_info_toast("No labels in file"),
+            Ok(labels) => {
+                let count = labels.len();
+                state.snapshot_annotations();
+                state.push_annotations(idx, labels);
+                state.show_info_toast(format!("Imported {count} labels"));
+            }
+            Err(e) => state.show_error_toast(format!("Import error: {e}")),
+        }
+    });
+}
+
+fn import_annotations(state: AppState) {
+    pick_text_file(".batm,.yaml,.yml", move |text| {
+        match yaml_serde::from_str::<AnnotationSet>(&text) {
+            Ok(imported) => {
+                let idx = state.current_file_index.get_untracked().unwrap_or(0);
+                super::merge_conflicts::merge_into_file(state, idx, imported, "imported .batm");
+            }
+            Err(e) => {
+                state.show_error_toast(format!("Import error: {e}"));
+            }
+        }
+    });
+}
@@ -2,7 +2,7 @@ use leptos::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::File;
 use std::sync::Arc;
-use crate::audio::loader::{id3v2_tag_size, is_m4a, is_mp3, is_ogg, parse_flac_header, parse_m4a_chapters, parse_mp3_header, parse_ogg_header, parse_wav_header_with_file_size};
+use crate::audio::loader::{id3v2_tag_size, is_m4a, is_mp3, is_ogg, parse_flac_header, parse_m4a_chapters, parse_mp3_header, parse_ogg_header, parse_wav_header_with_file_size, WavHeader};
 use crate::audio::streaming_source::{FileHandle, StreamingFlacSource, StreamingM4aSource, StreamingMp3Source, StreamingOggSource, StreamingWavSource, read_blob_range};
 use crate::dsp::fft::compute_preview;
 use crate::state::{AppState, FileSettings, LoadedFile};
@@ -38,19 +38,27 @@ pub(super) async fn try_streaming_wav(file: &File, name: &str, state: AppState,
     if header_bytes.len() < 12 {
         return Err("Header too small".into());
     }
-    let magic = &header_bytes[0..4];
-    if magic != b"RIFF" && magic != b"RF64" {
-        return Err("Not a RIFF/RF64 file".into());
-    }
-
-    let header = parse_wav_header_with_file_size(&header_bytes, Some(file.size() as u64))?;
+    let is_riff = crate::audio::riff::is_wave(&header_bytes);
+    let wave64 = crate::audio::riff::is_wave64(&header_bytes);
+
+    // AIFF and CAF are plain PCM too and stream through the same source
+    let (header, format) = if is_riff {
+        (parse_wav_header_with_file_size(&header_bytes, Some(file.size() as u64))?, "WAV")
+    } else if crate::audio::aiff::is_aiff(&header_bytes) {
+        (crate::audio::aiff::parse_aiff_header(&header_bytes)?, "AIFF")
+    } else if crate::audio::caf::is_caf(&header_bytes) {
+        (crate::audio::caf::parse_caf_header(&header_bytes, Some(file.size() as u64))?, "CAF")
+    } else {
+        return Err("Not a RIFF/RF64/W64/AIFF/CAF file".into());
+    };
 
     // Check if decoded size warrants streaming
     let decoded_bytes = header.total_frames * header.channels as u64 * 4; // f32 per sample
     should_stream_from_decoded_size(decoded_bytes, force_streaming)?;
 
     log::info!(
-        "Streaming WAV: {} — {} frames, {} ch, {} Hz, {:.1}s, decoded {:.0} MB",
+        "Streaming {}: {} — {} frames, {} ch, {} Hz, {:.1}s, decoded {:.0} MB",
+        format,
         name,
         header.total_frames,
         header.channels,
@@ -83,12 +91,7 @@ pub(super) async fn try_streaming_wav(file: &File, name: &str, state: AppState,
     let head_pcm_bytes = read_blob_range(file, head_byte_start as f64, head_byte_end as f64).await?;
 
     // Decode PCM to f32
-    let head_interleaved = decode_head_pcm(
-        &head_pcm_bytes,
-        header.bits_per_sample,
-        header.is_float,
-        header.channels,
-    );
+    let head_interleaved = decode_head_pcm(&head_pcm_bytes, &header);
 
     let channels = header.channels as usize;
     let (head_mono, head_raw) = if channels == 1 {
@@ -103,19 +106,31 @@ pub(super) async fn try_streaming_wav(file: &File, name: &str, state: AppState,
 
     // Try to get GUANO metadata if not already in header
     let mut guano = header.guano.clone();
-    if guano.is_none() {
+    let mut bwf_fields = header.bwf.clone();
+    if guano.is_none() && is_riff {
         // GUANO might be after the data chunk — read tail of file
         let file_size = file.size();
         let data_end = header.data_offset + header.data_size;
+        // Wave64 chunks are 8-byte aligned
+        let data_end = if wave64 { data_end.next_multiple_of(8) } else { data_end };
         if (data_end as f64) < file_size {
             let tail_start = data_end as f64;
             // Read up to 64KB from after the data chunk
             let tail_end = file_size.min(tail_start + 65536.0);
             if let Ok(tail_bytes) = read_blob_range(file, tail_start, tail_end).await {
-                guano = scan_tail_for_guano(&tail_bytes);
+                guano = scan_tail_for_guano(&tail_bytes, wave64);
+                // iXML is often written after the audio too
+                if bwf_fields.is_empty() {
+                    bwf_fields = crate::audio::bwf::bwf_fields(
+                        crate::audio::riff::chunks_from(&tail_bytes, 0, wave64).with_bodies(),
+                        Some(header.sample_rate),
+                    );
+                }
             }
         }
     }
+    let guano = crate::audio::bwf::merge_into_guano(guano, bwf_fields);
+    let guano = crate::audio::audiomoth::merge_into_guano(guano, header.audiomoth.as_ref(), Some(header.sample_rate));
 
     // Create StreamingWavSource
     let source = Arc::new(StreamingWavSource::new(
@@ -132,7 +147,7 @@ pub(super) async fn try_streaming_wav(file: &File, name: &str, state: AppState,
     // For backward compat: audio.samples = head_mono
     let samples = Arc::new(head_mono);
 
-    let audio = AudioData {
+    let mut audio = AudioData {
         samples,
         source,
         sample_rate,
@@ -140,14 +155,17 @@ pub(super) async fn try_streaming_wav(file: &File, name: &str, state: AppState,
         duration_secs,
         metadata: crate::types::FileMetadata {
             file_size: file.size() as usize,
-            format: "WAV",
+            format,
             bits_per_sample: header.bits_per_sample,
             is_float: header.is_float,
             guano,
             data_offset: Some(header.data_offset),
             data_size: Some(header.data_size),
+            time_expansion: 1.0,
         },
     };
+    crate::audio::time_expansion::detect_and_apply(&mut audio, name);
+    let sample_rate = audio.sample_rate;
 
     // Compute preview from head samples (fast)
     let preview = compute_preview(&audio, 256, 128);
@@ -223,6 +241,7 @@ pub(super) async fn try_streaming_wav(file: &File, name: &str, state: AppState,
                 loading_id: Some(load_id),
                 min_display_freq: None,
                 max_display_freq: None,
+                zc_dots: None,
             });
             state.current_file_index.set(Some(idx));
         });
@@ -254,7 +273,7 @@ pub(super) async fn try_streaming_wav(file: &File, name: &str, state: AppState,
     // Prefetch first viewport worth of audio, then schedule tiles
     let audio_ref = state.files.get_untracked().get(file_index).cloned();
     if let Some(f) = audio_ref {
-        if let Some(streaming) = f.audio.source.as_any().downcast_ref::<StreamingWavSource>() {
+        if let Some(streaming) = crate::audio::time_expansion::base_source(f.audio.source.as_ref()).as_any().downcast_ref::<StreamingWavSource>() {
             // Prefetch the head region — already loaded, but schedule visible tiles
             let scroll = state.scroll_offset.get_untracked();
             let zoom = state.zoom_level.get_untracked();
@@ -300,6 +319,8 @@ pub(super) async fn try_streaming_flac(file: &File, name: &str, state: AppState,
     }
 
     let header = parse_flac_header(&header_bytes)?;
+    // GUANO and cue markers written by our encoder live in the metadata blocks
+    let (guano, wav_markers) = crate::audio::flac::parse_flac_metadata(&header_bytes);
 
     // Check if decoded size warrants streaming
     let decoded_bytes = header.total_frames * header.channels as u64 * 4; // f32 per sample
@@ -429,7 +450,7 @@ pub(super) async fn try_streaming_flac(file: &File, name: &str, state: AppState,
 
     let samples = Arc::new(head_mono);
 
-    let audio = AudioData {
+    let mut audio = AudioData {
         samples,
         source: source.clone(),
         sample_rate,
@@ -440,11 +461,14 @@ pub(super) async fn try_streaming_flac(file: &File, name: &str, state: AppState,
             format: "FLAC",
             bits_per_sample: header.bits_per_sample,
             is_float: false,
-            guano: None,
+            guano,
             data_offset: Some(header.first_frame_offset),
             data_size: Some((file.size() as u64).saturating_sub(header.first_frame_offset)),
+            time_expansion: 1.0,
         },
     };
+    crate::audio::time_expansion::detect_and_apply(&mut audio, name);
+    let sample_rate = audio.sample_rate;
 
     // Compute preview from head samples
     let preview = compute_preview(&audio, 256, 128);
@@ -515,10 +539,11 @@ pub(super) async fn try_streaming_flac(file: &File, name: &str, state: AppState,
                 had_sidecar: false,
                 verify_outcome: crate::state::VerifyOutcome::Pending,
                 all_hashes_verified: false,
-                wav_markers: Vec::new(),
+                wav_markers,
                 loading_id: Some(load_id),
                 min_display_freq: None,
                 max_display_freq: None,
+                zc_dots: None,
             });
             state.current_file_index.set(Some(idx));
         });
@@ -811,7 +836,7 @@ pub(super) async fn try_streaming_mp3(file: &File, name: &str, state: AppState,
 
     let samples = Arc::new(head_mono);
 
-    let audio = AudioData {
+    let mut audio = AudioData {
         samples,
         source: source.clone(),
         sample_rate,
@@ -825,8 +850,11 @@ pub(super) async fn try_streaming_mp3(file: &File, name: &str, state: AppState,
             guano: None,
             data_offset: Some(header.data_offset),
             data_size: Some((file.size() as u64).saturating_sub(header.data_offset)),
+            time_expansion: 1.0,
         },
     };
+    crate::audio::time_expansion::detect_and_apply(&mut audio, name);
+    let sample_rate = audio.sample_rate;
 
     // Compute preview from head samples
     let preview = compute_preview(&audio, 256, 128);
@@ -901,6 +929,7 @@ pub(super) async fn try_streaming_mp3(file: &File, name: &str, state: AppState,
                 loading_id: Some(load_id),
                 min_display_freq: None,
                 max_display_freq: None,
+                zc_dots: None,
             });
             state.current_file_index.set(Some(idx));
         });
@@ -1200,7 +1229,7 @@ pub(super) async fn try_streaming_ogg(file: &File, name: &str, state: AppState,
 
     let samples = Arc::new(head_mono);
 
-    let audio = AudioData {
+    let mut audio = AudioData {
         samples,
         source: source.clone(),
         sample_rate,
@@ -1214,8 +1243,11 @@ pub(super) async fn try_streaming_ogg(file: &File, name: &str, state: AppState,
             guano: None,
             data_offset: None,
             data_size: None,
+            time_expansion: 1.0,
         },
     };
+    crate::audio::time_expansion::detect_and_apply(&mut audio, name);
+    let sample_rate = audio.sample_rate;
 
     // Compute preview from head samples
     let preview = compute_preview(&audio, 256, 128);
@@ -1290,6 +1322,7 @@ pub(super) async fn try_streaming_ogg(file: &File, name: &str, state: AppState,
                 loading_id: Some(load_id),
                 min_display_freq: None,
                 max_display_freq: None,
+                zc_dots: None,
             });
             state.current_file_index.set(Some(idx));
         });
@@ -1557,8 +1590,14 @@ pub(super) async fn build_streaming_overview(
 }
 
 /// Decode raw PCM bytes to f32 samples (used for head region during streaming load).
-pub(crate) fn decode_head_pcm(bytes: &[u8], bits_per_sample: u16, is_float: bool, _channels: u16) -> Vec<f32> {
-    match (is_float, bits_per_sample) {
+pub(crate) fn decode_head_pcm(bytes: &[u8], header: &WavHeader) -> Vec<f32> {
+    if header.big_endian {
+        return crate::audio::loader::decode_pcm(bytes, header.bits_per_sample, header.is_float, true);
+    }
+    match (header.is_float, header.bits_per_sample) {
+        (false, 8) if header.unsigned_8bit => {
+            bytes.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect()
+        }
         (true, 32) => {
             bytes.chunks_exact(4)
                 .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
@@ -1586,31 +1625,16 @@ pub(crate) fn decode_head_pcm(bytes: &[u8], bits_per_sample: u16, is_float: bool
                 .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / max)
                 .collect()
         }
-        _ => {
-            log::warn!("Unsupported PCM format for streaming: {}-bit {}", bits_per_sample, if is_float { "float" } else { "int" });
-            vec![0.0; bytes.len() / (bits_per_sample as usize / 8)]
-        }
+        // Signed 8-bit (AIFF-sowt, little-endian CAF) and 64-bit float
+        _ => crate::audio::loader::decode_pcm(bytes, header.bits_per_sample, header.is_float, false),
     }
 }
 
 /// Scan raw bytes (from after the data chunk) for a GUANO "guan" chunk.
-pub(crate) fn scan_tail_for_guano(tail_bytes: &[u8]) -> Option<crate::audio::guano::GuanoMetadata> {
-    let mut pos = 0usize;
-    while pos + 8 <= tail_bytes.len() {
-        let chunk_id = &tail_bytes[pos..pos + 4];
-        let chunk_size = u32::from_le_bytes(
-            tail_bytes[pos + 4..pos + 8].try_into().ok()?,
-        ) as usize;
-        let body_start = pos + 8;
-        let body_end = body_start + chunk_size;
-
-        if chunk_id == b"guan" && body_end <= tail_bytes.len() {
-            return crate::audio::guano::parse_guano_chunk(&tail_bytes[body_start..body_end]);
-        }
-
-        pos = body_start + ((chunk_size + 1) & !1);
-    }
-    None
+pub(crate) fn scan_tail_for_guano(tail_bytes: &[u8], wave64: bool) -> Option<crate::audio::guano::GuanoMetadata> {
+    use crate::audio::riff;
+    riff::find_chunk(riff::chunks_from(tail_bytes, 0, wave64), b"guan")
+        .and_then(crate::audio::guano::parse_guano_chunk)
 }
 
 /// Attempt to open a large M4A file using the streaming path.
@@ -1853,7 +1877,7 @@ pub(super) async fn try_streaming_m4a(file: &File, name: &str, state: AppState,
     let duration_secs = total_frames as f64 / sample_rate as f64;
     let samples = Arc::new(head_mono);
 
-    let audio = AudioData {
+    let mut audio = AudioData {
         samples,
         source: source.clone(),
         sample_rate,
@@ -1867,8 +1891,11 @@ pub(super) async fn try_streaming_m4a(file: &File, name: &str, state: AppState,
             guano: if tags.fields.is_empty() { None } else { Some(tags) },
             data_offset: None,
             data_size: None,
+            time_expansion: 1.0,
         },
     };
+    crate::audio::time_expansion::detect_and_apply(&mut audio, name);
+    let sample_rate = audio.sample_rate;
 
     let preview = compute_preview(&audio, 256, 128);
 
@@ -1936,6 +1963,7 @@ pub(super) async fn try_streaming_m4a(file: &File, name: &str, state: AppState,
                 loading_id: Some(load_id),
                 min_display_freq: None,
                 max_display_freq: None,
+                zc_dots: None,
             });
             state.current_file_index.set(Some(idx));
         });
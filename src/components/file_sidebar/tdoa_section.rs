@@ -0,0 +1,321 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use crate::state::AppState;
+use crate::audio::source::ChannelView;
+use crate::annotations::{Annotation, AnnotationKind, Group, Region, generate_uuid, now_iso8601};
+use crate::components::file_sidebar::file_groups::file_track_suffix;
+use crate::dsp::pulse_detect::DetectedPulse;
+use crate::dsp::tdoa::{self, Delay, SourcePosition, TdoaParams, SPEED_OF_SOUND};
+
+const MIC_STORAGE_KEY: &str = "oversample_mic_positions";
+
+/// Samples read either side of a pulse on top of the largest possible lag.
+const PAD_SECS: f64 = 0.002;
+
+/// One synchronised microphone: a channel of the current file, or the
+/// matching track of a multitrack group.
+#[derive(Clone, Debug, PartialEq)]
+struct MicChannel {
+    file_idx: usize,
+    channel: ChannelView,
+    label: String,
+}
+
+#[derive(Clone, Debug)]
+struct PulseFix {
+    pulse: DetectedPulse,
+    delays: Vec<Option<Delay>>,
+    position: Option<SourcePosition>,
+}
+
+fn persist_mic_text(text: &str) {
+    if let Some(ls) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = ls.set_item(MIC_STORAGE_KEY, text);
+    }
+}
+
+fn load_mic_text() -> String {
+    web_sys::window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|ls| ls.get_item(MIC_STORAGE_KEY).ok().flatten())
+        .unwrap_or_default()
+}
+
+/// Channels of the current file when it has several, otherwise the loaded
+/// tracks of its multitrack group (ordered by track label, mixdown excluded).
+fn array_channels(state: &AppState) -> Vec<MicChannel> {
+    let Some(idx) = state.current_file_index.get() else { return Vec::new() };
+    state.files.with(|files| {
+        let Some(file) = files.get(idx) else { return Vec::new() };
+        if file.audio.channels >= 2 {
+            return (0..file.audio.channels)
+                .map(|ch| MicChannel { file_idx: idx, channel: ChannelView::Channel(ch), label: format!("Ch{}", ch + 1) })
+                .collect();
+        }
+        let Some(track) = file_track_suffix(file) else { return Vec::new() };
+        let mut tracks: Vec<MicChannel> = files
+            .iter()
+            .enumerate()
+            .filter_map(|(i, f)| {
+                let t = file_track_suffix(f)?;
+                (t.group_key == track.group_key && !t.label.eq_ignore_ascii_case("mix"))
+                    .then_some(MicChannel { file_idx: i, channel: ChannelView::Channel(0), label: t.label })
+            })
+            .collect();
+        tracks.sort_by(|a, b| a.label.cmp(&b.label));
+        if tracks.len() >= 2 { tracks } else { Vec::new() }
+    })
+}
+
+/// TDOA per detected pulse between synchronised channels, with an optional
+/// position fix from user-supplied microphone coordinates.
+#[component]
+pub(crate) fn TdoaSection() -> impl IntoView {
+    let state = expect_context::<AppState>();
+    let mic_text = RwSignal::new(load_mic_text());
+    let three_d = RwSignal::new(false);
+    let running = RwSignal::new(false);
+    let results: RwSignal<Vec<PulseFix>> = RwSignal::new(Vec::new());
+
+    let channels = Memo::new(move |_| array_channels(&state));
+    let mics = Memo::new(move |_| tdoa::parse_mic_positions(&mic_text.get()));
+
+    // Results belong to one file's pulses
+    Effect::new(move || {
+        let _ = state.current_file_index.get();
+        let _ = state.detected_pulses.get();
+        results.set(Vec::new());
+    });
+
+    let on_compute = move |_: web_sys::MouseEvent| {
+        if running.get_untracked() { return; }
+        let chans = channels.get_untracked();
+        let pulses = state.detected_pulses.get_untracked();
+        if chans.len() < 2 || pulses.is_empty() { return; }
+        let mics = match mics.get_untracked() {
+            Ok(m) if m.is_empty() => None,
+            Ok(m) if m.len() == chans.len() => Some(m),
+            Ok(m) => {
+                state.show_info_toast(format!("{} mic positions for {} channels", m.len(), chans.len()));
+                return;
+            }
+            Err(e) => {
+                state.show_info_toast(e);
+                return;
+            }
+        };
+        let three_d = three_d.get_untracked();
+        let (band_lo, band_hi) = (state.band_ff_freq_lo.get_untracked(), state.band_ff_freq_hi.get_untracked());
+        let params = TdoaParams {
+            max_lag_secs: mics.as_ref().map(|m| tdoa::max_lag_for(m, SPEED_OF_SOUND)).unwrap_or(0.003),
+            // Without a band-pass, ignore low-frequency noise below bat calls
+            freq_low_hz: if band_lo > 0.0 { band_lo } else { 15_000.0 },
+            freq_high_hz: if band_hi > band_lo { band_hi } else { 0.0 },
+            ..TdoaParams::default()
+        };
+
+        running.set(true);
+        spawn_local(async move {
+            let audios: Vec<_> = state.files.with_untracked(|files| {
+                chans.iter().filter_map(|c| files.get(c.file_idx).map(|f| f.audio.clone())).collect()
+            });
+            if audios.len() != chans.len() {
+                running.set(false);
+                return;
+            }
+            let mut fixes = Vec::with_capacity(pulses.len());
+            for (n, pulse) in pulses.into_iter().enumerate() {
+                if n % 50 == 0 {
+                    crate::canvas::tile_cache::yield_to_browser().await;
+                }
+                let sr = audios[0].sample_rate;
+                let pad = params.max_lag_secs + PAD_SECS;
+                let start = ((pulse.start_time - pad).max(0.0) * sr as f64) as u64;
+                let len = ((pulse.end_time - pulse.start_time + 2.0 * pad) * sr as f64) as usize;
+                let samples: Vec<Vec<f32>> = chans.iter().zip(audios.iter())
+                    .map(|(c, a)| a.source.read_region(c.channel, start, len))
+                    .collect();
+                let delays = tdoa::channel_delays(&samples, sr, &params);
+                let position = mics.as_ref().and_then(|m| {
+                    let secs: Vec<Option<f64>> = delays.iter().map(|d| d.map(|d| d.secs)).collect();
+                    tdoa::localize(m, &secs, three_d, params.speed_of_sound)
+                });
+                fixes.push(PulseFix { pulse, delays, position });
+            }
+            results.set(fixes);
+            running.set(false);
+        });
+    };
+
+    let on_save = move |_: web_sys::MouseEvent| {
+        let Some(idx) = state.current_file_index.get_untracked() else { return };
+        let fixes: Vec<PulseFix> = results.get_untracked().into_iter().filter(|f| f.position.is_some()).collect();
+        if fixes.is_empty() {
+            state.show_info_toast("No position fixes to save");
+            return;
+        }
+        state.snapshot_annotations();
+        let count = fixes.len();
+        state.push_annotations(idx, position_annotations(fixes));
+        state.show_info_toast(format!("{} position(s) saved as annotations", count));
+    };
+
+    view! {
+        {move || {
+            let chans = channels.get();
+            (chans.len() >= 2).then(|| {
+                let labels = chans.iter().map(|c| c.label.clone()).collect::<Vec<_>>().join(", ");
+                let placeholder = format!("One line per mic ({}): x y [z] in metres", labels);
+                view! {
+                    <div class="setting-group">
+                        <div class="setting-group-title">{format!("Localisation ({} channels)", chans.len())}</div>
+                        <div class="setting-row">
+                            <textarea
+                                class="tdoa-mic-input"
+                                rows="4"
+                                placeholder=placeholder
+                                prop:value=move || mic_text.get_untracked()
+                                on:change=move |ev: web_sys::Event| {
+                                    let target = ev.target().unwrap();
+                                    let textarea: web_sys::HtmlTextAreaElement = target.unchecked_into();
+                                    let text = textarea.value();
+                                    persist_mic_text(&text);
+                                    mic_text.set(text);
+                                }
+                            ></textarea>
+                        </div>
+                        {move || match mics.get() {
+                            Err(e) => Some(view! { <div class="tdoa-error">{e}</div> }),
+                            _ => None,
+                        }}
+                        <div class="setting-row">
+                            <label class="setting-label">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || three_d.get()
+                                    on:change=move |ev| three_d.set(event_target_checked(&ev))
+                                />
+                                " 3D position"
+                            </label>
+                        </div>
+                        <div class="setting-row">
+                            <button class="setting-button" on:click=on_compute disabled=move || running.get()>
+                                {move || if running.get() { "Computing\u{2026}" } else { "Compute TDOA" }}
+                            </button>
+                        </div>
+                        {move || {
+                            let fixes = results.get();
+                            (!fixes.is_empty()).then(|| {
+                                let has_positions = fixes.iter().any(|f| f.position.is_some());
+                                view! {
+                                    {results_table(fixes)}
+                                    {has_positions.then(|| view! {
+                                        <div class="copy-report-row">
+                                            <button class="copy-report-btn" on:click=on_save>"Save positions as annotations"</button>
+                                        </div>
+                                    })}
+                                }
+                            })
+                        }}
+                    </div>
+                }
+            })
+        }}
+    }
+}
+
+fn results_table(fixes: Vec<PulseFix>) -> impl IntoView {
+    let n_delays = fixes.first().map(|f| f.delays.len()).unwrap_or(0);
+    let header: Vec<_> = (1..n_delays).map(|i| view! { <th>{format!("\u{0394}t{} ms", i + 1)}</th> }).collect();
+    let rows: Vec<_> = fixes.iter().map(|f| {
+        let delays: Vec<_> = f.delays.iter().skip(1).map(|d| {
+            let text = d.map(|d| format!("{:.3}", d.secs * 1000.0)).unwrap_or_else(|| "\u{2014}".into());
+            view! { <td>{text}</td> }
+        }).collect();
+        let pos = f.position.as_ref().map(format_position).unwrap_or_else(|| "\u{2014}".into());
+        view! {
+            <tr>
+                <td>{format!("#{}", f.pulse.index)}</td>
+                {delays}
+                <td>{pos}</td>
+            </tr>
+        }
+    }).collect();
+    view! {
+        <table class="cf-pass-table">
+            <tr><th>"Pulse"</th>{header}<th>"Position (m)"</th></tr>
+            {rows}
+        </table>
+    }
+}
+
+fn format_position(p: &SourcePosition) -> String {
+    match p.z {
+        Some(z) => format!("{:.2}, {:.2}, {:.2}", p.x, p.y, z),
+        None => format!("{:.2}, {:.2}", p.x, p.y),
+    }
+}
+
+fn position_annotations(fixes: Vec<PulseFix>) -> Vec<Annotation> {
+    let group_id = generate_uuid();
+    let mut annotations = vec![Annotation {
+        id: group_id.clone(),
+        kind: AnnotationKind::Group(Group {
+            label: Some("TDOA positions".to_string()),
+            color: None,
+            collapsed: Some(true),
+        }),
+        created_at: now_iso8601(),
+        modified_at: now_iso8601(),
+        notes: None,
+        parent_id: None,
+        sort_order: None,
+        tags: Vec::new(),
+        label_default: None,
+        confidence: None,
+        review: None,
+    }];
+    annotations.extend(fixes.into_iter().filter_map(|f| {
+        let position = f.position?;
+        let notes = format!(
+            "TDOA fit residual {:.3} m\nDelays (ms): {}",
+            position.residual_m,
+            position.delays_ms.iter()
+                .map(|d| d.map(|v| format!("{:.3}", v)).unwrap_or_else(|| "\u{2014}".into()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        Some(Annotation {
+            id: generate_uuid(),
+            kind: AnnotationKind::Region(Region {
+                time_start: f.pulse.start_time,
+                time_end: f.pulse.end_time,
+                freq_low: None,
+                freq_high: None,
+                label: Some(format!("#{} @ {} m", f.pulse.index, format_position(&position))),
+                color: None,
+                locked: None,
+                contour: None,
+                position: Some(position),
+            }),
+            created_at: now_iso8601(),
+            modified_at: now_iso8601(),
+            notes: Some(notes),
+            parent_id: Some(group_id.clone()),
+            sort_order: None,
+            tags: vec!["tdoa".to_string()],
+            label_default: None,
+            confidence: None,
+            review: None,
+        })
+    }));
+    annotations
+}
+
+fn event_target_checked(ev: &web_sys::Event) -> bool {
+    ev.target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+        .map(|el| el.checked())
+        .unwrap_or(false)
+}
@@ -34,4 +34,5 @@ pub mod bat_book_ref_panel;
 pub mod display_filter_button;
 pub mod overflow_menu;
 pub mod annotation_label_editor;
+pub mod review_bar;
 pub mod modal;
@@ -78,6 +78,7 @@ pub fn NotchCombo() -> impl IntoView {
 
     let left_click = Callback::new(move |_: web_sys::MouseEvent| {
         if no_file() { return; }
+        state.snapshot_settings();
         state.notch_enabled.update(|v| *v = !*v);
     });
     let toggle_menu = Callback::new(move |()| {
@@ -111,6 +112,7 @@ pub fn NotchCombo() -> impl IntoView {
                 crate::canvas::tile_cache::yield_to_browser,
             ).await;
             let count = bands.len();
+            state.snapshot_settings();
             state.notch_bands.set(bands);
             if count > 0 {
                 state.notch_enabled.set(true);
@@ -132,11 +134,13 @@ pub fn NotchCombo() -> impl IntoView {
     let on_harmonic_change = move |ev: web_sys::Event| {
         let target: web_sys::HtmlInputElement = ev.target().unwrap().unchecked_into();
         if let Ok(val) = target.value().parse::<f64>() {
+            state.snapshot_settings();
             state.notch_harmonic_suppression.set(val / 100.0);
         }
     };
 
     let toggle_band = move |index: usize| {
+        state.snapshot_settings();
         state.notch_bands.update(|bands| {
             if let Some(band) = bands.get_mut(index) {
                 band.enabled = !band.enabled;
@@ -144,11 +148,13 @@ pub fn NotchCombo() -> impl IntoView {
         });
     };
     let remove_band = move |index: usize| {
+        state.snapshot_settings();
         state.notch_bands.update(|bands| {
             if index < bands.len() { bands.remove(index); }
         });
     };
     let set_all_enabled = move |enabled: bool| {
+        state.snapshot_settings();
         state.notch_bands.update(|bands| {
             for band in bands.iter_mut() { band.enabled = enabled; }
         });
@@ -173,10 +179,10 @@ pub fn NotchCombo() -> impl IntoView {
             // ── Enable ──
             <div style="display: flex; gap: 2px; padding: 0 6px 4px;">
                 <button class=move || layer_opt_class(state.notch_enabled.get())
-                    on:click=move |_| state.notch_enabled.set(true)
+                    on:click=move |_| { state.snapshot_settings(); state.notch_enabled.set(true); }
                 >"On"</button>
                 <button class=move || layer_opt_class(!state.notch_enabled.get())
-                    on:click=move |_| state.notch_enabled.set(false)
+                    on:click=move |_| { state.snapshot_settings(); state.notch_enabled.set(false); }
                 >"Off"</button>
             </div>
 
@@ -264,6 +270,7 @@ pub fn NotchCombo() -> impl IntoView {
                             >"All Off"</button>
                             <button class="layer-panel-opt" style="flex: 1; font-size: 10px;"
                                 on:click=move |_: web_sys::MouseEvent| {
+                                    state.snapshot_settings();
                                     state.notch_bands.set(Vec::new());
                                     state.notch_enabled.set(false);
                                 }
@@ -341,6 +348,7 @@ pub fn NrCombo() -> impl IntoView {
 
     let left_click = Callback::new(move |_: web_sys::MouseEvent| {
         if no_file() { return; }
+        state.snapshot_settings();
         state.noise_reduce_enabled.update(|v| *v = !*v);
     });
     let toggle_menu = Callback::new(move |()| {
@@ -368,6 +376,7 @@ pub fn NrCombo() -> impl IntoView {
                 crate::canvas::tile_cache::yield_to_browser,
             ).await;
             if let Some(f) = floor {
+                state.snapshot_settings();
                 state.noise_reduce_floor.set(Some(f));
                 state.noise_reduce_enabled.set(true);
                 state.show_info_toast("Noise floor learned");
@@ -381,6 +390,7 @@ pub fn NrCombo() -> impl IntoView {
     let on_strength_change = move |ev: web_sys::Event| {
         let target: web_sys::HtmlInputElement = ev.target().unwrap().unchecked_into();
         if let Ok(val) = target.value().parse::<f64>() {
+            state.snapshot_settings();
             state.noise_reduce_strength.set(val / 100.0);
         }
     };
@@ -404,10 +414,10 @@ pub fn NrCombo() -> impl IntoView {
             // ── Enable ──
             <div style="display: flex; gap: 2px; padding: 0 6px 4px;">
                 <button class=move || layer_opt_class(state.noise_reduce_enabled.get())
-                    on:click=move |_| state.noise_reduce_enabled.set(true)
+                    on:click=move |_| { state.snapshot_settings(); state.noise_reduce_enabled.set(true); }
                 >"On"</button>
                 <button class=move || layer_opt_class(!state.noise_reduce_enabled.get())
-                    on:click=move |_| state.noise_reduce_enabled.set(false)
+                    on:click=move |_| { state.snapshot_settings(); state.noise_reduce_enabled.set(false); }
                 >"Off"</button>
             </div>
 
@@ -452,6 +462,7 @@ pub fn NrCombo() -> impl IntoView {
                         <div style="display: flex; gap: 4px; padding: 2px 6px 0;">
                             <button class="layer-panel-opt" style="flex: 1; font-size: 10px;"
                                 on:click=move |_: web_sys::MouseEvent| {
+                                    state.snapshot_settings();
                                     state.noise_reduce_floor.set(None);
                                     state.noise_reduce_enabled.set(false);
                                 }
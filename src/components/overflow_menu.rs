@@ -1,10 +1,11 @@
 use leptos::prelude::*;
 use crate::state::{ActiveFocus, AppState, Selection};
-use crate::annotations::{Annotation, AnnotationKind, AnnotationSet, Marker, Region, generate_default_label, generate_uuid, now_iso8601};
+use crate::annotations::{Annotation, AnnotationKind, AnnotationSet, Marker, Measurement, Region, generate_default_label, generate_uuid, now_iso8601};
 use crate::canvas::spectrogram_renderer::freq_to_y;
 use crate::components::file_sidebar::settings_panel::{
     toggle_annotation_lock, delete_annotation,
 };
+use crate::template_search::{self, TemplateBox};
 
 // Icons for the expand/contract freq buttons.
 // Expand = "remove frequency bounds" (treat as full range).
@@ -44,6 +45,8 @@ pub fn annotate_selection(state: &AppState) {
                     label: None,
                     color: None,
                     locked: None,
+                    contour: None,
+                    position: None,
                 });
                 let default_label = generate_default_label(&set.annotations, &kind, None);
                 if let AnnotationKind::Region(ref mut r) = kind {
@@ -59,6 +62,8 @@ pub fn annotate_selection(state: &AppState) {
                     sort_order: None,
                     tags: Vec::new(),
                     label_default: Some(true),
+                    confidence: None,
+                    review: None,
                 });
             }
         });
@@ -121,6 +126,8 @@ pub fn add_marker_at_time(state: &AppState, time: f64) {
                 sort_order: None,
                 tags: Vec::new(),
                 label_default: Some(true),
+                confidence: None,
+                review: None,
             });
         }
     });
@@ -133,6 +140,46 @@ pub fn add_marker_at_time(state: &AppState, time: f64) {
     state.show_info_toast("Marker added");
 }
 
+/// Save a measurement dragged out with the Measure tool on the current file.
+/// Unlike regions and markers it does not open the label editor, so several
+/// pulses can be measured in a row.
+pub fn add_measurement(state: &AppState, mut measurement: Measurement) {
+    let Some(idx) = state.current_file_index.get_untracked() else { return; };
+    // Store points in time order; slope and rate don't depend on drag direction
+    if measurement.end_time < measurement.start_time {
+        std::mem::swap(&mut measurement.start_time, &mut measurement.end_time);
+        std::mem::swap(&mut measurement.start_freq, &mut measurement.end_freq);
+    }
+    let readout = measurement.readout();
+    let mut kind = AnnotationKind::Measurement(measurement);
+    let default_label = state.annotation_store.with_untracked(|store| {
+        let existing = store.sets.get(idx).and_then(|s| s.as_ref()).map(|s| s.annotations.as_slice()).unwrap_or(&[]);
+        generate_default_label(existing, &kind, None)
+    });
+    if let AnnotationKind::Measurement(ref mut m) = kind {
+        m.label = Some(default_label);
+    }
+
+    state.snapshot_annotations();
+    let ann_id = generate_uuid();
+    state.push_annotations(idx, vec![Annotation {
+        id: ann_id.clone(),
+        kind,
+        created_at: now_iso8601(),
+        modified_at: now_iso8601(),
+        notes: None,
+        parent_id: None,
+        sort_order: None,
+        tags: Vec::new(),
+        label_default: Some(true),
+        confidence: None,
+        review: None,
+    }]);
+    state.selected_annotation_ids.set(vec![ann_id]);
+    state.active_focus.set(Some(ActiveFocus::Annotations));
+    state.show_info_toast(readout);
+}
+
 /// Get frequency bounds from focus stack or display range.
 fn get_freq_bounds(state: &AppState) -> (f64, f64) {
     let ff = state.focus_stack.get_untracked().effective_range_ignoring_hfr();
@@ -302,6 +349,48 @@ fn FreqRow(
     }
 }
 
+/// Template box for a Region annotation with frequency bounds on the current file.
+fn annotation_template_box(state: &AppState, id: &str) -> Option<TemplateBox> {
+    let idx = state.current_file_index.get_untracked()?;
+    let store = state.annotation_store.get_untracked();
+    let set = store.sets.get(idx)?.as_ref()?;
+    match &set.annotations.iter().find(|a| a.id == id)?.kind {
+        AnnotationKind::Region(r) => Some(TemplateBox {
+            time_start: r.time_start,
+            time_end: r.time_end,
+            freq_low: r.freq_low?,
+            freq_high: r.freq_high?,
+        }),
+        _ => None,
+    }
+}
+
+/// "Find similar" menu entries: search the current file (or all loaded files)
+/// for calls matching `bx` and add them as annotations.
+fn find_similar_items(state: AppState, is_open: RwSignal<bool>, bx: TemplateBox) -> impl IntoView {
+    let run = move |all_files: bool| {
+        let Some(idx) = state.current_file_index.get_untracked() else { return };
+        is_open.set(false);
+        state.show_info_toast("Searching for similar calls\u{2026}");
+        leptos::task::spawn_local(template_search::find_similar(state, idx, bx, all_files));
+    };
+    let multi = state.files.with_untracked(|f| f.len() > 1);
+    view! {
+        <button
+            class="canvas-overflow-item"
+            title="Find calls that look like this one (spectrogram cross-correlation)"
+            on:click=move |_| run(false)
+        >
+            "Find similar"
+        </button>
+        {multi.then(|| view! {
+            <button class="canvas-overflow-item" on:click=move |_| run(true)>
+                "Find similar in all files"
+            </button>
+        })}
+    }
+}
+
 /// "..." overflow button + dropdown for transient selection.
 #[component]
 fn SelectionOverflowMenu() -> impl IntoView {
@@ -458,6 +547,14 @@ fn SelectionOverflowMenu() -> impl IntoView {
                                             >
                                                 {btn_label}
                                             </button>
+                                            {state.selection.get_untracked()
+                                                .and_then(|s| Some(TemplateBox {
+                                                    time_start: s.time_start,
+                                                    time_end: s.time_end,
+                                                    freq_low: s.freq_low?,
+                                                    freq_high: s.freq_high?,
+                                                }))
+                                                .map(|bx| find_similar_items(state, is_open, bx))}
                                         }.into_any()
                                     } else {
                                         view! { <span></span> }.into_any()
@@ -719,6 +816,8 @@ fn AnnotationOverflowMenu() -> impl IntoView {
                                                     </button>
                                                 }
                                             })}
+                                            {annotation_template_box(&state, &id)
+                                                .map(|bx| find_similar_items(state, is_open, bx))}
                                             <div class="canvas-overflow-separator"></div>
                                             <button
                                                 class="canvas-overflow-item danger"
@@ -0,0 +1,287 @@
+use leptos::prelude::*;
+use crate::annotation_review::{missing_from_loaded, ReviewQueue};
+use crate::annotation_table::RowSource;
+use crate::annotations::{Annotation, AnnotationId, AnnotationKind, Review, ReviewStatus, now_iso8601};
+use crate::audio::playback;
+use crate::components::file_sidebar::settings_panel::{annotation_display, restore_selection};
+use crate::project::BatProject;
+use crate::state::{AppState, LoadedFile};
+
+const REVIEWER_STORAGE_KEY: &str = "oversample_reviewer_name";
+
+fn persist_reviewer(name: &str) {
+    if let Some(ls) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = ls.set_item(REVIEWER_STORAGE_KEY, name);
+    }
+}
+
+/// Queue every unreviewed region, segment and marker in file then time
+/// order, and show the first. With a project open that's every file in it,
+/// loaded or not; otherwise the loaded files.
+pub fn start_review(state: AppState) {
+    let files = state.files.get_untracked();
+    let store = state.annotation_store.get_untracked();
+    let loaded = |i: usize| store.sets.get(i).and_then(|s| s.as_ref()).map_or(&[][..], |set| &set.annotations[..]);
+    let queue = state.current_project.with_untracked(|project| match project {
+        Some(proj) => ReviewQueue::build(proj.files.iter().enumerate().map(|(pi, pf)| {
+            match loaded_index(&files, proj, pi) {
+                Some(i) => (RowSource::Loaded(i), loaded(i)),
+                None => (RowSource::Project(pi), &pf.annotations[..]),
+            }
+        })),
+        None => ReviewQueue::build((0..files.len()).map(|i| (RowSource::Loaded(i), loaded(i)))),
+    });
+    let Some(queue) = queue else {
+        state.show_info_toast("No unreviewed annotations");
+        return;
+    };
+    state.review_queue.set(Some(queue));
+    show_current(state);
+}
+
+/// Index of the loaded file matching project file `pi`, if it's loaded.
+fn loaded_index(files: &[LoadedFile], proj: &BatProject, pi: usize) -> Option<usize> {
+    files.iter().position(|f| f.identity.as_ref().is_some_and(|id| proj.find_file(id) == Some(pi)))
+}
+
+/// If the current item's file has been loaded since the queue was built,
+/// point its items at the loaded file, copying across any annotation its
+/// sidecar lacks. Returns false while the file still isn't loaded.
+fn resolve_current(state: AppState) -> bool {
+    let Some(pi) = state.review_queue.with_untracked(|q| q.as_ref().and_then(ReviewQueue::waiting_on)) else {
+        return true;
+    };
+    let found = state.current_project.with_untracked(|p| {
+        let proj = p.as_ref()?;
+        let i = state.files.with_untracked(|files| loaded_index(files, proj, pi))?;
+        Some((i, proj.files.get(pi)?.annotations.clone()))
+    });
+    let Some((i, project_copy)) = found else { return false };
+    let mut ids = Default::default();
+    state.review_queue.update(|q| {
+        if let Some(q) = q {
+            ids = q.relink(pi, i);
+        }
+    });
+    let missing = state.annotation_store.with_untracked(|store| {
+        let set = store.sets.get(i).and_then(|s| s.as_ref());
+        missing_from_loaded(project_copy, &ids, set.map(|set| &set.annotations[..]))
+    });
+    if !missing.is_empty() {
+        state.push_annotations(i, missing);
+        if state.current_file_index.get_untracked() != Some(i) {
+            crate::opfs::save_annotations(state, i);
+        }
+    }
+    true
+}
+
+/// Start loading project file `pi` so its annotations can be reviewed.
+/// Desktop opens it from its recorded path; the browser can only ask.
+fn load_project_file(state: AppState, pi: usize) {
+    let Some((name, path)) = state.current_project.with_untracked(|p| {
+        let pf = p.as_ref()?.files.get(pi)?;
+        Some((pf.identity.filename.clone(), pf.identity.file_path.clone()))
+    }) else { return };
+    match path.filter(|_| state.is_tauri) {
+        Some(path) => {
+            let load_id = state.loading_start(&name);
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = crate::components::file_sidebar::load_native_file(path, state, load_id).await;
+                state.loading_done(load_id);
+                match result {
+                    // The identity hash lands asynchronously; give it a moment
+                    Ok(()) => {
+                        for _ in 0..50 {
+                            if resolve_current(state) {
+                                show_current(state);
+                                return;
+                            }
+                            crate::canvas::tile_cache::yield_to_browser().await;
+                        }
+                        state.show_info_toast(format!("Opened {name}; press Replay to review it"));
+                    }
+                    Err(e) => state.show_error_toast(format!("Couldn't open {name}: {e}")),
+                }
+            });
+        }
+        None => state.show_info_toast(format!("Open {name} to review its annotations, then press Replay")),
+    }
+}
+
+pub fn stop_review(state: AppState) {
+    let decided = state.review_queue.with_untracked(|q| q.as_ref().map(|q| q.decided).unwrap_or(0));
+    state.review_queue.set(None);
+    playback::stop(&state);
+    if decided > 0 {
+        state.show_info_toast(format!("Reviewed {decided} annotation(s)"));
+    }
+}
+
+/// Open the current item's file, select the annotation and play it. Files
+/// in the project that aren't loaded yet are loaded first.
+pub fn show_current(state: AppState) {
+    playback::stop(&state);
+    if !resolve_current(state) {
+        if let Some(pi) = state.review_queue.with_untracked(|q| q.as_ref().and_then(ReviewQueue::waiting_on)) {
+            load_project_file(state, pi);
+        }
+        return;
+    }
+    let Some((RowSource::Loaded(file_idx), id)) = state.review_queue.with_untracked(|q| q.as_ref().and_then(|q| q.current().cloned())) else {
+        return;
+    };
+    if state.current_file_index.get_untracked() != Some(file_idx) {
+        state.current_file_index.set(Some(file_idx));
+    }
+    // Let the file-switch effect restore the incoming file's settings first
+    wasm_bindgen_futures::spawn_local(async move {
+        crate::canvas::tile_cache::yield_to_browser().await;
+        state.selected_annotation_ids.set(vec![id.clone()]);
+        restore_selection(state, &id);
+        let marker_time = state.annotation_store.with_untracked(|store| {
+            store.sets.get(file_idx).and_then(|s| s.as_ref())
+                .and_then(|set| set.annotations.iter().find(|a| a.id == id))
+                .and_then(|a| match a.kind {
+                    AnnotationKind::Marker(ref m) => Some(m.time),
+                    _ => None,
+                })
+        });
+        if let Some(t) = marker_time {
+            playback::play_from_time(&state, (t - 0.5).max(0.0));
+        } else {
+            playback::play(&state);
+        }
+    });
+}
+
+/// Review status of a queued annotation as it is now (None if deleted).
+fn current_status(state: AppState, source: RowSource, id: &AnnotationId) -> Option<ReviewStatus> {
+    let find = |anns: &[Annotation]| anns.iter().find(|a| &a.id == id).map(Annotation::review_status);
+    match source {
+        RowSource::Loaded(i) => state.annotation_store.with_untracked(|store| {
+            store.sets.get(i).and_then(|s| s.as_ref()).and_then(|set| find(&set.annotations))
+        }),
+        RowSource::Project(pi) => state.current_project.with_untracked(|p| {
+            p.as_ref().and_then(|p| p.files.get(pi)).and_then(|pf| find(&pf.annotations))
+        }),
+    }
+}
+
+fn step(state: AppState, decided: bool) {
+    let mut done = false;
+    state.review_queue.update(|q| {
+        if let Some(q) = q {
+            done = !q.advance(decided, |source, id| current_status(state, source, id));
+        }
+    });
+    if done {
+        stop_review(state);
+    } else {
+        show_current(state);
+    }
+}
+
+/// Record `status` for the annotation under review and move to the next one.
+pub fn record_decision(state: AppState, status: ReviewStatus) {
+    let Some((source, id)) = state.review_queue.with_untracked(|q| q.as_ref().and_then(|q| q.current().cloned())) else {
+        return;
+    };
+    let reviewer = state.reviewer_name.get_untracked();
+    let reviewer = (!reviewer.trim().is_empty()).then(|| reviewer.trim().to_string());
+    let now = now_iso8601();
+    let review = |a: &mut Annotation| {
+        a.review = Some(Review { status, reviewer: reviewer.clone(), reviewed_at: Some(now.clone()) });
+        a.modified_at = now.clone();
+    };
+    match source {
+        RowSource::Loaded(file_idx) => {
+            state.snapshot_file_annotations(file_idx);
+            state.annotation_store.update(|store| {
+                if let Some(Some(set)) = store.sets.get_mut(file_idx) {
+                    set.annotations.iter_mut().filter(|a| a.id == id).for_each(review);
+                }
+            });
+            state.annotations_dirty.set(true);
+        }
+        // Not loaded: record it in the project's copy
+        RowSource::Project(pi) => {
+            state.current_project.update(|p| {
+                if let Some(p) = p {
+                    if let Some(pf) = p.files.get_mut(pi) {
+                        pf.annotations.iter_mut().filter(|a| a.id == id).for_each(review);
+                    }
+                    p.touch();
+                }
+            });
+            state.project_dirty.set(true);
+        }
+    }
+    step(state, true);
+}
+
+pub fn skip(state: AppState) {
+    step(state, false);
+}
+
+/// Floating bar shown while the review queue is active.
+#[component]
+pub fn ReviewBar() -> impl IntoView {
+    let state = expect_context::<AppState>();
+
+    let current = move || {
+        let (source, id, pos, len) = state.review_queue.with(|q| {
+            q.as_ref().and_then(|q| q.current().map(|(f, id)| (*f, id.clone(), q.pos, q.items.len())))
+        })?;
+        let (filename, ann) = match source {
+            RowSource::Loaded(file_idx) => state.files.with(|files| files.get(file_idx).map(|f| f.name.clone()))
+                .zip(state.annotation_store.with(|store| {
+                    store.sets.get(file_idx).and_then(|s| s.as_ref())
+                        .and_then(|set| set.annotations.iter().find(|a| a.id == id).cloned())
+                }))?,
+            RowSource::Project(pi) => state.current_project.with(|p| {
+                let pf = p.as_ref()?.files.get(pi)?;
+                let ann = pf.annotations.iter().find(|a| a.id == id)?.clone();
+                Some((format!("{} (not loaded)", pf.identity.filename), ann))
+            })?,
+        };
+        Some((filename, ann, pos, len))
+    };
+
+    let on_name = move |ev: web_sys::Event| {
+        let name = event_target_value(&ev);
+        persist_reviewer(&name);
+        state.reviewer_name.set(name);
+    };
+
+    view! {
+        <div class="review-bar" on:click=|ev: web_sys::MouseEvent| ev.stop_propagation()>
+            {move || current().map(|(filename, ann, pos, len)| {
+                let (label, _, _) = annotation_display(&ann);
+                let tags = (!ann.tags.is_empty()).then(|| format!("[{}]", ann.tags.join(", ")));
+                let confidence = ann.confidence.map(|c| format!("{:.0}% confident", c * 100.0));
+                view! {
+                    <span class="review-bar-pos">{format!("{} / {}", pos + 1, len)}</span>
+                    <span class="review-bar-file" title=filename.clone()>{filename.clone()}</span>
+                    <span class="review-bar-label">{label}</span>
+                    {tags.map(|t| view! { <span class="review-bar-meta">{t}</span> })}
+                    {confidence.map(|c| view! { <span class="review-bar-meta">{c}</span> })}
+                }
+            })}
+            <span style="flex: 1;"></span>
+            <input class="review-bar-name" type="text" placeholder="Reviewer"
+                prop:value=move || state.reviewer_name.get()
+                on:change=on_name
+            />
+            <button class="layer-panel-opt" title="Play again (P)" on:click=move |_| show_current(state)>"Replay"</button>
+            <button class="layer-panel-opt review-accept" title="Accept (A)"
+                on:click=move |_| record_decision(state, ReviewStatus::Accepted)>"Accept"</button>
+            <button class="layer-panel-opt review-reject" title="Reject (X)"
+                on:click=move |_| record_decision(state, ReviewStatus::Rejected)>"Reject"</button>
+            <button class="layer-panel-opt review-dispute" title="Dispute (D)"
+                on:click=move |_| record_decision(state, ReviewStatus::Disputed)>"Dispute"</button>
+            <button class="layer-panel-opt" title="Skip (N)" on:click=move |_| skip(state)>"Skip"</button>
+            <button class="layer-panel-opt" title="Stop reviewing (Esc)" on:click=move |_| stop_review(state)>"Done"</button>
+        </div>
+    }
+}
@@ -166,6 +166,7 @@ pub fn Spectrogram() -> impl IntoView {
         let bookmarks = state.bookmarks.get();
         let canvas_tool = state.canvas_tool.get();
         let selection = state.selection.get();
+        let measure_preview = state.measure_preview.get();
         let is_playing = state.is_playing.get();
         let het_interacting = state.het_interacting.get();
         let dragging = state.is_dragging.get();
@@ -208,6 +209,7 @@ pub fn Spectrogram() -> impl IntoView {
         let detected_pulses = state.detected_pulses.get();
         let pulse_overlay = state.pulse_overlay_enabled.get();
         let selected_pulse = state.selected_pulse_index.get();
+        let call_contours = state.call_contours.get();
         let main_view = state.main_view.get();
         let (spect_floor, spect_range, spect_gamma, spect_gain) = if main_view == MainView::XformedSpec {
             (state.xform_spect_floor_db.get(), state.xform_spect_range_db.get(), state.xform_spect_gamma.get(), state.xform_spect_gain_db.get())
@@ -855,18 +857,24 @@ pub fn Spectrogram() -> impl IntoView {
             // <TimeGutter/> strip below, which keeps the bottom rows of
             // the spectrogram readable for low frequencies.
 
+            let view = spectrogram_renderer::OverlayView {
+                min_freq,
+                max_freq,
+                scroll_offset: scroll,
+                time_resolution: time_res,
+                zoom,
+                canvas_width: display_w as f64,
+                canvas_height: display_h as f64,
+            };
+
             // Pulse detection overlay
             if pulse_overlay && !detected_pulses.is_empty() {
-                spectrogram_renderer::draw_pulses(
-                    &ctx,
-                    &detected_pulses,
-                    selected_pulse,
-                    scroll,
-                    time_res,
-                    zoom,
-                    display_w as f64,
-                    display_h as f64,
-                );
+                spectrogram_renderer::draw_pulses(&ctx, &detected_pulses, &call_contours, selected_pulse, &view);
+            }
+
+            // Anabat ZC dots (the file's audio is a silent carrier)
+            if let Some(dots) = file.filter(|_| timeline.is_none()).and_then(|f| f.zc_dots.as_ref()) {
+                spectrogram_renderer::draw_zc_dots(&ctx, dots, &view);
             }
 
             // Notch filter band markers
@@ -1001,6 +1009,11 @@ pub fn Spectrogram() -> impl IntoView {
                 }
             }
 
+            // Measurement being dragged out with the Measure tool
+            if let Some(ref m) = measure_preview {
+                spectrogram_renderer::draw_measurement(&ctx, m, true, &view);
+            }
+
             // File-embedded time markers (WAV cue points, M4A chapters).
             // Rendered on top of annotations so a label on the line is visible.
             if !xform_on && annotations_visible {
@@ -1441,7 +1454,7 @@ pub fn Spectrogram() -> impl IntoView {
                     } else {
                         format!("cursor: grab; touch-action: {ta};")
                     },
-                    CanvasTool::Selection => format!("cursor: crosshair; touch-action: {ta};"),
+                    CanvasTool::Selection | CanvasTool::Measure => format!("cursor: crosshair; touch-action: {ta};"),
                 }
             }
         >
@@ -3,8 +3,9 @@ use wasm_bindgen::JsCast;
 use web_sys::{HtmlCanvasElement, MouseEvent, PointerEvent};
 use crate::canvas::coord::pointer_to_xtf;
 use crate::canvas::hit_test::{hit_test_spec_handles, is_in_band_ff_drag_zone, hit_test_annotation_handles, hit_test_annotation_body, hit_test_band_ff_body};
+use crate::annotations::Measurement;
 use crate::canvas::spectrogram_renderer;
-use crate::state::{ActiveFocus, AppState, CanvasTool, SpectrogramHandle, Selection, UndoEntry};
+use crate::state::{ActiveFocus, AppState, CanvasTool, SpectrogramHandle, Selection};
 use crate::viewport;
 
 pub const LABEL_AREA_WIDTH: f64 = 60.0;
@@ -359,10 +360,7 @@ pub fn on_pointerdown(
 
         if !locked {
             // Snapshot for undo
-            let snapshot = store.sets.get(file_idx).and_then(|s| s.clone());
-            state.undo_stack.update(|stack| {
-                stack.push_undo(UndoEntry { file_idx, snapshot });
-            });
+            state.snapshot_file_annotations(file_idx);
             // Store original bounds
             if let Some(set) = store.sets.get(file_idx).and_then(|s| s.as_ref()) {
                 if let Some(a) = set.annotations.iter().find(|a| a.id == *ann_id) {
@@ -524,6 +522,18 @@ pub fn on_pointerdown(
                 state.selection.set(None);
             }
         }
+        CanvasTool::Measure => {
+            if let Some((_, _, t, f)) = pointer_to_xtf(ev.client_x() as f64, ev.client_y() as f64, canvas_ref, &state) {
+                state.is_dragging.set(true);
+                state.measure_preview.set(Some(Measurement {
+                    start_time: t,
+                    start_freq: f,
+                    end_time: t,
+                    end_freq: f,
+                    label: None,
+                }));
+            }
+        }
     }
 
     // Capture pointer so drag continues even when cursor leaves the canvas
@@ -599,6 +609,14 @@ pub fn on_pointermove(
                         freq_high: Some(f0.max(f)),
                     }));
                 }
+                CanvasTool::Measure => {
+                    state.measure_preview.update(|m| {
+                        if let Some(m) = m {
+                            m.end_time = t;
+                            m.end_freq = f;
+                        }
+                    });
+                }
             }
         } else {
             // Not dragging — do spec handle hover detection (BandFF + HET)
@@ -776,6 +794,19 @@ pub fn on_pointerup(
         ix.pending_selection_hit.set(false);
         return;
     }
+    if state.canvas_tool.get_untracked() == CanvasTool::Measure {
+        let Some(mut m) = state.measure_preview.get_untracked() else { return };
+        state.measure_preview.set(None);
+        if let Some((_, _, t, f)) = pointer_to_xtf(ev.client_x() as f64, ev.client_y() as f64, canvas_ref, &state) {
+            m.end_time = t;
+            m.end_freq = f;
+        }
+        // A click without a drag measures nothing
+        if m.delta_time().abs() > 0.0001 || m.delta_freq().abs() > 100.0 {
+            crate::components::overflow_menu::add_measurement(&state, m);
+        }
+        return;
+    }
     if state.canvas_tool.get_untracked() != CanvasTool::Selection { return; }
     if let Some((_, _, t, f)) = pointer_to_xtf(ev.client_x() as f64, ev.client_y() as f64, canvas_ref, &state) {
         let (t0, f0) = ix.drag_start.get_untracked();
@@ -1005,10 +1036,7 @@ pub fn on_touchstart(
                             .unwrap_or(false);
                         if !locked {
                             // Snapshot for undo
-                            let snapshot = store.sets.get(file_idx).and_then(|s| s.clone());
-                            state.undo_stack.update(|stack| {
-                                stack.push_undo(UndoEntry { file_idx, snapshot });
-                            });
+                            state.snapshot_file_annotations(file_idx);
                             // Store original bounds
                             if let Some(a) = set.annotations.iter().find(|a| a.id == *ann_id) {
                                 if let crate::annotations::AnnotationKind::Region(ref r) = a.kind {
@@ -1093,7 +1121,7 @@ pub fn on_touchstart(
             state.is_dragging.set(true);
             ix.hand_drag_start.set((touch.client_x() as f64, state.scroll_offset.get_untracked()));
         }
-        CanvasTool::Selection => {
+        CanvasTool::Selection | CanvasTool::Measure => {
             ev.prevent_default();
         }
     }
@@ -1173,7 +1201,7 @@ pub fn on_touchmove(
             let now = web_sys::window().unwrap().performance().unwrap().now();
             ix.velocity_tracker.update_value(|t| t.push(now, touch.client_x() as f64));
         }
-        CanvasTool::Selection => {}
+        CanvasTool::Selection | CanvasTool::Measure => {}
     }
 }
 
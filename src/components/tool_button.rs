@@ -36,6 +36,7 @@ pub fn ToolButton() -> impl IntoView {
                     <span class="layer-btn-value">{move || match state.canvas_tool.get() {
                         CanvasTool::Hand => "Hand",
                         CanvasTool::Selection => "Select",
+                        CanvasTool::Measure => "Measure",
                     }}</span>
                 </button>
                 <PopupPanel
@@ -59,6 +60,13 @@ pub fn ToolButton() -> impl IntoView {
                             state.layer_panel_open.set(None);
                         }
                     >"Selection"</button>
+                    <button
+                        class=move || layer_opt_class(state.canvas_tool.get() == CanvasTool::Measure)
+                        on:click=move |_| {
+                            state.canvas_tool.set(CanvasTool::Measure);
+                            state.layer_panel_open.set(None);
+                        }
+                    >"Measure"</button>
                 </PopupPanel>
             </div>
         </div>
@@ -260,8 +260,8 @@ pub fn Toolbar() -> impl IntoView {
                     // On web, trigger browser download with preserved GUANO + cue markers
                     let total = f.audio.source.total_samples() as usize;
                     let samples = f.audio.source.read_region(crate::audio::source::ChannelView::MonoMix, 0, total);
-                    crate::audio::wav_encoder::download_recording_wav(
-                        &samples, f.audio.sample_rate, &f.name,
+                    crate::audio::wav_encoder::download_recording(
+                        &samples, f.audio.sample_rate, f.audio.metadata.bits_per_sample, &f.name,
                         f.audio.metadata.guano.as_ref(), &f.wav_markers,
                     );
                 }
@@ -503,7 +503,7 @@ pub fn Toolbar() -> impl IntoView {
                         <div class="toolbar-overflow-menu" on:click=move |_| overflow_menu_open.set(false)>
                             <button
                                 class="toolbar-overflow-item"
-                                on:click=move |_| state.undo_annotations()
+                                on:click=move |_| state.undo()
                                 disabled=move || !state.can_undo()
                             >
                                 <span class="toolbar-overflow-icon">{"\u{21B6}"}</span>
@@ -511,7 +511,7 @@ pub fn Toolbar() -> impl IntoView {
                             </button>
                             <button
                                 class="toolbar-overflow-item"
-                                on:click=move |_| state.redo_annotations()
+                                on:click=move |_| state.redo()
                                 disabled=move || !state.can_redo()
                             >
                                 <span class="toolbar-overflow-icon">{"\u{21B7}"}</span>
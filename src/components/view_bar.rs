@@ -99,6 +99,7 @@ fn ToolCombo() -> impl IntoView {
                 <span class="layer-btn-value">{move || match state.canvas_tool.get() {
                     CanvasTool::Hand => "Hand",
                     CanvasTool::Selection => "Select",
+                    CanvasTool::Measure => "Measure",
                 }}</span>
             </button>
             <Show when=move || is_open()>
@@ -118,6 +119,13 @@ fn ToolCombo() -> impl IntoView {
                             state.layer_panel_open.set(None);
                         }
                     >"Selection"</button>
+                    <button
+                        class=move || layer_opt_class(state.canvas_tool.get() == CanvasTool::Measure)
+                        on:click=move |_| {
+                            state.canvas_tool.set(CanvasTool::Measure);
+                            state.layer_panel_open.set(None);
+                        }
+                    >"Measure"</button>
                 </div>
             </Show>
         </div>
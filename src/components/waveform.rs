@@ -825,7 +825,7 @@ pub fn Waveform() -> impl IntoView {
                     } else {
                         format!("cursor: grab; touch-action: {ta};")
                     },
-                    CanvasTool::Selection => format!("cursor: crosshair; touch-action: {ta};"),
+                    CanvasTool::Selection | CanvasTool::Measure => format!("cursor: crosshair; touch-action: {ta};"),
                 }
             }
         >
@@ -71,6 +71,10 @@ pub fn ZcDotChart() -> impl IntoView {
         let quality = state.filter_quality.get();
 
         idx.and_then(|i| files.get(i).cloned()).map(|file| {
+            // ZC files carry their own dots; the audio is silent
+            if file.zc_dots.is_some() {
+                return Vec::new();
+            }
             let sr = file.audio.sample_rate;
             // Use audio.samples directly — read_region would allocate a
             // duplicate Vec that for multi-hour M4A files OOMs the WASM heap.
@@ -137,8 +141,9 @@ pub fn ZcDotChart() -> impl IntoView {
         ctx.fill_rect(0.0, 0.0, cw, ch);
 
         let Some(file) = idx.and_then(|i| files.get(i)) else { return };
-        let Some(bins) = zc_bins.get().as_ref().cloned() else { return };
-        if bins.is_empty() { return; }
+        let file_dots = file.zc_dots.clone();
+        let bins = zc_bins.get().unwrap_or_default();
+        if bins.is_empty() && file_dots.is_none() { return; }
 
         let time_res = file.spectrogram.time_resolution;
         let total_duration = file.audio.duration_secs;
@@ -242,6 +247,22 @@ pub fn ZcDotChart() -> impl IntoView {
         }
         ctx.fill();
 
+        // Recorded dots from a ZC file, drawn at their exact times
+        if let Some(dots) = file_dots {
+            let first = dots.partition_point(|d| d.time_secs < start_time);
+            let last = dots.partition_point(|d| d.time_secs <= end_time);
+            ctx.set_fill_style_str(&format!("rgba(100, {armed_g}, 100, {armed_alpha:.2})"));
+            ctx.begin_path();
+            for d in &dots[first..last] {
+                if d.freq_hz < min_freq || d.freq_hz > max_freq { continue; }
+                let x = LABEL_AREA_WIDTH + data_x + (d.time_secs - start_time) * px_per_sec;
+                let y = spectrogram_renderer::freq_to_y(d.freq_hz, min_freq, max_freq, ch);
+                ctx.move_to(x + radius_armed, y);
+                let _ = ctx.arc(x, y, radius_armed, 0.0, TAU);
+            }
+            ctx.fill();
+        }
+
         // Draw "play here" marker when not playing
         if state.play_start_mode.get() .uses_from_here() && !is_playing && canvas_tool == CanvasTool::Hand {
             let here_x = LABEL_AREA_WIDTH + dot_area_w * viewport::PLAY_FROM_HERE_FRACTION;
@@ -807,7 +828,7 @@ pub fn ZcDotChart() -> impl IntoView {
                     } else {
                         format!("cursor: grab; touch-action: {ta};")
                     },
-                    CanvasTool::Selection => format!("cursor: crosshair; touch-action: {ta};"),
+                    CanvasTool::Selection | CanvasTool::Measure => format!("cursor: crosshair; touch-action: {ta};"),
                 }
             }
         >
@@ -3,5 +3,5 @@
 pub use oversample_core::dsp::{
     agc, bit_analysis, fft, filters, harmonics, heterodyne, notch,
     phase_vocoder, pitch_shift, spectral_sub, zc_divide, wsnr,
-    zero_crossing, chromagram, psd, pulse_detect, resonators,
+    zero_crossing, chromagram, psd, pulse_detect, call_params, call_shape, call_events, bat_passes, contour, template_match, cf_analysis, tdoa, acoustic_indices, resonators,
 };
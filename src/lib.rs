@@ -8,12 +8,19 @@ pub mod focus_stack;
 pub mod tauri_bridge;
 pub mod bat_book;
 pub mod annotations;
+pub mod annotation_merge;
+pub mod annotation_table;
+pub mod annotation_review;
+pub mod raven;
+pub mod audacity;
 pub mod file_identity;
 pub mod format_time;
 pub mod opfs;
 pub mod project;
 pub mod project_store;
 pub mod timeline;
+pub mod bat_activity;
+pub mod template_search;
 pub mod viewport;
 
 use leptos::prelude::*;
@@ -119,44 +119,19 @@ pub async fn load_batm_by_key(key: &str) -> Result<Option<crate::annotations::An
     }
 }
 
-/// Build a NoiseProfile from current app state, or None if there's nothing to save.
-fn sync_noise_profile_from_state(state: crate::state::AppState) -> Option<crate::dsp::notch::NoiseProfile> {
-    use leptos::prelude::GetUntracked;
-
-    let bands = state.notch_bands.get_untracked();
-    let noise_floor = state.noise_reduce_floor.get_untracked();
-    if bands.is_empty() && noise_floor.is_none() {
-        return None;
-    }
+/// Build a NoiseProfile from file `file_idx`'s settings (the live signals for
+/// the current file), or None if there's nothing to save.
+fn sync_noise_profile(state: crate::state::AppState, file_idx: usize) -> Option<crate::dsp::notch::NoiseProfile> {
+    use leptos::prelude::WithUntracked;
 
-    let files = state.files.get_untracked();
-    let idx = state.current_file_index.get_untracked();
-    let sample_rate = idx
-        .and_then(|i| files.get(i))
-        .map(|f| f.audio.sample_rate)
-        .unwrap_or(0);
-
-    let name = state.notch_profile_name.get_untracked();
-    let profile_name = if name.is_empty() {
-        idx.and_then(|i| files.get(i))
-            .map(|f| {
-                let base = f.name.rsplit('/').next().unwrap_or(&f.name);
-                let base = base.rsplit('\\').next().unwrap_or(base);
-                base.rsplit_once('.').map(|(n, _)| n).unwrap_or(base).to_string()
-            })
-            .unwrap_or_else(|| "Noise Profile".to_string())
-    } else {
-        name
-    };
-
-    Some(crate::dsp::notch::NoiseProfile {
-        name: profile_name,
-        bands,
-        source_sample_rate: sample_rate,
-        created: crate::annotations::now_iso8601(),
-        noise_floor,
-        harmonic_suppression: state.notch_harmonic_suppression.get_untracked(),
-    })
+    let (name, sample_rate) = state.files.with_untracked(|files| {
+        files.get(file_idx).map(|f| (f.name.clone(), f.audio.sample_rate)).unzip()
+    });
+    state.file_settings(file_idx).noise_profile(
+        name.as_deref(),
+        sample_rate.unwrap_or(0),
+        crate::annotations::now_iso8601(),
+    )
 }
 
 /// Produce YAML for a file-adjacent sidecar with `file_path` stripped from the identity.
@@ -193,7 +168,7 @@ pub fn save_annotations(state: crate::state::AppState, file_idx: usize) {
                 set.file_identity = id;
             }
             // Capture current NR state into the sidecar
-            set.noise_profile = sync_noise_profile_from_state(state);
+            set.noise_profile = sync_noise_profile(state, file_idx);
             set.touch();
         }
     });
@@ -282,7 +257,7 @@ pub fn save_sidecar_explicit(state: crate::state::AppState, file_idx: usize) {
             }) {
                 set.file_identity = id;
             }
-            set.noise_profile = sync_noise_profile_from_state(state);
+            set.noise_profile = sync_noise_profile(state, file_idx);
             set.touch();
         }
     });
@@ -316,8 +291,8 @@ pub fn save_annotations_to_opfs(state: crate::state::AppState, file_idx: usize)
 }
 
 /// Apply a loaded sidecar to the annotation store and restore NR profile to file settings.
-fn apply_loaded_sidecar(state: crate::state::AppState, file_idx: usize, loaded: crate::annotations::AnnotationSet) {
-    use leptos::prelude::Update;
+fn apply_loaded_sidecar(state: crate::state::AppState, file_idx: usize, mut loaded: crate::annotations::AnnotationSet) {
+    use leptos::prelude::{Update, WithUntracked};
 
     // If the sidecar has a noise profile, store it in the file's per-file settings.
     // Also restore cached peak values from sidecar metadata if not yet computed.
@@ -352,6 +327,31 @@ fn apply_loaded_sidecar(state: crate::state::AppState, file_idx: usize, loaded:
         });
     }
 
+    // A time-expansion factor chosen by hand travels with the annotations.
+    // Apply it before the set is stored: its annotations already use it.
+    if let Some(te) = loaded.audio_metadata.as_ref().and_then(|m| m.time_expansion) {
+        crate::components::file_sidebar::set_time_expansion(state, file_idx, te);
+    } else {
+        // Sidecars from before time expansion was recorded were made against
+        // the file as stored (×1). Move them to the factor detected on load.
+        let current = state.files.with_untracked(|files| {
+            files.get(file_idx).map(|f| (f.audio.metadata.time_expansion, f.audio.clone()))
+        });
+        if let Some((te, audio)) = current {
+            if te != 1.0 {
+                loaded.rescale_time_expansion(1.0, te);
+            }
+            if loaded.audio_metadata.is_none() {
+                loaded.audio_metadata = crate::annotations::AnnotationSet::new_with_metadata(
+                    loaded.file_identity.clone(), &audio, peak_30s, peak_full,
+                ).audio_metadata;
+            }
+            if let Some(meta) = loaded.audio_metadata.as_mut() {
+                meta.time_expansion = Some(te);
+            }
+        }
+    }
+
     state.annotation_store.update(|store| {
         store.ensure_len(file_idx + 1);
         store.sets[file_idx] = Some(loaded);
@@ -469,11 +469,21 @@ fn load_annotations_tauri(state: crate::state::AppState, file_idx: usize, identi
                                 .and_then(|s| s.as_ref())
                                 .is_some();
                             if !already_has {
+                                let central_modified = loaded.modified_at.clone();
                                 apply_loaded_sidecar(state, file_idx, loaded);
                                 log::debug!("Tauri loaded central annotations for file {file_idx}: {try_key}");
                                 if i > 0 {
                                     save_annotations(state, file_idx);
                                 }
+                                // The file-adjacent sidecar may have been edited
+                                // elsewhere (e.g. synced from another device)
+                                let sidecar = sidecar_yaml.as_deref()
+                                    .and_then(|y| yaml_serde::from_str::<crate::annotations::AnnotationSet>(y).ok());
+                                if let Some(sidecar) = sidecar.filter(|s| s.modified_at != central_modified) {
+                                    crate::components::file_sidebar::merge_conflicts::merge_into_file(
+                                        state, file_idx, sidecar, "sidecar next to the audio file",
+                                    );
+                                }
                             }
                         }
                         Err(e) => log::warn!("Tauri central deserialize error for {try_key}: {e}"),
@@ -1,5 +1,6 @@
 use serde::{Serialize, Deserialize};
 use crate::annotations::{FileIdentity, AudioFileMetadata, Annotation, generate_uuid, now_iso8601};
+use crate::annotation_merge::{MergeBase, MergeOutcome};
 use crate::dsp::notch::NoiseProfile;
 
 /// An Oversample project file (.batproj) — groups multiple audio files with their
@@ -72,6 +73,10 @@ pub struct ProjectFile {
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub noise_profile: Option<NoiseProfile>,
 
+    /// Common ancestor left by the last .batm merge into this file.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub merge_base: Option<MergeBase>,
+
     /// Date/time adjustment in seconds. Added to the file's detected recording
     /// start time. Useful when the recorder's clock was wrong.
     #[serde(default, skip_serializing_if = "is_zero")]
@@ -208,18 +213,32 @@ impl BatProject {
         })
     }
 
-    /// Merge a .batm AnnotationSet into the corresponding project file entry.
-    /// Returns true if annotations were merged, false if no matching file found.
-    pub fn merge_batm(&mut self, set: &crate::annotations::AnnotationSet, batm_key: &str) -> bool {
-        let Some(idx) = self.find_file(&set.file_identity) else { return false };
-        let pf = &mut self.files[idx];
+    /// Three-way merge a .batm AnnotationSet against the corresponding
+    /// project file entry. Returns the entry's index and the unresolved
+    /// outcome, or None if no matching file is found.
+    pub fn plan_batm_merge(&self, set: &crate::annotations::AnnotationSet) -> Option<(usize, MergeOutcome)> {
+        let idx = self.find_file(&set.file_identity)?;
+        let pf = &self.files[idx];
+        let outcome = crate::annotation_merge::three_way_merge(
+            &pf.annotations, pf.merge_base.as_ref(),
+            &set.annotations, set.merge_base.as_ref(),
+        );
+        Some((idx, outcome))
+    }
 
-        // Merge annotations (append, skip duplicates by id)
-        for ann in &set.annotations {
-            if !pf.annotations.iter().any(|a| a.id == ann.id) {
-                pf.annotations.push(ann.clone());
-            }
-        }
+    /// Store the resolved result of [`Self::plan_batm_merge`] in file `idx`
+    /// and record the merge.
+    pub fn apply_batm_merge(
+        &mut self,
+        idx: usize,
+        set: &crate::annotations::AnnotationSet,
+        annotations: Vec<Annotation>,
+        base: MergeBase,
+        batm_key: &str,
+    ) {
+        let Some(pf) = self.files.get_mut(idx) else { return };
+        pf.annotations = annotations;
+        pf.merge_base = Some(base);
 
         // Take noise profile if the project file doesn't have one
         if pf.noise_profile.is_none() {
@@ -240,12 +259,24 @@ impl BatProject {
         });
 
         self.touch();
-        true
     }
 
-    /// Check if a .batm key has already been merged (avoids re-prompting).
-    pub fn was_merged(&self, batm_key: &str) -> bool {
-        self.merge_history.iter().any(|r| r.batm_key == batm_key)
+    /// Check if a .batm key has been merged since the sidecar was last
+    /// modified (avoids re-prompting). A sidecar edited after its last merge
+    /// needs merging again.
+    pub fn was_merged(&self, batm_key: &str, modified_at: Option<&str>) -> bool {
+        self.merge_history.iter().any(|r| {
+            r.batm_key == batm_key && modified_at.is_none_or(|m| r.merged_at.as_str() >= m)
+        })
+    }
+
+    /// Resolved recording start (ms since epoch) for a project file: the user
+    /// override if set, else `detected_ms` (GUANO / file date), plus the file's
+    /// time offset.
+    pub fn file_start_epoch_ms(&self, idx: usize, detected_ms: Option<f64>) -> Option<f64> {
+        let pf = self.files.get(idx);
+        let start = pf.and_then(|f| f.recording_start_override_ms).or(detected_ms)?;
+        Some(start + pf.map(|f| f.time_offset_secs * 1000.0).unwrap_or(0.0))
     }
 
     /// Add a file to the project from a FileIdentity and optional audio metadata.
@@ -256,6 +287,7 @@ impl BatProject {
             audio_metadata,
             annotations: Vec::new(),
             noise_profile: None,
+            merge_base: None,
             time_offset_secs: 0.0,
             recording_start_override_ms: None,
             metadata_from_tauri: false,
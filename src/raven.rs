@@ -0,0 +1,470 @@
+//! Raven Pro selection tables.
+//!
+//! A selection table is tab-separated text: one header row, then one row per
+//! selection with `Begin Time (s)`, `End Time (s)`, `Low Freq (Hz)` and
+//! `High Freq (Hz)` plus any number of measurement or annotation columns.
+//! Raven writes one row per view (`Waveform 1`, `Spectrogram 1`) for the same
+//! `Selection` number; those are folded into a single annotation.
+//!
+//! Regions map to box selections, segments to selections spanning the full
+//! band and markers to zero-length selections. The label is written to the
+//! `Annotation` column, tags to `Tags` and notes to `Notes`. Any other column
+//! is kept as a `raven:<column>=<value>` tag and written back as a column on
+//! export, so tables round-trip without losing custom columns.
+//!
+//! Measurements export as the box they span, with `Start Freq (Hz)`,
+//! `End Freq (Hz)` and `Slope (kHz/ms)` columns so the direction of the line
+//! survives; rows with both start and end frequencies import as measurements.
+//!
+//! For timelines (and Raven's multi-file tables) begin/end times are on the
+//! shared axis; `Begin File` and `File Offset (s)` say which file a row
+//! belongs to.
+
+use crate::annotations::{generate_uuid, now_iso8601, Annotation, AnnotationKind, Marker, Measurement, Region};
+
+/// Tag prefix for Raven columns Oversample has no field for.
+pub const RAVEN_TAG_PREFIX: &str = "raven:";
+
+const SELECTION: &str = "Selection";
+const VIEW: &str = "View";
+const CHANNEL: &str = "Channel";
+const BEGIN_TIME: &str = "Begin Time (s)";
+const END_TIME: &str = "End Time (s)";
+const LOW_FREQ: &str = "Low Freq (Hz)";
+const HIGH_FREQ: &str = "High Freq (Hz)";
+const BEGIN_FILE: &str = "Begin File";
+const FILE_OFFSET: &str = "File Offset (s)";
+const ANNOTATION: &str = "Annotation";
+const TAGS: &str = "Tags";
+const NOTES: &str = "Notes";
+const START_FREQ: &str = "Start Freq (Hz)";
+const END_FREQ: &str = "End Freq (Hz)";
+const SLOPE: &str = "Slope (kHz/ms)";
+
+/// Columns that are read into annotation fields or are derived from them
+/// (and so are not kept as tags on import).
+const KNOWN_COLUMNS: &[&str] = &[
+    SELECTION, VIEW, BEGIN_TIME, END_TIME, LOW_FREQ, HIGH_FREQ, BEGIN_FILE, FILE_OFFSET,
+    ANNOTATION, TAGS, NOTES, START_FREQ, END_FREQ, SLOPE,
+    "Delta Time (s)", "Delta Freq (Hz)",
+];
+
+/// A file's place in a selection table.
+#[derive(Clone, Debug)]
+pub struct RavenFile {
+    /// Filename written to / matched against `Begin File`.
+    pub name: String,
+    /// Start of the file on the table's time axis (0 for a single file).
+    pub offset_secs: f64,
+    pub duration_secs: f64,
+    /// Nyquist frequency; segments export as 0..nyquist and rows spanning
+    /// the whole band import as segments.
+    pub nyquist: f64,
+}
+
+/// Annotations read from a selection table.
+#[derive(Debug, Default)]
+pub struct RavenImport {
+    /// `(index into the files passed to the parser, annotation)`.
+    pub annotations: Vec<(usize, Annotation)>,
+    /// Rows that matched no file or had unreadable times.
+    pub skipped: usize,
+}
+
+fn clean_cell(s: &str) -> String {
+    s.replace(['\t', '\r', '\n'], " ").trim().to_string()
+}
+
+fn format_num(v: f64) -> String {
+    let s = format!("{v:.6}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+fn raven_tag(tag: &str) -> Option<(&str, &str)> {
+    tag.strip_prefix(RAVEN_TAG_PREFIX)?.split_once('=')
+}
+
+/// Write annotations from one or more files as a selection table.
+/// Groups have no Raven equivalent and are skipped.
+pub fn export_selection_table(files: &[(RavenFile, &[Annotation])]) -> String {
+    let has_measurements = files.iter().any(|(_, annotations)| {
+        annotations.iter().any(|a| matches!(a.kind, AnnotationKind::Measurement(_)))
+    });
+    // Extra columns in order of first appearance
+    let mut extra: Vec<String> = Vec::new();
+    for (_, annotations) in files {
+        for a in annotations.iter() {
+            for tag in &a.tags {
+                if let Some((col, _)) = raven_tag(tag) {
+                    if col != CHANNEL && !KNOWN_COLUMNS.contains(&col) && !extra.iter().any(|c| c == col) {
+                        extra.push(col.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut header = vec![
+        SELECTION, VIEW, CHANNEL, BEGIN_TIME, END_TIME, LOW_FREQ, HIGH_FREQ,
+        BEGIN_FILE, FILE_OFFSET, ANNOTATION, TAGS, NOTES,
+    ].into_iter().map(String::from).collect::<Vec<_>>();
+    if has_measurements {
+        header.extend([START_FREQ, END_FREQ, SLOPE].map(String::from));
+    }
+    header.extend(extra.iter().cloned());
+
+    let mut out = header.join("\t");
+    out.push('\n');
+    let mut selection = 0;
+    for (file, annotations) in files {
+        let mut rows: Vec<(f64, String)> = Vec::new();
+        for a in annotations.iter() {
+            let mut line = None;
+            let (start, end, low, high, label) = match &a.kind {
+                AnnotationKind::Region(r) => (
+                    r.time_start,
+                    r.time_end,
+                    r.freq_low.unwrap_or(0.0),
+                    r.freq_high.unwrap_or(file.nyquist),
+                    r.label.as_deref(),
+                ),
+                AnnotationKind::Marker(m) => (m.time, m.time, 0.0, 0.0, m.label.as_deref()),
+                AnnotationKind::Measurement(m) => {
+                    line = Some(m);
+                    (
+                        m.start_time.min(m.end_time),
+                        m.start_time.max(m.end_time),
+                        m.start_freq.min(m.end_freq),
+                        m.start_freq.max(m.end_freq),
+                        m.label.as_deref(),
+                    )
+                }
+                AnnotationKind::Group(_) => continue,
+            };
+            let mut channel = "1".to_string();
+            let mut columns = vec![String::new(); extra.len()];
+            let mut tags = Vec::new();
+            for tag in &a.tags {
+                match raven_tag(tag) {
+                    Some((CHANNEL, v)) => channel = clean_cell(v),
+                    Some((col, v)) => {
+                        if let Some(i) = extra.iter().position(|c| c == col) {
+                            columns[i] = clean_cell(v);
+                        }
+                    }
+                    None => tags.push(clean_cell(tag)),
+                }
+            }
+            let mut row = vec![
+                String::new(), // selection number, filled in after sorting
+                "Spectrogram 1".to_string(),
+                channel,
+                format_num(file.offset_secs + start),
+                format_num(file.offset_secs + end),
+                format_num(low),
+                format_num(high),
+                clean_cell(&file.name),
+                format_num(start),
+                label.map(clean_cell).unwrap_or_default(),
+                tags.join("; "),
+                a.notes.as_deref().map(clean_cell).unwrap_or_default(),
+            ];
+            if has_measurements {
+                row.extend(match line {
+                    Some(m) => [
+                        format_num(m.start_freq),
+                        format_num(m.end_freq),
+                        m.slope_khz_per_ms().map(format_num).unwrap_or_default(),
+                    ],
+                    None => Default::default(),
+                });
+            }
+            row.extend(columns);
+            rows.push((file.offset_secs + start, row.join("\t")));
+        }
+        rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, row) in rows {
+            selection += 1;
+            out.push_str(&selection.to_string());
+            out.push_str(&row);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Parse a selection table and assign each row to one of `files`.
+///
+/// Rows name their file with `Begin File`; otherwise the file whose span on
+/// the time axis contains the row's begin time is used. With a single file,
+/// every row goes to it.
+pub fn parse_selection_table(text: &str, files: &[RavenFile]) -> Result<RavenImport, String> {
+    parse_table(text, files, || (generate_uuid(), now_iso8601()))
+}
+
+/// `parse_selection_table`, with `stamp` giving each annotation's id and
+/// creation time.
+fn parse_table(text: &str, files: &[RavenFile], stamp: impl Fn() -> (String, String)) -> Result<RavenImport, String> {
+    let mut lines = text.lines().map(|l| l.trim_end_matches('\r')).filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = lines
+        .next()
+        .ok_or("Empty selection table")?
+        .split('\t')
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_string())
+        .collect();
+    let col = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (Some(begin_col), Some(end_col)) = (col(BEGIN_TIME), col(END_TIME)) else {
+        return Err("Not a Raven selection table (no Begin/End Time columns)".into());
+    };
+    let (selection_col, view_col) = (col(SELECTION), col(VIEW));
+    let (file_col, offset_col) = (col(BEGIN_FILE), col(FILE_OFFSET));
+    let columns = Columns {
+        header: &header,
+        low: col(LOW_FREQ),
+        high: col(HIGH_FREQ),
+        channel: col(CHANNEL),
+        label: col(ANNOTATION),
+        tags: col(TAGS),
+        notes: col(NOTES),
+        start_freq: col(START_FREQ),
+        end_freq: col(END_FREQ),
+    };
+
+    let mut result = RavenImport::default();
+    // Selection number -> index in result.annotations, for multi-view tables
+    let mut seen: Vec<(String, usize, bool)> = Vec::new();
+
+    for line in lines {
+        let cells: Vec<&str> = line.split('\t').map(str::trim).collect();
+        let cell = |i: Option<usize>| i.and_then(|i| cells.get(i)).copied().filter(|s| !s.is_empty());
+        let num = |i: Option<usize>| cell(i).and_then(|s| s.parse::<f64>().ok());
+
+        let (Some(begin), Some(end)) = (num(Some(begin_col)), num(Some(end_col))) else {
+            result.skipped += 1;
+            continue;
+        };
+
+        // Route the row to a file and convert to file-local time
+        let by_name = cell(file_col).and_then(|name| {
+            let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+            files.iter().position(|f| f.name.eq_ignore_ascii_case(base))
+        });
+        let routed = by_name.or_else(|| {
+            files.iter().position(|f| begin >= f.offset_secs && begin < f.offset_secs + f.duration_secs)
+        }).or_else(|| (files.len() == 1).then_some(0));
+        let Some(file_idx) = routed else {
+            result.skipped += 1;
+            continue;
+        };
+        let file = &files[file_idx];
+        let start = match (by_name, num(offset_col)) {
+            (Some(_), Some(offset)) => offset,
+            _ => begin - file.offset_secs,
+        };
+        let end = start + (end - begin).max(0.0);
+
+        let is_spectrogram = cell(view_col).is_none_or(|v| v.to_ascii_lowercase().starts_with("spectrogram"));
+        if let Some(sel) = cell(selection_col) {
+            if let Some(entry) = seen.iter_mut().find(|(s, _, _)| s == sel) {
+                // Another view of the same selection; the spectrogram row wins
+                // as it carries the frequency bounds
+                if is_spectrogram && !entry.2 {
+                    entry.2 = true;
+                    let slot = entry.1;
+                    result.annotations[slot].1 = build_annotation(&columns, &cells, start, end, file, stamp());
+                }
+                continue;
+            }
+            seen.push((sel.to_string(), result.annotations.len(), is_spectrogram));
+        }
+        let a = build_annotation(&columns, &cells, start, end, file, stamp());
+        result.annotations.push((file_idx, a));
+    }
+    Ok(result)
+}
+
+/// Header and the column indices `build_annotation` reads.
+struct Columns<'a> {
+    header: &'a [String],
+    low: Option<usize>,
+    high: Option<usize>,
+    channel: Option<usize>,
+    label: Option<usize>,
+    tags: Option<usize>,
+    notes: Option<usize>,
+    start_freq: Option<usize>,
+    end_freq: Option<usize>,
+}
+
+fn build_annotation(
+    columns: &Columns,
+    cells: &[&str],
+    start: f64,
+    end: f64,
+    file: &RavenFile,
+    (id, now): (String, String),
+) -> Annotation {
+    let cell = |i: Option<usize>| i.and_then(|i| cells.get(i)).copied().filter(|s| !s.is_empty());
+    let low = cell(columns.low).and_then(|s| s.parse::<f64>().ok());
+    let high = cell(columns.high).and_then(|s| s.parse::<f64>().ok());
+    let label = cell(columns.label).map(str::to_string);
+
+    let mut tags: Vec<String> = cell(columns.tags)
+        .map(|t| t.split(';').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect())
+        .unwrap_or_default();
+    if let Some(ch) = cell(columns.channel).filter(|c| *c != "1") {
+        tags.push(format!("{RAVEN_TAG_PREFIX}{CHANNEL}={ch}"));
+    }
+    for (i, name) in columns.header.iter().enumerate() {
+        let known = KNOWN_COLUMNS.iter().any(|k| k.eq_ignore_ascii_case(name)) || name.eq_ignore_ascii_case(CHANNEL);
+        if known || name.is_empty() {
+            continue;
+        }
+        if let Some(v) = cell(Some(i)) {
+            tags.push(format!("{RAVEN_TAG_PREFIX}{name}={v}"));
+        }
+    }
+
+    let start_freq = cell(columns.start_freq).and_then(|s| s.parse::<f64>().ok());
+    let end_freq = cell(columns.end_freq).and_then(|s| s.parse::<f64>().ok());
+    let kind = if let (Some(start_freq), Some(end_freq)) = (start_freq, end_freq) {
+        AnnotationKind::Measurement(Measurement { start_time: start, start_freq, end_time: end, end_freq, label })
+    } else if end <= start && low.unwrap_or(0.0) == 0.0 && high.unwrap_or(0.0) == 0.0 {
+        AnnotationKind::Marker(Marker { time: start, label, color: None })
+    } else {
+        // A row spanning the whole band is a time-only segment
+        let full_band = low.unwrap_or(0.0) <= 0.0 && high.is_none_or(|h| file.nyquist > 0.0 && h >= file.nyquist * 0.999);
+        let (freq_low, freq_high) = if full_band { (None, None) } else { (low, high) };
+        AnnotationKind::Region(Region {
+            time_start: start,
+            time_end: end,
+            freq_low,
+            freq_high,
+            label,
+            color: None,
+            locked: None,
+            contour: None,
+            position: None,
+        })
+    };
+    Annotation {
+        id,
+        kind,
+        created_at: now.clone(),
+        modified_at: now,
+        notes: cell(columns.notes).map(str::to_string),
+        parent_id: None,
+        sort_order: None,
+        tags,
+        label_default: None,
+        confidence: None,
+        review: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, offset_secs: f64) -> RavenFile {
+        RavenFile { name: name.into(), offset_secs, duration_secs: 10.0, nyquist: 192_000.0 }
+    }
+
+    fn annotation(kind: AnnotationKind, tags: &[&str]) -> Annotation {
+        Annotation {
+            id: String::new(),
+            kind,
+            created_at: String::new(),
+            modified_at: String::new(),
+            notes: Some("note".into()),
+            parent_id: None,
+            sort_order: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            label_default: None,
+            confidence: None,
+            review: None,
+        }
+    }
+
+    fn parse(text: &str, files: &[RavenFile]) -> RavenImport {
+        parse_table(text, files, || (String::new(), String::new())).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_timeline() {
+        let region = annotation(
+            AnnotationKind::Region(Region {
+                time_start: 1.5,
+                time_end: 1.75,
+                freq_low: Some(20_000.0),
+                freq_high: Some(50_000.0),
+                label: Some("Myotis".into()),
+                color: None,
+                locked: None,
+                contour: None,
+                position: None,
+            }),
+            &["social", "raven:Begin Path=D:\\bats\\b.wav", "raven:Call Type=FM"],
+        );
+        let measurement = annotation(
+            AnnotationKind::Measurement(Measurement {
+                start_time: 0.3,
+                start_freq: 60_000.0,
+                end_time: 0.2,
+                end_freq: 40_000.0,
+                label: None,
+            }),
+            &[],
+        );
+        let files = [file("a.wav", 0.0), file("b.wav", 10.0)];
+        let text = export_selection_table(&[
+            (files[0].clone(), std::slice::from_ref(&measurement)),
+            (files[1].clone(), std::slice::from_ref(&region)),
+        ]);
+        let header = text.lines().next().unwrap();
+        assert!(header.ends_with("Slope (kHz/ms)\tBegin Path\tCall Type"), "{header}");
+        // Begin times are on the shared axis
+        assert!(text.lines().nth(2).unwrap().contains("\t11.5\t11.75\t"));
+
+        let import = parse(&text, &files);
+        assert_eq!(import.skipped, 0);
+        assert_eq!(import.annotations.len(), 2);
+        let (idx, a) = &import.annotations[0];
+        assert_eq!(*idx, 0);
+        let AnnotationKind::Measurement(m) = &a.kind else { panic!("not a measurement") };
+        assert_eq!((m.start_time, m.start_freq, m.end_time, m.end_freq), (0.2, 60_000.0, 0.3, 40_000.0));
+        let (idx, a) = &import.annotations[1];
+        assert_eq!(*idx, 1);
+        let AnnotationKind::Region(r) = &a.kind else { panic!("not a region") };
+        assert_eq!((r.time_start, r.time_end, r.freq_low, r.freq_high), (1.5, 1.75, Some(20_000.0), Some(50_000.0)));
+        assert_eq!(r.label.as_deref(), Some("Myotis"));
+        assert_eq!(a.tags, region.tags);
+        assert_eq!(a.notes.as_deref(), Some("note"));
+    }
+
+    #[test]
+    fn test_parse_folds_views() {
+        let text = "Selection\tView\tChannel\tBegin Time (s)\tEnd Time (s)\tLow Freq (Hz)\tHigh Freq (Hz)\tBegin Path\tDelta Time (s)\tSpecies\n\
+            1\tWaveform 1\t1\t12.5\t12.6\t0\t0\tC:\\b.wav\t0.1\t\n\
+            1\tSpectrogram 1\t2\t12.5\t12.6\t30000\t45000\tC:\\b.wav\t0.1\tMYOLUC\n\
+            2\tSpectrogram 1\t1\t3\t3\t0\t0\t\t0\t\n\
+            3\tSpectrogram 1\t1\t25\t26\t0\t1000\t\t1\t\n";
+        let files = [file("a.wav", 0.0), file("b.wav", 10.0)];
+        let import = parse(text, &files);
+        // Selection 3 is past the end of the timeline
+        assert_eq!(import.skipped, 1);
+        assert_eq!(import.annotations.len(), 2);
+
+        let (idx, a) = &import.annotations[0];
+        assert_eq!(*idx, 1);
+        let AnnotationKind::Region(r) = &a.kind else { panic!("not a region") };
+        assert_eq!((r.time_start, r.freq_low, r.freq_high), (2.5, Some(30_000.0), Some(45_000.0)));
+        assert_eq!(a.tags, ["raven:Channel=2", "raven:Begin Path=C:\\b.wav", "raven:Species=MYOLUC"]);
+
+        let (idx, a) = &import.annotations[1];
+        assert_eq!(*idx, 0);
+        assert!(matches!(a.kind, AnnotationKind::Marker(Marker { time: 3.0, .. })));
+        assert!(a.tags.is_empty());
+    }
+}
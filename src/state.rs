@@ -75,6 +75,49 @@ impl Default for FileSettings {
     }
 }
 
+impl FileSettings {
+    /// Replace the notch / noise reduction settings with `restored`'s and
+    /// return the settings they replaced. Gain is kept (see `UndoSnapshot`).
+    pub fn swap_undoable(&mut self, restored: FileSettings) -> FileSettings {
+        let gain = (self.gain_mode, self.gain_db, self.gain_db_stash);
+        let previous = std::mem::replace(self, restored);
+        (self.gain_mode, self.gain_db, self.gain_db_stash) = gain;
+        previous
+    }
+
+    /// Noise profile to store in the file's sidecar, or None if there's
+    /// nothing to save. Unnamed profiles take the file's base name.
+    pub fn noise_profile(
+        &self,
+        file_name: Option<&str>,
+        sample_rate: u32,
+        created: String,
+    ) -> Option<crate::dsp::notch::NoiseProfile> {
+        if self.notch_bands.is_empty() && self.noise_reduce_floor.is_none() {
+            return None;
+        }
+        let name = if self.notch_profile_name.is_empty() {
+            file_name
+                .map(|name| {
+                    let base = name.rsplit('/').next().unwrap_or(name);
+                    let base = base.rsplit('\\').next().unwrap_or(base);
+                    base.rsplit_once('.').map(|(n, _)| n).unwrap_or(base).to_string()
+                })
+                .unwrap_or_else(|| "Noise Profile".to_string())
+        } else {
+            self.notch_profile_name.clone()
+        };
+        Some(crate::dsp::notch::NoiseProfile {
+            name,
+            bands: self.notch_bands.clone(),
+            source_sample_rate: sample_rate,
+            created,
+            noise_floor: self.noise_reduce_floor.clone(),
+            harmonic_suppression: self.notch_harmonic_suppression,
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LoadedFile {
     pub name: String,
@@ -126,14 +169,20 @@ pub struct LoadedFile {
     pub min_display_freq: Option<f64>,
     /// Per-file vertical zoom: upper frequency bound in Hz. `None` = default (Nyquist).
     pub max_display_freq: Option<f64>,
+    /// Zero-crossing dots for files loaded from Anabat ZC data (the audio is
+    /// a silent carrier for the timebase).
+    pub zc_dots: Option<std::sync::Arc<Vec<crate::audio::anabat::ZcDot>>>,
 }
 
 impl LoadedFile {
     /// Get the recording start time as milliseconds since Unix epoch, if available.
     ///
     /// Sources (in priority order):
-    /// 1. GUANO "Timestamp" field (ISO 8601) — actual recording start
-    /// 2. `last_modified_ms` from the File API — file modification time as fallback,
+    /// 1. GUANO "Timestamp" field (ISO 8601) — actual recording start; AudioMoth
+    ///    comments are synthesised into this field at load time
+    /// 2. BWF `bext` time reference, then origination date/time
+    /// 3. AudioMoth filename (`YYYYMMDD_HHMMSS`)
+    /// 4. `last_modified_ms` from the File API — file modification time as fallback,
     ///    adjusted backwards by the file duration to approximate recording start
     pub fn recording_start_epoch_ms(&self) -> Option<f64> {
         self.recording_start_info().map(|(ms, _)| ms)
@@ -142,16 +191,28 @@ impl LoadedFile {
     /// Get the recording start time and its source description.
     ///
     /// Returns `(epoch_ms, source_label)` where `source_label` describes the
-    /// origin: "GUANO Timestamp" or "File modified date (approx.)".
+    /// origin, e.g. "GUANO Timestamp" or "File modified date (approx.)".
     pub fn recording_start_info(&self) -> Option<(f64, &'static str)> {
-        // Try GUANO Timestamp first
+        // Try GUANO Timestamp first, then BWF time reference / origination
         if let Some(ref guano) = self.audio.metadata.guano {
-            if let Some((_, ts)) = guano.fields.iter().find(|(k, _)| k == "Timestamp") {
-                if let Some(epoch) = parse_iso8601_to_epoch_ms(ts) {
-                    return Some((epoch, "GUANO Timestamp"));
+            for (key, label) in [
+                ("Timestamp", "GUANO Timestamp"),
+                ("BWF|Start", "BWF time reference"),
+                ("BWF|Origination", "BWF origination date"),
+            ] {
+                if let Some((_, ts)) = guano.fields.iter().find(|(k, _)| k == key) {
+                    if let Some(epoch) = parse_iso8601_to_epoch_ms(ts) {
+                        return Some((epoch, label));
+                    }
                 }
             }
         }
+        // AudioMoth names files by start time
+        if let Some(epoch) = crate::audio::audiomoth::filename_timestamp(&self.name)
+            .and_then(|ts| parse_iso8601_to_epoch_ms(&ts))
+        {
+            return Some((epoch, "AudioMoth filename"));
+        }
         // Fallback: file last-modified minus duration ≈ recording start
         self.last_modified_ms
             .map(|lm| (lm - self.audio.duration_secs * 1000.0, "File modified date (approx.)"))
@@ -191,7 +252,11 @@ pub enum PlaybackMode {
 pub enum ExportFormat {
     #[default]
     Wav,
+    /// Lossless FLAC (16-bit, or 24-bit for high-resolution sources).
+    Flac,
     Mp4,
+    /// Anabat zero-crossing file.
+    Zc,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
@@ -387,6 +452,29 @@ pub enum RecordMode {
     ListenOnly,  // grey out record, user can only listen
 }
 
+/// File format for saved recordings.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum RecordFormat {
+    #[default]
+    Wav,
+    /// Lossless FLAC: roughly half the size of WAV for typical bat recordings.
+    Flac,
+}
+
+impl RecordFormat {
+    /// Filename extension, also the key persisted to localStorage.
+    pub fn extension(self) -> &'static str {
+        match self {
+            RecordFormat::Wav => "wav",
+            RecordFormat::Flac => "flac",
+        }
+    }
+
+    pub fn from_extension(ext: &str) -> Self {
+        if ext.eq_ignore_ascii_case("flac") { RecordFormat::Flac } else { RecordFormat::Wav }
+    }
+}
+
 /// Waveform sub-view mode.
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum WaveformView {
@@ -429,6 +517,7 @@ pub enum CanvasTool {
     #[default]
     Hand,      // drag to pan
     Selection, // drag to select
+    Measure,   // drag between two time-frequency points
 }
 
 /// Which entity type currently has interactive focus.
@@ -798,22 +887,37 @@ pub struct NavEntry {
     pub zoom_level: f64,
 }
 
-/// A snapshot of a file's annotation set for undo/redo.
+/// State an undo entry restores.
+#[derive(Clone, Debug)]
+pub enum UndoSnapshot {
+    /// The file's whole annotation set (None = the file had no set yet).
+    Annotations(Option<Box<crate::annotations::AnnotationSet>>),
+    /// The file's notch and noise reduction settings. Gain is a listening
+    /// control and is left alone on undo.
+    Settings(FileSettings),
+}
+
+/// A snapshot of one file's annotations or settings for undo/redo.
 #[derive(Clone, Debug)]
 pub struct UndoEntry {
     pub file_idx: usize,
-    pub snapshot: Option<crate::annotations::AnnotationSet>,
+    pub snapshot: UndoSnapshot,
 }
 
-/// Undo/redo stack for annotation operations.
+/// Undo/redo stack for annotation and file-settings operations.
 #[derive(Clone, Debug, Default)]
 pub struct UndoStack {
     pub undo: Vec<UndoEntry>,
     pub redo: Vec<UndoEntry>,
+    /// (file_idx, time in ms) of the last settings snapshot, so a run of
+    /// slider input events becomes a single undo step.
+    settings_burst: Option<(usize, f64)>,
 }
 
 impl UndoStack {
     const MAX_SIZE: usize = 100;
+    /// Settings changes closer together than this merge into one step.
+    const SETTINGS_BURST_MS: f64 = 600.0;
 
     pub fn push_undo(&mut self, entry: UndoEntry) {
         self.undo.push(entry);
@@ -821,9 +925,34 @@ impl UndoStack {
             self.undo.remove(0);
         }
         self.redo.clear();
+        self.settings_burst = None;
+    }
+
+    /// Take the newest undo entry; the next settings change starts a new step.
+    pub fn pop_undo(&mut self) -> Option<UndoEntry> {
+        self.settings_burst = None;
+        self.undo.pop()
+    }
+
+    /// Take the newest redo entry; the next settings change starts a new step.
+    pub fn pop_redo(&mut self) -> Option<UndoEntry> {
+        self.settings_burst = None;
+        self.redo.pop()
     }
 }
 
+/// Put an annotation snapshot in `slot` and return what it replaced.
+/// Going back to "no annotations" stores `empty_set()` rather than None, so
+/// the next save overwrites the stored sidecar.
+fn swap_annotation_set(
+    slot: &mut Option<crate::annotations::AnnotationSet>,
+    snapshot: Option<Box<crate::annotations::AnnotationSet>>,
+    empty_set: impl FnOnce() -> Option<crate::annotations::AnnotationSet>,
+) -> Option<Box<crate::annotations::AnnotationSet>> {
+    let restored = snapshot.map(|s| *s).or_else(empty_set);
+    std::mem::replace(slot, restored).map(Box::new)
+}
+
 /// A time-position bookmark created during or after playback.
 #[derive(Clone, Copy, Debug)]
 pub struct Bookmark {
@@ -1192,6 +1321,8 @@ pub struct AppState {
 
     // Tool
     pub canvas_tool: RwSignal<CanvasTool>,
+    /// Measurement being dragged out with the Measure tool (not yet saved)
+    pub measure_preview: RwSignal<Option<crate::annotations::Measurement>>,
 
     // HFR (High Frequency Range) mode
     pub hfr_enabled: RwSignal<bool>,
@@ -1219,6 +1350,8 @@ pub struct AppState {
 
     // Record mode (ToFile / ToMemory / ListenOnly)
     pub record_mode: RwSignal<RecordMode>,
+    /// File format for saved recordings (persisted to localStorage).
+    pub record_format: RwSignal<RecordFormat>,
 
     // Play-from-here time (updated by Spectrogram on scroll/zoom change)
     pub play_from_here_time: RwSignal<f64>,
@@ -1471,6 +1604,16 @@ pub struct AppState {
     pub pulse_overlay_enabled: RwSignal<bool>,
     pub selected_pulse_index: RwSignal<Option<usize>>,
     pub pulse_detecting: RwSignal<bool>,
+    /// Per-call measurements for `detected_pulses` (same order).
+    pub call_parameters: RwSignal<Vec<crate::dsp::call_params::CallParameters>>,
+    /// Call-shape classification for `detected_pulses` (same order).
+    pub call_shapes: RwSignal<Vec<crate::dsp::call_shape::ShapeClassification>>,
+    /// Feeding buzzes and social calls found in `detected_pulses`.
+    pub call_events: RwSignal<Vec<crate::dsp::call_events::CallEvent>>,
+    /// Traced frequency contour per detected pulse.
+    pub call_contours: RwSignal<Vec<crate::dsp::contour::CallContour>>,
+    /// CF-call measurements, Doppler passes and CF histogram for the current file.
+    pub cf_analysis: RwSignal<Option<crate::dsp::cf_analysis::CfAnalysis>>,
 
     // File identity hashing
     /// Whether a full hash computation (Layer 3/4) is currently running.
@@ -1496,6 +1639,14 @@ pub struct AppState {
     pub drop_target: RwSignal<Option<(AnnotationId, String)>>,
     /// Undo/redo stack for annotation operations.
     pub undo_stack: RwSignal<UndoStack>,
+    /// Sidecar merges waiting for the user to resolve conflicts (first one is shown).
+    pub pending_merges: RwSignal<Vec<crate::annotation_merge::PendingMerge>>,
+    /// Active review queue (None = not reviewing).
+    pub review_queue: RwSignal<Option<crate::annotation_review::ReviewQueue>>,
+    /// Name recorded with review decisions. Persisted to localStorage.
+    pub reviewer_name: RwSignal<String>,
+    /// Whether the project-wide annotation table is open.
+    pub show_annotation_table: RwSignal<bool>,
     /// Active annotation resize drag: (annotation_id, handle position).
     pub annotation_drag_handle: RwSignal<Option<(AnnotationId, ResizeHandlePosition)>>,
     /// Hovered annotation resize handle (for cursor + highlight).
@@ -1619,6 +1770,8 @@ pub struct AppState {
     pub export_section_open: RwSignal<bool>,
     /// Selected export format: WAV or MP4.
     pub export_format: RwSignal<ExportFormat>,
+    /// Write a BWF `bext` chunk (start time from the GUANO Timestamp) into WAV exports.
+    pub export_write_bext: RwSignal<bool>,
     /// Video export progress (0.0 to 1.0), None = not exporting.
     pub video_export_progress: RwSignal<Option<f64>>,
     /// Video export status message.
@@ -1762,6 +1915,7 @@ impl AppState {
 
             // New
             canvas_tool: RwSignal::new(CanvasTool::Hand),
+            measure_preview: RwSignal::new(None),
             hfr_enabled: RwSignal::new(false),
             waveform_view: RwSignal::new(WaveformView::Frequency),
             bandpass_mode: RwSignal::new(BandpassMode::Auto),
@@ -1773,6 +1927,13 @@ impl AppState {
             show_bookmark_popup: RwSignal::new(false),
             play_start_mode: RwSignal::new(PlayStartMode::Auto),
             record_mode: RwSignal::new(if detect_tauri() { RecordMode::ToFile } else { RecordMode::ToMemory }),
+            record_format: RwSignal::new({
+                web_sys::window()
+                    .and_then(|w| w.local_storage().ok().flatten())
+                    .and_then(|ls| ls.get_item("oversample_record_format").ok().flatten())
+                    .map(|v| RecordFormat::from_extension(&v))
+                    .unwrap_or_default()
+            }),
             play_from_here_time: RwSignal::new(0.0),
             tile_ready_signal: RwSignal::new(0),
             bg_preload_gen: RwSignal::new(0),
@@ -1907,6 +2068,11 @@ impl AppState {
             pulse_overlay_enabled: RwSignal::new(false),
             selected_pulse_index: RwSignal::new(None),
             pulse_detecting: RwSignal::new(false),
+            call_parameters: RwSignal::new(Vec::new()),
+            call_shapes: RwSignal::new(Vec::new()),
+            call_events: RwSignal::new(Vec::new()),
+            call_contours: RwSignal::new(Vec::new()),
+            cf_analysis: RwSignal::new(None),
 
             hash_computing: RwSignal::new(false),
             hash_generation: RwSignal::new(0),
@@ -1921,6 +2087,15 @@ impl AppState {
             dragging_annotation_id: RwSignal::new(None),
             drop_target: RwSignal::new(None),
             undo_stack: RwSignal::new(UndoStack::default()),
+            pending_merges: RwSignal::new(Vec::new()),
+            review_queue: RwSignal::new(None),
+            reviewer_name: RwSignal::new({
+                web_sys::window()
+                    .and_then(|w| w.local_storage().ok().flatten())
+                    .and_then(|ls| ls.get_item("oversample_reviewer_name").ok().flatten())
+                    .unwrap_or_default()
+            }),
+            show_annotation_table: RwSignal::new(false),
             annotation_drag_handle: RwSignal::new(None),
             annotation_hover_handle: RwSignal::new(None),
             annotation_drag_original: RwSignal::new(None),
@@ -2041,6 +2216,13 @@ impl AppState {
             // Export UI
             export_section_open: RwSignal::new(false),
             export_format: RwSignal::new(ExportFormat::default()),
+            export_write_bext: RwSignal::new({
+                web_sys::window()
+                    .and_then(|w| w.local_storage().ok().flatten())
+                    .and_then(|ls| ls.get_item("oversample_export_write_bext").ok().flatten())
+                    .map(|v| v == "true")
+                    .unwrap_or(false)
+            }),
             video_export_progress: RwSignal::new(None),
             video_export_status: RwSignal::new(None),
             video_export_cancel: RwSignal::new(false),
@@ -2098,71 +2280,154 @@ impl AppState {
     /// Snapshot the current file's annotation set onto the undo stack.
     /// Call this BEFORE making any annotation mutation.
     pub fn snapshot_annotations(&self) {
-        let idx = match self.current_file_index.get_untracked() {
-            Some(i) => i,
-            None => return,
-        };
+        if let Some(idx) = self.current_file_index.get_untracked() {
+            self.snapshot_file_annotations(idx);
+        }
+    }
+
+    /// Snapshot file `idx`'s annotation set onto the undo stack (for edits
+    /// that touch files other than the current one).
+    pub fn snapshot_file_annotations(&self, idx: usize) {
         let store = self.annotation_store.get_untracked();
         let snapshot = store.sets.get(idx).cloned().flatten();
         self.undo_stack.update(|stack| {
-            stack.push_undo(UndoEntry { file_idx: idx, snapshot });
+            stack.push_undo(UndoEntry { file_idx: idx, snapshot: UndoSnapshot::Annotations(snapshot.map(Box::new)) });
         });
     }
 
-    /// Undo the last annotation operation.
-    pub fn undo_annotations(&self) {
-        let entry = {
-            let mut popped = None;
-            self.undo_stack.update(|stack| {
-                popped = stack.undo.pop();
-            });
-            match popped {
-                Some(e) => e,
-                None => return,
-            }
-        };
-
-        // Save current state to redo before restoring
-        let store = self.annotation_store.get_untracked();
-        let current = store.sets.get(entry.file_idx).cloned().flatten();
+    /// Snapshot the current file's notch / noise reduction settings onto the
+    /// undo stack. Call this BEFORE changing them; repeated calls in quick
+    /// succession (slider drags) record one step.
+    pub fn snapshot_settings(&self) {
+        let Some(idx) = self.current_file_index.get_untracked() else { return };
+        let now = js_sys::Date::now();
+        let snapshot = self.file_settings(idx);
         self.undo_stack.update(|stack| {
-            stack.redo.push(UndoEntry { file_idx: entry.file_idx, snapshot: current });
+            let in_burst = matches!(stack.settings_burst, Some((i, t)) if i == idx && now - t < UndoStack::SETTINGS_BURST_MS)
+                && matches!(stack.undo.last(), Some(e) if matches!(e.snapshot, UndoSnapshot::Settings(_)));
+            if !in_burst {
+                stack.push_undo(UndoEntry { file_idx: idx, snapshot: UndoSnapshot::Settings(snapshot) });
+            }
+            stack.settings_burst = Some((idx, now));
         });
+    }
 
-        // Restore the snapshot
-        self.annotation_store.update(|store| {
-            store.ensure_len(entry.file_idx + 1);
-            store.sets[entry.file_idx] = entry.snapshot;
-        });
-        self.annotations_dirty.set(true);
+    /// Settings of file `idx`. The current file's live in the individual
+    /// signals and are only copied to `LoadedFile::settings` on file switch.
+    pub fn file_settings(&self, idx: usize) -> FileSettings {
+        if self.current_file_index.get_untracked() == Some(idx) {
+            FileSettings {
+                gain_mode: self.gain_mode.get_untracked(),
+                gain_db: self.gain_db.get_untracked(),
+                gain_db_stash: self.gain_db_stash.get_untracked(),
+                notch_enabled: self.notch_enabled.get_untracked(),
+                notch_bands: self.notch_bands.get_untracked(),
+                notch_profile_name: self.notch_profile_name.get_untracked(),
+                notch_harmonic_suppression: self.notch_harmonic_suppression.get_untracked(),
+                noise_reduce_enabled: self.noise_reduce_enabled.get_untracked(),
+                noise_reduce_strength: self.noise_reduce_strength.get_untracked(),
+                noise_reduce_floor: self.noise_reduce_floor.get_untracked(),
+            }
+        } else {
+            self.files.with_untracked(|files| files.get(idx).map(|f| f.settings.clone()).unwrap_or_default())
+        }
     }
 
-    /// Redo the last undone annotation operation.
-    pub fn redo_annotations(&self) {
-        let entry = {
-            let mut popped = None;
-            self.undo_stack.update(|stack| {
-                popped = stack.redo.pop();
+    /// Restore the notch / noise reduction part of `settings` to file `idx`.
+    /// Gain is expected to be the file's own (see `FileSettings::swap_undoable`).
+    fn restore_file_settings(&self, idx: usize, settings: FileSettings) {
+        if self.current_file_index.get_untracked() == Some(idx) {
+            self.notch_enabled.set(settings.notch_enabled);
+            self.notch_bands.set(settings.notch_bands);
+            self.notch_profile_name.set(settings.notch_profile_name);
+            self.notch_harmonic_suppression.set(settings.notch_harmonic_suppression);
+            self.noise_reduce_enabled.set(settings.noise_reduce_enabled);
+            self.noise_reduce_strength.set(settings.noise_reduce_strength);
+            self.noise_reduce_floor.set(settings.noise_reduce_floor);
+        } else {
+            self.files.update(|files| {
+                if let Some(f) = files.get_mut(idx) {
+                    f.settings = settings;
+                }
             });
-            match popped {
-                Some(e) => e,
-                None => return,
+        }
+    }
+
+    /// Swap the state an undo entry describes with the file's current state:
+    /// restores the entry and returns an entry that reverses it.
+    fn swap_undo_entry(&self, entry: UndoEntry) -> UndoEntry {
+        let idx = entry.file_idx;
+        let current = match entry.snapshot {
+            UndoSnapshot::Annotations(snapshot) => {
+                let mut current = None;
+                self.annotation_store.update(|store| {
+                    store.ensure_len(idx + 1);
+                    current = swap_annotation_set(&mut store.sets[idx], snapshot, || self.empty_annotation_set(idx));
+                });
+                UndoSnapshot::Annotations(current)
+            }
+            UndoSnapshot::Settings(restored) => {
+                let mut settings = self.file_settings(idx);
+                let current = settings.swap_undoable(restored);
+                self.restore_file_settings(idx, settings);
+                UndoSnapshot::Settings(current)
             }
         };
+        // Persist what is now on screen: the auto-save effect covers the
+        // current file, other files are saved directly
+        if self.current_file_index.get_untracked() == Some(idx) {
+            self.annotations_dirty.set(true);
+        } else {
+            crate::opfs::save_annotations(*self, idx);
+        }
+        UndoEntry { file_idx: idx, snapshot: current }
+    }
 
-        // Save current state to undo before restoring
-        let store = self.annotation_store.get_untracked();
-        let current = store.sets.get(entry.file_idx).cloned().flatten();
-        self.undo_stack.update(|stack| {
-            stack.undo.push(UndoEntry { file_idx: entry.file_idx, snapshot: current });
-        });
+    /// A new, empty annotation set for loaded file `idx`.
+    fn empty_annotation_set(&self, idx: usize) -> Option<crate::annotations::AnnotationSet> {
+        self.files.with_untracked(|files| {
+            files.get(idx).map(|f| {
+                let id = f.identity.clone().unwrap_or_else(|| {
+                    crate::file_identity::identity_layer1(&f.name, f.audio.metadata.file_size as u64)
+                });
+                crate::annotations::AnnotationSet::new_with_metadata(id, &f.audio, f.cached_peak_db, f.cached_full_peak_db)
+            })
+        })
+    }
 
-        // Restore the snapshot
+    /// Append annotations to file `idx`, creating its annotation set if needed.
+    pub fn push_annotations(&self, idx: usize, annotations: Vec<crate::annotations::Annotation>) {
         self.annotation_store.update(|store| {
-            store.ensure_len(entry.file_idx + 1);
-            store.sets[entry.file_idx] = entry.snapshot;
+            store.ensure_len(idx + 1);
+            if store.sets[idx].is_none() {
+                if let Some(set) = self.empty_annotation_set(idx) {
+                    store.sets[idx] = Some(set);
+                }
+            }
+            if let Some(ref mut set) = store.sets[idx] {
+                set.annotations.extend(annotations);
+            }
         });
         self.annotations_dirty.set(true);
+        self.annotations_visible.set(true);
+    }
+
+    /// Undo the last annotation or settings operation.
+    pub fn undo(&self) {
+        let mut popped = None;
+        self.undo_stack.update(|stack| popped = stack.pop_undo());
+        let Some(entry) = popped else { return };
+        let reverse = self.swap_undo_entry(entry);
+        self.undo_stack.update(|stack| stack.redo.push(reverse));
+    }
+
+    /// Redo the last undone operation.
+    pub fn redo(&self) {
+        let mut popped = None;
+        self.undo_stack.update(|stack| popped = stack.pop_redo());
+        let Some(entry) = popped else { return };
+        let reverse = self.swap_undo_entry(entry);
+        self.undo_stack.update(|stack| stack.undo.push(reverse));
     }
 
     /// Whether there's something to undo.
@@ -2595,3 +2860,178 @@ impl AppState {
             .unwrap_or(96_000.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::{Annotation, AnnotationSet, Region};
+    use crate::dsp::notch::NoiseBand;
+    use crate::dsp::spectral_sub::NoiseFloor;
+
+    const FILE_NAME: &str = "night1/rec_001.wav";
+
+    fn empty_set() -> AnnotationSet {
+        yaml_serde::from_str("version: 3\nid: set-1\nfile_identity:\n  filename: rec_001.wav\n  file_size: 1000\n").unwrap()
+    }
+
+    fn region(id: &str) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            kind: AnnotationKind::Region(Region {
+                time_start: 1.0,
+                time_end: 1.2,
+                freq_low: Some(40_000.0),
+                freq_high: Some(60_000.0),
+                label: Some("Call".to_string()),
+                color: None,
+                locked: None,
+                contour: None,
+                position: None,
+            }),
+            created_at: "2026-01-01T00:00:00.000Z".to_string(),
+            modified_at: "2026-01-01T00:00:00.000Z".to_string(),
+            notes: None,
+            parent_id: None,
+            sort_order: None,
+            tags: Vec::new(),
+            label_default: None,
+            confidence: None,
+            review: None,
+        }
+    }
+
+    fn denoised_settings() -> FileSettings {
+        FileSettings {
+            notch_enabled: true,
+            notch_bands: vec![NoiseBand { center_hz: 15_000.0, bandwidth_hz: 400.0, q: 30.0, enabled: true, strength_db: 18.0 }],
+            notch_harmonic_suppression: 0.3,
+            noise_reduce_enabled: true,
+            noise_reduce_strength: 0.8,
+            noise_reduce_floor: Some(NoiseFloor {
+                bin_magnitudes: vec![0.1, 0.2, 0.1],
+                fft_size: 4,
+                sample_rate: 384_000,
+                analysis_duration_secs: 2.0,
+                frame_count: 100,
+            }),
+            ..FileSettings::default()
+        }
+    }
+
+    /// The sidecar `save_annotations` would write for this set and settings.
+    fn sidecar(set: &Option<AnnotationSet>, settings: &FileSettings) -> String {
+        let mut set = set.clone().expect("file has an annotation set");
+        set.noise_profile = settings.noise_profile(Some(FILE_NAME), 384_000, "2026-01-01T00:00:00.000Z".to_string());
+        yaml_serde::to_string(&set).unwrap()
+    }
+
+    /// `AppState::swap_undo_entry` on plain data.
+    fn swap(entry: UndoEntry, set: &mut Option<AnnotationSet>, settings: &mut FileSettings) -> UndoEntry {
+        let snapshot = match entry.snapshot {
+            UndoSnapshot::Annotations(snapshot) => {
+                UndoSnapshot::Annotations(swap_annotation_set(set, snapshot, || Some(empty_set())))
+            }
+            UndoSnapshot::Settings(restored) => UndoSnapshot::Settings(settings.swap_undoable(restored)),
+        };
+        UndoEntry { file_idx: entry.file_idx, snapshot }
+    }
+
+    fn undo(stack: &mut UndoStack, set: &mut Option<AnnotationSet>, settings: &mut FileSettings) {
+        let entry = stack.pop_undo().expect("something to undo");
+        let reverse = swap(entry, set, settings);
+        stack.redo.push(reverse);
+    }
+
+    fn redo(stack: &mut UndoStack, set: &mut Option<AnnotationSet>, settings: &mut FileSettings) {
+        let entry = stack.pop_redo().expect("something to redo");
+        let reverse = swap(entry, set, settings);
+        stack.undo.push(reverse);
+    }
+
+    #[test]
+    fn test_undo_redo_settings() {
+        let mut set = Some(empty_set());
+        let mut settings = denoised_settings();
+        let saved = sidecar(&set, &settings);
+        assert!(saved.contains("noise_profile"));
+        assert!(saved.contains("name: rec_001"));
+
+        let mut stack = UndoStack::default();
+        stack.push_undo(UndoEntry { file_idx: 0, snapshot: UndoSnapshot::Settings(settings.clone()) });
+        settings.notch_enabled = false;
+        settings.notch_bands.clear();
+        settings.noise_reduce_enabled = false;
+        settings.noise_reduce_floor = None;
+        // Gain is a listening control, not part of the undo history
+        settings.gain_db = 12.0;
+        let cleared = sidecar(&set, &settings);
+        assert!(!cleared.contains("noise_profile"));
+
+        undo(&mut stack, &mut set, &mut settings);
+        assert!(settings.notch_enabled && settings.noise_reduce_enabled);
+        assert_eq!(settings.notch_bands.len(), 1);
+        assert_eq!(settings.notch_bands[0].center_hz, 15_000.0);
+        assert_eq!(settings.noise_reduce_strength, 0.8);
+        assert!(settings.noise_reduce_floor.is_some());
+        assert_eq!(settings.gain_db, 12.0);
+        assert_eq!(sidecar(&set, &settings), saved);
+        assert!(stack.undo.is_empty());
+
+        settings.gain_db = 3.0;
+        redo(&mut stack, &mut set, &mut settings);
+        assert!(!settings.notch_enabled && !settings.noise_reduce_enabled);
+        assert!(settings.notch_bands.is_empty() && settings.noise_reduce_floor.is_none());
+        assert_eq!(settings.gain_db, 3.0);
+        assert_eq!(sidecar(&set, &settings), cleared);
+
+        undo(&mut stack, &mut set, &mut settings);
+        assert_eq!(sidecar(&set, &settings), saved);
+    }
+
+    #[test]
+    fn test_undo_redo_lock_and_tags() {
+        let mut set = Some(empty_set());
+        set.as_mut().unwrap().annotations.push(region("r1"));
+        let mut settings = denoised_settings();
+        let saved = sidecar(&set, &settings);
+
+        let mut stack = UndoStack::default();
+        stack.push_undo(UndoEntry { file_idx: 0, snapshot: UndoSnapshot::Annotations(set.clone().map(Box::new)) });
+        let a = &mut set.as_mut().unwrap().annotations[0];
+        if let AnnotationKind::Region(ref mut r) = a.kind {
+            r.locked = Some(true);
+        }
+        a.tags.push("Pipistrellus".to_string());
+        let edited = sidecar(&set, &settings);
+        assert_ne!(edited, saved);
+
+        undo(&mut stack, &mut set, &mut settings);
+        let a = &set.as_ref().unwrap().annotations[0];
+        assert!(matches!(a.kind, AnnotationKind::Region(ref r) if !r.is_locked()));
+        assert!(a.tags.is_empty());
+        assert_eq!(sidecar(&set, &settings), saved);
+
+        redo(&mut stack, &mut set, &mut settings);
+        let a = &set.as_ref().unwrap().annotations[0];
+        assert!(matches!(a.kind, AnnotationKind::Region(ref r) if r.is_locked()));
+        assert_eq!(a.tags, ["Pipistrellus"]);
+        assert_eq!(sidecar(&set, &settings), edited);
+    }
+
+    #[test]
+    fn test_undo_to_no_annotations_keeps_an_empty_set() {
+        // The file had no set before the first annotation was added
+        let mut stack = UndoStack::default();
+        stack.push_undo(UndoEntry { file_idx: 0, snapshot: UndoSnapshot::Annotations(None) });
+        let mut set = Some(empty_set());
+        set.as_mut().unwrap().annotations.push(region("r1"));
+        let mut settings = FileSettings::default();
+
+        undo(&mut stack, &mut set, &mut settings);
+        // An empty set, not None, so saving overwrites the old sidecar
+        assert!(set.as_ref().is_some_and(|s| s.annotations.is_empty()));
+
+        redo(&mut stack, &mut set, &mut settings);
+        assert_eq!(set.as_ref().map(|s| s.annotations.len()), Some(1));
+    }
+}
@@ -0,0 +1,178 @@
+use leptos::prelude::{GetUntracked, WithUntracked};
+use crate::annotations::{Annotation, AnnotationKind, Group, Region, generate_uuid, now_iso8601};
+use crate::canvas::tile_cache::yield_to_browser;
+use crate::dsp::template_match::{self, Template, TemplateHit, TemplateMatchParams};
+use crate::state::{AppState, LoadedFile};
+
+/// Seconds of audio scanned per chunk when STFT columns aren't in memory.
+const CHUNK_SECS: f64 = 10.0;
+
+/// Time–frequency box to use as the exemplar.
+#[derive(Clone, Copy, Debug)]
+pub struct TemplateBox {
+    pub time_start: f64,
+    pub time_end: f64,
+    pub freq_low: f64,
+    pub freq_high: f64,
+}
+
+/// FFT size and hop of a file's spectrogram.
+fn stft_geometry(file: &LoadedFile) -> (usize, usize) {
+    let sr = file.audio.sample_rate as f64;
+    let fft = (sr / file.spectrogram.freq_resolution).round() as usize;
+    let hop = (file.spectrogram.time_resolution * sr).round() as usize;
+    (fft.max(2), hop.max(1))
+}
+
+/// Cut the template from the source file's samples (works for streamed files too).
+fn build_template(file: &LoadedFile, bx: TemplateBox, state: &AppState, params: &TemplateMatchParams) -> Option<Template> {
+    let sr = file.audio.sample_rate;
+    let (fft, hop) = stft_geometry(file);
+    let start = ((bx.time_start * sr as f64) as u64).saturating_sub(fft as u64);
+    let end = (((bx.time_end * sr as f64) as u64) + fft as u64).min(file.audio.source.total_samples());
+    if end <= start {
+        return None;
+    }
+    let channel = state.channel_view.get_untracked();
+    let samples = file.audio.source.read_region(channel, start, (end - start) as usize);
+    template_match::template_from_samples(
+        &samples, sr, start as f64 / sr as f64, (fft, hop),
+        (bx.time_start, bx.time_end), (bx.freq_low, bx.freq_high), params,
+    )
+}
+
+/// Scan one file. Uses the in-memory spectrogram when it matches the
+/// template's resolution, otherwise computes STFT columns chunk by chunk.
+async fn scan_file(file: &LoadedFile, template: &Template, state: &AppState, params: &TemplateMatchParams) -> Vec<TemplateHit> {
+    let spec = &file.spectrogram;
+    let same_grid = (spec.freq_resolution - template.freq_resolution).abs() < 1e-6
+        && (spec.time_resolution - template.time_resolution).abs() < 1e-9;
+    if same_grid && !spec.columns.is_empty() {
+        return template_match::match_template(template, spec, params);
+    }
+
+    let sr = file.audio.sample_rate;
+    let total = file.audio.source.total_samples();
+    let chunk = (CHUNK_SECS * sr as f64) as u64;
+    // Overlap so a call straddling a chunk boundary is still seen whole
+    let overlap = ((template.duration + 2.0 / template.freq_resolution) * sr as f64).ceil() as u64;
+    let channel = state.channel_view.get_untracked();
+    let mut hits = Vec::new();
+    let mut start = 0u64;
+    while start < total {
+        let len = (chunk + overlap).min(total - start) as usize;
+        let samples = file.audio.source.read_region(channel, start, len);
+        let chunk_start = start as f64 / sr as f64;
+        let chunk_end = (start + chunk) as f64 / sr as f64;
+        hits.extend(
+            template_match::match_template_samples(template, &samples, sr, chunk_start, params)
+                .into_iter()
+                .filter(|h| h.start_time < chunk_end),
+        );
+        start += chunk;
+        yield_to_browser().await;
+    }
+    // Keep the best hits across chunks, back in time order
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(params.max_hits);
+    hits.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    hits
+}
+
+/// Search for calls similar to `bx` on file `source_idx` and add the hits as
+/// proposed annotations (one group per file, regions tagged `template-match`).
+///
+/// With `all_files` every loaded file is searched, otherwise only the source.
+pub async fn find_similar(state: AppState, source_idx: usize, bx: TemplateBox, all_files: bool) {
+    let params = TemplateMatchParams::default();
+    let source = state.files.with_untracked(|files| files.get(source_idx).cloned());
+    let Some(source) = source else { return };
+    let Some(template) = build_template(&source, bx, &state, &params) else {
+        state.show_info_toast("Selection too small to use as a template");
+        return;
+    };
+
+    let targets: Vec<usize> = if all_files {
+        (0..state.files.with_untracked(|f| f.len())).collect()
+    } else {
+        vec![source_idx]
+    };
+
+    let mut total_hits = 0;
+    let mut files_with_hits = 0;
+    for idx in targets {
+        let file = state.files.with_untracked(|files| files.get(idx).cloned());
+        let Some(file) = file else { continue };
+        if file.is_live_listen {
+            continue;
+        }
+        yield_to_browser().await;
+        let mut hits = scan_file(&file, &template, &state, &params).await;
+        if idx == source_idx {
+            // The exemplar itself always matches
+            hits.retain(|h| h.end_time <= bx.time_start || h.start_time >= bx.time_end);
+        }
+        if hits.is_empty() {
+            continue;
+        }
+        total_hits += hits.len();
+        files_with_hits += 1;
+        state.snapshot_file_annotations(idx);
+        state.push_annotations(idx, hit_annotations(&hits, bx));
+    }
+
+    state.show_info_toast(match total_hits {
+        0 => "No similar calls found".to_string(),
+        n if all_files => format!("{} similar call(s) in {} file(s)", n, files_with_hits),
+        n => format!("{} similar call(s) found", n),
+    });
+}
+
+fn hit_annotations(hits: &[TemplateHit], bx: TemplateBox) -> Vec<Annotation> {
+    let group_id = generate_uuid();
+    let mut annotations = vec![Annotation {
+        id: group_id.clone(),
+        kind: AnnotationKind::Group(Group {
+            label: Some(format!(
+                "Similar to {} ({:.0}\u{2013}{:.0} kHz)",
+                crate::format_time::format_time_display(bx.time_start, 3),
+                bx.freq_low / 1000.0, bx.freq_high / 1000.0,
+            )),
+            color: None,
+            collapsed: Some(true),
+        }),
+        created_at: now_iso8601(),
+        modified_at: now_iso8601(),
+        notes: None,
+        parent_id: None,
+        sort_order: None,
+        tags: Vec::new(),
+        label_default: None,
+        confidence: None,
+        review: None,
+    }];
+    annotations.extend(hits.iter().map(|h| Annotation {
+        id: generate_uuid(),
+        kind: AnnotationKind::Region(Region {
+            time_start: h.start_time,
+            time_end: h.end_time,
+            freq_low: Some(h.freq_low_hz),
+            freq_high: Some(h.freq_high_hz),
+            label: Some(format!("Match {:.2}", h.score)),
+            color: Some("#44bbff".to_string()),
+            locked: None,
+            contour: None,
+            position: None,
+        }),
+        created_at: now_iso8601(),
+        modified_at: now_iso8601(),
+        notes: Some(format!("Template correlation {:.3}", h.score)),
+        parent_id: Some(group_id.clone()),
+        sort_order: None,
+        tags: vec!["template-match".to_string()],
+        label_default: None,
+        confidence: None,
+        review: None,
+    }));
+    annotations
+}
@@ -123,7 +123,7 @@ impl TimelineView {
 
         // Detect multitrack groups from the selected files
         let all_names: Vec<String> = files.iter().map(|f| f.name.clone()).collect();
-        let all_groups = file_groups::compute_file_groups(&all_names);
+        let all_groups = file_groups::compute_file_groups(&all_names, files);
 
         let mut multitrack_groups = Vec::new();
         let mut seen_groups = std::collections::HashSet::new();